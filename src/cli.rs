@@ -1,9 +1,12 @@
-use clap::App;
-use std::str::FromStr;
-use std::net::{ 
-    IpAddr,
-    Ipv6Addr,
+use clap::{
+    App,
+    Arg,
 };
+use std::str::FromStr;
+use std::path::PathBuf;
+use std::net::IpAddr;
+
+use log::error;
 
 use crate::err::ConmxErr;
 
@@ -13,7 +16,15 @@ pub enum CliOpts {
 }
 
 pub struct CliOptValues {
-    pub node_ip: IpAddr,
+    pub node_ip: Option<IpAddr>,
+    pub show: Option<PathBuf>,
+    pub artnet_timecode: bool,
+    pub osc_port: Option<u16>,
+    pub midi_device: Option<PathBuf>,
+    pub web_port: Option<u16>,
+    pub web_token: Option<String>,
+    pub sacn_output: bool,
+    pub headless: bool,
     pub version: String,
 }
 
@@ -23,31 +34,105 @@ impl CliOpts {
             .version(crate_version!())
             .author(crate_authors!())
             .about("run visualization Software, usage: rnet <TargetAddr>")
+            .arg(Arg::with_name("show")
+                .long("show")
+                .value_name("FILE")
+                .help("Show file to load and save")
+                .takes_value(true))
+            .arg(Arg::with_name("artnet-timecode")
+                .long("artnet-timecode")
+                .help("Follow ArtTimeCode received on the Art-Net port"))
+            .arg(Arg::with_name("osc-port")
+                .long("osc-port")
+                .value_name("PORT")
+                .help("Start the OSC remote control server on PORT")
+                .takes_value(true))
+            .arg(Arg::with_name("midi-device")
+                .long("midi-device")
+                .value_name("DEVICE")
+                .help("Raw MIDI device to read controls from, e.g. /dev/snd/midiC1D0")
+                .takes_value(true))
+            .arg(Arg::with_name("web-port")
+                .long("web-port")
+                .value_name("PORT")
+                .help("Start the HTTP API and live stream on PORT, needs --web-token")
+                .takes_value(true))
+            .arg(Arg::with_name("web-token")
+                .long("web-token")
+                .value_name("TOKEN")
+                .help("Token web clients authenticate with")
+                .takes_value(true))
+            .arg(Arg::with_name("node-ip")
+                .long("node-ip")
+                .value_name("ADDR")
+                .help("Ip of the target artnet node, the universes are sent to it (or a broadcast address), defaults to ::1")
+                .takes_value(true))
+            .arg(Arg::with_name("sacn-output")
+                .long("sacn-output")
+                .help("Send the universes with sACN"))
+            .arg(Arg::with_name("headless")
+                .long("headless")
+                .help("Run the show without a window, controlled by OSC, the web API and stdin"))
             .get_matches();
 
         let node_ip = match m.value_of("node-ip") {
-            Some(ip) => IpAddr::from_str(ip)
-                .map_err(|e| ConmxErr::Net(format!("Ip \"{}\" not valid ({})", ip, e)))?,
-            None => IpAddr::V6(Ipv6Addr::new(0,0,0,0,0,0,0,1)), // Local address
+            Some(ip) => Some(IpAddr::from_str(ip)
+                .map_err(|e| ConmxErr::Net(format!("Ip \"{}\" not valid ({})", ip, e)))?),
+            None => None,
+        };
+
+        let osc_port = match m.value_of("osc-port") {
+            Some(port) => Some(u16::from_str(port)
+                .map_err(|e| ConmxErr::Net(format!("OSC port \"{}\" not valid ({})", port, e)))?),
+            None => None,
+        };
+
+        let web_port = match m.value_of("web-port") {
+            Some(port) => Some(u16::from_str(port)
+                .map_err(|e| ConmxErr::Net(format!("Web port \"{}\" not valid ({})", port, e)))?),
+            None => None,
         };
 
         Ok(
             CliOpts::Unvalidated(
                 CliOptValues {
                     node_ip,
+                    show: m.value_of("show").map(PathBuf::from),
+                    artnet_timecode: m.is_present("artnet-timecode"),
+                    osc_port,
+                    midi_device: m.value_of("midi-device").map(PathBuf::from),
+                    web_port,
+                    web_token: m.value_of("web-token").map(String::from),
+                    sacn_output: m.is_present("sacn-output"),
+                    headless: m.is_present("headless"),
                     version: String::from(crate_version!()),
                 }
             )
         )
     }
 
+    /// Check flags which only work together, invalid combinations stay unvalidated
     pub fn validate(self) -> Self {
-        // TODO( Implement validation behavior )
         match self {
-            CliOpts::Unvalidated(v) => CliOpts::Validated(v),
+            CliOpts::Unvalidated(v) => match v.check() {
+                Ok(()) => CliOpts::Validated(v),
+                Err(e) => {
+                    error!("{}", e);
+                    CliOpts::Unvalidated(v)
+                }
+            },
             val => val,
         }
     }
 }
 
+impl CliOptValues {
+    fn check(&self) -> Result<(), String> {
+        if self.web_port.is_some() && self.web_token.is_none() {
+            return Err("--web-port needs --web-token, web clients could not authenticate otherwise".to_owned());
+        }
+        Ok(())
+    }
+}
+
 
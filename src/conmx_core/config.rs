@@ -1,3 +1,22 @@
-pub struct Config {
+use std::net::IpAddr;
+use std::path::PathBuf;
 
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    /// Show file loaded on startup and written when saving
+    pub show: Option<PathBuf>,
+    /// Follow ArtTimeCode received on the Art-Net port instead of the internal clock
+    pub artnet_timecode: bool,
+    /// Port of the OSC remote control server, it is not started without one
+    pub osc_port: Option<u16>,
+    /// Raw MIDI device the mapped controls are read from and fader positions are sent to
+    pub midi_device: Option<PathBuf>,
+    /// Port of the HTTP API and live stream, it is not started without one
+    pub web_port: Option<u16>,
+    /// Token every web client has to send, the web server is not started without one
+    pub web_token: Option<String>,
+    /// Art-Net node or broadcast address the universes are sent to, the local address if not given
+    pub node_ip: Option<IpAddr>,
+    /// Send the universes with sACN to their multicast groups
+    pub sacn_output: bool,
 }
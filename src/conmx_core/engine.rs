@@ -0,0 +1,408 @@
+use std::net::{
+    IpAddr,
+    Ipv6Addr,
+    SocketAddr,
+};
+use std::path::{
+    Path,
+    PathBuf,
+};
+use std::time::Duration;
+
+use log::{ info, warn, error };
+
+use crate::{
+    dmx,
+    midi,
+    node,
+    osc,
+    playback,
+    show,
+    timecode,
+    web,
+};
+
+use super::Config;
+
+/// Show state and everything running it once per frame, without any window:
+/// remote controls, timecode, playback and the DMX output.
+/// The node graph is owned by whoever draws it and passed into `tick`.
+#[derive(Debug)]
+pub struct Engine {
+    pub dmx: dmx::DMX,
+    pub show: show::Show,
+    pub show_path: Option<PathBuf>,
+    pub programmer: playback::Programmer,
+    pub clock: timecode::TimecodeClock,
+    event_player: timecode::EventPlayer,
+    timecode_receiver: Option<timecode::ArtTimecodeReceiver>,
+    osc: Option<osc::OscServer>,
+    midi_device: Option<Box<dyn midi::MidiBackend>>,
+    web: Option<web::WebServer>,
+    outputs: Vec<dmx::DmxOutput>,
+}
+
+impl Engine {
+    pub const FRAME_TIME: Duration = Duration::from_millis(33);
+
+    /// Universes configured on startup
+    pub const UNIVERSES: usize = 2;
+
+    /// Show file written when saving without a path given on the command line
+    pub const DEFAULT_SHOW_PATH: &'static str = "show.json";
+
+    /// Start everything enabled in the config, parts which fail to start are logged and left out
+    pub fn new(config: &Config, show: show::Show) -> Self {
+        let mut dmx = dmx::DMX::new();
+        for c_id in 0..Self::UNIVERSES {
+            dmx.add_universe(dmx::DMXUniverse::new(c_id));
+        }
+        let timecode_receiver = Self::timecode_receiver(config);
+        let clock_source = if timecode_receiver.is_some() { timecode::ClockSource::External } else { timecode::ClockSource::Internal };
+        Self {
+            dmx,
+            show,
+            show_path: config.show.clone(),
+            programmer: playback::Programmer::new(),
+            clock: timecode::TimecodeClock::new(clock_source),
+            event_player: timecode::EventPlayer::new(),
+            timecode_receiver,
+            osc: Self::osc_server(config),
+            midi_device: Self::midi_device(config),
+            web: Self::web_server(config),
+            outputs: Self::outputs(config),
+        }
+    }
+
+    /// Receiver for ArtTimeCode if enabled in the config, the clock runs internally without it
+    fn timecode_receiver(config: &Config) -> Option<timecode::ArtTimecodeReceiver> {
+        if !config.artnet_timecode {
+            return None;
+        }
+        match timecode::ArtTimecodeReceiver::bind(("0.0.0.0", timecode::ArtTimecodeReceiver::PORT)) {
+            Ok(receiver) => Some(receiver),
+            Err(e) => {
+                error!("{}", e);
+                None
+            }
+        }
+    }
+
+    /// OSC server on the port given in the config
+    fn osc_server(config: &Config) -> Option<osc::OscServer> {
+        let port = config.osc_port?;
+        match osc::OscServer::bind(("0.0.0.0", port)) {
+            Ok(server) => {
+                info!("OSC server listening on port {}", port);
+                Some(server)
+            }
+            Err(e) => {
+                error!("{}", e);
+                None
+            }
+        }
+    }
+
+    /// MIDI device given in the config
+    fn midi_device(config: &Config) -> Option<Box<dyn midi::MidiBackend>> {
+        let path = config.midi_device.as_ref()?;
+        match midi::RawMidiDevice::open(path) {
+            Ok(device) => {
+                info!("Reading MIDI from {}", path.display());
+                Some(Box::new(device))
+            }
+            Err(e) => {
+                error!("{}", e);
+                None
+            }
+        }
+    }
+
+    /// Web server on the port given in the config, only started with a token
+    fn web_server(config: &Config) -> Option<web::WebServer> {
+        let port = config.web_port?;
+        let token = match config.web_token.as_ref() {
+            Some(token) => token,
+            None => {
+                error!("Web server not started, no token given");
+                return None;
+            }
+        };
+        match web::WebServer::bind(("0.0.0.0", port), token) {
+            Ok(server) => {
+                info!("Web server listening on port {}", port);
+                Some(server)
+            }
+            Err(e) => {
+                error!("{}", e);
+                None
+            }
+        }
+    }
+
+    /// Art-Net and sACN outputs enabled in the config
+    fn outputs(config: &Config) -> Vec<dmx::DmxOutput> {
+        let node_ip = config.node_ip.unwrap_or(IpAddr::V6(Ipv6Addr::LOCALHOST));
+        let artnet = dmx::DmxOutput::artnet(SocketAddr::new(node_ip, dmx::DmxProtocol::ArtNet.port()));
+        let sacn = if config.sacn_output { Some(dmx::DmxOutput::sacn()) } else { None };
+        std::iter::once(artnet).chain(sacn)
+            .filter_map(|output| match output {
+                Ok(output) => {
+                    info!("Sending DMX with {:?}", output.protocol());
+                    Some(output)
+                }
+                Err(e) => {
+                    error!("{}", e);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    pub fn toggle_clock(&mut self) {
+        if self.clock.is_running() {
+            self.clock.stop();
+        } else {
+            self.clock.play();
+        }
+    }
+
+    /// Node graph of the show, built from the templates of the default registry
+    pub fn node_graph(&self) -> node::Graph<node::Node> {
+        self.show.nodes.build(&node::NodeRegistry::default())
+    }
+
+    /// Write the show together with `graph` to the path given on the command line, or the default path
+    pub fn save_show(&mut self, graph: &node::Graph<node::Node>) {
+        self.show.nodes = node::GraphFile::from_graph(graph);
+        let path = self.show_path.clone().unwrap_or_else(|| PathBuf::from(Self::DEFAULT_SHOW_PATH));
+        match self.show.save(&path) {
+            Ok(()) => info!("Saved show to {}", path.display()),
+            Err(e) => error!("{}", e),
+        }
+    }
+
+    /// Load the show to run, a path which does not exist yet gives an empty show saved there later
+    pub fn load_show(path: Option<&Path>) -> Result<show::Show, crate::err::ConmxErr> {
+        match path {
+            Some(path) if path.exists() => {
+                let show = show::Show::load(path)?;
+                info!("Loaded show {}", path.display());
+                Ok(show)
+            }
+            _ => Ok(show::Show::new()),
+        }
+    }
+
+    /// Run one frame of `dt` seconds: read the remote controls, evaluate the node graph into the universes, run the playback,
+    /// apply the programmer and send the result to the remotes and outputs
+    pub fn tick(&mut self, dt: f32, graph: &mut node::Graph<node::Node>) {
+        self.run_timecode(dt);
+        self.run_osc(graph);
+        self.run_midi(graph);
+        self.run_web();
+        graph.feed_groups(&self.show.groups);
+        graph.evaluate();
+        graph.write_dmx(&mut self.dmx);
+        self.show.run_playback(dt, &mut self.dmx);
+        self.programmer.apply(&self.show.patch, &mut self.dmx);
+        self.osc_feedback();
+        self.midi_feedback();
+        self.web_feedback();
+        for c_output in self.outputs.iter_mut() {
+            c_output.send(&self.dmx, dt);
+        }
+    }
+
+    /// Advance the timecode clock and fire the events of the show's event lists it passes
+    fn run_timecode(&mut self, dt: f32) {
+        if let Some((tc, rate)) = self.timecode_receiver.as_ref().and_then(|r| r.poll()) {
+            self.clock.receive(tc, rate);
+        }
+        self.clock.update(dt);
+        for c_action in self.event_player.update(&self.show.event_lists, self.clock.time(), &self.show.executors) {
+            info!("Timecode {}: {:?}", self.clock.timecode(), c_action);
+            let (executors, content) = self.show.playback();
+            c_action.run(executors, content);
+        }
+    }
+
+    /// Run a remote control command, a request for a value gives the answer
+    pub fn run_command(&mut self, command: osc::OscCommand, graph: &mut node::Graph<node::Node>) -> Option<osc::OscMessage> {
+        match command {
+            osc::OscCommand::SetChannel { universe, channel, value } => match self.dmx.get_universe(universe) {
+                Some(u) => {
+                    u.set_channel(channel, value);
+                }
+                None => warn!("given Universe {} is not configured!", universe),
+            },
+            osc::OscCommand::GetChannel { universe, channel } => {
+                let value = self.dmx.get_universe_ref(universe)
+                    .and_then(|u| u.get_channel(channel))
+                    .map(|c| c.get_val());
+                match value {
+                    Some(value) => return Some(osc::channel_message(universe, channel, value)),
+                    None => warn!("given Universe {} is not configured!", universe),
+                }
+            }
+            osc::OscCommand::Go(index) => {
+                let (executors, content) = self.show.playback();
+                executors.go(index, content);
+            }
+            osc::OscCommand::Back(index) => {
+                let (executors, content) = self.show.playback();
+                executors.back(index, content);
+            }
+            osc::OscCommand::Release(index) => self.show.executors.release(index),
+            osc::OscCommand::Fader(index, level) => {
+                let (executors, content) = self.show.playback();
+                executors.set_level(index, level, content);
+            }
+            osc::OscCommand::GrandMaster(level) => self.show.executors.set_grand_master(level),
+            osc::OscCommand::NodeParam { node, param, value } => {
+                if graph.set_param_by_name(&node, &param, value).is_none() {
+                    warn!("Node \"{}\" has no editable input \"{}\"", node, param);
+                }
+            }
+            osc::OscCommand::Subscribe | osc::OscCommand::Unsubscribe => {}
+        }
+        None
+    }
+
+    /// Run the commands received by the OSC server
+    fn run_osc(&mut self, graph: &mut node::Graph<node::Node>) {
+        let commands = match self.osc.as_mut() {
+            Some(server) => server.poll(),
+            None => return,
+        };
+        for (c_command, c_from) in commands {
+            if let Some(reply) = self.run_command(c_command, graph) {
+                if let Some(server) = self.osc.as_ref() {
+                    server.send(&reply, c_from);
+                }
+            }
+        }
+    }
+
+    /// Send changed values to subscribed OSC clients
+    fn osc_feedback(&mut self) {
+        if let Some(server) = self.osc.as_mut().filter(|s| !s.subscribers().is_empty()) {
+            server.feedback(osc::state_messages(&self.dmx, &self.show.executors));
+        }
+    }
+
+    /// Run the actions of the mapped controls moved on the MIDI device
+    fn run_midi(&mut self, graph: &mut node::Graph<node::Node>) {
+        let messages = match self.midi_device.as_mut() {
+            Some(device) => device.receive(),
+            None => return,
+        };
+        for c_msg in messages {
+            let action = match self.show.midi.handle(&c_msg) {
+                Some(action) => action,
+                None => continue,
+            };
+            let pressed = action.value > 0.;
+            match action.target {
+                midi::MidiTarget::Channel { universe, channel } => match self.dmx.get_universe(universe) {
+                    Some(u) => {
+                        u.set_channel(channel, (action.value * 255.).round() as u32);
+                    }
+                    None => warn!("given Universe {} is not configured!", universe),
+                },
+                midi::MidiTarget::Fader(index) => {
+                    let (executors, content) = self.show.playback();
+                    executors.set_level(index, action.value, content);
+                }
+                midi::MidiTarget::Go(index) if pressed => {
+                    let (executors, content) = self.show.playback();
+                    executors.go(index, content);
+                }
+                midi::MidiTarget::Back(index) if pressed => {
+                    let (executors, content) = self.show.playback();
+                    executors.back(index, content);
+                }
+                midi::MidiTarget::Flash(index) => {
+                    let (executors, content) = self.show.playback();
+                    executors.flash(index, pressed, content);
+                }
+                midi::MidiTarget::Release(index) if pressed => self.show.executors.release(index),
+                midi::MidiTarget::GrandMaster => self.show.executors.set_grand_master(action.value),
+                midi::MidiTarget::NodeParam { node, param } => {
+                    if graph.set_param_fraction_by_name(&node, &param, action.value).is_none() {
+                        warn!("Node \"{}\" has no editable input \"{}\"", node, param);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Move motorised faders and light buttons to the current values
+    fn midi_feedback(&mut self) {
+        let device = match self.midi_device.as_mut() {
+            Some(device) => device,
+            None => return,
+        };
+        let (dmx, executors) = (&self.dmx, &self.show.executors);
+        let value = |target: &midi::MidiTarget| match target {
+            midi::MidiTarget::Channel { universe, channel } => dmx.get_universe_ref(*universe)
+                .and_then(|u| u.get_channel(*channel))
+                .map(|c| c.get_val() as f32 / 255.),
+            midi::MidiTarget::Fader(index) => executors.get(*index).map(|e| e.get_level()),
+            midi::MidiTarget::Go(index) | midi::MidiTarget::Back(index) | midi::MidiTarget::Flash(index) | midi::MidiTarget::Release(index) =>
+                executors.get(*index).map(|e| if e.is_active() { 1. } else { 0. }),
+            midi::MidiTarget::GrandMaster => Some(executors.grand_master()),
+            midi::MidiTarget::NodeParam { .. } => None,
+        };
+        for c_msg in self.show.midi.feedback(value) {
+            device.send(&c_msg);
+        }
+    }
+
+    /// Answer the requests of web clients
+    fn run_web(&mut self) {
+        if let Some(server) = self.web.as_mut() {
+            server.poll(&mut web::ApiContext {
+                dmx: &mut self.dmx,
+                show: &mut self.show,
+                programmer: &mut self.programmer,
+            });
+        }
+    }
+
+    /// Stream changed values to connected web clients
+    fn web_feedback(&mut self) {
+        if let Some(server) = self.web.as_mut() {
+            server.broadcast(&self.dmx, &self.show);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commands() {
+        let mut engine = Engine::new(&Config::default(), show::Show::new());
+        let mut graph = node::Graph::new();
+        assert_eq!(engine.dmx.get_universe_ids(), vec![0, 1]);
+
+        let command = |address: &str, value: f32| osc::OscCommand::parse(
+            &osc::OscMessage::new(address).with_arg(osc::OscArg::Float(value))
+        ).unwrap();
+        assert_eq!(engine.run_command(command("/conmx/universe/1/channel/3", 1.), &mut graph), None);
+        let get = osc::OscCommand::parse(&osc::OscMessage::new("/conmx/universe/1/channel/3")).unwrap();
+        assert_eq!(engine.run_command(get, &mut graph), Some(osc::channel_message(1, 2, 255)));
+
+        engine.run_command(command("/conmx/executor/2/fader", 0.5), &mut graph);
+        engine.run_command(command("/conmx/grandmaster", 0.25), &mut graph);
+        engine.tick(Engine::FRAME_TIME.as_secs_f32(), &mut graph);
+        assert_eq!(engine.show.executors.get(1).map(|e| e.get_level()), Some(0.5));
+        assert_eq!(engine.show.executors.grand_master(), 0.25);
+        // Playback does not touch channels no executor plays
+        assert_eq!(engine.dmx.get_universe_ref(1).unwrap().get_channel(2).unwrap().get_val(), 255);
+    }
+}
@@ -1,3 +1,5 @@
 pub mod config;
+pub mod engine;
 
 pub use config::Config;
+pub use engine::Engine;
@@ -22,4 +22,15 @@ impl DMX {
     pub fn get_universe(&mut self, id: usize) -> Option<&mut DMXUniverse> {
         self.universes.get_mut(&id)
     }
+
+    pub fn get_universe_ref(&self, id: usize) -> Option<&DMXUniverse> {
+        self.universes.get(&id)
+    }
+
+    /// Get the ids of all configured universes in ascending order
+    pub fn get_universe_ids(&self) -> Vec<usize> {
+        let mut ids: Vec<usize> = self.universes.keys().cloned().collect();
+        ids.sort();
+        ids
+    }
 }
@@ -17,7 +17,13 @@ impl DMXChannel {
 
     pub fn set_val(&mut self, val: u32) -> &mut Self { self.value = val; self }
 
-    pub fn get_val(&mut self) -> u32 {
+    /// Get the effective value, which is the override if one is active
+    pub fn get_val(&self) -> u32 {
         if self.or { self.or_value } else { self.value }
     }
+
+    /// Get the programmed value, ignoring any override
+    pub fn get_programmed(&self) -> u32 { self.value }
+
+    pub fn is_overridden(&self) -> bool { self.or }
 }
@@ -9,10 +9,12 @@ pub struct DMXUniverse {
 }
 
 impl DMXUniverse {
+    pub const CHANNELS: usize = 512;
+
     pub fn new(id: usize) -> Self {
         DMXUniverse {
             id,
-            channels: vec![DMXChannel::new(); 512],
+            channels: vec![DMXChannel::new(); Self::CHANNELS],
         }
     }
 
@@ -32,6 +34,14 @@ impl DMXUniverse {
         self
     }
 
+    pub fn revert_override_channel(&mut self, id: usize) -> &Self {
+        match self.channels.get_mut(id) {
+            Some(channel) => { channel.revert_override(); },
+            None => warn!("Trying to revert override of channel outside of range (Index: {})", id),
+        }
+        self
+    }
+
     pub fn get_channel(&self, id: usize) -> Option<&DMXChannel> { self.channels.get(id) }
 
     pub fn get_id(&self) -> usize { self.id }
@@ -1,8 +1,9 @@
 mod dmx;
 mod dmx_universe;
 mod dmx_channel;
+mod output;
 
 pub use dmx::*;
 pub use dmx_universe::*;
 pub use dmx_channel::*;
-
+pub use output::*;
@@ -0,0 +1,244 @@
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::net::{
+    Ipv4Addr,
+    SocketAddr,
+    UdpSocket,
+};
+
+use artnet_protocol::{
+    ArtCommand,
+    Output,
+    PortAddress,
+};
+use log::warn;
+
+use crate::err::ConmxErr;
+
+use super::{
+    DMX,
+    DMXUniverse,
+};
+
+const ACN_ID: &[u8; 12] = b"ASC-E1.17\0\0\0";
+const SACN_HEADER_LEN: usize = 126;
+const SACN_PRIORITY: u8 = 100;
+const SOURCE_NAME: &str = "ConMX";
+
+/// Network protocol the universes are sent with
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DmxProtocol {
+    ArtNet,
+    /// Streaming ACN (E1.31), sent to the multicast group of each universe
+    Sacn,
+}
+
+impl DmxProtocol {
+    pub fn port(&self) -> u16 {
+        match self {
+            DmxProtocol::ArtNet => 6454,
+            DmxProtocol::Sacn => 5568,
+        }
+    }
+}
+
+/// ArtDmx packet for the universe id used as Art-Net port address
+pub fn art_dmx_packet(universe: usize, sequence: u8, data: &[u8]) -> Result<Vec<u8>, ConmxErr> {
+    let port_address = PortAddress::try_from((universe & 0x7FFF) as u16)
+        .map_err(|e| ConmxErr::Net(format!("Universe {} has no Art-Net port address ({:?})", universe, e)))?;
+    let command = ArtCommand::Output(Output {
+        sequence,
+        port_address,
+        data: data.to_vec().into(),
+        ..Output::default()
+    });
+    command.write_to_buffer()
+        .map_err(|e| ConmxErr::Net(format!("Could not build ArtDmx packet ({:?})", e)))
+}
+
+/// sACN universes start at 1, so universe 0 of the desk is sent as 1
+pub fn sacn_universe(universe: usize) -> u16 {
+    (universe + 1).min(63999) as u16
+}
+
+/// Multicast group a sACN universe is sent to
+pub fn sacn_address(universe: usize) -> SocketAddr {
+    let [hi, lo] = sacn_universe(universe).to_be_bytes();
+    SocketAddr::from((Ipv4Addr::new(239, 255, hi, lo), DmxProtocol::Sacn.port()))
+}
+
+/// E1.31 data packet with its root, framing and DMP layer
+pub fn sacn_packet(universe: usize, sequence: u8, cid: &[u8; 16], data: &[u8]) -> Vec<u8> {
+    let len = SACN_HEADER_LEN + data.len();
+    // Flags and length of a layer counted from its start
+    let flags_len = |start: usize| (0x7000 | (len - start) as u16).to_be_bytes();
+
+    let mut packet = Vec::with_capacity(len);
+    packet.extend_from_slice(&[0x00, 0x10, 0x00, 0x00]);
+    packet.extend_from_slice(ACN_ID);
+    packet.extend_from_slice(&flags_len(16));
+    packet.extend_from_slice(&4u32.to_be_bytes());
+    packet.extend_from_slice(cid);
+
+    packet.extend_from_slice(&flags_len(38));
+    packet.extend_from_slice(&2u32.to_be_bytes());
+    let mut name = [0u8; 64];
+    name[..SOURCE_NAME.len()].copy_from_slice(SOURCE_NAME.as_bytes());
+    packet.extend_from_slice(&name);
+    packet.push(SACN_PRIORITY);
+    // No synchronization universe, sequence and no options
+    packet.extend_from_slice(&[0, 0, sequence, 0]);
+    packet.extend_from_slice(&sacn_universe(universe).to_be_bytes());
+
+    packet.extend_from_slice(&flags_len(115));
+    packet.extend_from_slice(&[0x02, 0xA1, 0x00, 0x00, 0x00, 0x01]);
+    packet.extend_from_slice(&(data.len() as u16 + 1).to_be_bytes());
+    // Start code
+    packet.push(0);
+    packet.extend_from_slice(data);
+    packet
+}
+
+/// Sends the universes over the network, once they change and regularly to keep nodes alive
+#[derive(Debug)]
+pub struct DmxOutput {
+    socket: UdpSocket,
+    protocol: DmxProtocol,
+    /// Art-Net node or broadcast address, sACN is sent per universe
+    target: Option<SocketAddr>,
+    cid: [u8; 16],
+    sequences: BTreeMap<usize, u8>,
+    /// Channel values last sent per universe with the seconds since
+    sent: BTreeMap<usize, (Vec<u8>, f32)>,
+}
+
+impl DmxOutput {
+    /// Seconds after which an unchanged universe is sent again
+    pub const REFRESH: f32 = 1.;
+
+    fn bind(protocol: DmxProtocol, target: Option<SocketAddr>) -> Result<Self, ConmxErr> {
+        let socket = UdpSocket::bind("0.0.0.0:0")
+            .map_err(|e| ConmxErr::Net(format!("Could not bind DMX output socket ({})", e)))?;
+        socket.set_broadcast(true)
+            .map_err(|e| ConmxErr::Net(format!("Could not configure DMX output socket ({})", e)))?;
+        let mut cid = *b"ConMX sACN \0\0\0\0\0";
+        cid[12..].copy_from_slice(&std::process::id().to_be_bytes());
+        Ok(Self {
+            socket,
+            protocol,
+            target,
+            cid,
+            sequences: BTreeMap::new(),
+            sent: BTreeMap::new(),
+        })
+    }
+
+    /// Art-Net to a node or a broadcast address
+    pub fn artnet(target: SocketAddr) -> Result<Self, ConmxErr> {
+        Self::bind(DmxProtocol::ArtNet, Some(target))
+    }
+
+    pub fn sacn() -> Result<Self, ConmxErr> {
+        Self::bind(DmxProtocol::Sacn, None)
+    }
+
+    pub fn protocol(&self) -> DmxProtocol {
+        self.protocol
+    }
+
+    /// Send the universes which changed or were not sent for `REFRESH` seconds, `dt` seconds after the last call
+    pub fn send(&mut self, dmx: &DMX, dt: f32) {
+        for c_id in dmx.get_universe_ids() {
+            let universe = match dmx.get_universe_ref(c_id) {
+                Some(u) => u,
+                None => continue,
+            };
+            let data: Vec<u8> = (0..DMXUniverse::CHANNELS)
+                .map(|c| universe.get_channel(c).map(|c| c.get_val().min(255) as u8).unwrap_or(0))
+                .collect();
+            let due = match self.sent.get_mut(&c_id) {
+                Some((sent, age)) => {
+                    *age += dt;
+                    *sent != data || *age >= Self::REFRESH
+                }
+                None => true,
+            };
+            if !due {
+                continue;
+            }
+
+            // Sequence 0 means no sequencing for Art-Net, so it is skipped
+            let sequence = self.sequences.entry(c_id).or_insert(0);
+            *sequence = sequence.wrapping_add(1).max(1);
+            let (packet, target) = match (self.protocol, self.target) {
+                (DmxProtocol::ArtNet, Some(target)) => match art_dmx_packet(c_id, *sequence, &data) {
+                    Ok(packet) => (packet, target),
+                    Err(e) => {
+                        warn!("{}", e);
+                        continue;
+                    }
+                },
+                (DmxProtocol::ArtNet, None) => continue,
+                (DmxProtocol::Sacn, target) => (sacn_packet(c_id, *sequence, &self.cid, &data), target.unwrap_or_else(|| sacn_address(c_id))),
+            };
+            if let Err(e) = self.socket.send_to(&packet, target) {
+                warn!("Could not send universe {} to {} ({})", c_id, target, e);
+            }
+            self.sent.insert(c_id, (data, 0.));
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const ART_DMX_HEADER_LEN: usize = 18;
+
+    #[test]
+    fn test_packets() {
+        let packet = art_dmx_packet(0x123, 7, &[1, 2, 3, 4]).unwrap();
+        assert_eq!(packet[..8], b"Art-Net\0"[..]);
+        assert_eq!(packet.len(), ART_DMX_HEADER_LEN + 4);
+        assert_eq!(packet[8..18], [0x00, 0x50, 0, 14, 7, 0, 0x23, 0x01, 0, 4]);
+
+        let data = [255u8; DMXUniverse::CHANNELS];
+        let packet = sacn_packet(0, 1, &[0; 16], &data);
+        assert_eq!(packet.len(), 638);
+        assert_eq!(packet[16..18], [0x72, 0x6E]);
+        assert_eq!(packet[38..40], [0x72, 0x58]);
+        assert_eq!(packet[113..117], [0, 1, 0x72, 0x0B]);
+        assert_eq!(packet[123..126], [0x02, 0x01, 0]);
+        assert_eq!(sacn_address(256), "239.255.1.1:5568".parse().unwrap());
+    }
+
+    #[test]
+    fn test_send() {
+        let node = UdpSocket::bind("127.0.0.1:0").unwrap();
+        node.set_read_timeout(Some(Duration::from_millis(500))).unwrap();
+        let mut output = DmxOutput::artnet(node.local_addr().unwrap()).unwrap();
+        let mut dmx = DMX::new();
+        dmx.add_universe(DMXUniverse::new(1));
+        dmx.get_universe(1).unwrap().set_channel(0, 300);
+
+        let mut buf = [0u8; 1024];
+        output.send(&dmx, 0.);
+        let len = node.recv(&mut buf).unwrap();
+        assert_eq!(len, ART_DMX_HEADER_LEN + DMXUniverse::CHANNELS);
+        assert_eq!(buf[12..15], [1, 0, 1]);
+        assert_eq!(buf[ART_DMX_HEADER_LEN], 255);
+
+        // Unchanged universes are only refreshed
+        output.send(&dmx, 0.5);
+        dmx.get_universe(1).unwrap().set_channel(1, 10);
+        output.send(&dmx, 0.1);
+        node.recv(&mut buf).unwrap();
+        assert_eq!(buf[12], 2);
+        assert_eq!(buf[ART_DMX_HEADER_LEN + 1], 10);
+        output.send(&dmx, DmxOutput::REFRESH);
+        node.recv(&mut buf).unwrap();
+        assert_eq!(buf[12], 3);
+    }
+}
@@ -9,5 +9,17 @@ pub enum ConmxErr {
     Win(String),
     #[error(display = "Localization Error: {}", _0)]
     Locale(String),
+    #[error(display = "Patch Error: {}", _0)]
+    Patch(String),
+    #[error(display = "Show Error: {}", _0)]
+    Show(String),
+    #[error(display = "Timecode Error: {}", _0)]
+    Timecode(String),
+    #[error(display = "OSC Error: {}", _0)]
+    Osc(String),
+    #[error(display = "MIDI Error: {}", _0)]
+    Midi(String),
+    #[error(display = "Web Error: {}", _0)]
+    Web(String),
 }
 
@@ -0,0 +1,50 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+/// Property of a fixture controlled by one or more DMX channels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Attribute {
+    Intensity,
+    Red,
+    Green,
+    Blue,
+    White,
+    Amber,
+    UV,
+    Cyan,
+    Magenta,
+    Yellow,
+    ColorWheel,
+    Pan,
+    Tilt,
+    Zoom,
+    Focus,
+    Gobo,
+    Shutter,
+}
+
+impl Attribute {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Attribute::Intensity => "Intensity",
+            Attribute::Red => "Red",
+            Attribute::Green => "Green",
+            Attribute::Blue => "Blue",
+            Attribute::White => "White",
+            Attribute::Amber => "Amber",
+            Attribute::UV => "UV",
+            Attribute::Cyan => "Cyan",
+            Attribute::Magenta => "Magenta",
+            Attribute::Yellow => "Yellow",
+            Attribute::ColorWheel => "Color wheel",
+            Attribute::Pan => "Pan",
+            Attribute::Tilt => "Tilt",
+            Attribute::Zoom => "Zoom",
+            Attribute::Focus => "Focus",
+            Attribute::Gobo => "Gobo",
+            Attribute::Shutter => "Shutter",
+        }
+    }
+}
@@ -0,0 +1,294 @@
+//! Colour spaces and the conversion of colours to the emitters of a fixture
+
+use super::{
+    Attribute,
+    FixtureMode,
+    WheelSlot,
+};
+
+/// Colour as fractions of the sRGB primaries
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rgb {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+}
+
+/// Hue in degrees, saturation and value as fractions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsv {
+    pub h: f32,
+    pub s: f32,
+    pub v: f32,
+}
+
+/// CIE 1931 chromaticity coordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Xy {
+    pub x: f32,
+    pub y: f32,
+}
+
+/// Approximate colour of an amber emitter
+const AMBER: Rgb = Rgb { r: 1., g: 0.75, b: 0. };
+
+impl Rgb {
+    pub const WHITE: Rgb = Rgb { r: 1., g: 1., b: 1. };
+    pub const BLACK: Rgb = Rgb { r: 0., g: 0., b: 0. };
+
+    pub fn new(r: f32, g: f32, b: f32) -> Self {
+        Self { r, g, b }
+    }
+
+    pub fn from_array(c: [f32; 3]) -> Self {
+        Self::new(c[0], c[1], c[2])
+    }
+
+    pub fn to_array(&self) -> [f32; 3] {
+        [self.r, self.g, self.b]
+    }
+
+    pub fn clamped(&self) -> Self {
+        let c = |v: f32| v.max(0.).min(1.);
+        Self::new(c(self.r), c(self.g), c(self.b))
+    }
+
+    pub fn scaled(&self, f: f32) -> Self {
+        Self::new(self.r * f, self.g * f, self.b * f)
+    }
+
+    fn max(&self) -> f32 {
+        self.r.max(self.g).max(self.b)
+    }
+
+    /// Same hue and saturation at full brightness
+    pub fn normalized(&self) -> Self {
+        let m = self.max();
+        if m > 0. { self.scaled(1. / m) } else { Self::WHITE }
+    }
+
+    pub fn distance(&self, other: &Rgb) -> f32 {
+        ((self.r - other.r).powi(2) + (self.g - other.g).powi(2) + (self.b - other.b).powi(2)).sqrt()
+    }
+
+    pub fn to_hex(&self) -> String {
+        let c = self.clamped();
+        format!("#{:02X}{:02X}{:02X}", (c.r * 255.).round() as u8, (c.g * 255.).round() as u8, (c.b * 255.).round() as u8)
+    }
+
+    pub fn from_hsv(hsv: Hsv) -> Self {
+        let h = hsv.h.rem_euclid(360.) / 60.;
+        let c = hsv.v * hsv.s;
+        let x = c * (1. - (h % 2. - 1.).abs());
+        let (r, g, b) = match h as u32 {
+            0 => (c, x, 0.),
+            1 => (x, c, 0.),
+            2 => (0., c, x),
+            3 => (0., x, c),
+            4 => (x, 0., c),
+            _ => (c, 0., x),
+        };
+        let m = hsv.v - c;
+        Self::new(r + m, g + m, b + m)
+    }
+
+    pub fn to_hsv(&self) -> Hsv {
+        let max = self.max();
+        let min = self.r.min(self.g).min(self.b);
+        let d = max - min;
+
+        let h = if d <= 0. {
+            0.
+        } else if max == self.r {
+            60. * ((self.g - self.b) / d).rem_euclid(6.)
+        } else if max == self.g {
+            60. * ((self.b - self.r) / d + 2.)
+        } else {
+            60. * ((self.r - self.g) / d + 4.)
+        };
+        Hsv {
+            h,
+            s: if max > 0. { d / max } else { 0. },
+            v: max,
+        }
+    }
+
+    fn to_linear(c: f32) -> f32 {
+        if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+    }
+
+    fn from_linear(c: f32) -> f32 {
+        if c <= 0.003_130_8 { c * 12.92 } else { 1.055 * c.powf(1. / 2.4) - 0.055 }
+    }
+
+    /// Chromaticity of the colour, `None` for black
+    pub fn to_xy(&self) -> Option<Xy> {
+        let (r, g, b) = (Self::to_linear(self.r), Self::to_linear(self.g), Self::to_linear(self.b));
+        let x = 0.4124 * r + 0.3576 * g + 0.1805 * b;
+        let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+        let z = 0.0193 * r + 0.1192 * g + 0.9505 * b;
+        let sum = x + y + z;
+        if sum <= 0. {
+            return None;
+        }
+        Some(Xy { x: x / sum, y: y / sum })
+    }
+
+    /// Brightest displayable colour with the given chromaticity.
+    /// Chromaticities outside of the sRGB gamut are clipped.
+    pub fn from_xy(xy: Xy) -> Self {
+        let y_lum = 1.;
+        let y = xy.y.max(1e-4);
+        let x = xy.x * y_lum / y;
+        let z = (1. - xy.x - xy.y) * y_lum / y;
+
+        let lin = Self::new(
+            3.2406 * x - 1.5372 * y_lum - 0.4986 * z,
+            -0.9689 * x + 1.8758 * y_lum + 0.0415 * z,
+            0.0557 * x - 0.2040 * y_lum + 1.0570 * z,
+        );
+        let lin = Self::new(lin.r.max(0.), lin.g.max(0.), lin.b.max(0.)).normalized();
+        Self::new(Self::from_linear(lin.r), Self::from_linear(lin.g), Self::from_linear(lin.b))
+    }
+}
+
+impl Hsv {
+    pub fn new(h: f32, s: f32, v: f32) -> Self {
+        Self { h, s, v }
+    }
+}
+
+/// Values for the colour attributes of `mode` producing `color` as closely as possible.
+/// Additive emitters get white and amber extracted first, subtractive ones are inverted
+/// and colour wheels select the slot nearest in hue.
+pub fn mix(color: Rgb, mode: &FixtureMode, wheel: &[WheelSlot]) -> Vec<(Attribute, f32)> {
+    let color = color.clamped();
+    let mut values = Vec::new();
+
+    if mode.has_attribute(Attribute::Red) && mode.has_attribute(Attribute::Green) && mode.has_attribute(Attribute::Blue) {
+        let mut rest = color;
+        if mode.has_attribute(Attribute::White) {
+            let w = rest.r.min(rest.g).min(rest.b);
+            rest = Rgb::new(rest.r - w, rest.g - w, rest.b - w);
+            values.push((Attribute::White, w));
+        }
+        if mode.has_attribute(Attribute::Amber) {
+            let a = rest.r.min(rest.g / AMBER.g);
+            rest = Rgb::new(rest.r - a * AMBER.r, rest.g - a * AMBER.g, rest.b);
+            values.push((Attribute::Amber, a));
+        }
+        if mode.has_attribute(Attribute::UV) {
+            values.push((Attribute::UV, 0.));
+        }
+        values.push((Attribute::Red, rest.r));
+        values.push((Attribute::Green, rest.g));
+        values.push((Attribute::Blue, rest.b));
+    } else if mode.has_attribute(Attribute::Cyan) && mode.has_attribute(Attribute::Magenta) && mode.has_attribute(Attribute::Yellow) {
+        values.push((Attribute::Cyan, 1. - color.r));
+        values.push((Attribute::Magenta, 1. - color.g));
+        values.push((Attribute::Yellow, 1. - color.b));
+    } else if mode.has_attribute(Attribute::ColorWheel) {
+        if let Some(slot) = nearest_slot(color, wheel) {
+            values.push((Attribute::ColorWheel, slot.dmx as f32 / 255.));
+        }
+    }
+    values
+}
+
+/// Colour wheel slot closest to the hue and saturation of `color`
+pub fn nearest_slot(color: Rgb, wheel: &[WheelSlot]) -> Option<&WheelSlot> {
+    let target = color.normalized();
+    wheel.iter()
+        .min_by(|a, b| {
+            let da = Rgb::from_array(a.color).normalized().distance(&target);
+            let db = Rgb::from_array(b.color).normalized().distance(&target);
+            da.partial_cmp(&db).unwrap_or(std::cmp::Ordering::Equal)
+        })
+}
+
+/// Colour emitted for the given attribute values, ignoring intensity.
+/// `None` if there are no colour attributes.
+pub fn emitted(get: impl Fn(Attribute) -> Option<f32>, wheel: &[WheelSlot]) -> Option<Rgb> {
+    let rgb = match (get(Attribute::Red), get(Attribute::Green), get(Attribute::Blue)) {
+        (Some(r), Some(g), Some(b)) => {
+            let w = get(Attribute::White).unwrap_or(0.);
+            let a = get(Attribute::Amber).unwrap_or(0.);
+            Rgb::new(r + w + a * AMBER.r, g + w + a * AMBER.g, b + w + a * AMBER.b)
+        }
+        _ => match (get(Attribute::Cyan), get(Attribute::Magenta), get(Attribute::Yellow)) {
+            (Some(c), Some(m), Some(y)) => Rgb::new(1. - c, 1. - m, 1. - y),
+            _ => {
+                let dmx = (get(Attribute::ColorWheel)? * 255.).round() as u8;
+                // Slots cover the range up to the next slot
+                wheel.iter()
+                    .filter(|s| s.dmx <= dmx)
+                    .max_by_key(|s| s.dmx)
+                    .map(|s| Rgb::from_array(s.color))
+                    .unwrap_or(Rgb::WHITE)
+            }
+        },
+    };
+    Some(rgb.clamped())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::library;
+
+    fn close(a: Rgb, b: Rgb) -> bool {
+        a.distance(&b) < 0.01
+    }
+
+    #[test]
+    fn test_hsv() {
+        assert!(close(Rgb::from_hsv(Hsv::new(0., 1., 1.)), Rgb::new(1., 0., 0.)));
+        assert!(close(Rgb::from_hsv(Hsv::new(120., 1., 1.)), Rgb::new(0., 1., 0.)));
+        assert!(close(Rgb::from_hsv(Hsv::new(240., 0.5, 1.)), Rgb::new(0.5, 0.5, 1.)));
+        assert!(close(Rgb::from_hsv(Hsv::new(360., 1., 1.)), Rgb::new(1., 0., 0.)));
+
+        let c = Rgb::new(0.2, 0.6, 0.9);
+        assert!(close(Rgb::from_hsv(c.to_hsv()), c));
+    }
+
+    #[test]
+    fn test_xy() {
+        // D65 white point
+        let white = Rgb::WHITE.to_xy().unwrap();
+        assert!((white.x - 0.3127).abs() < 0.001 && (white.y - 0.3290).abs() < 0.001);
+        assert!(Rgb::BLACK.to_xy().is_none());
+
+        let c = Rgb::new(1., 0.5, 0.2);
+        assert!(close(Rgb::from_xy(c.to_xy().unwrap()), c));
+    }
+
+    #[test]
+    fn test_mix() {
+        let rgbw = library::generic_rgbw();
+        let values = mix(Rgb::new(1., 0.5, 0.5), &rgbw.modes[0], &[]);
+        assert!(values.contains(&(Attribute::White, 0.5)));
+        assert!(values.contains(&(Attribute::Red, 0.5)));
+        assert!(values.contains(&(Attribute::Blue, 0.)));
+
+        let rgbaw = library::generic_rgbaw_uv();
+        let values = mix(Rgb::new(1., 0.75, 0.), &rgbaw.modes[0], &[]);
+        assert!(values.contains(&(Attribute::Amber, 1.)));
+        assert!(values.contains(&(Attribute::Red, 0.)));
+        let get = |a| values.iter().find(|(v, _)| *v == a).map(|(_, v)| *v);
+        assert!(close(emitted(get, &[]).unwrap(), Rgb::new(1., 0.75, 0.)));
+
+        let cmy = library::generic_cmy_wash();
+        let values = mix(Rgb::new(1., 0., 1.), &cmy.modes[0], &[]);
+        assert!(values.contains(&(Attribute::Magenta, 1.)) && values.contains(&(Attribute::Cyan, 0.)));
+
+        let head = library::generic_moving_head();
+        let values = mix(Rgb::new(0.1, 0.1, 0.45), &head.modes[0], &head.color_wheel);
+        assert_eq!(values, vec![(Attribute::ColorWheel, 96. / 255.)]);
+        let get = |a| values.iter().find(|(v, _)| *v == a).map(|(_, v)| *v);
+        assert!(close(emitted(get, &head.color_wheel).unwrap(), Rgb::new(0., 0., 1.)));
+
+        // Fixtures without colour attributes get nothing
+        assert!(mix(Rgb::WHITE, &library::generic_dimmer().modes[0], &[]).is_empty());
+    }
+}
@@ -0,0 +1,132 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use super::attribute::Attribute;
+use super::position::PositionRange;
+
+/// A single DMX channel of a fixture mode
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChannelDef {
+    pub attribute: Attribute,
+    /// Whether this channel carries the lower byte of a 16 bit attribute
+    pub fine: bool,
+    pub default: u8,
+}
+
+impl ChannelDef {
+    pub fn coarse(attribute: Attribute) -> Self {
+        Self { attribute, fine: false, default: 0 }
+    }
+
+    pub fn fine(attribute: Attribute) -> Self {
+        Self { attribute, fine: true, default: 0 }
+    }
+
+    pub fn with_default(mut self, default: u8) -> Self {
+        self.default = default;
+        self
+    }
+}
+
+/// Channel layout of a fixture type
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FixtureMode {
+    pub name: String,
+    pub channels: Vec<ChannelDef>,
+}
+
+impl FixtureMode {
+    pub fn new(name: &str, channels: Vec<ChannelDef>) -> Self {
+        Self {
+            name: name.to_owned(),
+            channels,
+        }
+    }
+
+    /// Number of DMX channels the mode occupies
+    pub fn footprint(&self) -> usize {
+        self.channels.len()
+    }
+
+    /// Offset of the coarse (or only) channel of `attribute`
+    pub fn coarse_offset(&self, attribute: Attribute) -> Option<usize> {
+        self.channels.iter().position(|c| c.attribute == attribute && !c.fine)
+    }
+
+    /// Offset of the fine channel of `attribute`, if it has 16 bit resolution
+    pub fn fine_offset(&self, attribute: Attribute) -> Option<usize> {
+        self.channels.iter().position(|c| c.attribute == attribute && c.fine)
+    }
+
+    /// All attributes of the mode in channel order, without duplicates for fine channels
+    pub fn attributes(&self) -> Vec<Attribute> {
+        self.channels.iter()
+            .filter(|c| !c.fine)
+            .map(|c| c.attribute)
+            .collect()
+    }
+
+    pub fn has_attribute(&self, attribute: Attribute) -> bool {
+        self.coarse_offset(attribute).is_some()
+    }
+}
+
+/// A slot of a colour wheel
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WheelSlot {
+    pub name: String,
+    /// Colour of the filter as RGB fractions
+    pub color: [f32; 3],
+    /// DMX value centering the slot in the beam
+    pub dmx: u8,
+}
+
+impl WheelSlot {
+    pub fn new(name: &str, color: [f32; 3], dmx: u8) -> Self {
+        Self {
+            name: name.to_owned(),
+            color,
+            dmx,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FixtureType {
+    pub manufacturer: String,
+    pub name: String,
+    pub modes: Vec<FixtureMode>,
+    #[serde(default)]
+    pub color_wheel: Vec<WheelSlot>,
+    #[serde(default)]
+    pub position_range: PositionRange,
+}
+
+impl FixtureType {
+    pub fn new(manufacturer: &str, name: &str, modes: Vec<FixtureMode>) -> Self {
+        Self {
+            manufacturer: manufacturer.to_owned(),
+            name: name.to_owned(),
+            modes,
+            color_wheel: Vec::new(),
+            position_range: PositionRange::default(),
+        }
+    }
+
+    pub fn with_color_wheel(mut self, slots: Vec<WheelSlot>) -> Self {
+        self.color_wheel = slots;
+        self
+    }
+
+    /// Set the movement range in degrees
+    pub fn with_position_range(mut self, pan: f32, tilt: f32) -> Self {
+        self.position_range = PositionRange { pan, tilt };
+        self
+    }
+
+    pub fn get_mode(&self, mode: usize) -> Option<&FixtureMode> {
+        self.modes.get(mode)
+    }
+}
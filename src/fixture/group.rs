@@ -0,0 +1,205 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use super::{
+    patch::FixtureID,
+    selection::{
+        Selection,
+        SelectionOp,
+    },
+};
+
+/// Named set of fixtures recalled by number.
+/// The fixtures keep the order they were stored in, so effects spread the same way every time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Group {
+    pub number: usize,
+    pub name: String,
+    fixtures: Selection,
+}
+
+impl Group {
+    pub fn new(number: usize, name: &str) -> Self {
+        Self {
+            number,
+            name: name.to_owned(),
+            fixtures: Selection::new(),
+        }
+    }
+
+    pub fn from_selection(number: usize, name: &str, selection: &Selection) -> Self {
+        Self {
+            fixtures: selection.clone(),
+            ..Self::new(number, name)
+        }
+    }
+
+    pub fn with_fixtures(mut self, ids: &[FixtureID]) -> Self {
+        self.fixtures.set(ids.iter().cloned());
+        self
+    }
+
+    pub fn ids(&self) -> &[FixtureID] {
+        self.fixtures.ids()
+    }
+
+    pub fn contains(&self, id: FixtureID) -> bool {
+        self.fixtures.contains(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.fixtures.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fixtures.is_empty()
+    }
+
+    /// Append fixtures not in the group yet
+    pub fn add(&mut self, ids: &[FixtureID]) {
+        self.fixtures.combine(SelectionOp::Add, ids);
+    }
+
+    pub fn remove(&mut self, ids: &[FixtureID]) {
+        self.fixtures.combine(SelectionOp::Remove, ids);
+    }
+
+    /// Keep only fixtures also in `ids`, in the order of the group
+    pub fn intersect(&mut self, ids: &[FixtureID]) {
+        self.fixtures.combine(SelectionOp::Intersect, ids);
+    }
+
+    /// Combine the group with `selection`, replacing it keeps the order of the group
+    pub fn select(&self, selection: &mut Selection, op: SelectionOp) {
+        selection.combine(op, self.ids());
+    }
+
+    pub fn as_selection(&self) -> &Selection {
+        &self.fixtures
+    }
+}
+
+/// All groups of a show ordered by number, at most one per number
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(from = "Vec<Group>", into = "Vec<Group>")]
+pub struct Groups {
+    groups: Vec<Group>,
+}
+
+impl Groups {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, number: usize) -> Option<&Group> {
+        self.index(number).ok().map(|i| &self.groups[i])
+    }
+
+    pub fn get_mut(&mut self, number: usize) -> Option<&mut Group> {
+        match self.index(number) {
+            Ok(i) => Some(&mut self.groups[i]),
+            Err(_) => None,
+        }
+    }
+
+    fn index(&self, number: usize) -> Result<usize, usize> {
+        self.groups.binary_search_by_key(&number, |g| g.number)
+    }
+
+    /// Insert a group, replacing the one stored under the same number
+    pub fn store(&mut self, group: Group) {
+        match self.index(group.number) {
+            Ok(i) => self.groups[i] = group,
+            Err(i) => self.groups.insert(i, group),
+        }
+    }
+
+    pub fn remove(&mut self, number: usize) -> Option<Group> {
+        self.index(number).ok().map(|i| self.groups.remove(i))
+    }
+
+    /// Lowest free number, starting at 1
+    pub fn next_number(&self) -> usize {
+        (1..).find(|n| self.get(*n).is_none()).unwrap_or(1)
+    }
+
+    /// Store the selection as a new group under the next free number, returns the number
+    pub fn record(&mut self, selection: &Selection) -> usize {
+        let number = self.next_number();
+        self.store(Group::from_selection(number, &format!("Group {}", number), selection));
+        number
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Group> {
+        self.groups.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+}
+
+/// Groups are sorted again when loading, later groups win over earlier ones with the same number
+impl From<Vec<Group>> for Groups {
+    fn from(list: Vec<Group>) -> Self {
+        let mut groups = Self::new();
+        for c_group in list {
+            groups.store(c_group);
+        }
+        groups
+    }
+}
+
+impl From<Groups> for Vec<Group> {
+    fn from(groups: Groups) -> Self {
+        groups.groups
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_ops() {
+        let mut group = Group::new(1, "Front").with_fixtures(&[3, 1, 2]);
+        group.add(&[4, 1]);
+        assert_eq!(group.ids(), &[3, 1, 2, 4]);
+        group.remove(&[1]);
+        group.intersect(&[4, 3, 7]);
+        assert_eq!(group.ids(), &[3, 4]);
+
+        let mut sel = Selection::from(vec![4, 5]);
+        group.select(&mut sel, SelectionOp::Add);
+        assert_eq!(sel.ids(), &[4, 5, 3]);
+        group.select(&mut sel, SelectionOp::Replace);
+        assert_eq!(sel.ids(), &[3, 4]);
+    }
+
+    #[test]
+    fn test_groups() {
+        let mut groups = Groups::new();
+        assert_eq!(groups.record(&Selection::from(vec![2, 1])), 1);
+        groups.store(Group::new(3, "Spots").with_fixtures(&[5]));
+        assert_eq!(groups.record(&Selection::from(vec![1])), 2);
+        assert_eq!(groups.iter().map(|g| g.number).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(groups.get(1).unwrap().name, "Group 1");
+
+        groups.store(Group::new(1, "Wash").with_fixtures(&[7]));
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups.get(1).unwrap().ids(), &[7]);
+
+        assert!(groups.remove(2).is_some());
+        assert_eq!(groups.next_number(), 2);
+
+        let json = serde_json::to_string(&groups).unwrap();
+        assert_eq!(serde_json::from_str::<Groups>(&json).unwrap(), groups);
+    }
+}
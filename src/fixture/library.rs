@@ -0,0 +1,112 @@
+//! Generic fixture types available without any fixture definition files
+
+use std::sync::Arc;
+
+use super::{
+    Attribute,
+    ChannelDef,
+    FixtureMode,
+    FixtureType,
+    WheelSlot,
+};
+
+pub fn generic_dimmer() -> FixtureType {
+    FixtureType::new("Generic", "Dimmer", vec![
+        FixtureMode::new("8 bit", vec![ChannelDef::coarse(Attribute::Intensity)]),
+        FixtureMode::new("16 bit", vec![
+            ChannelDef::coarse(Attribute::Intensity),
+            ChannelDef::fine(Attribute::Intensity),
+        ]),
+    ])
+}
+
+pub fn generic_rgb() -> FixtureType {
+    FixtureType::new("Generic", "RGB Par", vec![
+        FixtureMode::new("3 channel", vec![
+            ChannelDef::coarse(Attribute::Red),
+            ChannelDef::coarse(Attribute::Green),
+            ChannelDef::coarse(Attribute::Blue),
+        ]),
+        FixtureMode::new("4 channel", vec![
+            ChannelDef::coarse(Attribute::Intensity),
+            ChannelDef::coarse(Attribute::Red),
+            ChannelDef::coarse(Attribute::Green),
+            ChannelDef::coarse(Attribute::Blue),
+        ]),
+    ])
+}
+
+pub fn generic_rgbw() -> FixtureType {
+    FixtureType::new("Generic", "RGBW Par", vec![
+        FixtureMode::new("5 channel", vec![
+            ChannelDef::coarse(Attribute::Intensity),
+            ChannelDef::coarse(Attribute::Red),
+            ChannelDef::coarse(Attribute::Green),
+            ChannelDef::coarse(Attribute::Blue),
+            ChannelDef::coarse(Attribute::White),
+        ]),
+    ])
+}
+
+pub fn generic_moving_head() -> FixtureType {
+    FixtureType::new("Generic", "Moving Head", vec![
+        FixtureMode::new("Standard", vec![
+            ChannelDef::coarse(Attribute::Pan).with_default(128),
+            ChannelDef::fine(Attribute::Pan),
+            ChannelDef::coarse(Attribute::Tilt).with_default(128),
+            ChannelDef::fine(Attribute::Tilt),
+            ChannelDef::coarse(Attribute::Intensity),
+            ChannelDef::coarse(Attribute::Shutter).with_default(255),
+            ChannelDef::coarse(Attribute::ColorWheel),
+            ChannelDef::coarse(Attribute::Gobo),
+        ]),
+    ])
+    .with_position_range(540., 270.)
+    .with_color_wheel(vec![
+        WheelSlot::new("Open", [1., 1., 1.], 0),
+        WheelSlot::new("Red", [1., 0., 0.], 16),
+        WheelSlot::new("Orange", [1., 0.5, 0.], 32),
+        WheelSlot::new("Yellow", [1., 1., 0.], 48),
+        WheelSlot::new("Green", [0., 1., 0.], 64),
+        WheelSlot::new("Cyan", [0., 1., 1.], 80),
+        WheelSlot::new("Blue", [0., 0., 1.], 96),
+        WheelSlot::new("Magenta", [1., 0., 1.], 112),
+    ])
+}
+
+pub fn generic_rgbaw_uv() -> FixtureType {
+    FixtureType::new("Generic", "RGBAW+UV Par", vec![
+        FixtureMode::new("7 channel", vec![
+            ChannelDef::coarse(Attribute::Intensity),
+            ChannelDef::coarse(Attribute::Red),
+            ChannelDef::coarse(Attribute::Green),
+            ChannelDef::coarse(Attribute::Blue),
+            ChannelDef::coarse(Attribute::Amber),
+            ChannelDef::coarse(Attribute::White),
+            ChannelDef::coarse(Attribute::UV),
+        ]),
+    ])
+}
+
+pub fn generic_cmy_wash() -> FixtureType {
+    FixtureType::new("Generic", "CMY Wash", vec![
+        FixtureMode::new("Standard", vec![
+            ChannelDef::coarse(Attribute::Intensity),
+            ChannelDef::coarse(Attribute::Cyan),
+            ChannelDef::coarse(Attribute::Magenta),
+            ChannelDef::coarse(Attribute::Yellow),
+        ]),
+    ])
+}
+
+/// All built in fixture types
+pub fn builtin_types() -> Vec<Arc<FixtureType>> {
+    vec![
+        Arc::new(generic_dimmer()),
+        Arc::new(generic_rgb()),
+        Arc::new(generic_rgbw()),
+        Arc::new(generic_moving_head()),
+        Arc::new(generic_rgbaw_uv()),
+        Arc::new(generic_cmy_wash()),
+    ]
+}
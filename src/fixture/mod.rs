@@ -0,0 +1,19 @@
+mod attribute;
+pub use attribute::*;
+
+mod fixture_type;
+pub use fixture_type::*;
+
+mod group;
+pub use group::*;
+
+mod patch;
+pub use patch::*;
+
+mod selection;
+pub use selection::*;
+
+pub mod color;
+pub mod library;
+pub mod position;
+pub mod value;
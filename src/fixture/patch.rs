@@ -0,0 +1,358 @@
+use std::sync::Arc;
+
+use crate::{
+    dmx::{
+        DMX,
+        DMXUniverse,
+    },
+    err::ConmxErr,
+};
+
+use super::attribute::Attribute;
+use super::fixture_type::{
+    FixtureMode,
+    FixtureType,
+};
+use super::color::{
+    self,
+    Rgb,
+};
+use super::position;
+use super::value;
+
+pub type FixtureID = usize;
+
+/// A fixture instance placed at an address of a universe
+#[derive(Debug, Clone)]
+pub struct Fixture {
+    id: FixtureID,
+    name: String,
+    fixture_type: Arc<FixtureType>,
+    mode: usize,
+    universe: usize,
+    /// Index of the first channel, starting at 0
+    address: usize,
+    invert_pan: bool,
+    invert_tilt: bool,
+}
+
+impl Fixture {
+    pub fn get_id(&self) -> FixtureID { self.id }
+
+    pub fn get_name(&self) -> &str { &self.name }
+
+    pub fn set_name(&mut self, name: String) { self.name = name; }
+
+    pub fn get_type(&self) -> &FixtureType { &self.fixture_type }
+
+    pub fn get_mode(&self) -> &FixtureMode {
+        &self.fixture_type.modes[self.mode]
+    }
+
+    pub fn get_mode_index(&self) -> usize { self.mode }
+
+    pub fn get_universe(&self) -> usize { self.universe }
+
+    pub fn get_address(&self) -> usize { self.address }
+
+    pub fn footprint(&self) -> usize {
+        self.get_mode().footprint()
+    }
+
+    /// Channel indices occupied in the universe
+    pub fn channels(&self) -> std::ops::Range<usize> {
+        self.address..self.address + self.footprint()
+    }
+
+    /// Address in the usual `universe.address` notation, with addresses starting at 1
+    pub fn address_name(&self) -> String {
+        format!("{}.{:03}", self.universe, self.address + 1)
+    }
+
+    pub fn has_attribute(&self, attribute: Attribute) -> bool {
+        self.get_mode().has_attribute(attribute)
+    }
+
+    /// Current output of an attribute as a fraction, using the fine channel if there is one
+    pub fn get_attribute(&self, dmx: &DMX, attribute: Attribute) -> Option<f32> {
+        let mode = self.get_mode();
+        let universe = dmx.get_universe_ref(self.universe)?;
+        let read = |offset: usize| universe.get_channel(self.address + offset)
+            .map(|c| c.get_val().min(255) as u8);
+
+        let coarse = read(mode.coarse_offset(attribute)?)?;
+        match mode.fine_offset(attribute).and_then(read) {
+            Some(fine) => Some(value::from_dmx16(coarse, fine)),
+            None => Some(value::from_dmx8(coarse)),
+        }
+    }
+
+    /// Write an attribute given as a fraction to the channels of the fixture.
+    /// Returns false if the fixture has no such attribute or its universe is not configured.
+    pub fn set_attribute(&self, dmx: &mut DMX, attribute: Attribute, val: f32) -> bool {
+        let channels = self.channel_values(attribute, val);
+        let universe = match dmx.get_universe(self.universe) {
+            Some(u) if !channels.is_empty() => u,
+            _ => return false,
+        };
+        for (c_channel, c_value) in channels {
+            universe.set_channel(c_channel, c_value as u32);
+        }
+        true
+    }
+
+    /// Channels of the universe and their values representing an attribute given as a fraction.
+    /// Empty if the fixture has no such attribute.
+    pub fn channel_values(&self, attribute: Attribute, val: f32) -> Vec<(usize, u8)> {
+        let mode = self.get_mode();
+        match (mode.coarse_offset(attribute), mode.fine_offset(attribute)) {
+            (Some(coarse), Some(fine)) => {
+                let (c, f) = value::to_dmx16(val);
+                vec![(self.address + coarse, c), (self.address + fine, f)]
+            }
+            (Some(coarse), None) => vec![(self.address + coarse, value::to_dmx8(val))],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Whether pan and tilt run in the opposite direction, e.g. for fixtures hung upside down
+    pub fn get_inverted(&self) -> (bool, bool) { (self.invert_pan, self.invert_tilt) }
+
+    pub fn set_inverted(&mut self, pan: bool, tilt: bool) {
+        self.invert_pan = pan;
+        self.invert_tilt = tilt;
+    }
+
+    /// Current pan and tilt in degrees from the center position
+    pub fn get_position(&self, dmx: &DMX) -> Option<(f32, f32)> {
+        let range = self.fixture_type.position_range;
+        let pan = self.get_attribute(dmx, Attribute::Pan)?;
+        let tilt = self.get_attribute(dmx, Attribute::Tilt)?;
+        Some((
+            position::fraction_to_degrees(pan, range.pan, self.invert_pan),
+            position::fraction_to_degrees(tilt, range.tilt, self.invert_tilt),
+        ))
+    }
+
+    /// Attribute values for pan and tilt given in degrees from the center position,
+    /// clamped to the range of the fixture
+    pub fn position_values(&self, pan: f32, tilt: f32) -> Vec<(Attribute, f32)> {
+        let range = self.fixture_type.position_range;
+        let mut values = Vec::new();
+        if self.has_attribute(Attribute::Pan) {
+            values.push((Attribute::Pan, position::degrees_to_fraction(pan, range.pan, self.invert_pan)));
+        }
+        if self.has_attribute(Attribute::Tilt) {
+            values.push((Attribute::Tilt, position::degrees_to_fraction(tilt, range.tilt, self.invert_tilt)));
+        }
+        values
+    }
+
+    /// Move to pan and tilt given in degrees from the center position.
+    /// Returns false for fixtures without pan and tilt.
+    pub fn set_position(&self, dmx: &mut DMX, pan: f32, tilt: f32) -> bool {
+        let values = self.position_values(pan, tilt);
+        for (c_attr, c_value) in values.iter() {
+            self.set_attribute(dmx, *c_attr, *c_value);
+        }
+        !values.is_empty()
+    }
+
+    /// Approximate colour of the emitted light, ignoring intensity.
+    /// `None` for fixtures without colour attributes.
+    pub fn get_color(&self, dmx: &DMX) -> Option<Rgb> {
+        color::emitted(|a| self.get_attribute(dmx, a), &self.fixture_type.color_wheel)
+    }
+
+    /// Attribute values matching `rgb` as closely as the emitters allow
+    pub fn color_values(&self, rgb: Rgb) -> Vec<(Attribute, f32)> {
+        color::mix(rgb, self.get_mode(), &self.fixture_type.color_wheel)
+    }
+
+    /// Set the colour attributes to match `rgb` as closely as the emitters allow.
+    /// Returns false for fixtures without colour attributes.
+    pub fn set_color(&self, dmx: &mut DMX, rgb: Rgb) -> bool {
+        let values = self.color_values(rgb);
+        for (c_attr, c_value) in values.iter() {
+            self.set_attribute(dmx, *c_attr, *c_value);
+        }
+        !values.is_empty()
+    }
+}
+
+/// All fixtures patched into the universes
+#[derive(Debug, Clone, Default)]
+pub struct Patch {
+    fixtures: Vec<Fixture>,
+    next_id: FixtureID,
+}
+
+impl Patch {
+    pub fn new() -> Self {
+        Self {
+            fixtures: Vec::new(),
+            next_id: 1,
+        }
+    }
+
+    /// Patch a fixture with the next free id.
+    /// Fails if the mode does not exist or the channels overlap another fixture or the end of the universe.
+    pub fn add(&mut self, name: String, fixture_type: Arc<FixtureType>, mode: usize, universe: usize, address: usize) -> Result<FixtureID, ConmxErr> {
+        let id = self.next_id.max(1);
+        self.add_with_id(id, name, fixture_type, mode, universe, address)
+    }
+
+    pub fn add_with_id(&mut self, id: FixtureID, name: String, fixture_type: Arc<FixtureType>, mode: usize, universe: usize, address: usize) -> Result<FixtureID, ConmxErr> {
+        let footprint = fixture_type.get_mode(mode)
+            .ok_or_else(|| ConmxErr::Patch(format!("\"{}\" has no mode {}", fixture_type.name, mode)))?
+            .footprint();
+        if self.get(id).is_some() {
+            return Err(ConmxErr::Patch(format!("Fixture id {} is already in use", id)));
+        }
+        if address + footprint > DMXUniverse::CHANNELS {
+            return Err(ConmxErr::Patch(format!("\"{}\" does not fit into universe {} at address {}", name, universe, address + 1)));
+        }
+        let new_range = address..address + footprint;
+        if let Some(other) = self.fixtures.iter()
+            .find(|f| f.universe == universe && f.address < new_range.end && new_range.start < f.address + f.footprint())
+        {
+            return Err(ConmxErr::Patch(format!("\"{}\" overlaps \"{}\" at {}", name, other.name, other.address_name())));
+        }
+
+        self.fixtures.push(Fixture {
+            id,
+            name,
+            fixture_type,
+            mode,
+            universe,
+            address,
+            invert_pan: false,
+            invert_tilt: false,
+        });
+        self.fixtures.sort_by_key(|f| f.id);
+        self.next_id = self.next_id.max(id + 1);
+        Ok(id)
+    }
+
+    pub fn remove(&mut self, id: FixtureID) -> Option<Fixture> {
+        let i = self.fixtures.iter().position(|f| f.id == id)?;
+        Some(self.fixtures.remove(i))
+    }
+
+    pub fn get(&self, id: FixtureID) -> Option<&Fixture> {
+        self.fixtures.iter().find(|f| f.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: FixtureID) -> Option<&mut Fixture> {
+        self.fixtures.iter_mut().find(|f| f.id == id)
+    }
+
+    /// All fixtures ordered by id
+    pub fn fixtures(&self) -> &[Fixture] {
+        &self.fixtures
+    }
+
+    /// Fixtures of a universe ordered by address
+    pub fn fixtures_in(&self, universe: usize) -> Vec<&Fixture> {
+        let mut in_univ: Vec<&Fixture> = self.fixtures.iter()
+            .filter(|f| f.universe == universe)
+            .collect();
+        in_univ.sort_by_key(|f| f.address);
+        in_univ
+    }
+
+    /// Fixture occupying a channel of a universe
+    pub fn fixture_at(&self, universe: usize, channel: usize) -> Option<&Fixture> {
+        self.fixtures.iter()
+            .find(|f| f.universe == universe && f.channels().contains(&channel))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::library;
+
+    #[test]
+    fn test_patch() {
+        let mut patch = Patch::new();
+        let rgb = Arc::new(library::generic_rgb());
+
+        let a = patch.add("Par 1".to_owned(), rgb.clone(), 0, 0, 0).unwrap();
+        let b = patch.add("Par 2".to_owned(), rgb.clone(), 1, 0, 3).unwrap();
+        assert_eq!((a, b), (1, 2));
+        assert_eq!(patch.get(b).unwrap().channels(), 3..7);
+        assert_eq!(patch.get(b).unwrap().address_name(), "0.004");
+
+        // Overlapping another fixture or the end of the universe
+        assert!(patch.add("Par 3".to_owned(), rgb.clone(), 0, 0, 5).is_err());
+        assert!(patch.add("Par 3".to_owned(), rgb.clone(), 0, 0, 510).is_err());
+        assert!(patch.add("Par 3".to_owned(), rgb.clone(), 5, 0, 100).is_err());
+        // Same address in another universe is fine
+        assert!(patch.add("Par 3".to_owned(), rgb.clone(), 0, 1, 5).is_ok());
+
+        assert_eq!(patch.fixture_at(0, 6).map(|f| f.get_id()), Some(b));
+        assert!(patch.fixture_at(0, 7).is_none());
+        assert_eq!(patch.fixtures_in(0).len(), 2);
+
+        assert!(patch.remove(a).is_some());
+        assert!(patch.add("Par 4".to_owned(), rgb, 0, 0, 0).is_ok());
+    }
+
+    #[test]
+    fn test_attributes() {
+        let mut dmx = DMX::new();
+        dmx.add_universe(DMXUniverse::new(0));
+        let mut patch = Patch::new();
+        let head = patch.add("Spot".to_owned(), Arc::new(library::generic_moving_head()), 0, 0, 10).unwrap();
+        let par = patch.add("Par".to_owned(), Arc::new(library::generic_rgb()), 1, 0, 0).unwrap();
+        let (head, par) = (patch.get(head).unwrap(), patch.get(par).unwrap());
+
+        // 16 bit attributes are split into coarse and fine channel
+        assert!(head.set_attribute(&mut dmx, Attribute::Pan, 0.5));
+        let univ = dmx.get_universe_ref(0).unwrap();
+        assert_eq!(univ.get_channel(10).unwrap().get_val(), 128);
+        assert_eq!(univ.get_channel(11).unwrap().get_val(), 0);
+        assert!((head.get_attribute(&dmx, Attribute::Pan).unwrap() - 0.5).abs() < 1e-4);
+
+        assert!(!head.set_attribute(&mut dmx, Attribute::Red, 1.));
+        // Colour wheel at its first slot
+        assert_eq!(head.get_color(&dmx), Some(Rgb::WHITE));
+
+        par.set_attribute(&mut dmx, Attribute::Red, 1.);
+        par.set_attribute(&mut dmx, Attribute::Blue, 0.2);
+        let rgb = par.get_color(&dmx).unwrap();
+        assert_eq!(rgb.r, 1.);
+        assert_eq!(rgb.g, 0.);
+        assert!((rgb.b - 0.2).abs() < 0.01);
+
+        assert!(par.set_color(&mut dmx, Rgb::new(0., 1., 0.)));
+        assert_eq!(par.get_color(&dmx), Some(Rgb::new(0., 1., 0.)));
+        assert!(!par.set_position(&mut dmx, 0., 0.));
+    }
+
+    #[test]
+    fn test_position() {
+        let mut dmx = DMX::new();
+        dmx.add_universe(DMXUniverse::new(0));
+        let mut patch = Patch::new();
+        let head = Arc::new(library::generic_moving_head());
+        let a = patch.add("Spot 1".to_owned(), head.clone(), 0, 0, 0).unwrap();
+        let b = patch.add("Spot 2".to_owned(), head, 0, 0, 8).unwrap();
+        patch.get_mut(b).unwrap().set_inverted(true, false);
+        let (a, b) = (patch.get(a).unwrap(), patch.get(b).unwrap());
+
+        assert!(a.set_position(&mut dmx, 135., -135.));
+        assert!(b.set_position(&mut dmx, 135., 0.));
+        let univ = dmx.get_universe_ref(0).unwrap();
+        // Pan 135° of 540° is three quarters of the range, tilt -135° of 270° the lower end
+        assert_eq!(univ.get_channel(0).unwrap().get_val(), 191);
+        assert_eq!(univ.get_channel(2).unwrap().get_val(), 0);
+        // Inverted pan runs the other way
+        assert_eq!(univ.get_channel(8).unwrap().get_val(), 64);
+
+        let (pan, tilt) = b.get_position(&dmx).unwrap();
+        assert!((pan - 135.).abs() < 0.01 && tilt.abs() < 0.01);
+    }
+}
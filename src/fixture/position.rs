@@ -0,0 +1,56 @@
+//! Conversion between pan/tilt angles and attribute values
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+/// Full movement range of a fixture in degrees
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PositionRange {
+    pub pan: f32,
+    pub tilt: f32,
+}
+
+impl Default for PositionRange {
+    fn default() -> Self {
+        Self { pan: 540., tilt: 270. }
+    }
+}
+
+/// Attribute value for an angle in degrees, 0° being the center of `range`.
+/// Angles outside of the range are clamped.
+pub fn degrees_to_fraction(degrees: f32, range: f32, invert: bool) -> f32 {
+    if range <= 0. {
+        return 0.5;
+    }
+    let frac = (degrees / range + 0.5).max(0.).min(1.);
+    if invert { 1. - frac } else { frac }
+}
+
+pub fn fraction_to_degrees(frac: f32, range: f32, invert: bool) -> f32 {
+    let frac = if invert { 1. - frac } else { frac };
+    (frac - 0.5) * range
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::value;
+
+    #[test]
+    fn test_degrees() {
+        assert_eq!(degrees_to_fraction(0., 540., false), 0.5);
+        assert_eq!(degrees_to_fraction(270., 540., false), 1.);
+        assert_eq!(degrees_to_fraction(-270., 540., true), 1.);
+        assert_eq!(degrees_to_fraction(1000., 540., false), 1.);
+        assert_eq!(fraction_to_degrees(0.25, 540., false), -135.);
+        assert_eq!(fraction_to_degrees(0.25, 540., true), 135.);
+
+        // 16 bit resolution keeps fractions of a degree
+        let (c, f) = value::to_dmx16(degrees_to_fraction(10.01, 540., false));
+        let back = fraction_to_degrees(value::from_dmx16(c, f), 540., false);
+        assert!((back - 10.01).abs() < 0.01);
+    }
+}
@@ -0,0 +1,149 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use super::patch::FixtureID;
+
+/// How fixtures are combined with a selection, e.g. when recalling a group
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectionOp {
+    Replace,
+    Add,
+    Remove,
+    /// Keep only fixtures in both, in the order of the selection
+    Intersect,
+}
+
+/// Fixtures chosen by the user, in the order they were selected.
+/// The order is kept because effects and fans spread across it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(from = "Vec<FixtureID>", into = "Vec<FixtureID>")]
+pub struct Selection {
+    ids: Vec<FixtureID>,
+}
+
+impl Selection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn contains(&self, id: FixtureID) -> bool {
+        self.ids.contains(&id)
+    }
+
+    /// Append a fixture, keeping its position if it is already selected
+    pub fn add(&mut self, id: FixtureID) {
+        if !self.contains(id) {
+            self.ids.push(id);
+        }
+    }
+
+    pub fn remove(&mut self, id: FixtureID) {
+        self.ids.retain(|i| *i != id);
+    }
+
+    pub fn toggle(&mut self, id: FixtureID) {
+        if self.contains(id) {
+            self.remove(id);
+        } else {
+            self.add(id);
+        }
+    }
+
+    pub fn extend<I: IntoIterator<Item = FixtureID>>(&mut self, ids: I) {
+        for c_id in ids {
+            self.add(c_id);
+        }
+    }
+
+    /// Replace the selection
+    pub fn set<I: IntoIterator<Item = FixtureID>>(&mut self, ids: I) {
+        self.clear();
+        self.extend(ids);
+    }
+
+    pub fn clear(&mut self) {
+        self.ids.clear();
+    }
+
+    pub fn remove_all(&mut self, ids: &[FixtureID]) {
+        self.ids.retain(|i| !ids.contains(i));
+    }
+
+    pub fn intersect(&mut self, ids: &[FixtureID]) {
+        self.ids.retain(|i| ids.contains(i));
+    }
+
+    pub fn combine(&mut self, op: SelectionOp, ids: &[FixtureID]) {
+        match op {
+            SelectionOp::Replace => self.set(ids.iter().cloned()),
+            SelectionOp::Add => self.extend(ids.iter().cloned()),
+            SelectionOp::Remove => self.remove_all(ids),
+            SelectionOp::Intersect => self.intersect(ids),
+        }
+    }
+
+    pub fn ids(&self) -> &[FixtureID] {
+        &self.ids
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+}
+
+/// Duplicates are dropped, so a hand edited show file can not select a fixture twice
+impl From<Vec<FixtureID>> for Selection {
+    fn from(ids: Vec<FixtureID>) -> Self {
+        let mut selection = Self::new();
+        selection.extend(ids);
+        selection
+    }
+}
+
+impl From<Selection> for Vec<FixtureID> {
+    fn from(selection: Selection) -> Self {
+        selection.ids
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_selection_order() {
+        let mut sel = Selection::new();
+        sel.extend(vec![3, 1, 2, 1]);
+        assert_eq!(sel.ids(), &[3, 1, 2]);
+
+        sel.toggle(1);
+        sel.toggle(5);
+        assert_eq!(sel.ids(), &[3, 2, 5]);
+
+        sel.set(vec![7]);
+        assert_eq!(sel.ids(), &[7]);
+        assert!(sel.contains(7) && !sel.contains(3));
+    }
+
+    #[test]
+    fn test_combine() {
+        let mut sel = Selection::from(vec![4, 1, 2, 1]);
+        assert_eq!(sel.ids(), &[4, 1, 2]);
+
+        sel.combine(SelectionOp::Add, &[3, 4]);
+        assert_eq!(sel.ids(), &[4, 1, 2, 3]);
+        sel.combine(SelectionOp::Intersect, &[3, 2, 4, 9]);
+        assert_eq!(sel.ids(), &[4, 2, 3]);
+        sel.combine(SelectionOp::Remove, &[2]);
+        assert_eq!(sel.ids(), &[4, 3]);
+        sel.combine(SelectionOp::Replace, &[5, 6]);
+        assert_eq!(Vec::from(sel), vec![5, 6]);
+    }
+}
@@ -0,0 +1,39 @@
+//! Conversion between attribute values and DMX channel values.
+//! Attribute values are fractions between 0 and 1.
+
+/// Split a fraction into coarse and fine byte of a 16 bit value
+pub fn to_dmx16(value: f32) -> (u8, u8) {
+    let v = (value.max(0.).min(1.) * 65535.).round() as u16;
+    ((v >> 8) as u8, (v & 0xFF) as u8)
+}
+
+pub fn from_dmx16(coarse: u8, fine: u8) -> f32 {
+    (((coarse as u16) << 8) | fine as u16) as f32 / 65535.
+}
+
+pub fn to_dmx8(value: f32) -> u8 {
+    (value.max(0.).min(1.) * 255.).round() as u8
+}
+
+pub fn from_dmx8(value: u8) -> f32 {
+    value as f32 / 255.
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dmx16() {
+        assert_eq!(to_dmx16(0.), (0, 0));
+        assert_eq!(to_dmx16(1.), (255, 255));
+        assert_eq!(to_dmx16(2.), (255, 255));
+        assert_eq!(to_dmx16(0.5), (128, 0));
+
+        let (c, f) = to_dmx16(0.3);
+        assert!((from_dmx16(c, f) - 0.3).abs() < 1. / 65535.);
+        assert_eq!(to_dmx8(0.5), 128);
+        assert_eq!(from_dmx8(255), 1.);
+    }
+}
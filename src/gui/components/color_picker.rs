@@ -0,0 +1,228 @@
+use iced::{
+    mouse,
+    canvas,
+    Color,
+    Element,
+    Length,
+    Point,
+    Rectangle,
+    Size,
+};
+
+use crate::fixture::color::{
+    Hsv,
+    Rgb,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PickArea {
+    SatVal,
+    Hue,
+}
+
+/// Saturation/value square with a hue bar next to it.
+/// Emits the picked colour on every change while dragging.
+#[derive(Debug)]
+pub struct ColorPicker {
+    hsv: Hsv,
+    drag: Option<PickArea>,
+    cache: canvas::Cache,
+}
+
+fn to_color(rgb: Rgb) -> Color {
+    Color::from_rgb(rgb.r, rgb.g, rgb.b)
+}
+
+impl ColorPicker {
+    const HEIGHT: u16 = 180;
+    const HUE_WIDTH: f32 = 20.;
+    const GAP: f32 = 8.;
+    /// Cells per axis used to approximate the gradients
+    const SV_STEPS: usize = 24;
+    const HUE_STEPS: usize = 60;
+
+    pub fn new() -> Self {
+        Self {
+            hsv: Hsv::new(0., 0., 1.),
+            drag: None,
+            cache: canvas::Cache::new(),
+        }
+    }
+
+    pub fn get_hsv(&self) -> Hsv {
+        self.hsv
+    }
+
+    pub fn set_hsv(&mut self, hsv: Hsv) {
+        if hsv != self.hsv {
+            self.hsv = hsv;
+            self.cache.clear();
+        }
+    }
+
+    pub fn view<'a>(&'a mut self) -> Element<'a, Hsv> {
+        canvas::Canvas::new(self)
+            .width(Length::Fill)
+            .height(Length::Units(Self::HEIGHT))
+            .into()
+    }
+
+    fn sv_rect(size: Size) -> Rectangle {
+        Rectangle::new(Point::ORIGIN, Size::new((size.width - Self::HUE_WIDTH - Self::GAP).max(1.), size.height))
+    }
+
+    fn hue_rect(size: Size) -> Rectangle {
+        Rectangle::new(
+            Point::new(size.width - Self::HUE_WIDTH, 0.),
+            Size::new(Self::HUE_WIDTH, size.height),
+        )
+    }
+
+    fn area_at(p: Point, size: Size) -> Option<PickArea> {
+        if Self::sv_rect(size).contains(p) {
+            Some(PickArea::SatVal)
+        } else if Self::hue_rect(size).contains(p) {
+            Some(PickArea::Hue)
+        } else {
+            None
+        }
+    }
+
+    /// Colour at `p` when dragging in `area`, positions outside the area are clamped to it
+    fn pick(&self, area: PickArea, p: Point, size: Size) -> Hsv {
+        let frac = |v: f32, start: f32, len: f32| ((v - start) / len).max(0.).min(1.);
+        match area {
+            PickArea::SatVal => {
+                let r = Self::sv_rect(size);
+                Hsv::new(self.hsv.h, frac(p.x, r.x, r.width), 1. - frac(p.y, r.y, r.height))
+            }
+            PickArea::Hue => {
+                let r = Self::hue_rect(size);
+                Hsv::new(frac(p.y, r.y, r.height) * 360., self.hsv.s, self.hsv.v)
+            }
+        }
+    }
+
+    fn draw_gradients(&self, frame: &mut canvas::Frame) {
+        let size = frame.size();
+
+        let sv = Self::sv_rect(size);
+        let (cw, ch) = (sv.width / Self::SV_STEPS as f32, sv.height / Self::SV_STEPS as f32);
+        for x in 0..Self::SV_STEPS {
+            for y in 0..Self::SV_STEPS {
+                let s = (x as f32 + 0.5) / Self::SV_STEPS as f32;
+                let v = 1. - (y as f32 + 0.5) / Self::SV_STEPS as f32;
+                frame.fill_rectangle(
+                    Point::new(sv.x + x as f32 * cw, sv.y + y as f32 * ch),
+                    Size::new(cw + 0.5, ch + 0.5),
+                    to_color(Rgb::from_hsv(Hsv::new(self.hsv.h, s, v))),
+                );
+            }
+        }
+
+        let hue = Self::hue_rect(size);
+        let hh = hue.height / Self::HUE_STEPS as f32;
+        for i in 0..Self::HUE_STEPS {
+            let h = (i as f32 + 0.5) / Self::HUE_STEPS as f32 * 360.;
+            frame.fill_rectangle(
+                Point::new(hue.x, hue.y + i as f32 * hh),
+                Size::new(hue.width, hh + 0.5),
+                to_color(Rgb::from_hsv(Hsv::new(h, 1., 1.))),
+            );
+        }
+    }
+
+    fn draw_markers(&self, frame: &mut canvas::Frame) {
+        let size = frame.size();
+        let sv = Self::sv_rect(size);
+        let hue = Self::hue_rect(size);
+        let marker = canvas::Stroke::default()
+            .with_color(if self.hsv.v > 0.5 { Color::BLACK } else { Color::WHITE })
+            .with_width(2.);
+
+        frame.stroke(
+            &canvas::Path::circle(
+                Point::new(sv.x + self.hsv.s * sv.width, sv.y + (1. - self.hsv.v) * sv.height),
+                5.,
+            ),
+            marker.clone(),
+        );
+
+        let y = hue.y + self.hsv.h.rem_euclid(360.) / 360. * hue.height;
+        frame.stroke(
+            &canvas::Path::rectangle(Point::new(hue.x - 2., y - 2.), Size::new(hue.width + 4., 4.)),
+            marker.with_color(Color::WHITE),
+        );
+    }
+}
+
+impl canvas::Program<Hsv> for ColorPicker {
+    fn update(
+        &mut self,
+        event: canvas::Event,
+        bounds: Rectangle,
+        cursor: canvas::Cursor,
+    ) -> ( canvas::event::Status, Option<Hsv> ) {
+        let cursor_pos = match cursor.position_in(&bounds) {
+            Some(p) => p,
+            // Keep following the mouse outside while dragging
+            None => match (cursor.position(), self.drag) {
+                (Some(p), Some(_)) => Point::new(p.x - bounds.x, p.y - bounds.y),
+                _ => return (canvas::event::Status::Ignored, None),
+            },
+        };
+
+        let area = match event {
+            canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                self.drag = Self::area_at(cursor_pos, bounds.size());
+                self.drag
+            }
+            canvas::Event::Mouse(mouse::Event::CursorMoved{ .. }) => self.drag,
+            canvas::Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                self.drag = None;
+                None
+            }
+            _ => None,
+        };
+
+        match area {
+            Some(area) => {
+                let hsv = self.pick(area, cursor_pos, bounds.size());
+                self.set_hsv(hsv);
+                (canvas::event::Status::Captured, Some(hsv))
+            }
+            None => (canvas::event::Status::Ignored, None),
+        }
+    }
+
+    fn draw(&self, bounds: Rectangle, _cursor: canvas::Cursor) -> Vec<canvas::Geometry> {
+        let picker = self.cache.draw(bounds.size(), |frame| {
+            self.draw_gradients(frame);
+            self.draw_markers(frame);
+        });
+        vec![picker]
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pick() {
+        let mut picker = ColorPicker::new();
+        let size = Size::new(228., 100.);
+        picker.set_hsv(Hsv::new(120., 0.5, 0.5));
+
+        // Upper right corner of the square is full saturation and value
+        assert_eq!(ColorPicker::area_at(Point::new(199., 1.), size), Some(PickArea::SatVal));
+        assert_eq!(picker.pick(PickArea::SatVal, Point::new(200., 0.), size), Hsv::new(120., 1., 1.));
+        // Dragging out of the area clamps
+        assert_eq!(picker.pick(PickArea::SatVal, Point::new(-10., 500.), size), Hsv::new(120., 0., 0.));
+
+        assert_eq!(ColorPicker::area_at(Point::new(220., 50.), size), Some(PickArea::Hue));
+        assert_eq!(ColorPicker::area_at(Point::new(204., 50.), size), None);
+        assert_eq!(picker.pick(PickArea::Hue, Point::new(220., 50.), size), Hsv::new(180., 0.5, 0.5));
+    }
+}
@@ -0,0 +1,109 @@
+use iced::{
+    mouse,
+    canvas,
+    Color,
+    Element,
+    HorizontalAlignment,
+    Length,
+    Point,
+    Rectangle,
+    VerticalAlignment,
+};
+
+/// Button emitting `true` when pressed and `false` when released again,
+/// unlike `iced::Button` which only reports a click after releasing
+#[derive(Debug)]
+pub struct FlashButton {
+    label: String,
+    pressed: bool,
+}
+
+impl FlashButton {
+    const HEIGHT: u16 = 30;
+
+    pub fn new(label: &str) -> Self {
+        Self {
+            label: label.to_owned(),
+            pressed: false,
+        }
+    }
+
+    pub fn is_pressed(&self) -> bool {
+        self.pressed
+    }
+
+    pub fn view<'a>(&'a mut self) -> Element<'a, bool> {
+        canvas::Canvas::new(self)
+            .width(Length::Fill)
+            .height(Length::Units(Self::HEIGHT))
+            .into()
+    }
+
+    fn handle(&mut self, event: canvas::Event, over: bool) -> Option<bool> {
+        match event {
+            canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) if over => {
+                self.pressed = true;
+                Some(true)
+            }
+            // Released anywhere, so the flash does not get stuck when leaving the button
+            canvas::Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) if self.pressed => {
+                self.pressed = false;
+                Some(false)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl canvas::Program<bool> for FlashButton {
+    fn update(
+        &mut self,
+        event: canvas::Event,
+        bounds: Rectangle,
+        cursor: canvas::Cursor,
+    ) -> ( canvas::event::Status, Option<bool> ) {
+        match self.handle(event, cursor.is_over(&bounds)) {
+            Some(pressed) => (canvas::event::Status::Captured, Some(pressed)),
+            None => (canvas::event::Status::Ignored, None),
+        }
+    }
+
+    fn draw(&self, bounds: Rectangle, _cursor: canvas::Cursor) -> Vec<canvas::Geometry> {
+        let mut frame = canvas::Frame::new(bounds.size());
+        let (background, text) = if self.pressed {
+            (Color::from_rgb8(0x6F, 0xFF, 0xE9), Color::BLACK) // #6FFFE9
+        } else {
+            (Color::from_rgb8(0x40, 0x44, 0x4B), Color::WHITE) // #40444B
+        };
+        frame.fill_rectangle(Point::ORIGIN, bounds.size(), background);
+        frame.fill_text(canvas::Text {
+            content: self.label.clone(),
+            position: frame.center(),
+            color: text,
+            size: 16.,
+            horizontal_alignment: HorizontalAlignment::Center,
+            vertical_alignment: VerticalAlignment::Center,
+            ..canvas::Text::default()
+        });
+        vec![frame.into_geometry()]
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_press_release() {
+        let mut button = FlashButton::new("Flash");
+        let press = canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left));
+        let release = canvas::Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left));
+
+        assert_eq!(button.handle(press.clone(), false), None);
+        assert_eq!(button.handle(press, true), Some(true));
+        assert!(button.is_pressed());
+        assert_eq!(button.handle(release.clone(), false), Some(false));
+        assert_eq!(button.handle(release, true), None);
+    }
+}
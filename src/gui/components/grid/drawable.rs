@@ -6,4 +6,10 @@ use iced::{
 pub trait Drawable {
     fn draw(&self, frame: &mut canvas::Frame);
     fn get_bounding_box(&self) -> Rectangle;
+
+    /// Draw with knowledge of the current zoom level.
+    /// Needed for text, which is not affected by the scaling of the frame.
+    fn draw_scaled(&self, frame: &mut canvas::Frame, _scaling: f32) {
+        self.draw(frame);
+    }
 }
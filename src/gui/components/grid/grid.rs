@@ -2,6 +2,7 @@ use log::warn;
 
 use iced::{
     mouse,
+    keyboard,
     Length,
     canvas,
     Element,
@@ -16,33 +17,92 @@ use log::info;
 use super::{
     helpers,
     drawable::Drawable,
+    palette::Palette,
+    minimap::Minimap,
 };
 
 use crate::node::{
     self,
     Graph,
+    GraphNode,
+    Edge,
+    Wire,
+    NodeIndex,
+    EdgeIndex,
+    NodeRegistry,
+    NodeTemplate,
+    ParamWidget,
+    connections::{
+        Addr,
+        PortType,
+    },
 };
 
 
 pub enum Msg {
     PosChange(Point),
     ScaleChange(f32, Option<iced::Vector>),
+    ZoomToFit,
+    FrameSelection,
+}
+
+/// A connection currently being dragged from an output port
+#[derive(Debug, Clone, Copy)]
+struct WireDrag {
+    from: Addr,
+    to: Point,
+}
+
+/// An inline parameter widget currently being dragged
+#[derive(Debug, Clone, Copy)]
+struct ParamDrag {
+    param: Addr,
+    start: Point,
+    start_value: f32,
+    moved: bool,
+}
+
+/// Keyboard entry into an inline parameter widget
+#[derive(Debug, Clone)]
+struct ParamEdit {
+    param: Addr,
+    text: String,
+}
+
+/// Keyboard entry into the source of a script node
+#[derive(Debug, Clone)]
+struct ScriptEdit {
+    node: NodeIndex,
+    text: String,
 }
 
 #[derive(Debug, Default)]
 pub struct Grid {
     graph: Graph<node::Node>, 
+    registry: NodeRegistry,
+    palette: Option<Palette>,
 
     mouse_last_pos: Option<iced::Point>,
-    mouse_drag_node: bool,
+    mouse_drag_node: Option<NodeIndex>,
     mouse_drag_screen: bool,
+    mouse_drag_wire: Option<WireDrag>,
+    mouse_drag_param: Option<ParamDrag>,
+    /// Mapping of the minimap when the drag started, moving the view would change it otherwise
+    mouse_drag_minimap: Option<Minimap>,
+    param_edit: Option<ParamEdit>,
+    script_edit: Option<ScriptEdit>,
+    modifiers: keyboard::Modifiers,
+
+    selected: Vec<NodeIndex>,
+    viewport_size: Option<Size>,
 
     scale_sensivity: f32,
     translation: Vector,
     scaling: f32,
     grid_dist: f32,
 
-    graph_cache: canvas::Cache,
+    node_cache: canvas::Cache,
+    connection_cache: canvas::Cache,
     overlay_cache: canvas::Cache,
     background_cache: canvas::Cache,
 }
@@ -50,6 +110,13 @@ pub struct Grid {
 impl Grid {
     const MIN_SCALING: f32 = 1.;
     const MAX_SCALING: f32 = 100.;
+    const WIRE_HIT_TOLERANCE: f32 = 0.5;
+    /// Steps a number field changes per graph unit dragged
+    const NUMBER_DRAG_STEPS: f32 = 4.;
+    /// Space kept around the content when zooming to fit (in graph units)
+    const FIT_MARGIN: f32 = 2.;
+    /// Line height of the script editor (in graph units)
+    const SCRIPT_LINE_HEIGHT: f32 = 0.8;
 
     pub fn new() -> Self{
         Self {
@@ -66,6 +133,12 @@ impl Grid {
         }
     }
 
+    /// Start with `graph` instead of an empty graph, e.g. the one of the loaded show
+    pub fn with_graph(mut self, graph: Graph<node::Node>) -> Self {
+        self.graph = graph;
+        self
+    }
+
     pub fn view<'a>(&'a mut self) -> Element<'a, Msg> {
         canvas::Canvas::new(self)
             .width(Length::Fill)
@@ -106,18 +179,85 @@ impl Grid {
     }
 
     pub fn update(&mut self, msg: Msg) {
-                        if self.mouse_drag_node {
-
-
-                        }
         match msg {
             Msg::PosChange(_p) => {
             }
             Msg::ScaleChange(_new_scale, _transl) => {
             }
+            Msg::ZoomToFit => self.zoom_to_fit(),
+            Msg::FrameSelection => self.frame_selection(),
+        }
+    }
+
+    /// Area of the graph currently visible
+    fn visible_rect(&self, size: Size) -> iced::Rectangle {
+        let reg = self.calc_visible_region(size, self.grid_dist);
+        iced::Rectangle { x: reg.x, y: reg.y, width: reg.w, height: reg.h }
+    }
+
+    /// Adjust translation and scaling so `rect` fills the viewport
+    fn fit_rect(&mut self, rect: iced::Rectangle) {
+        let size = match self.viewport_size {
+            Some(size) if size.width > 0. && size.height > 0. => size,
+            _ => return,
+        };
+        let w = rect.width + 2. * Self::FIT_MARGIN;
+        let h = rect.height + 2. * Self::FIT_MARGIN;
+
+        self.scaling = (size.width / w).min(size.height / h)
+            .min(Self::MAX_SCALING)
+            .max(Self::MIN_SCALING);
+        self.translation = Vector::new(-rect.center_x(), -rect.center_y());
+        self.clear_caches();
+    }
+
+    /// Show the whole graph
+    pub fn zoom_to_fit(&mut self) {
+        if self.graph.get_nodes().is_empty() {
+            return;
+        }
+        self.fit_rect(self.graph.get_bounding_box());
+    }
+
+    /// Show all selected nodes, or the whole graph if nothing is selected
+    pub fn frame_selection(&mut self) {
+        match self.graph.get_bounding_box_of(&self.selected) {
+            Some(rect) => self.fit_rect(rect),
+            None => self.zoom_to_fit(),
         }
     }
 
+    /// The minimap, with the mapping frozen while it is dragged
+    fn minimap(&self, size: Size) -> Option<Minimap> {
+        if self.graph.get_nodes().is_empty() {
+            return None;
+        }
+        self.mouse_drag_minimap
+            .or_else(|| Some(Minimap::new(self.graph.get_bounding_box(), self.visible_rect(size), size)))
+    }
+
+    /// Center the view on the graph position below `cursor_pos` on `map`
+    fn jump_minimap(&mut self, map: Minimap, cursor_pos: Point) {
+        let target = map.to_world(cursor_pos);
+        self.translation = Vector::new(-target.x, -target.y);
+        self.clear_caches();
+    }
+
+    /// Redraw the live values after the graph was evaluated
+    pub fn tick(&mut self) {
+        self.node_cache.clear();
+    }
+
+    pub fn graph(&self) -> &Graph<node::Node> {
+        &self.graph
+    }
+
+    /// The node graph, evaluated by the engine every frame.
+    /// Changes are drawn with the next `tick`.
+    pub fn graph_mut(&mut self) -> &mut Graph<node::Node> {
+        &mut self.graph
+    }
+
     fn clear_caches(&mut self) {
         self.background_cache.clear();
         self.node_cache.clear();
@@ -125,6 +265,368 @@ impl Grid {
         self.overlay_cache.clear();
     }
 
+    /// Create a node from `template` at the screen position `screen_pos`
+    fn place_node(&mut self, template: &NodeTemplate, screen_pos: Point, size: Size) {
+        let pos = self.project_point(screen_pos, size);
+        match (template.build)(pos) {
+            Ok(node) => {
+                let id = self.graph.add_node(node);
+                info!("Added node \"{}\" ({}) at {:?}", template.name, id, pos);
+                self.node_cache.clear();
+            }
+            Err(e) => warn!("Node \"{}\" could not be constructed: {}", template.name, e),
+        }
+    }
+
+    /// Place the node currently selected in the palette and close it
+    fn place_selected(&mut self, size: Size) {
+        if let Some(palette) = self.palette.take() {
+            if let Some(template) = palette.get_selected(&self.registry).cloned() {
+                self.place_node(&template, palette.get_pos(), size);
+            }
+        }
+    }
+
+    fn handle_palette_click(&mut self, cursor_pos: Point, size: Size) {
+        if let Some(palette) = self.palette.take() {
+            if let Some(template) = palette.entry_at(cursor_pos, &self.registry).cloned() {
+                self.place_node(&template, palette.get_pos(), size);
+            }
+        }
+    }
+
+    fn handle_key(&mut self, event: keyboard::Event, cursor_pos: Point, size: Size) -> canvas::event::Status {
+        use keyboard::KeyCode;
+
+        let palette = match self.palette.as_mut() {
+            Some(palette) => palette,
+            None => return match event {
+                keyboard::Event::KeyPressed{ key_code: KeyCode::Tab, modifiers } if !modifiers.control => {
+                    self.palette = Some(Palette::new(cursor_pos));
+                    canvas::event::Status::Captured
+                }
+                keyboard::Event::KeyPressed{ key_code: KeyCode::Home, .. } => {
+                    self.zoom_to_fit();
+                    canvas::event::Status::Captured
+                }
+                keyboard::Event::KeyPressed{ key_code: KeyCode::F, .. } => {
+                    self.frame_selection();
+                    canvas::event::Status::Captured
+                }
+                _ => canvas::event::Status::Ignored,
+            },
+        };
+
+        match event {
+            keyboard::Event::KeyPressed{ key_code, .. } => match key_code {
+                KeyCode::Escape|KeyCode::Tab => self.palette = None,
+                KeyCode::Up => palette.select_prev(),
+                KeyCode::Down => palette.select_next(&self.registry),
+                KeyCode::Backspace => palette.pop_char(),
+                KeyCode::Enter|KeyCode::NumpadEnter => self.place_selected(size),
+                _ => (),
+            },
+            keyboard::Event::CharacterReceived(c) if !c.is_control() => palette.push_char(c),
+            _ => (),
+        }
+        canvas::event::Status::Captured
+    }
+
+    /// Start dragging whatever is located at `pos` (in graph coordinates).
+    /// Ports start a new wire, connected inputs and wires get detached and dragged from their output,
+    /// nodes get moved.
+    fn start_left_drag(&mut self, pos: Point) {
+        let hit_port = self.graph.get_nodes()
+            .into_iter()
+            .find_map(|(id, n)| n.port_at(pos).map(|p| (id, p)));
+
+        match hit_port {
+            Some((id, PortType::Output(port))) => {
+                self.mouse_drag_wire = Some(WireDrag { from: Addr { node: id, port }, to: pos });
+                return;
+            }
+            Some((id, PortType::Input(port))) => {
+                if let Some(e_id) = self.graph.edges_into(Addr { node: id, port }).pop() {
+                    self.detach_edge(e_id, pos);
+                }
+                return;
+            }
+            None => (),
+        }
+
+        let hit_param = self.graph.get_nodes()
+            .into_iter()
+            .find_map(|(id, n)| n.param_at(pos).map(|port| (Addr { node: id, port }, n)));
+        if let Some((param, n)) = hit_param {
+            let start_value = n.get_input(param.port)
+                .map(|i| i.get_port().as_f32())
+                .unwrap_or(0.);
+            self.mouse_drag_param = Some(ParamDrag { param, start: pos, start_value, moved: false });
+            return;
+        }
+
+        let hit_script = self.graph.get_nodes()
+            .into_iter()
+            .find(|(_, n)| n.script_rect().map(|r| r.contains(pos)).unwrap_or(false))
+            .and_then(|(id, n)| Some(ScriptEdit { node: id, text: n.get_script()?.get_source().to_owned() }));
+        if let Some(edit) = hit_script {
+            self.script_edit = Some(edit);
+            return;
+        }
+
+        let hit_edge = self.graph.get_edges()
+            .into_iter()
+            .find(|(_, e)| self.graph.get_wire(e)
+                .map(|w| w.hit(pos, Self::WIRE_HIT_TOLERANCE))
+                .unwrap_or(false))
+            .map(|(id, _)| id);
+        if let Some(e_id) = hit_edge {
+            self.detach_edge(e_id, pos);
+            return;
+        }
+
+        self.mouse_drag_node = self.graph.get_nodes()
+            .into_iter()
+            .rev()
+            .find(|(_, n)| n.get_bounding_box().contains(pos))
+            .map(|(id, _)| id);
+        self.select(self.mouse_drag_node);
+    }
+
+    /// Select the clicked node. With shift held the selection is extended instead.
+    fn select(&mut self, clicked: Option<NodeIndex>) {
+        match (clicked, self.modifiers.shift) {
+            (Some(id), true) => {
+                if let Some(i) = self.selected.iter().position(|s| *s == id) {
+                    self.selected.remove(i);
+                } else {
+                    self.selected.push(id);
+                }
+            }
+            (Some(id), false) => {
+                if !self.selected.contains(&id) {
+                    self.selected = vec![id];
+                }
+            }
+            (None, true) => (),
+            (None, false) => self.selected.clear(),
+        }
+        self.node_cache.clear();
+    }
+
+    fn draw_selection(&self, frame: &mut canvas::Frame) {
+        let stroke_s = canvas::Stroke::default()
+            .with_color(Color::from_rgb8(0x6F, 0xFF, 0xE9)) // #6FFFE9
+            .with_width(0.15);
+        for bb in self.selected.iter().filter_map(|id| self.graph.get_node(*id)).map(|n| n.get_bounding_box()) {
+            let path = canvas::Path::rectangle(
+                Point::new(bb.x - 0.3, bb.y - 0.3),
+                Size::new(bb.width + 0.6, bb.height + 0.6),
+            );
+            frame.stroke(&path, stroke_s.clone());
+        }
+    }
+
+    /// Update the value of the dragged parameter for the cursor at `pos`.
+    /// Sliders follow the cursor, number fields change relative to the drag distance.
+    fn drag_param(&mut self, pos: Point) {
+        let drag = match self.mouse_drag_param.as_mut() {
+            Some(drag) => drag,
+            None => return,
+        };
+        let node = match self.graph.get_node_mut(drag.param.node) {
+            Some(node) => node,
+            None => return,
+        };
+        let (range, rect) = match (node.get_input(drag.param.port).and_then(|i| i.get_range()), node.param_rect(drag.param.port)) {
+            (Some(range), Some(rect)) => (range, rect),
+            _ => return,
+        };
+
+        if pos != drag.start {
+            drag.moved = true;
+        }
+        let value = match range.widget() {
+            ParamWidget::Slider if drag.moved => {
+                range.min + (pos.x - rect.x) / rect.width * (range.max - range.min)
+            }
+            ParamWidget::Number if drag.moved => {
+                drag.start_value + (pos.x - drag.start.x) * Self::NUMBER_DRAG_STEPS * range.step
+            }
+            _ => return,
+        };
+        node.set_param(drag.param.port, value);
+        self.node_cache.clear();
+    }
+
+    /// Finish dragging a parameter. A click without moving toggles a switch or starts keyboard entry.
+    fn finish_param_drag(&mut self, drag: ParamDrag) {
+        if drag.moved {
+            return;
+        }
+        let node = match self.graph.get_node_mut(drag.param.node) {
+            Some(node) => node,
+            None => return,
+        };
+        match node.get_input(drag.param.port).and_then(|i| i.get_range()) {
+            Some(range) if range.widget() == ParamWidget::Toggle => {
+                let toggled = if drag.start_value > range.min { range.min } else { range.max };
+                node.set_param(drag.param.port, toggled);
+                self.node_cache.clear();
+            }
+            Some(_) => {
+                self.param_edit = Some(ParamEdit { param: drag.param, text: String::new() });
+            }
+            None => (),
+        }
+    }
+
+    /// Handle keys while a parameter is edited with the keyboard
+    fn handle_param_key(&mut self, event: keyboard::Event) -> canvas::event::Status {
+        use keyboard::KeyCode;
+
+        let edit = match self.param_edit.as_mut() {
+            Some(edit) => edit,
+            None => return canvas::event::Status::Ignored,
+        };
+
+        match event {
+            keyboard::Event::KeyPressed{ key_code, .. } => match key_code {
+                KeyCode::Escape => self.param_edit = None,
+                KeyCode::Backspace => { edit.text.pop(); },
+                KeyCode::Enter|KeyCode::NumpadEnter => {
+                    let param = edit.param;
+                    match edit.text.trim().parse::<f32>() {
+                        Ok(v) => {
+                            if let Some(n) = self.graph.get_node_mut(param.node) {
+                                n.set_param(param.port, v);
+                            }
+                            self.node_cache.clear();
+                        }
+                        Err(e) => warn!("\"{}\" is not a valid value: {}", edit.text, e),
+                    }
+                    self.param_edit = None;
+                }
+                _ => (),
+            },
+            keyboard::Event::CharacterReceived(c) if c.is_ascii_digit() || c == '.' || c == '-' => edit.text.push(c),
+            _ => (),
+        }
+        canvas::event::Status::Captured
+    }
+
+    /// Handle keys while the source of a script node is edited.
+    /// Enter starts a new line, Ctrl+Enter compiles the script and Escape discards the changes.
+    fn handle_script_key(&mut self, event: keyboard::Event) -> canvas::event::Status {
+        use keyboard::KeyCode;
+
+        let edit = match self.script_edit.as_mut() {
+            Some(edit) => edit,
+            None => return canvas::event::Status::Ignored,
+        };
+
+        match event {
+            keyboard::Event::KeyPressed{ key_code, modifiers } => match key_code {
+                KeyCode::Escape => self.script_edit = None,
+                KeyCode::Backspace => { edit.text.pop(); },
+                KeyCode::Enter|KeyCode::NumpadEnter if modifiers.control => {
+                    if let Some(edit) = self.script_edit.take() {
+                        let title = self.graph.get_node(edit.node).map(|n| n.get_title().to_owned()).unwrap_or_default();
+                        if let Err(e) = self.graph.set_script_source(edit.node, edit.text) {
+                            warn!("Script of \"{}\" does not compile: {}", title, e);
+                        }
+                        // Declared ports may have moved the wires
+                        self.node_cache.clear();
+                        self.connection_cache.clear();
+                    }
+                }
+                KeyCode::Enter|KeyCode::NumpadEnter => edit.text.push('\n'),
+                KeyCode::Tab => edit.text.push_str("    "),
+                _ => (),
+            },
+            keyboard::Event::CharacterReceived(c) if !c.is_control() => edit.text.push(c),
+            _ => (),
+        }
+        canvas::event::Status::Captured
+    }
+
+    /// Draw the source of the edited script below its node
+    fn draw_script_edit(&self, frame: &mut canvas::Frame, edit: &ScriptEdit) {
+        let footer = match self.graph.get_node(edit.node).and_then(|n| n.script_rect()) {
+            Some(footer) => footer,
+            None => return,
+        };
+        let source = format!("{}_", edit.text);
+        let lines: Vec<&str> = source.lines().chain(std::iter::once("Ctrl+Enter: apply, Esc: discard")).collect();
+        let rect = iced::Rectangle {
+            x: footer.x,
+            y: footer.y + footer.height + 0.3,
+            width: footer.width * 2.,
+            height: lines.len() as f32 * Self::SCRIPT_LINE_HEIGHT + 0.4,
+        };
+
+        frame.fill_rectangle(rect.position(), rect.size(), Color::from_rgb8(0x40, 0x44, 0x4B)); // #40444B
+        frame.stroke(
+            &canvas::Path::rectangle(rect.position(), rect.size()),
+            canvas::Stroke::default()
+                .with_color(Color::from_rgb8(0x6F, 0xFF, 0xE9)) // #6FFFE9
+                .with_width(0.08),
+        );
+        let hint = lines.len() - 1;
+        for (i, c_line) in lines.into_iter().enumerate() {
+            frame.fill_text(canvas::Text {
+                content: c_line.to_owned(),
+                position: Point::new(rect.x + 0.2, rect.y + 0.2 + (i as f32 + 0.5) * Self::SCRIPT_LINE_HEIGHT),
+                color: if i == hint { Color::from_rgb8(0xA0, 0xA0, 0xA0) } else { Color::WHITE }, // #A0A0A0
+                size: 0.5 * self.scaling,
+                vertical_alignment: iced::VerticalAlignment::Center,
+                ..canvas::Text::default()
+            });
+        }
+    }
+
+    /// Remove an edge from the graph and continue dragging it from its output
+    fn detach_edge(&mut self, e_id: EdgeIndex, pos: Point) {
+        if let Some(e) = self.graph.delete_edge(e_id) {
+            info!("Detached edge {:?}", e);
+            self.mouse_drag_wire = Some(WireDrag { from: e.get_start(), to: pos });
+            self.connection_cache.clear();
+        }
+    }
+
+    /// Finish a wire drag, connecting it if dropped onto a compatible input
+    fn finish_wire_drag(&mut self, drag: WireDrag) {
+        let target = self.graph.get_nodes()
+            .into_iter()
+            .find_map(|(id, n)| match n.port_at(drag.to) {
+                Some(PortType::Input(port)) => Some(Addr { node: id, port }),
+                _ => None,
+            });
+
+        let to = match target {
+            Some(to) => to,
+            None => return,
+        };
+
+        let compatible = match (self.graph.get_node(drag.from.node), self.graph.get_node(to.node)) {
+            (Some(from_node), Some(to_node)) => from_node.can_connect(drag.from.port, to_node, to.port),
+            _ => false,
+        };
+        if !compatible || drag.from.node == to.node {
+            info!("Ports {:?} and {:?} can not be connected", drag.from, to);
+            return;
+        }
+
+        // Inputs only take a single connection
+        for e_id in self.graph.edges_into(to) {
+            self.graph.delete_edge(e_id);
+        }
+        if let Err(e) = self.graph.add_edge(Edge::between(drag.from, to)) {
+            warn!("Could not connect {:?} to {:?}: {}", drag.from, to, e);
+        }
+        self.connection_cache.clear();
+    }
+
     fn draw_bg(&self, frame: &mut canvas::Frame, color: Color) {
         let bg = canvas::Path::rectangle(iced::Point::ORIGIN, frame.size());
 
@@ -187,6 +689,7 @@ impl<'a> canvas::Program<Msg> for Grid {
                 return (canvas::event::Status::Ignored, None);
             };
         //info!("Event: {:?}, bounds: {:?}, cursor: {:?}", event, bounds, cursor);
+        self.viewport_size = Some(bounds.size());
 
         match event {
             canvas::Event::Mouse(mouse_event) => match mouse_event {
@@ -196,8 +699,26 @@ impl<'a> canvas::Program<Msg> for Grid {
                             self.mouse_drag_screen = true;
                             (canvas::event::Status::Captured, None)
                         }
+                        mouse::Button::Left if self.palette.is_some() => {
+                            self.handle_palette_click(cursor_pos, bounds.size());
+                            (canvas::event::Status::Captured, None)
+                        }
+                        mouse::Button::Left if self.minimap(bounds.size()).map(|m| m.contains(cursor_pos)).unwrap_or(false) => {
+                            if let Some(map) = self.minimap(bounds.size()) {
+                                self.mouse_drag_minimap = Some(map);
+                                self.jump_minimap(map, cursor_pos);
+                            }
+                            (canvas::event::Status::Captured, None)
+                        }
                         mouse::Button::Left => {
-                            self.mouse_drag_node = true;
+                            let pos = self.project_point(cursor_pos, bounds.size());
+                            self.param_edit = None;
+                            self.script_edit = None;
+                            self.start_left_drag(pos);
+                            (canvas::event::Status::Captured, None)
+                        }
+                        mouse::Button::Right => {
+                            self.palette = Some(Palette::new(cursor_pos));
                             (canvas::event::Status::Captured, None)
                         }
                         _ => (canvas::event::Status::Ignored, None)
@@ -211,7 +732,16 @@ impl<'a> canvas::Program<Msg> for Grid {
                             (canvas::event::Status::Captured, None)
                         }
                         mouse::Button::Left => {
-                            self.mouse_drag_node = false;
+                            self.mouse_drag_node = None;
+                            if self.mouse_drag_minimap.take().is_some() {
+                                self.clear_caches();
+                            }
+                            if let Some(drag) = self.mouse_drag_wire.take() {
+                                self.finish_wire_drag(drag);
+                            }
+                            if let Some(drag) = self.mouse_drag_param.take() {
+                                self.finish_param_drag(drag);
+                            }
                             (canvas::event::Status::Captured, None)
                         }
                         _ => (canvas::event::Status::Ignored, None)
@@ -224,10 +754,26 @@ impl<'a> canvas::Program<Msg> for Grid {
                             self.translation = self.translation + self.project_vec(pos_delta);
                             self.clear_caches();
                         }
-                        if self.mouse_drag_node {
-
+                        if let Some(id) = self.mouse_drag_node {
+                            let transl = self.project_vec(cursor_pos - last_pos);
+                            if let Some(n) = self.graph.get_node_mut(id) {
+                                n.translate(transl);
+                            }
+                            self.node_cache.clear();
+                            self.connection_cache.clear();
                         }
                     }
+                    let pos = self.project_point(cursor_pos, bounds.size());
+                    if let Some(drag) = self.mouse_drag_wire.as_mut() {
+                        drag.to = pos;
+                    }
+                    if let Some(map) = self.mouse_drag_minimap {
+                        self.jump_minimap(map, cursor_pos);
+                    }
+                    if self.mouse_drag_param.is_some() {
+                        let pos = self.project_point(cursor_pos, bounds.size());
+                        self.drag_param(pos);
+                    }
                     self.mouse_last_pos = Some(cursor_pos);
                     (canvas::event::Status::Ignored, None)
                 },
@@ -261,7 +807,19 @@ impl<'a> canvas::Program<Msg> for Grid {
                 },
                 _ => (canvas::event::Status::Ignored, None)
             }
-            _ => (canvas::event::Status::Ignored, None)
+            canvas::Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
+                self.modifiers = modifiers;
+                (canvas::event::Status::Ignored, None)
+            }
+            canvas::Event::Keyboard(key_event) if self.param_edit.is_some() => {
+                (self.handle_param_key(key_event), None)
+            }
+            canvas::Event::Keyboard(key_event) if self.script_edit.is_some() => {
+                (self.handle_script_key(key_event), None)
+            }
+            canvas::Event::Keyboard(key_event) => {
+                (self.handle_key(key_event, cursor_pos, bounds.size()), None)
+            }
         }
     }
 
@@ -286,35 +844,33 @@ impl<'a> canvas::Program<Msg> for Grid {
         });
 
         let nodes = self.node_cache.draw(bounds.size(), |frame| {
-            use crate::node::{
-                Node,
-                Port,
-            };
             self.translate_viewport(frame, &bounds);
 
-            self.graph.draw(frame);
-
-            let node = Node::new()
-                .with_in("Input".to_owned(), Port::Float(0.))
-                .with_in("IN 2".to_owned(), Port::Unsigned8(0))
-                .with_in("IN 3".to_owned(), Port::Integer(0))
-                .with_in("IN 4".to_owned(), Port::Array(vec![]))
-                .with_out("Output".to_owned(), Port::Float(0.))
-                //.with_driver()
-                .build();
-
-            match node {
-                Ok(node) => node.draw(frame),
-                Err(e) => warn!("Example node could not be constructed: {}", e),
-            }
+            self.graph.draw_nodes(frame, self.scaling);
+            self.draw_selection(frame);
         });
 
         let connections = self.connection_cache.draw(bounds.size(), |frame| {
+            self.translate_viewport(frame, &bounds);
+
+            self.graph.draw_edges(frame);
         });
 
         let overlay = {
             let mut frame = canvas::Frame::new(bounds.size());
 
+            if let Some(drag) = self.mouse_drag_wire {
+                let from = self.graph.get_node(drag.from.node)
+                    .and_then(|n| n.output_anchor(drag.from.port));
+                if let Some(from) = from {
+                    frame.with_save(|frame| {
+                        self.translate_viewport(frame, &bounds);
+                        Wire::new(from, drag.to)
+                            .draw_with_color(frame, Color::from_rgb8(0x6F, 0xFF, 0xE9)); // #6FFFE9
+                    });
+                }
+            }
+
             let text = canvas::Text {
                 color: Color::WHITE,
                 size: 20.,
@@ -324,8 +880,52 @@ impl<'a> canvas::Program<Msg> for Grid {
                 ..canvas::Text::default()
             };
 
+            let edit_rect = self.param_edit.as_ref().and_then(|edit| Some((
+                edit,
+                self.graph.get_node(edit.param.node)?.param_rect(edit.param.port)?,
+            )));
+            if let Some((edit, rect)) = edit_rect {
+                frame.with_save(|frame| {
+                    self.translate_viewport(frame, &bounds);
+                    frame.fill_rectangle(rect.position(), rect.size(), Color::from_rgb8(0x40, 0x44, 0x4B)); // #40444B
+                    frame.stroke(
+                        &canvas::Path::rectangle(rect.position(), rect.size()),
+                        canvas::Stroke::default()
+                            .with_color(Color::from_rgb8(0x6F, 0xFF, 0xE9)) // #6FFFE9
+                            .with_width(0.08),
+                    );
+                    frame.fill_text(canvas::Text {
+                        content: format!("{}_", edit.text),
+                        position: Point::new(rect.x + 0.15, rect.center_y()),
+                        color: Color::WHITE,
+                        size: rect.height * self.scaling,
+                        vertical_alignment: iced::VerticalAlignment::Center,
+                        ..canvas::Text::default()
+                    });
+                });
+            }
+
+            if let Some(edit) = &self.script_edit {
+                frame.with_save(|frame| {
+                    self.translate_viewport(frame, &bounds);
+                    self.draw_script_edit(frame, edit);
+                });
+            }
+
+            if let Some(map) = self.minimap(bounds.size()) {
+                let nodes: Vec<iced::Rectangle> = self.graph.get_nodes()
+                    .into_iter()
+                    .map(|(_, n)| n.get_bounding_box())
+                    .collect();
+                map.draw(&mut frame, &nodes, self.visible_rect(bounds.size()));
+            }
+
+            if let Some(palette) = &self.palette {
+                palette.draw(&mut frame, &self.registry);
+            }
+
             frame.fill_text(canvas::Text {
-                content: format!("drag_node: {:6} drag_screen: {:6} translation: {:2.3?}",
+                content: format!("drag_node: {:6?} drag_screen: {:6} translation: {:2.3?}",
                     self.mouse_drag_node,
                     self.mouse_drag_screen,
                     self.translation,
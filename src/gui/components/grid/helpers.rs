@@ -33,6 +33,37 @@ impl Region {
     }
 }
 
+/// Smallest rectangle containing both `a` and `b`
+pub fn union_rect(a: Rectangle, b: Rectangle) -> Rectangle {
+    let x = a.x.min(b.x);
+    let y = a.y.min(b.y);
+    Rectangle {
+        x,
+        y,
+        width: (a.x + a.width).max(b.x + b.width) - x,
+        height: (a.y + a.height).max(b.y + b.height) - y,
+    }
+}
+
+/// Whether `p` lies inside the closed polygon through `points` (even-odd rule)
+pub fn point_in_polygon(p: Point, points: &[Point]) -> bool {
+    if points.len() < 3 {
+        return false;
+    }
+    let mut inside = false;
+    let mut j = points.len() - 1;
+    for i in 0..points.len() {
+        let (a, b) = (points[i], points[j]);
+        if (a.y > p.y) != (b.y > p.y)
+            && p.x < (b.x - a.x) * (p.y - a.y) / (b.y - a.y) + a.x
+        {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
 pub fn rounded_rect_path(dim: Rectangle, radius: f32) -> canvas::Path {
     let mut path_builder = canvas::path::Builder::new();
 
@@ -72,3 +103,34 @@ pub fn rounded_rect_path(dim: Rectangle, radius: f32) -> canvas::Path {
 
     path_builder.build()
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_point_in_polygon() {
+        let square = [
+            Point::new(0., 0.),
+            Point::new(2., 0.),
+            Point::new(2., 2.),
+            Point::new(0., 2.),
+        ];
+        assert!(point_in_polygon(Point::new(1., 1.), &square));
+        assert!(!point_in_polygon(Point::new(3., 1.), &square));
+
+        // Concave lasso shape
+        let l_shape = [
+            Point::new(0., 0.),
+            Point::new(1., 0.),
+            Point::new(1., 1.),
+            Point::new(2., 1.),
+            Point::new(2., 2.),
+            Point::new(0., 2.),
+        ];
+        assert!(point_in_polygon(Point::new(0.5, 0.5), &l_shape));
+        assert!(!point_in_polygon(Point::new(1.5, 0.5), &l_shape));
+        assert!(!point_in_polygon(Point::new(1., 1.), &square[..2]));
+    }
+}
@@ -0,0 +1,135 @@
+use iced::{
+    canvas,
+    Color,
+    Point,
+    Rectangle,
+    Size,
+};
+
+use super::helpers;
+
+/// Overview of the whole graph drawn in a corner of the grid.
+/// Maps between graph coordinates (`world`) and its area on the screen (`screen`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Minimap {
+    world: Rectangle,
+    screen: Rectangle,
+    scale: f32,
+}
+
+impl Minimap {
+    pub const SIZE: Size = Size { width: 200., height: 140. };
+    const MARGIN: f32 = 10.;
+    const WORLD_PADDING: f32 = 2.;
+
+    /// Create a minimap in the lower right corner of `bounds` showing `content` and the visible `viewport`
+    pub fn new(content: Rectangle, viewport: Rectangle, bounds: Size) -> Self {
+        let world = helpers::union_rect(content, viewport);
+        let world = Rectangle {
+            x: world.x - Self::WORLD_PADDING,
+            y: world.y - Self::WORLD_PADDING,
+            width: world.width + 2. * Self::WORLD_PADDING,
+            height: world.height + 2. * Self::WORLD_PADDING,
+        };
+        let screen = Rectangle::new(
+            Point::new(
+                bounds.width - Self::SIZE.width - Self::MARGIN,
+                bounds.height - Self::SIZE.height - Self::MARGIN,
+            ),
+            Self::SIZE,
+        );
+        let scale = (screen.width / world.width).min(screen.height / world.height);
+
+        Self { world, screen, scale }
+    }
+
+    pub fn contains(&self, p: Point) -> bool {
+        self.screen.contains(p)
+    }
+
+    /// Offset centering the scaled world inside the minimap area
+    fn inset(&self) -> (f32, f32) {
+        (
+            (self.screen.width - self.world.width * self.scale) / 2.,
+            (self.screen.height - self.world.height * self.scale) / 2.,
+        )
+    }
+
+    /// Project a point in graph coordinates onto the minimap
+    pub fn to_screen(&self, p: Point) -> Point {
+        let (ix, iy) = self.inset();
+        Point::new(
+            self.screen.x + ix + (p.x - self.world.x) * self.scale,
+            self.screen.y + iy + (p.y - self.world.y) * self.scale,
+        )
+    }
+
+    /// Get the graph coordinates of a point on the minimap
+    pub fn to_world(&self, p: Point) -> Point {
+        let (ix, iy) = self.inset();
+        Point::new(
+            (p.x - self.screen.x - ix) / self.scale + self.world.x,
+            (p.y - self.screen.y - iy) / self.scale + self.world.y,
+        )
+    }
+
+    fn rect_to_screen(&self, r: Rectangle) -> Rectangle {
+        Rectangle::new(
+            self.to_screen(r.position()),
+            Size::new(r.width * self.scale, r.height * self.scale),
+        )
+    }
+
+    pub fn draw(&self, frame: &mut canvas::Frame, nodes: &[Rectangle], viewport: Rectangle) {
+        frame.fill_rectangle(
+            self.screen.position(),
+            self.screen.size(),
+            Color { a: 0.8, ..Color::from_rgb8(0x11, 0x11, 0x11) }, // #111111
+        );
+
+        for c_node in nodes {
+            let r = self.rect_to_screen(*c_node);
+            frame.fill_rectangle(r.position(), r.size(), Color::from_rgb8(0x00, 0x80, 0x00)); // #008000
+        }
+
+        let view = self.rect_to_screen(viewport);
+        frame.stroke(
+            &canvas::Path::rectangle(view.position(), view.size()),
+            canvas::Stroke::default()
+                .with_color(Color::from_rgb8(0x6F, 0xFF, 0xE9)) // #6FFFE9
+                .with_width(1.),
+        );
+        frame.stroke(
+            &canvas::Path::rectangle(self.screen.position(), self.screen.size()),
+            canvas::Stroke::default()
+                .with_color(Color::from_rgb8(0x40, 0x44, 0x4B)) // #40444B
+                .with_width(1.),
+        );
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_projection() {
+        let content = Rectangle { x: -50., y: -10., width: 100., height: 20. };
+        let viewport = Rectangle { x: -10., y: -10., width: 20., height: 20. };
+        let map = Minimap::new(content, viewport, Size::new(800., 600.));
+
+        // Minimap sits in the lower right corner
+        assert!(map.contains(Point::new(700., 500.)));
+        assert!(!map.contains(Point::new(100., 100.)));
+
+        // Content is projected into the minimap area
+        let tl = map.to_screen(Point::new(-50., -10.));
+        let br = map.to_screen(Point::new(50., 10.));
+        assert!(map.contains(tl) && map.contains(br));
+
+        let p = Point::new(12.5, -3.);
+        let back = map.to_world(map.to_screen(p));
+        assert!(back.distance(p) < 1e-3);
+    }
+}
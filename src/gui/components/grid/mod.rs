@@ -3,6 +3,12 @@ pub use drawable::*;
 
 pub mod helpers;
 
+mod palette;
+pub use palette::*;
+
+mod minimap;
+pub use minimap::*;
+
 mod grid;
 pub use grid::*;
 
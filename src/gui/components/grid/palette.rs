@@ -0,0 +1,151 @@
+use iced::{
+    canvas,
+    Color,
+    Point,
+    Rectangle,
+    Size,
+    Vector,
+};
+
+use super::helpers;
+
+use crate::node::{
+    NodeRegistry,
+    NodeTemplate,
+};
+
+/// Searchable popup listing all registered node types.
+/// It is positioned in screen coordinates, the created node is placed at the projection of `pos`.
+#[derive(Debug, Clone)]
+pub struct Palette {
+    pos: Point,
+    query: String,
+    selected: usize,
+}
+
+impl Palette {
+    const WIDTH: f32 = 240.;
+    const ROW_HEIGHT: f32 = 20.;
+    const TEXT_SIZE: f32 = 16.;
+    const PADDING: f32 = 4.;
+    const MAX_ROWS: usize = 12;
+
+    pub fn new(pos: Point) -> Self {
+        Self {
+            pos,
+            query: String::new(),
+            selected: 0,
+        }
+    }
+
+    pub fn get_pos(&self) -> Point {
+        self.pos
+    }
+
+    pub fn push_char(&mut self, c: char) {
+        self.query.push(c);
+        self.selected = 0;
+    }
+
+    pub fn pop_char(&mut self) {
+        self.query.pop();
+        self.selected = 0;
+    }
+
+    pub fn select_next(&mut self, reg: &NodeRegistry) {
+        let count = reg.search(&self.query).len();
+        if self.selected + 1 < count {
+            self.selected += 1;
+        }
+    }
+
+    pub fn select_prev(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    /// Get the currently highlighted node type
+    pub fn get_selected<'a>(&self, reg: &'a NodeRegistry) -> Option<&'a NodeTemplate> {
+        reg.search(&self.query).get(self.selected).copied()
+    }
+
+    fn scroll_offset(&self) -> usize {
+        self.selected.saturating_sub(Self::MAX_ROWS - 1)
+    }
+
+    fn visible_rows(&self, reg: &NodeRegistry) -> usize {
+        reg.search(&self.query).len().min(Self::MAX_ROWS)
+    }
+
+    /// Screen area covered by the palette
+    pub fn get_bounds(&self, reg: &NodeRegistry) -> Rectangle {
+        Rectangle::new(self.pos, Size::new(
+            Self::WIDTH,
+            (self.visible_rows(reg) + 1) as f32 * Self::ROW_HEIGHT + 2. * Self::PADDING,
+        ))
+    }
+
+    /// Get the node type shown at the screen position `p`
+    pub fn entry_at<'a>(&self, p: Point, reg: &'a NodeRegistry) -> Option<&'a NodeTemplate> {
+        if !self.get_bounds(reg).contains(p) {
+            return None;
+        }
+        let row = ((p.y - self.pos.y - Self::PADDING) / Self::ROW_HEIGHT).floor();
+        if row < 1. {
+            return None;
+        }
+        reg.search(&self.query).get(row as usize - 1 + self.scroll_offset()).copied()
+    }
+
+    pub fn draw(&self, frame: &mut canvas::Frame, reg: &NodeRegistry) {
+        let bounds = self.get_bounds(reg);
+        let path = helpers::rounded_rect_path(bounds, Self::PADDING);
+        let stroke_s = canvas::Stroke::default()
+            .with_color(Color::from_rgb8(0x72, 0x89, 0xDA)) // #7289DA
+            .with_width(1.);
+
+        frame.with_save(|frame| {
+            frame.translate(Vector::new(self.pos.x, self.pos.y));
+            frame.fill(&path, Color::from_rgb8(0x36, 0x39, 0x3F)); // #36393F
+            frame.stroke(&path, stroke_s);
+
+            let text = canvas::Text {
+                color: Color::WHITE,
+                size: Self::TEXT_SIZE,
+                vertical_alignment: iced::VerticalAlignment::Center,
+                ..canvas::Text::default()
+            };
+            let row_y = |row: usize| Self::PADDING + (row as f32 + 0.5) * Self::ROW_HEIGHT;
+
+            frame.fill_text(canvas::Text {
+                content: format!("> {}_", self.query),
+                position: Point::new(Self::PADDING, row_y(0)),
+                ..text.clone()
+            });
+
+            let offset = self.scroll_offset();
+            let entries = reg.search(&self.query);
+            for (i, c_template) in entries.iter().enumerate().skip(offset).take(Self::MAX_ROWS) {
+                let row = i - offset + 1;
+                if i == self.selected {
+                    frame.fill_rectangle(
+                        Point::new(0., row_y(row) - Self::ROW_HEIGHT / 2.),
+                        Size::new(Self::WIDTH, Self::ROW_HEIGHT),
+                        Color::from_rgb8(0x67, 0x7B, 0xC4), // #677BC4
+                    );
+                }
+                frame.fill_text(canvas::Text {
+                    content: c_template.name.to_owned(),
+                    position: Point::new(2. * Self::PADDING, row_y(row)),
+                    ..text.clone()
+                });
+                frame.fill_text(canvas::Text {
+                    content: c_template.category.to_owned(),
+                    position: Point::new(Self::WIDTH - 2. * Self::PADDING, row_y(row)),
+                    color: Color::from_rgb(0.6, 0.6, 0.6),
+                    horizontal_alignment: iced::HorizontalAlignment::Right,
+                    ..text.clone()
+                });
+            }
+        });
+    }
+}
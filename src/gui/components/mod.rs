@@ -1 +1,5 @@
 pub mod grid;
+pub mod stage;
+pub mod color_picker;
+pub mod xy_pad;
+pub mod flash_button;
@@ -0,0 +1,76 @@
+use iced::{
+    canvas,
+    Color,
+    Point,
+    Rectangle,
+    Size,
+};
+
+use crate::fixture::FixtureID;
+
+use super::super::grid::Drawable;
+
+/// A fixture on the stage plot, drawn with the light it currently emits
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixtureIcon {
+    pub id: FixtureID,
+    pub label: String,
+    pub pos: Point,
+    /// Emitted colour with intensity applied
+    pub light: Color,
+    pub selected: bool,
+}
+
+impl FixtureIcon {
+    pub const RADIUS: f32 = 0.35;
+    const LABEL_SIZE: f32 = 0.25;
+    const MIN_TEXT_PX: f32 = 6.;
+
+    pub fn contains(&self, p: Point) -> bool {
+        self.pos.distance(p) <= Self::RADIUS
+    }
+}
+
+impl Drawable for FixtureIcon {
+    fn draw(&self, frame: &mut canvas::Frame) {
+        frame.fill(
+            &canvas::Path::circle(self.pos, Self::RADIUS),
+            Color::from_rgb8(0x33, 0x33, 0x33), // #333333
+        );
+        frame.fill(&canvas::Path::circle(self.pos, Self::RADIUS * 0.75), self.light);
+
+        let (ring_color, ring_width) = if self.selected {
+            (Color::from_rgb8(0x6F, 0xFF, 0xE9), 0.08) // #6FFFE9
+        } else {
+            (Color::from_rgb8(0x70, 0x70, 0x70), 0.03) // #707070
+        };
+        frame.stroke(
+            &canvas::Path::circle(self.pos, Self::RADIUS),
+            canvas::Stroke::default()
+                .with_color(ring_color)
+                .with_width(ring_width),
+        );
+    }
+
+    fn get_bounding_box(&self) -> Rectangle {
+        Rectangle::new(
+            Point::new(self.pos.x - Self::RADIUS, self.pos.y - Self::RADIUS),
+            Size::new(2. * Self::RADIUS, 2. * Self::RADIUS),
+        )
+    }
+
+    fn draw_scaled(&self, frame: &mut canvas::Frame, scaling: f32) {
+        self.draw(frame);
+
+        if Self::LABEL_SIZE * scaling >= Self::MIN_TEXT_PX {
+            frame.fill_text(canvas::Text {
+                content: self.label.clone(),
+                position: Point::new(self.pos.x, self.pos.y + Self::RADIUS + 0.05),
+                size: Self::LABEL_SIZE * scaling,
+                color: Color::WHITE,
+                horizontal_alignment: iced::HorizontalAlignment::Center,
+                ..canvas::Text::default()
+            });
+        }
+    }
+}
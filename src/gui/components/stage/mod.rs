@@ -0,0 +1,5 @@
+mod fixture_icon;
+pub use fixture_icon::*;
+
+mod stage;
+pub use stage::*;
@@ -0,0 +1,410 @@
+use iced::{
+    mouse,
+    keyboard,
+    Length,
+    canvas,
+    Element,
+    Point,
+    Color,
+    Size,
+    Vector,
+};
+
+use crate::{
+    dmx,
+    fixture::{
+        Attribute,
+        FixtureID,
+        Selection,
+    },
+    show::Show,
+};
+
+use super::super::grid::{
+    helpers,
+    Drawable,
+};
+use super::fixture_icon::FixtureIcon;
+
+#[derive(Debug, Clone)]
+pub enum StageMsg {
+    Select(Vec<FixtureID>),
+    AddToSelection(Vec<FixtureID>),
+    Toggle(FixtureID),
+    /// Move fixtures by an offset in metres
+    Move(Vec<FixtureID>, Vector),
+}
+
+/// Top down view of the stage with all patched fixtures.
+/// Uses the same viewport handling as the node grid: middle mouse pans, the wheel zooms.
+#[derive(Debug, Default)]
+pub struct StagePlot {
+    icons: Vec<FixtureIcon>,
+    stage_size: Option<Size>,
+
+    mouse_last_pos: Option<Point>,
+    mouse_drag_screen: bool,
+    mouse_drag_icons: bool,
+    lasso: Option<Vec<Point>>,
+    modifiers: keyboard::Modifiers,
+    fitted: bool,
+
+    scale_sensivity: f32,
+    translation: Vector,
+    scaling: f32,
+
+    icon_cache: canvas::Cache,
+    overlay_cache: canvas::Cache,
+    background_cache: canvas::Cache,
+}
+
+impl StagePlot {
+    const MIN_SCALING: f32 = 5.;
+    const MAX_SCALING: f32 = 400.;
+    /// Space kept around the stage when fitting it into the view (in metres)
+    const FIT_MARGIN: f32 = 1.;
+    /// Minimal distance between recorded lasso points (in metres)
+    const LASSO_STEP: f32 = 0.1;
+    const FRONT_LABEL_SIZE: f32 = 0.4;
+
+    pub fn new() -> Self {
+        Self {
+            scale_sensivity: 20.,
+            scaling: 40.,
+            ..Self::default()
+        }
+    }
+
+    pub fn view<'a>(&'a mut self) -> Element<'a, StageMsg> {
+        canvas::Canvas::new(self)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into()
+    }
+
+    /// Take over fixture positions, selection and current output
+    pub fn refresh(&mut self, show: &Show, dmx: &dmx::DMX, selection: &Selection) {
+        let icons: Vec<FixtureIcon> = show.patch.fixtures()
+            .iter()
+            .enumerate()
+            .map(|(i, f)| {
+                let [x, y] = show.stage.position_or_default(f.get_id(), i);
+                let intensity = f.get_attribute(dmx, Attribute::Intensity);
+                let (color, level) = match (f.get_color(dmx), intensity) {
+                    (Some(rgb), level) => (Color::from_rgb(rgb.r, rgb.g, rgb.b), level.unwrap_or(1.)),
+                    (None, Some(level)) => (Color::from_rgb8(0xFF, 0xE4, 0xB5), level), // #FFE4B5
+                    (None, None) => (Color::BLACK, 0.),
+                };
+                FixtureIcon {
+                    id: f.get_id(),
+                    label: format!("{} {}", f.get_id(), f.get_name()),
+                    pos: Point::new(x, y),
+                    light: Color::from_rgb(color.r * level, color.g * level, color.b * level),
+                    selected: selection.contains(f.get_id()),
+                }
+            })
+            .collect();
+        if icons != self.icons {
+            self.icons = icons;
+            self.icon_cache.clear();
+        }
+
+        let stage_size = Size::new(show.stage.width, show.stage.depth);
+        if self.stage_size != Some(stage_size) {
+            self.stage_size = Some(stage_size);
+            self.fitted = false;
+            self.background_cache.clear();
+        }
+    }
+
+    fn clear_caches(&mut self) {
+        self.background_cache.clear();
+        self.icon_cache.clear();
+        self.overlay_cache.clear();
+    }
+
+    fn project_point(&self, pos: Point, size: Size) -> Point {
+        Point::new(
+            (pos.x - size.width / 2.) / self.scaling - self.translation.x,
+            (pos.y - size.height / 2.) / self.scaling - self.translation.y,
+        )
+    }
+
+    fn project_vec(&self, v: Vector) -> Vector {
+        Vector::new(v.x / self.scaling, v.y / self.scaling)
+    }
+
+    fn translate_viewport(&self, frame: &mut canvas::Frame, bounds: &iced::Rectangle) {
+        frame.translate(Vector::new(bounds.width / 2.0, bounds.height / 2.0));
+        frame.scale(self.scaling);
+        frame.translate(self.translation);
+    }
+
+    /// Center the stage and zoom so it fills the view
+    fn fit_stage(&mut self, size: Size) {
+        let stage = match self.stage_size {
+            Some(s) => s,
+            None => return,
+        };
+        self.scaling = (size.width / (stage.width + 2. * Self::FIT_MARGIN))
+            .min(size.height / (stage.height + 2. * Self::FIT_MARGIN))
+            .min(Self::MAX_SCALING)
+            .max(Self::MIN_SCALING);
+        self.translation = Vector::new(-stage.width / 2., -stage.height / 2.);
+        self.fitted = true;
+        self.clear_caches();
+    }
+
+    fn icon_at(&self, p: Point) -> Option<&FixtureIcon> {
+        self.icons.iter().rev().find(|i| i.contains(p))
+    }
+
+    fn selected_ids(&self) -> Vec<FixtureID> {
+        self.icons.iter().filter(|i| i.selected).map(|i| i.id).collect()
+    }
+
+    fn start_left_drag(&mut self, pos: Point) -> Option<StageMsg> {
+        let hit = self.icon_at(pos).map(|i| (i.id, i.selected));
+        match hit {
+            Some((id, _)) if self.modifiers.shift => Some(StageMsg::Toggle(id)),
+            Some((id, selected)) => {
+                self.mouse_drag_icons = true;
+                if selected { None } else { Some(StageMsg::Select(vec![id])) }
+            }
+            None => {
+                self.lasso = Some(vec![pos]);
+                None
+            }
+        }
+    }
+
+    fn finish_lasso(&mut self, lasso: Vec<Point>) -> Option<StageMsg> {
+        self.overlay_cache.clear();
+        let inside: Vec<FixtureID> = self.icons.iter()
+            .filter(|i| helpers::point_in_polygon(i.pos, &lasso))
+            .map(|i| i.id)
+            .collect();
+
+        if self.modifiers.shift {
+            Some(StageMsg::AddToSelection(inside))
+        } else {
+            Some(StageMsg::Select(inside))
+        }
+    }
+
+    fn draw_stage(&self, frame: &mut canvas::Frame, bounds: &iced::Rectangle) {
+        frame.fill_rectangle(Point::ORIGIN, frame.size(), Color::from_rgb8(0x22, 0x22, 0x22)); // #222222
+
+        let stage = match self.stage_size {
+            Some(s) => s,
+            None => return,
+        };
+        frame.with_save(|frame| {
+            self.translate_viewport(frame, bounds);
+
+            frame.fill_rectangle(Point::ORIGIN, stage, Color::from_rgb8(0x2E, 0x30, 0x35)); // #2E3035
+
+            let line = canvas::Stroke::default()
+                .with_color(Color::from_rgb8(0x36, 0x39, 0x3F)) // #36393F
+                .with_width(0.02);
+            for x in 1..stage.width.ceil() as usize {
+                frame.stroke(&canvas::Path::line(Point::new(x as f32, 0.), Point::new(x as f32, stage.height)), line.clone());
+            }
+            for y in 1..stage.height.ceil() as usize {
+                frame.stroke(&canvas::Path::line(Point::new(0., y as f32), Point::new(stage.width, y as f32)), line.clone());
+            }
+
+            frame.stroke(
+                &canvas::Path::rectangle(Point::ORIGIN, stage),
+                canvas::Stroke::default()
+                    .with_color(Color::from_rgb8(0x40, 0x44, 0x4B)) // #40444B
+                    .with_width(0.05),
+            );
+            frame.fill_text(canvas::Text {
+                content: "FRONT".to_owned(),
+                position: Point::new(stage.width / 2., stage.height + 0.1),
+                size: Self::FRONT_LABEL_SIZE * self.scaling,
+                color: Color::from_rgb8(0x70, 0x70, 0x70), // #707070
+                horizontal_alignment: iced::HorizontalAlignment::Center,
+                ..canvas::Text::default()
+            });
+        });
+    }
+
+    fn draw_lasso(&self, frame: &mut canvas::Frame, lasso: &[Point]) {
+        if lasso.len() < 2 {
+            return;
+        }
+        let mut builder = canvas::path::Builder::new();
+        builder.move_to(lasso[0]);
+        for c_point in lasso.iter().skip(1) {
+            builder.line_to(*c_point);
+        }
+        builder.close();
+        let path = builder.build();
+
+        frame.fill(&path, Color { a: 0.15, ..Color::from_rgb8(0x6F, 0xFF, 0xE9) }); // #6FFFE9
+        frame.stroke(
+            &path,
+            canvas::Stroke::default()
+                .with_color(Color::from_rgb8(0x6F, 0xFF, 0xE9)) // #6FFFE9
+                .with_width(0.03),
+        );
+    }
+}
+
+impl canvas::Program<StageMsg> for StagePlot {
+    fn update(
+        &mut self,
+        event: canvas::Event,
+        bounds: iced::Rectangle,
+        cursor: canvas::Cursor,
+    ) -> ( canvas::event::Status, Option<StageMsg> ) {
+        let cursor_pos =
+            if let Some(position) = cursor.position_in(&bounds) {
+                position
+            } else {
+                return (canvas::event::Status::Ignored, None);
+            };
+        if !self.fitted {
+            self.fit_stage(bounds.size());
+        }
+
+        match event {
+            canvas::Event::Mouse(mouse_event) => match mouse_event {
+                mouse::Event::ButtonPressed(mouse::Button::Middle) => {
+                    self.mouse_drag_screen = true;
+                    (canvas::event::Status::Captured, None)
+                }
+                mouse::Event::ButtonPressed(mouse::Button::Left) => {
+                    let pos = self.project_point(cursor_pos, bounds.size());
+                    (canvas::event::Status::Captured, self.start_left_drag(pos))
+                }
+                mouse::Event::ButtonReleased(mouse::Button::Middle) => {
+                    self.mouse_drag_screen = false;
+                    (canvas::event::Status::Captured, None)
+                }
+                mouse::Event::ButtonReleased(mouse::Button::Left) => {
+                    self.mouse_drag_icons = false;
+                    let msg = self.lasso.take().and_then(|lasso| self.finish_lasso(lasso));
+                    (canvas::event::Status::Captured, msg)
+                }
+                mouse::Event::CursorMoved{ .. } => {
+                    let mut msg = None;
+                    if let Some(last_pos) = self.mouse_last_pos {
+                        if self.mouse_drag_screen {
+                            self.translation = self.translation + self.project_vec(cursor_pos - last_pos);
+                            self.clear_caches();
+                        }
+                        if self.mouse_drag_icons {
+                            msg = Some(StageMsg::Move(self.selected_ids(), self.project_vec(cursor_pos - last_pos)));
+                        }
+                    }
+                    let pos = self.project_point(cursor_pos, bounds.size());
+                    if let Some(lasso) = self.lasso.as_mut() {
+                        if lasso.last().map(|l| l.distance(pos) >= Self::LASSO_STEP).unwrap_or(true) {
+                            lasso.push(pos);
+                            self.overlay_cache.clear();
+                        }
+                    }
+                    self.mouse_last_pos = Some(cursor_pos);
+                    (canvas::event::Status::Ignored, msg)
+                }
+                mouse::Event::WheelScrolled{ delta } => {
+                    let y = match delta {
+                        mouse::ScrollDelta::Lines{ y, .. }|
+                        mouse::ScrollDelta::Pixels{ y, .. } => y,
+                    };
+                    let new_scale = (self.scaling * (1.0 + y / self.scale_sensivity))
+                        .min(Self::MAX_SCALING)
+                        .max(Self::MIN_SCALING);
+                    let old_scaling = self.scaling;
+
+                    if let Some(cursor_to_center) = cursor.position_from(bounds.center()) {
+                        let fac = new_scale - old_scaling;
+                        self.translation = self.translation - Vector::new(
+                            cursor_to_center.x * fac / (old_scaling * old_scaling),
+                            cursor_to_center.y * fac / (old_scaling * old_scaling),
+                        );
+                    }
+                    self.scaling = new_scale;
+                    self.clear_caches();
+                    (canvas::event::Status::Captured, None)
+                }
+                _ => (canvas::event::Status::Ignored, None)
+            }
+            canvas::Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
+                self.modifiers = modifiers;
+                (canvas::event::Status::Ignored, None)
+            }
+            canvas::Event::Keyboard(keyboard::Event::KeyPressed{ key_code: keyboard::KeyCode::Home, .. }) => {
+                self.fit_stage(bounds.size());
+                (canvas::event::Status::Captured, None)
+            }
+            canvas::Event::Keyboard(_) => (canvas::event::Status::Ignored, None),
+        }
+    }
+
+    fn draw(
+        &self,
+        bounds: iced::Rectangle,
+        _cursor: canvas::Cursor,
+    ) -> Vec<canvas::Geometry> {
+        let bg = self.background_cache.draw(bounds.size(), |frame| {
+            self.draw_stage(frame, &bounds);
+        });
+
+        let icons = self.icon_cache.draw(bounds.size(), |frame| {
+            self.translate_viewport(frame, &bounds);
+            for c_icon in self.icons.iter() {
+                c_icon.draw_scaled(frame, self.scaling);
+            }
+        });
+
+        let overlay = self.overlay_cache.draw(bounds.size(), |frame| {
+            if let Some(lasso) = self.lasso.as_ref() {
+                self.translate_viewport(frame, &bounds);
+                self.draw_lasso(frame, lasso);
+            }
+        });
+
+        vec![bg, icons, overlay]
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn icon(id: FixtureID, x: f32, y: f32, selected: bool) -> FixtureIcon {
+        FixtureIcon {
+            id,
+            label: String::new(),
+            pos: Point::new(x, y),
+            light: Color::BLACK,
+            selected,
+        }
+    }
+
+    #[test]
+    fn test_click_and_lasso() {
+        let mut plot = StagePlot::new();
+        plot.icons = vec![icon(1, 1., 1., false), icon(2, 3., 1., true), icon(3, 5., 5., false)];
+
+        // Clicking an unselected fixture selects only it, a selected one keeps the selection for dragging
+        assert!(matches!(plot.start_left_drag(Point::new(1.1, 1.)), Some(StageMsg::Select(ids)) if ids == vec![1]));
+        assert!(plot.start_left_drag(Point::new(3., 1.2)).is_none());
+        assert!(plot.mouse_drag_icons);
+
+        plot.modifiers.shift = true;
+        assert!(matches!(plot.start_left_drag(Point::new(5., 5.)), Some(StageMsg::Toggle(3))));
+        plot.modifiers.shift = false;
+
+        // Empty space starts a lasso
+        assert!(plot.start_left_drag(Point::new(0., 0.)).is_none());
+        assert!(plot.lasso.is_some());
+        let lasso = vec![Point::new(0., 0.), Point::new(4., 0.), Point::new(4., 2.), Point::new(0., 2.)];
+        assert!(matches!(plot.finish_lasso(lasso), Some(StageMsg::Select(ids)) if ids == vec![1, 2]));
+    }
+}
@@ -0,0 +1,208 @@
+use iced::{
+    mouse,
+    canvas,
+    Color,
+    Element,
+    Length,
+    Point,
+    Rectangle,
+    Size,
+};
+
+use crate::fixture::position::PositionRange;
+
+/// A drag on the pad, remembered to move relative to its start in fine mode
+#[derive(Debug, Clone, Copy)]
+struct PadDrag {
+    start: Point,
+    start_position: (f32, f32),
+}
+
+/// Square pad mapping pan to the horizontal and tilt to the vertical axis.
+/// Emits pan and tilt in degrees from the center position.
+#[derive(Debug)]
+pub struct XYPad {
+    range: PositionRange,
+    position: (f32, f32),
+    fine: bool,
+    drag: Option<PadDrag>,
+    cache: canvas::Cache,
+}
+
+impl XYPad {
+    const HEIGHT: u16 = 200;
+    /// Movement per dragged pixel in fine mode compared to normal mode
+    const FINE_FACTOR: f32 = 0.1;
+
+    pub fn new() -> Self {
+        Self {
+            range: PositionRange::default(),
+            position: (0., 0.),
+            fine: false,
+            drag: None,
+            cache: canvas::Cache::new(),
+        }
+    }
+
+    pub fn set_range(&mut self, range: PositionRange) {
+        if range != self.range {
+            self.range = range;
+            self.cache.clear();
+        }
+    }
+
+    pub fn set_position(&mut self, pan: f32, tilt: f32) {
+        if (pan, tilt) != self.position {
+            self.position = (pan, tilt);
+            self.cache.clear();
+        }
+    }
+
+    pub fn get_position(&self) -> (f32, f32) {
+        self.position
+    }
+
+    /// In fine mode drags move the position relative to where they started at a fraction of the speed
+    pub fn set_fine(&mut self, fine: bool) {
+        self.fine = fine;
+    }
+
+    pub fn is_fine(&self) -> bool {
+        self.fine
+    }
+
+    pub fn view<'a>(&'a mut self) -> Element<'a, (f32, f32)> {
+        canvas::Canvas::new(self)
+            .width(Length::Fill)
+            .height(Length::Units(Self::HEIGHT))
+            .into()
+    }
+
+    fn clamp(&self, (pan, tilt): (f32, f32)) -> (f32, f32) {
+        let (hp, ht) = (self.range.pan / 2., self.range.tilt / 2.);
+        (pan.max(-hp).min(hp), tilt.max(-ht).min(ht))
+    }
+
+    /// Degrees per pixel on both axes
+    fn degrees_per_px(&self, size: Size) -> (f32, f32) {
+        (self.range.pan / size.width, self.range.tilt / size.height)
+    }
+
+    fn to_position(&self, p: Point, size: Size) -> (f32, f32) {
+        self.clamp((
+            (p.x / size.width - 0.5) * self.range.pan,
+            (0.5 - p.y / size.height) * self.range.tilt,
+        ))
+    }
+
+    fn to_point(&self, (pan, tilt): (f32, f32), size: Size) -> Point {
+        Point::new(
+            (pan / self.range.pan + 0.5) * size.width,
+            (0.5 - tilt / self.range.tilt) * size.height,
+        )
+    }
+
+    fn drag_to(&self, drag: PadDrag, p: Point, size: Size) -> (f32, f32) {
+        if self.fine {
+            let (dpx, dpy) = self.degrees_per_px(size);
+            self.clamp((
+                drag.start_position.0 + (p.x - drag.start.x) * dpx * Self::FINE_FACTOR,
+                drag.start_position.1 - (p.y - drag.start.y) * dpy * Self::FINE_FACTOR,
+            ))
+        } else {
+            self.to_position(p, size)
+        }
+    }
+
+    fn draw_pad(&self, frame: &mut canvas::Frame) {
+        let size = frame.size();
+        frame.fill_rectangle(Point::ORIGIN, size, Color::from_rgb8(0x26, 0x29, 0x2E)); // #26292E
+
+        let grid = canvas::Stroke::default()
+            .with_color(Color::from_rgb8(0x40, 0x44, 0x4B)) // #40444B
+            .with_width(1.);
+        for i in 1..4 {
+            let f = i as f32 / 4.;
+            frame.stroke(&canvas::Path::line(Point::new(f * size.width, 0.), Point::new(f * size.width, size.height)), grid.clone());
+            frame.stroke(&canvas::Path::line(Point::new(0., f * size.height), Point::new(size.width, f * size.height)), grid.clone());
+        }
+
+        let marker = self.to_point(self.position, size);
+        let accent = Color::from_rgb8(0x6F, 0xFF, 0xE9); // #6FFFE9
+        let cross = canvas::Stroke::default()
+            .with_color(Color { a: 0.4, ..accent })
+            .with_width(1.);
+        frame.stroke(&canvas::Path::line(Point::new(marker.x, 0.), Point::new(marker.x, size.height)), cross.clone());
+        frame.stroke(&canvas::Path::line(Point::new(0., marker.y), Point::new(size.width, marker.y)), cross);
+        frame.fill(&canvas::Path::circle(marker, 6.), accent);
+    }
+}
+
+impl canvas::Program<(f32, f32)> for XYPad {
+    fn update(
+        &mut self,
+        event: canvas::Event,
+        bounds: Rectangle,
+        cursor: canvas::Cursor,
+    ) -> ( canvas::event::Status, Option<(f32, f32)> ) {
+        let cursor_pos = match (cursor.position(), cursor.is_over(&bounds), self.drag) {
+            (Some(p), true, _) | (Some(p), _, Some(_)) => Point::new(p.x - bounds.x, p.y - bounds.y),
+            _ => return (canvas::event::Status::Ignored, None),
+        };
+
+        let position = match event {
+            canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                let drag = PadDrag { start: cursor_pos, start_position: self.position };
+                self.drag = Some(drag);
+                Some(self.drag_to(drag, cursor_pos, bounds.size()))
+            }
+            canvas::Event::Mouse(mouse::Event::CursorMoved{ .. }) => self.drag
+                .map(|drag| self.drag_to(drag, cursor_pos, bounds.size())),
+            canvas::Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                self.drag = None;
+                None
+            }
+            _ => None,
+        };
+
+        match position {
+            Some((pan, tilt)) => {
+                self.set_position(pan, tilt);
+                (canvas::event::Status::Captured, Some((pan, tilt)))
+            }
+            None => (canvas::event::Status::Ignored, None),
+        }
+    }
+
+    fn draw(&self, bounds: Rectangle, _cursor: canvas::Cursor) -> Vec<canvas::Geometry> {
+        let pad = self.cache.draw(bounds.size(), |frame| {
+            self.draw_pad(frame);
+        });
+        vec![pad]
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pad_mapping() {
+        let mut pad = XYPad::new();
+        pad.set_range(PositionRange { pan: 540., tilt: 270. });
+        let size = Size::new(200., 100.);
+
+        assert_eq!(pad.to_position(Point::new(100., 50.), size), (0., 0.));
+        assert_eq!(pad.to_position(Point::new(200., 0.), size), (270., 135.));
+        assert_eq!(pad.to_position(Point::new(500., 500.), size), (270., -135.));
+        assert_eq!(pad.to_point((270., 135.), size), Point::new(200., 0.));
+
+        // Fine mode moves relative to the start at a tenth of the speed
+        let drag = PadDrag { start: Point::new(10., 10.), start_position: (20., 10.) };
+        assert_eq!(pad.drag_to(drag, Point::new(30., 10.), size), pad.to_position(Point::new(30., 10.), size));
+        pad.set_fine(true);
+        let (pan, tilt) = pad.drag_to(drag, Point::new(30., 0.), size);
+        assert!((pan - 25.4).abs() < 1e-3 && (tilt - 12.7).abs() < 1e-3);
+    }
+}
@@ -17,45 +17,169 @@ use iced::{
     VerticalAlignment,
     Image,
     Svg,
+    Subscription,
+    time,
+    keyboard,
 };
 
+use std::sync::Arc;
+
 use log::{ info, error };
 
 use crate::{
     err::ConmxErr,
     gui,
     gui::style,
-    dmx,
+    fixture,
+    show,
+    playback,
+    midi,
     conmx_core,
 };
 
 use super::components::grid::Grid;
+use super::components::stage::{
+    StagePlot,
+    StageMsg,
+};
+use super::views::direct::{
+    DirectView,
+    DirectMsg,
+};
+use super::views::fixtures::{
+    FixtureSheet,
+    FixtureMsg,
+};
+use super::views::monitor::{
+    MonitorView,
+    MonitorMsg,
+};
+use super::views::playback::{
+    PlaybackBar,
+    PlaybackMsg,
+};
+use super::views::palettes::{
+    PaletteView,
+    PaletteMsg,
+};
+use super::views::midi::{
+    MidiView,
+    MidiMsg,
+};
+use super::views::tabview::TabView;
 
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ViewMain {
     Fixtures,
     Direct,
+    NodeEditor,
+    Monitor,
+    Stage,
+    Palettes,
+    Midi,
+}
+
+impl ViewMain {
+    /// All views in the order they are shown in the tab bar
+    pub const ALL: [ViewMain; 7] = [
+        ViewMain::Fixtures,
+        ViewMain::Direct,
+        ViewMain::NodeEditor,
+        ViewMain::Monitor,
+        ViewMain::Stage,
+        ViewMain::Palettes,
+        ViewMain::Midi,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            ViewMain::Fixtures => "Fixtures",
+            ViewMain::Direct => "Direct",
+            ViewMain::NodeEditor => "Node editor",
+            ViewMain::Monitor => "Monitor",
+            ViewMain::Stage => "Stage",
+            ViewMain::Palettes => "Palettes",
+            ViewMain::Midi => "MIDI",
+        }
+    }
+
+    /// Key switching directly to this view
+    pub fn shortcut(&self) -> keyboard::KeyCode {
+        match self {
+            ViewMain::Fixtures => keyboard::KeyCode::F1,
+            ViewMain::Direct => keyboard::KeyCode::F2,
+            ViewMain::NodeEditor => keyboard::KeyCode::F3,
+            ViewMain::Monitor => keyboard::KeyCode::F4,
+            ViewMain::Stage => keyboard::KeyCode::F5,
+            ViewMain::Palettes => keyboard::KeyCode::F6,
+            ViewMain::Midi => keyboard::KeyCode::F7,
+        }
+    }
+
+    pub fn shortcut_name(&self) -> String {
+        format!("{:?}", self.shortcut())
+    }
+
+    pub fn from_shortcut(key_code: keyboard::KeyCode) -> Option<ViewMain> {
+        Self::ALL.iter().find(|v| v.shortcut() == key_code).cloned()
+    }
+
+    /// The view following this one in the tab bar, wrapping around
+    pub fn next(&self) -> ViewMain {
+        let i = Self::ALL.iter().position(|v| v == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum ConMXMsg {
     Grid,
     SwitchTab(ViewMain),
+    NextTab,
     ButtonPressed,
     SliderChange(usize, usize, u32),
+    Direct(DirectMsg),
+    Fixtures(FixtureMsg),
+    Monitor(MonitorMsg),
+    Stage(StageMsg),
+    Playback(PlaybackMsg),
+    Palettes(PaletteMsg),
+    Midi(MidiMsg),
+    SaveShow,
+    /// Playback of the main executor
+    Go,
+    Back,
+    Pause,
+    /// Record the programmer as a new cue at the end of the main cue list
+    RecordCue,
+    /// Record the programmer as a new step at the end of the first chase
+    RecordStep,
+    /// Run the first effect over the selected fixtures
+    EffectFromSelection,
+    /// Start or stop the internal timecode clock
+    ToggleClock,
     RandomChange,
+    Tick,
 }
 
 
 #[derive(Debug)]
 pub struct ConMX {
     grid: Grid,
+    direct: DirectView,
+    fixtures: FixtureSheet,
+    monitor: MonitorView,
+    playback: PlaybackBar,
+    palettes: PaletteView,
+    midi: MidiView,
+    stage: StagePlot,
+    tabs: TabView,
     bstate: button::State,
     imgbstate: button::State,
 
     title: String,
-    dmx: dmx::DMX,
+    engine: conmx_core::Engine,
 
     view: ViewMain,
 }
@@ -70,21 +194,136 @@ impl ConMX {
     }
 }
 
+impl ConMX {
+    /// Executor played by the GO, BACK and PAUSE shortcuts
+    const MAIN_EXECUTOR: usize = 0;
+
+    /// Map key presses to global shortcuts: the view shortcuts, ctrl+tab for the next view, ctrl+s to save
+    /// ctrl+g/b/p for GO, BACK and PAUSE, ctrl+r/t for recording a cue or chase step
+    /// ctrl+e for running the effect over the selection and ctrl+space for starting and stopping the timecode clock
+    fn shortcut_event(event: iced_native::Event, _status: iced_native::event::Status) -> Option<ConMXMsg> {
+        match event {
+            iced_native::Event::Keyboard(keyboard::Event::KeyPressed{ key_code, modifiers })
+                if modifiers.control && key_code != keyboard::KeyCode::Tab => match key_code {
+                    keyboard::KeyCode::S => Some(ConMXMsg::SaveShow),
+                    keyboard::KeyCode::G => Some(ConMXMsg::Go),
+                    keyboard::KeyCode::B => Some(ConMXMsg::Back),
+                    keyboard::KeyCode::P => Some(ConMXMsg::Pause),
+                    keyboard::KeyCode::R => Some(ConMXMsg::RecordCue),
+                    keyboard::KeyCode::T => Some(ConMXMsg::RecordStep),
+                    keyboard::KeyCode::E => Some(ConMXMsg::EffectFromSelection),
+                    keyboard::KeyCode::Space => Some(ConMXMsg::ToggleClock),
+                    _ => ViewMain::from_shortcut(key_code).map(ConMXMsg::SwitchTab),
+                },
+            iced_native::Event::Keyboard(keyboard::Event::KeyPressed{ key_code: keyboard::KeyCode::Tab, modifiers })
+                if modifiers.control => Some(ConMXMsg::NextTab),
+            iced_native::Event::Keyboard(keyboard::Event::KeyPressed{ key_code, .. }) =>
+                ViewMain::from_shortcut(key_code).map(ConMXMsg::SwitchTab),
+            _ => None,
+        }
+    }
+
+    /// Load the show given in the config, falling back to a small demo patch
+    fn initial_show(config: &conmx_core::Config) -> show::Show {
+        if let Some(path) = config.show.as_ref().filter(|p| p.exists()) {
+            match show::Show::load(path) {
+                Ok(show) => {
+                    info!("Loaded show {}", path.display());
+                    return show;
+                }
+                Err(e) => error!("{}", e),
+            }
+        }
+        show::Show {
+            patch: Self::initial_patch(),
+            ..show::Show::new()
+        }
+    }
+
+    /// Record the programmer into the first cue list, which the main executor plays
+    fn record_cue(&mut self) {
+        if self.engine.show.cue_lists.is_empty() {
+            self.engine.show.cue_lists.push(playback::CueList::new("Main"));
+        }
+        let index = self.engine.show.cue_lists[0].record(&self.engine.programmer);
+        info!("Recorded cue {} with {} values", index + 1, self.engine.programmer.touched().len());
+    }
+
+    /// Record the programmer into the first chase, which the second executor plays by default
+    fn record_chase_step(&mut self) {
+        if self.engine.show.chases.is_empty() {
+            self.engine.show.chases.push(playback::Chase::new("Chase"));
+        }
+        let index = self.engine.show.chases[0].record(&self.engine.programmer);
+        info!("Recorded step {} with {} values", index + 1, self.engine.programmer.touched().len());
+    }
+
+    /// Set the fixtures of the first effect, which the third executor plays by default, to the selection.
+    /// Without an effect an intensity wave is created.
+    fn effect_from_selection(&mut self) {
+        if self.engine.show.effects.is_empty() {
+            self.engine.show.effects.push(
+                playback::Effect::new("Wave")
+                    .with_attribute(playback::EffectAttribute::new(fixture::Attribute::Intensity, 0., 1.))
+            );
+        }
+        self.engine.show.effects[0].fixtures = self.engine.programmer.selection().ids().to_vec();
+        info!("Effect runs over {} fixtures", self.engine.show.effects[0].fixtures.len());
+    }
+
+    /// Control touched on screen which can be learned as MIDI target
+    fn midi_target(msg: &ConMXMsg) -> Option<midi::MidiTarget> {
+        match msg {
+            ConMXMsg::SliderChange(universe, channel, _) => Some(midi::MidiTarget::Channel { universe: *universe, channel: *channel }),
+            ConMXMsg::Playback(PlaybackMsg::SetLevel(i, _)) => Some(midi::MidiTarget::Fader(*i)),
+            ConMXMsg::Playback(PlaybackMsg::Flash(i, _)) => Some(midi::MidiTarget::Flash(*i)),
+            ConMXMsg::Playback(PlaybackMsg::Go(i)) => Some(midi::MidiTarget::Go(*i)),
+            ConMXMsg::Playback(PlaybackMsg::Back(i)) => Some(midi::MidiTarget::Back(*i)),
+            ConMXMsg::Playback(PlaybackMsg::Release(i)) => Some(midi::MidiTarget::Release(*i)),
+            _ => None,
+        }
+    }
+
+    /// Fixtures patched when starting without a show
+    fn initial_patch() -> fixture::Patch {
+        let mut patch = fixture::Patch::new();
+        let types = fixture::library::builtin_types();
+        let (dimmer, rgb, moving_head) = (&types[0], &types[1], &types[3]);
+
+        let fixtures = (0..4).map(|i| (format!("Par {}", i + 1), rgb, 1, 0, i * 4))
+            .chain((0..2).map(|i| (format!("Spot {}", i + 1), moving_head, 0, 0, 32 + i * 8)))
+            .chain((0..6).map(|i| (format!("Dimmer {}", i + 1), dimmer, 0, 1, i)));
+        for (name, fixture_type, mode, universe, address) in fixtures {
+            if let Err(e) = patch.add(name, Arc::clone(fixture_type), mode, universe, address) {
+                error!("{}", e);
+            }
+        }
+        patch
+    }
+}
+
 impl Application for ConMX {
     type Executor = iced::executor::Default;
     type Message = ConMXMsg;
     type Flags = conmx_core::Config;
 
     fn new(config: conmx_core::Config) -> (Self, Command<ConMXMsg>) {
-        let mut univ = dmx::DMX::new();
-        univ.add_universe(dmx::DMXUniverse::new(0))
-            .add_universe(dmx::DMXUniverse::new(0));
+        let engine = conmx_core::Engine::new(&config, Self::initial_show(&config));
+        let grid = Grid::new().with_graph(engine.node_graph());
         let conmx = ConMX {
             title: String::from("This is a test window"),
-            dmx: univ,
+            engine,
             view: ViewMain::Fixtures,
 
-            grid: Grid::new(),
+            grid,
+            direct: DirectView::new(),
+            fixtures: FixtureSheet::new(),
+            monitor: MonitorView::new(),
+            playback: PlaybackBar::new(),
+            palettes: PaletteView::new(),
+            midi: MidiView::new(),
+            stage: StagePlot::new(),
+            tabs: TabView::new(),
             imgbstate: button::State::new(),
             bstate: button::State::new(),
         };
@@ -96,28 +335,88 @@ impl Application for ConMX {
     }
 
     fn update(&mut self, msg: ConMXMsg, _cb: &mut Clipboard) -> Command<Self::Message> {
+        if let Some(target) = Self::midi_target(&msg) {
+            self.midi.pick(target, &mut self.engine.show.midi);
+        }
         match msg {
             ConMXMsg::ButtonPressed => {
                 info!("Button pressed");
             }
             ConMXMsg::SwitchTab(s) => {
-                error!("switched tab to {:?}", s);
+                info!("switched tab to {:?}", s);
+                self.set_view(s);
+            }
+            ConMXMsg::NextTab => {
+                let next = self.get_view().next();
+                self.set_view(next);
             }
             ConMXMsg::SliderChange(universe, channel, value) => {
                 info!("Changed slider [{}]:{} to {}", universe, channel, value);
-                match self.dmx.get_universe(universe) {
+                match self.engine.dmx.get_universe(universe) {
                     Some(u) => {
                         u.set_channel(channel, value);
                     }
                     None => error!("given Universe {} is not configured!", universe),
                 }
             }
+            ConMXMsg::Direct(direct_msg) => self.direct.update(direct_msg, &mut self.engine.dmx),
+            ConMXMsg::Fixtures(fixture_msg) => self.fixtures.update(fixture_msg, &self.engine.show.patch, &mut self.engine.show.groups, &mut self.engine.programmer, &mut self.engine.dmx),
+            ConMXMsg::Monitor(monitor_msg) => {
+                self.monitor.update(monitor_msg);
+                self.monitor.refresh(&self.engine.dmx, &self.engine.show.patch);
+            }
+            ConMXMsg::Stage(stage_msg) => {
+                match stage_msg {
+                    StageMsg::Select(ids) => self.engine.programmer.selection_mut().set(ids),
+                    StageMsg::AddToSelection(ids) => self.engine.programmer.selection_mut().extend(ids),
+                    StageMsg::Toggle(id) => self.engine.programmer.selection_mut().toggle(id),
+                    StageMsg::Move(ids, offset) => {
+                        for c_id in ids {
+                            self.engine.show.move_on_stage(c_id, [offset.x, offset.y]);
+                        }
+                    }
+                }
+                self.stage.refresh(&self.engine.show, &self.engine.dmx, self.engine.programmer.selection());
+            }
+            ConMXMsg::Palettes(palette_msg) => self.palettes.update(palette_msg, &mut self.engine.show.palettes, &self.engine.show.patch, &mut self.engine.programmer, &mut self.engine.dmx),
+            ConMXMsg::Midi(midi_msg) => self.midi.update(midi_msg, &mut self.engine.show.midi),
+            ConMXMsg::SaveShow => self.engine.save_show(self.grid.graph()),
+            ConMXMsg::Playback(playback_msg) => {
+                let (executors, content) = self.engine.show.playback();
+                self.playback.update(playback_msg, executors, content);
+            }
+            ConMXMsg::Go => {
+                let (executors, content) = self.engine.show.playback();
+                executors.go(Self::MAIN_EXECUTOR, content);
+            }
+            ConMXMsg::Back => {
+                let (executors, content) = self.engine.show.playback();
+                executors.back(Self::MAIN_EXECUTOR, content);
+            }
+            ConMXMsg::Pause => self.engine.show.executors.pause(Self::MAIN_EXECUTOR),
+            ConMXMsg::RecordCue => self.record_cue(),
+            ConMXMsg::RecordStep => self.record_chase_step(),
+            ConMXMsg::EffectFromSelection => self.effect_from_selection(),
+            ConMXMsg::ToggleClock => self.engine.toggle_clock(),
             ConMXMsg::Grid => info!("Grid change called!"),
             ConMXMsg::RandomChange => info!("RandomChange called!"),
+            ConMXMsg::Tick => {
+                self.engine.tick(conmx_core::Engine::FRAME_TIME.as_secs_f32(), self.grid.graph_mut());
+                self.grid.tick();
+                self.monitor.refresh(&self.engine.dmx, &self.engine.show.patch);
+                self.stage.refresh(&self.engine.show, &self.engine.dmx, self.engine.programmer.selection());
+            }
         }
         Command::none()
     }
 
+    fn subscription(&self) -> Subscription<ConMXMsg> {
+        Subscription::batch(vec![
+            time::every(conmx_core::Engine::FRAME_TIME).map(|_| ConMXMsg::Tick),
+            iced_native::subscription::events_with(Self::shortcut_event),
+        ])
+    }
+
     fn view(&mut self) -> Element<ConMXMsg>{
         let but = Button::new(
                 &mut self.bstate, 
@@ -144,12 +443,23 @@ impl Application for ConMX {
             .height(Length::Fill)
             .style(style::Theme);
 
+        let main_view = match self.view {
+            ViewMain::Fixtures => self.fixtures.view(&self.engine.show.patch, &self.engine.show.groups, &self.engine.programmer, &self.engine.dmx),
+            ViewMain::Direct => self.direct.view(&self.engine.dmx),
+            ViewMain::NodeEditor => self.grid.view()
+                .map(move |_msg| ConMXMsg::Grid),
+            ViewMain::Monitor => self.monitor.view(&self.engine.dmx),
+            ViewMain::Stage => self.stage.view()
+                .map(ConMXMsg::Stage),
+            ViewMain::Palettes => self.palettes.view(&self.engine.show.palettes, &self.engine.programmer),
+            ViewMain::Midi => self.midi.view(&self.engine.show.midi),
+        };
+
         let subcont = Row::new()
             .height(Length::Fill)
             .width(Length::Fill)
             .align_items(Align::Center)
-            .push(self.grid.view()
-                .map(move |_msg| ConMXMsg::Grid));
+            .push(main_view);
         /*
             .push(but)
             .push(img)
@@ -160,28 +470,36 @@ impl Application for ConMX {
             .height(Length::Units(100))
             .width(Length::Fill)
             .push(
-                Text::new("This is some text")
+                Text::new(self.view.title())
                     .width(Length::Fill)
                     .horizontal_alignment(HorizontalAlignment::Center)
                     .size(30)
+            )
+            .push(
+                Text::new(self.engine.clock.timecode().to_string())
+                    .width(Length::Units(200))
+                    .horizontal_alignment(HorizontalAlignment::Center)
+                    .size(30)
+                    .color(if self.engine.clock.is_running() { style::ACCENT } else { iced::Color::WHITE })
             );
 
 
+        let playback_line = Row::new()
+            .width(Length::Fill)
+            .push(self.playback.view(&self.engine.show.executors, self.engine.show.playables()));
+
         let bottom_tab_line = Row::new()
             .height(Length::Units(50))
             .width(Length::Fill)
             .align_items(Align::Center)
-            .push(
-                Text::new("This is some text")
-                    .width(Length::Fill)
-                    .horizontal_alignment(HorizontalAlignment::Center)
-            );
+            .push(self.tabs.view(self.view));
 
         let view_split = Column::new()
             .height(Length::Fill)
             .width(Length::Fill)
             .push(menu_line)
             .push(subcont)
+            .push(playback_line)
             .push(bottom_tab_line);
         
 
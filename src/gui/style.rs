@@ -17,7 +17,7 @@ const SURFACE: Color = Color::from_rgb(
     0x4B as f32 / 255.0,
 );
 
-const ACCENT: Color = Color::from_rgb(
+pub const ACCENT: Color = Color::from_rgb(
     0x6F as f32 / 255.0,
     0xFF as f32 / 255.0,
     0xE9 as f32 / 255.0,
@@ -176,6 +176,70 @@ impl button::StyleSheet for Button {
     }
 }
 
+/// Button marking a selected item
+pub struct Selected;
+
+impl button::StyleSheet for Selected {
+    fn active(&self) -> button::Style {
+        button::Style {
+            background: ACCENT.into(),
+            border_radius: 3.0,
+            text_color: Color::BLACK,
+            ..button::Style::default()
+        }
+    }
+
+    fn hovered(&self) -> button::Style {
+        button::Style {
+            background: Color { a: 0.8, ..ACCENT }.into(),
+            ..self.active()
+        }
+    }
+
+    fn pressed(&self) -> button::Style {
+        button::Style {
+            border_width: 1.0,
+            border_color: Color::WHITE,
+            ..self.hovered()
+        }
+    }
+}
+
+/// Flat button used for the rows of tables
+pub struct ListRow;
+
+impl button::StyleSheet for ListRow {
+    fn active(&self) -> button::Style {
+        button::Style {
+            background: Color::TRANSPARENT.into(),
+            text_color: Color::WHITE,
+            ..button::Style::default()
+        }
+    }
+
+    fn hovered(&self) -> button::Style {
+        button::Style {
+            background: SURFACE.into(),
+            ..self.active()
+        }
+    }
+}
+
+/// Container filled with a single colour, e.g. to preview fixture colours
+pub struct Swatch(pub Color);
+
+impl container::StyleSheet for Swatch {
+    fn style(&self) -> container::Style {
+        container::Style {
+            background: self.0.into(),
+            border_radius: 2.0,
+            border_width: 1.0,
+            border_color: SURFACE,
+            ..container::Style::default()
+        }
+    }
+}
+
 pub struct Scrollable;
 
 impl scrollable::StyleSheet for Scrollable {
@@ -0,0 +1,223 @@
+use std::collections::BTreeSet;
+
+use iced::{
+    button,
+    pick_list,
+    scrollable,
+    slider,
+    text_input,
+    Align,
+    Button,
+    Column,
+    Element,
+    HorizontalAlignment,
+    Length,
+    PickList,
+    Row,
+    Scrollable,
+    Slider,
+    Text,
+    TextInput,
+};
+
+use log::{ info, warn };
+
+use crate::{
+    dmx,
+    gui::style,
+};
+
+use super::super::conmx::ConMXMsg;
+
+#[derive(Debug, Clone)]
+pub enum DirectMsg {
+    SelectUniverse(usize),
+    ToggleChannel(usize),
+    SelectAll,
+    SelectNone,
+    EntryChanged(String),
+    SetSelected,
+}
+
+#[derive(Debug, Default)]
+struct ChannelStrip {
+    fader: slider::State,
+    select: button::State,
+}
+
+/// Direct access to all channels of a universe through faders
+#[derive(Debug)]
+pub struct DirectView {
+    universe: usize,
+    universe_ids: Vec<usize>,
+    selected: BTreeSet<usize>,
+    entry: String,
+
+    strips: Vec<ChannelStrip>,
+    universe_state: pick_list::State<usize>,
+    scroll_state: scrollable::State,
+    entry_state: text_input::State,
+    set_state: button::State,
+    all_state: button::State,
+    none_state: button::State,
+}
+
+impl DirectView {
+    const MAX_VALUE: u32 = 255;
+
+    pub fn new() -> Self {
+        Self {
+            universe: 0,
+            universe_ids: Vec::new(),
+            selected: BTreeSet::new(),
+            entry: String::new(),
+
+            strips: (0..dmx::DMXUniverse::CHANNELS).map(|_| ChannelStrip::default()).collect(),
+            universe_state: pick_list::State::default(),
+            scroll_state: scrollable::State::new(),
+            entry_state: text_input::State::new(),
+            set_state: button::State::new(),
+            all_state: button::State::new(),
+            none_state: button::State::new(),
+        }
+    }
+
+    pub fn get_universe(&self) -> usize {
+        self.universe
+    }
+
+    pub fn get_selected(&self) -> Vec<usize> {
+        self.selected.iter().cloned().collect()
+    }
+
+    pub fn update(&mut self, msg: DirectMsg, dmx: &mut dmx::DMX) {
+        match msg {
+            DirectMsg::SelectUniverse(u) => {
+                self.universe = u;
+                self.selected.clear();
+            }
+            DirectMsg::ToggleChannel(c) => {
+                if !self.selected.remove(&c) {
+                    self.selected.insert(c);
+                }
+            }
+            DirectMsg::SelectAll => self.selected = (0..dmx::DMXUniverse::CHANNELS).collect(),
+            DirectMsg::SelectNone => self.selected.clear(),
+            DirectMsg::EntryChanged(s) => self.entry = s,
+            DirectMsg::SetSelected => {
+                let value = match self.entry.trim().parse::<u32>() {
+                    Ok(v) if v <= Self::MAX_VALUE => v,
+                    _ => {
+                        warn!("\"{}\" is not a valid DMX value (0-{})", self.entry, Self::MAX_VALUE);
+                        return;
+                    }
+                };
+                match dmx.get_universe(self.universe) {
+                    Some(u) => {
+                        for c_channel in self.selected.iter() {
+                            u.set_channel(*c_channel, value);
+                        }
+                        info!("Set {} channels of universe {} to {}", self.selected.len(), self.universe, value);
+                    }
+                    None => warn!("given Universe {} is not configured!", self.universe),
+                }
+            }
+        }
+    }
+
+    pub fn view<'a>(&'a mut self, dmx: &dmx::DMX) -> Element<'a, ConMXMsg> {
+        self.universe_ids = dmx.get_universe_ids();
+        let universe_id = self.universe;
+        let universe = dmx.get_universe_ref(universe_id);
+
+        let controls = Row::new()
+            .spacing(10)
+            .padding(5)
+            .align_items(Align::Center)
+            .push(Text::new("Universe"))
+            .push(PickList::new(
+                &mut self.universe_state,
+                &self.universe_ids[..],
+                Some(universe_id),
+                |u| ConMXMsg::Direct(DirectMsg::SelectUniverse(u)),
+            ))
+            .push(Text::new(format!("{} selected", self.selected.len())))
+            .push(
+                TextInput::new(&mut self.entry_state, "Value", &self.entry, |s| ConMXMsg::Direct(DirectMsg::EntryChanged(s)))
+                    .on_submit(ConMXMsg::Direct(DirectMsg::SetSelected))
+                    .width(Length::Units(80))
+                    .padding(5)
+                    .style(style::Theme)
+            )
+            .push(
+                Button::new(&mut self.set_state, Text::new("Set"))
+                    .on_press(ConMXMsg::Direct(DirectMsg::SetSelected))
+                    .style(style::Theme)
+            )
+            .push(
+                Button::new(&mut self.all_state, Text::new("All"))
+                    .on_press(ConMXMsg::Direct(DirectMsg::SelectAll))
+                    .style(style::Theme)
+            )
+            .push(
+                Button::new(&mut self.none_state, Text::new("None"))
+                    .on_press(ConMXMsg::Direct(DirectMsg::SelectNone))
+                    .style(style::Theme)
+            );
+
+        let selected = &self.selected;
+        let faders = self.strips
+            .iter_mut()
+            .enumerate()
+            .fold(Column::new().spacing(2).padding(5), |col, (i, strip)| {
+                let channel = universe.and_then(|u| u.get_channel(i));
+                let programmed = channel.map(|c| c.get_programmed()).unwrap_or(0);
+                let effective = channel.map(|c| c.get_val()).unwrap_or(0);
+                let overridden = channel.map(|c| c.is_overridden()).unwrap_or(false);
+
+                let select = Button::new(
+                        &mut strip.select,
+                        Text::new(format!("{:03}", i + 1))
+                            .horizontal_alignment(HorizontalAlignment::Center))
+                    .on_press(ConMXMsg::Direct(DirectMsg::ToggleChannel(i)))
+                    .width(Length::Units(50));
+                let select = if selected.contains(&i) {
+                    select.style(style::Selected)
+                } else {
+                    select.style(style::Theme)
+                };
+
+                col.push(
+                    Row::new()
+                        .spacing(10)
+                        .align_items(Align::Center)
+                        .push(select)
+                        .push(
+                            Slider::new(&mut strip.fader, 0..=Self::MAX_VALUE, programmed,
+                                move |v| ConMXMsg::SliderChange(universe_id, i, v))
+                                .width(Length::Fill)
+                                .style(style::Theme)
+                        )
+                        .push(Text::new(format!("{:3}", programmed)).width(Length::Units(40)))
+                        .push(
+                            Text::new(if overridden { format!("OR {:3}", effective) } else { format!("{:3}", effective) })
+                                .width(Length::Units(60))
+                                .color(if overridden { style::ACCENT } else { iced::Color::WHITE })
+                        )
+                )
+            });
+
+        Column::new()
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .push(controls)
+            .push(
+                Scrollable::new(&mut self.scroll_state)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .push(faders)
+                    .style(style::Theme)
+            )
+            .into()
+    }
+}
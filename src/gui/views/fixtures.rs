@@ -0,0 +1,591 @@
+use std::collections::{
+    BTreeMap,
+    BTreeSet,
+};
+
+use iced::{
+    button,
+    scrollable,
+    slider,
+    text_input,
+    Align,
+    Button,
+    Checkbox,
+    Color,
+    Column,
+    Container,
+    Element,
+    Length,
+    Row,
+    Scrollable,
+    Slider,
+    Space,
+    Text,
+    TextInput,
+};
+
+use crate::{
+    dmx,
+    fixture::{
+        color::{
+            Hsv,
+            Rgb,
+        },
+        Attribute,
+        Fixture,
+        FixtureID,
+        Groups,
+        Patch,
+        Selection,
+        SelectionOp,
+    },
+    playback::Programmer,
+    gui::{
+        components::{
+            color_picker::ColorPicker,
+            xy_pad::XYPad,
+        },
+        style,
+    },
+};
+
+use super::super::conmx::ConMXMsg;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SheetColumn {
+    ID,
+    Name,
+    Type,
+    Mode,
+    Address,
+}
+
+impl SheetColumn {
+    const ALL: [SheetColumn; 5] = [
+        SheetColumn::ID,
+        SheetColumn::Name,
+        SheetColumn::Type,
+        SheetColumn::Mode,
+        SheetColumn::Address,
+    ];
+
+    fn title(&self) -> &'static str {
+        match self {
+            SheetColumn::ID => "ID",
+            SheetColumn::Name => "Name",
+            SheetColumn::Type => "Type",
+            SheetColumn::Mode => "Mode",
+            SheetColumn::Address => "Address",
+        }
+    }
+
+    fn width(&self) -> Length {
+        match self {
+            SheetColumn::ID => Length::Units(50),
+            SheetColumn::Name => Length::Fill,
+            SheetColumn::Type => Length::Units(180),
+            SheetColumn::Mode => Length::Units(110),
+            SheetColumn::Address => Length::Units(80),
+        }
+    }
+
+    fn cell(&self, fixture: &Fixture) -> String {
+        match self {
+            SheetColumn::ID => format!("{}", fixture.get_id()),
+            SheetColumn::Name => fixture.get_name().to_owned(),
+            SheetColumn::Type => format!("{} {}", fixture.get_type().manufacturer, fixture.get_type().name),
+            SheetColumn::Mode => fixture.get_mode().name.clone(),
+            SheetColumn::Address => fixture.address_name(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum FixtureMsg {
+    SortBy(SheetColumn),
+    FilterChanged(String),
+    ToggleFixture(FixtureID),
+    SelectAll,
+    SelectNone,
+    SetAttribute(Attribute, f32),
+    SetColor(Hsv),
+    /// Pan and tilt in degrees
+    SetPosition(f32, f32),
+    SetFine(bool),
+    /// Release the touched attributes of the selected fixtures
+    ReleaseSelected,
+    ClearProgrammer,
+    /// Combine the group with the given number with the selection
+    RecallGroup(usize),
+    SetGroupOp(SelectionOp),
+    /// Store the selection as a new group
+    StoreGroup,
+}
+
+/// Table of all patched fixtures with their current values
+#[derive(Debug)]
+pub struct FixtureSheet {
+    sort: SheetColumn,
+    descending: bool,
+    filter: String,
+
+    header_states: Vec<button::State>,
+    row_states: Vec<button::State>,
+    editor_states: BTreeMap<Attribute, slider::State>,
+    picker: ColorPicker,
+    xy_pad: XYPad,
+    editor_scroll: scrollable::State,
+    filter_state: text_input::State,
+    scroll_state: scrollable::State,
+    all_state: button::State,
+    none_state: button::State,
+    release_state: button::State,
+    clear_state: button::State,
+    group_op: SelectionOp,
+    group_states: Vec<button::State>,
+    group_op_states: Vec<button::State>,
+    store_group_state: button::State,
+}
+
+impl FixtureSheet {
+    const VALUE_WIDTH: u16 = 60;
+    const SWATCH_SIZE: u16 = 20;
+    const GROUP_OPS: [(SelectionOp, &'static str); 4] = [
+        (SelectionOp::Replace, "="),
+        (SelectionOp::Add, "+"),
+        (SelectionOp::Remove, "-"),
+        (SelectionOp::Intersect, "&"),
+    ];
+
+    pub fn new() -> Self {
+        Self {
+            sort: SheetColumn::ID,
+            descending: false,
+            filter: String::new(),
+
+            header_states: SheetColumn::ALL.iter().map(|_| button::State::new()).collect(),
+            row_states: Vec::new(),
+            editor_states: BTreeMap::new(),
+            picker: ColorPicker::new(),
+            xy_pad: XYPad::new(),
+            editor_scroll: scrollable::State::new(),
+            filter_state: text_input::State::new(),
+            scroll_state: scrollable::State::new(),
+            all_state: button::State::new(),
+            none_state: button::State::new(),
+            release_state: button::State::new(),
+            clear_state: button::State::new(),
+            group_op: SelectionOp::Replace,
+            group_states: Vec::new(),
+            group_op_states: Self::GROUP_OPS.iter().map(|_| button::State::new()).collect(),
+            store_group_state: button::State::new(),
+        }
+    }
+
+    /// Fixtures matching the filter in the chosen order
+    fn rows<'p>(&self, patch: &'p Patch) -> Vec<&'p Fixture> {
+        let filter = self.filter.trim().to_lowercase();
+        let mut rows: Vec<&Fixture> = patch.fixtures()
+            .iter()
+            .filter(|f| filter.is_empty() || SheetColumn::ALL.iter()
+                .any(|c| c.cell(f).to_lowercase().contains(&filter)))
+            .collect();
+
+        match self.sort {
+            SheetColumn::ID => rows.sort_by_key(|f| f.get_id()),
+            SheetColumn::Address => rows.sort_by_key(|f| (f.get_universe(), f.get_address())),
+            col => rows.sort_by_cached_key(|f| (col.cell(f).to_lowercase(), f.get_id())),
+        }
+        if self.descending {
+            rows.reverse();
+        }
+        rows
+    }
+
+    /// Attributes present on any of the selected fixtures
+    fn selected_attributes(selection: &Selection, patch: &Patch) -> BTreeSet<Attribute> {
+        selection.ids().iter()
+            .filter_map(|id| patch.get(*id))
+            .flat_map(|f| f.get_mode().attributes())
+            .collect()
+    }
+
+    /// Values are set in the programmer and applied right away so the sheet shows them
+    pub fn update(&mut self, msg: FixtureMsg, patch: &Patch, groups: &mut Groups, programmer: &mut Programmer, dmx: &mut dmx::DMX) {
+        match msg {
+            FixtureMsg::SortBy(col) => {
+                if self.sort == col {
+                    self.descending = !self.descending;
+                } else {
+                    self.sort = col;
+                    self.descending = false;
+                }
+            }
+            FixtureMsg::FilterChanged(s) => self.filter = s,
+            FixtureMsg::ToggleFixture(id) => programmer.selection_mut().toggle(id),
+            FixtureMsg::SelectAll => programmer.selection_mut().extend(self.rows(patch).iter().map(|f| f.get_id())),
+            FixtureMsg::SelectNone => programmer.selection_mut().clear(),
+            FixtureMsg::SetAttribute(attribute, value) => programmer.set_selected(patch, attribute, value),
+            FixtureMsg::SetColor(hsv) => {
+                self.picker.set_hsv(hsv);
+                programmer.set_selected_color(patch, Rgb::from_hsv(hsv));
+            }
+            FixtureMsg::SetPosition(pan, tilt) => {
+                self.xy_pad.set_position(pan, tilt);
+                programmer.set_selected_position(patch, pan, tilt);
+            }
+            FixtureMsg::SetFine(fine) => self.xy_pad.set_fine(fine),
+            FixtureMsg::ReleaseSelected => programmer.release_selected(),
+            FixtureMsg::ClearProgrammer => programmer.clear(),
+            FixtureMsg::RecallGroup(number) => {
+                if let Some(group) = groups.get(number) {
+                    group.select(programmer.selection_mut(), self.group_op);
+                }
+            }
+            FixtureMsg::SetGroupOp(op) => self.group_op = op,
+            FixtureMsg::StoreGroup => {
+                if !programmer.selection().is_empty() {
+                    groups.record(programmer.selection());
+                }
+            }
+        }
+        programmer.apply(patch, dmx);
+    }
+
+    fn header<'a>(states: &'a mut [button::State], sort: SheetColumn, descending: bool) -> Row<'a, ConMXMsg> {
+        let row = SheetColumn::ALL.iter()
+            .zip(states.iter_mut())
+            .fold(Row::new().spacing(5), |row, (col, state)| {
+                let title = if *col == sort {
+                    format!("{} {}", col.title(), if descending { "v" } else { "^" })
+                } else {
+                    col.title().to_owned()
+                };
+                row.push(
+                    Button::new(state, Text::new(title))
+                        .on_press(ConMXMsg::Fixtures(FixtureMsg::SortBy(*col)))
+                        .width(col.width())
+                        .style(style::ListRow)
+                )
+            });
+        ["Int.", "Color", "Pan/Tilt"].iter()
+            .fold(row, |row, title| row.push(Text::new(*title).width(Length::Units(Self::VALUE_WIDTH))))
+    }
+
+    /// Buttons recalling the groups by number with the chosen operation, followed by a button to store the selection
+    fn group_bar<'a>(op_states: &'a mut [button::State], group_states: &'a mut [button::State], store_state: &'a mut button::State, group_op: SelectionOp, groups: &Groups) -> Row<'a, ConMXMsg> {
+        let row = Self::GROUP_OPS.iter()
+            .zip(op_states.iter_mut())
+            .fold(Row::new().spacing(5).padding(5).align_items(Align::Center), |row, ((op, label), state)| {
+                let button = Button::new(state, Text::new(*label))
+                    .on_press(ConMXMsg::Fixtures(FixtureMsg::SetGroupOp(*op)));
+                row.push(if *op == group_op { button.style(style::Selected) } else { button.style(style::Theme) })
+            });
+        let row = groups.iter()
+            .zip(group_states.iter_mut())
+            .fold(row, |row, (group, state)| {
+                row.push(
+                    Button::new(state, Text::new(format!("{} {}", group.number, group.name)))
+                        .on_press(ConMXMsg::Fixtures(FixtureMsg::RecallGroup(group.number)))
+                        .style(style::Theme)
+                )
+            });
+        row.push(
+            Button::new(store_state, Text::new("Store group"))
+                .on_press(ConMXMsg::Fixtures(FixtureMsg::StoreGroup))
+                .style(style::Theme)
+        )
+    }
+
+    fn fixture_row<'a>(state: &'a mut button::State, fixture: &Fixture, dmx: &dmx::DMX, selected: bool) -> Element<'a, ConMXMsg> {
+        let percent = |a| fixture.get_attribute(dmx, a)
+            .map(|v| format!("{:.0}%", v * 100.))
+            .unwrap_or_else(|| "-".to_owned());
+
+        let intensity = fixture.get_attribute(dmx, Attribute::Intensity).unwrap_or(1.);
+        let swatch: Element<ConMXMsg> = match fixture.get_color(dmx) {
+            Some(rgb) => Container::new(Space::new(Length::Units(Self::SWATCH_SIZE), Length::Units(Self::SWATCH_SIZE)))
+                .style(style::Swatch(Color::from_rgb(rgb.r * intensity, rgb.g * intensity, rgb.b * intensity)))
+                .into(),
+            None => Text::new("-").into(),
+        };
+        let pan_tilt = if fixture.has_attribute(Attribute::Pan) || fixture.has_attribute(Attribute::Tilt) {
+            format!("{}/{}", percent(Attribute::Pan), percent(Attribute::Tilt))
+        } else {
+            "-".to_owned()
+        };
+
+        let cells = SheetColumn::ALL.iter()
+            .fold(Row::new().spacing(5).align_items(Align::Center), |row, col| {
+                row.push(Text::new(col.cell(fixture)).width(col.width()))
+            })
+            .push(Text::new(percent(Attribute::Intensity)).width(Length::Units(Self::VALUE_WIDTH)))
+            .push(Container::new(swatch).width(Length::Units(Self::VALUE_WIDTH)))
+            .push(Text::new(pan_tilt).width(Length::Units(Self::VALUE_WIDTH)));
+
+        let row = Button::new(state, cells)
+            .on_press(ConMXMsg::Fixtures(FixtureMsg::ToggleFixture(fixture.get_id())))
+            .width(Length::Fill);
+        if selected {
+            row.style(style::Selected).into()
+        } else {
+            row.style(style::ListRow).into()
+        }
+    }
+
+    /// Colour picker, XY pad and sliders for every attribute of the selection, showing the values of the first selected fixture
+    /// Attributes touched in the programmer are highlighted.
+    fn editor<'a>(states: &'a mut BTreeMap<Attribute, slider::State>, picker: &'a mut ColorPicker, xy_pad: &'a mut XYPad, programmer: &Programmer, patch: &Patch, dmx: &dmx::DMX) -> Column<'a, ConMXMsg> {
+        let selection = programmer.selection();
+        let attributes = Self::selected_attributes(selection, patch);
+        states.retain(|a, _| attributes.contains(a));
+        for c_attr in attributes.iter() {
+            states.entry(*c_attr).or_insert_with(slider::State::new);
+        }
+
+        let mut col = Column::new()
+            .spacing(5)
+            .padding(5)
+            .width(Length::Units(300))
+            .push(Text::new(format!("{} fixtures selected", selection.len())).size(20));
+
+        let color = selection.ids().iter()
+            .filter_map(|id| patch.get(*id))
+            .find_map(|f| f.get_color(dmx));
+        if let Some(color) = color {
+            let xy = color.to_xy()
+                .map(|xy| format!("x {:.3} y {:.3}", xy.x, xy.y))
+                .unwrap_or_default();
+            col = col
+                .push(picker.view().map(|hsv| ConMXMsg::Fixtures(FixtureMsg::SetColor(hsv))))
+                .push(Text::new(format!("{}  {}", color.to_hex(), xy)).size(16));
+        }
+
+        let moving = selection.ids().iter()
+            .filter_map(|id| patch.get(*id))
+            .find_map(|f| f.get_position(dmx).map(|pos| (f.get_type().position_range, pos)));
+        if let Some((range, (pan, tilt))) = moving {
+            xy_pad.set_range(range);
+            xy_pad.set_position(pan, tilt);
+            let fine = xy_pad.is_fine();
+            col = col
+                .push(xy_pad.view().map(|(pan, tilt)| ConMXMsg::Fixtures(FixtureMsg::SetPosition(pan, tilt))))
+                .push(
+                    Row::new()
+                        .spacing(10)
+                        .align_items(Align::Center)
+                        .push(Text::new(format!("Pan {:.1}°  Tilt {:.1}°", pan, tilt)).size(16).width(Length::Fill))
+                        .push(Checkbox::new(fine, "Fine", |f| ConMXMsg::Fixtures(FixtureMsg::SetFine(f))).style(style::Theme))
+                );
+        }
+
+        states.iter_mut()
+            .fold(col, |col, (attr, state)| {
+                let attr = *attr;
+                let value = selection.ids().iter()
+                    .filter_map(|id| patch.get(*id))
+                    .find_map(|f| f.get_attribute(dmx, attr))
+                    .unwrap_or(0.);
+                let touched = selection.ids().iter().any(|id| programmer.is_touched(*id, attr));
+                let name = Text::new(attr.name()).width(Length::Units(90));
+                col.push(
+                    Row::new()
+                        .spacing(10)
+                        .align_items(Align::Center)
+                        .push(if touched { name.color(style::ACCENT) } else { name })
+                        .push(
+                            Slider::new(state, 0.0..=1.0, value, move |v| ConMXMsg::Fixtures(FixtureMsg::SetAttribute(attr, v)))
+                                .step(0.001)
+                                .width(Length::Fill)
+                                .style(style::Theme)
+                        )
+                        .push(Text::new(format!("{:.0}%", value * 100.)).width(Length::Units(45)))
+                )
+            })
+    }
+
+    pub fn view<'a>(&'a mut self, patch: &Patch, groups: &Groups, programmer: &Programmer, dmx: &dmx::DMX) -> Element<'a, ConMXMsg> {
+        let selection = programmer.selection();
+        let rows: Vec<&Fixture> = self.rows(patch);
+        if self.row_states.len() < rows.len() {
+            self.row_states.resize_with(rows.len(), button::State::new);
+        }
+        if self.group_states.len() < groups.len() {
+            self.group_states.resize_with(groups.len(), button::State::new);
+        }
+        let header = Self::header(&mut self.header_states, self.sort, self.descending);
+        let group_bar = Self::group_bar(&mut self.group_op_states, &mut self.group_states, &mut self.store_group_state, self.group_op, groups);
+
+        let controls = Row::new()
+            .spacing(10)
+            .padding(5)
+            .align_items(Align::Center)
+            .push(
+                TextInput::new(&mut self.filter_state, "Filter", &self.filter, |s| ConMXMsg::Fixtures(FixtureMsg::FilterChanged(s)))
+                    .width(Length::Units(200))
+                    .padding(5)
+                    .style(style::Theme)
+            )
+            .push(Text::new(format!("{} of {} fixtures", rows.len(), patch.fixtures().len())))
+            .push(
+                Button::new(&mut self.all_state, Text::new("All"))
+                    .on_press(ConMXMsg::Fixtures(FixtureMsg::SelectAll))
+                    .style(style::Theme)
+            )
+            .push(
+                Button::new(&mut self.none_state, Text::new("None"))
+                    .on_press(ConMXMsg::Fixtures(FixtureMsg::SelectNone))
+                    .style(style::Theme)
+            )
+            .push(Space::with_width(Length::Fill))
+            .push(Text::new(format!("{} values in programmer", programmer.touched().len())))
+            .push(
+                Button::new(&mut self.release_state, Text::new("Release"))
+                    .on_press(ConMXMsg::Fixtures(FixtureMsg::ReleaseSelected))
+                    .style(style::Theme)
+            )
+            .push(
+                Button::new(&mut self.clear_state, Text::new("Clear"))
+                    .on_press(ConMXMsg::Fixtures(FixtureMsg::ClearProgrammer))
+                    .style(style::Theme)
+            );
+
+        let table = rows.iter()
+            .zip(self.row_states.iter_mut())
+            .fold(Column::new().spacing(2).padding(5).push(header), |col, (fixture, state)| {
+                col.push(Self::fixture_row(state, fixture, dmx, selection.contains(fixture.get_id())))
+            });
+
+        let sheet = Column::new()
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .push(controls)
+            .push(group_bar)
+            .push(
+                Scrollable::new(&mut self.scroll_state)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .push(table)
+                    .style(style::Theme)
+            );
+
+        Row::new()
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .push(sheet)
+            .push(
+                Scrollable::new(&mut self.editor_scroll)
+                    .width(Length::Units(300))
+                    .height(Length::Fill)
+                    .push(Self::editor(&mut self.editor_states, &mut self.picker, &mut self.xy_pad, programmer, patch, dmx))
+                    .style(style::Theme)
+            )
+            .into()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::fixture::library;
+
+    fn test_patch() -> Patch {
+        let mut patch = Patch::new();
+        let rgb = Arc::new(library::generic_rgb());
+        let dimmer = Arc::new(library::generic_dimmer());
+        patch.add("Wash".to_owned(), rgb.clone(), 0, 1, 0).unwrap();
+        patch.add("Back".to_owned(), rgb, 0, 0, 20).unwrap();
+        patch.add("Cyc".to_owned(), dimmer, 0, 0, 3).unwrap();
+        patch
+    }
+
+    #[test]
+    fn test_sort_and_filter() {
+        let patch = test_patch();
+        let mut dmx = dmx::DMX::new();
+        let mut groups = Groups::new();
+        let mut prog = Programmer::new();
+        let mut sheet = FixtureSheet::new();
+        let names = |sheet: &FixtureSheet| sheet.rows(&patch).iter().map(|f| f.get_name().to_owned()).collect::<Vec<_>>();
+
+        assert_eq!(names(&sheet), vec!["Wash", "Back", "Cyc"]);
+
+        sheet.update(FixtureMsg::SortBy(SheetColumn::Name), &patch, &mut groups, &mut prog, &mut dmx);
+        assert_eq!(names(&sheet), vec!["Back", "Cyc", "Wash"]);
+        sheet.update(FixtureMsg::SortBy(SheetColumn::Name), &patch, &mut groups, &mut prog, &mut dmx);
+        assert_eq!(names(&sheet), vec!["Wash", "Cyc", "Back"]);
+
+        sheet.update(FixtureMsg::SortBy(SheetColumn::Address), &patch, &mut groups, &mut prog, &mut dmx);
+        assert_eq!(names(&sheet), vec!["Cyc", "Back", "Wash"]);
+
+        sheet.update(FixtureMsg::FilterChanged("rgb".to_owned()), &patch, &mut groups, &mut prog, &mut dmx);
+        assert_eq!(names(&sheet), vec!["Back", "Wash"]);
+
+        // Select all only takes visible fixtures
+        sheet.update(FixtureMsg::SelectAll, &patch, &mut groups, &mut prog, &mut dmx);
+        assert_eq!(prog.selection().ids(), &[2, 1]);
+    }
+
+    #[test]
+    fn test_edit_selection() {
+        let patch = test_patch();
+        let mut dmx = dmx::DMX::new();
+        dmx.add_universe(dmx::DMXUniverse::new(0))
+            .add_universe(dmx::DMXUniverse::new(1));
+        let mut groups = Groups::new();
+        let mut prog = Programmer::new();
+        let mut sheet = FixtureSheet::new();
+
+        sheet.update(FixtureMsg::ToggleFixture(1), &patch, &mut groups, &mut prog, &mut dmx);
+        sheet.update(FixtureMsg::ToggleFixture(3), &patch, &mut groups, &mut prog, &mut dmx);
+        assert!(FixtureSheet::selected_attributes(prog.selection(), &patch).contains(&Attribute::Intensity));
+
+        sheet.update(FixtureMsg::SetAttribute(Attribute::Red, 1.), &patch, &mut groups, &mut prog, &mut dmx);
+        sheet.update(FixtureMsg::SetAttribute(Attribute::Intensity, 0.5), &patch, &mut groups, &mut prog, &mut dmx);
+        assert_eq!(dmx.get_universe_ref(1).unwrap().get_channel(0).unwrap().get_val(), 255);
+        assert_eq!(dmx.get_universe_ref(0).unwrap().get_channel(3).unwrap().get_val(), 128);
+        // Unselected fixtures stay untouched
+        assert_eq!(dmx.get_universe_ref(0).unwrap().get_channel(20).unwrap().get_val(), 0);
+
+        sheet.update(FixtureMsg::SetColor(Hsv::new(240., 1., 1.)), &patch, &mut groups, &mut prog, &mut dmx);
+        assert_eq!(patch.get(1).unwrap().get_color(&dmx), Some(Rgb::new(0., 0., 1.)));
+        assert!(prog.is_touched(1, Attribute::Blue) && !prog.is_touched(2, Attribute::Blue));
+
+        // Clearing hands the channels back to the values underneath
+        dmx.get_universe(1).unwrap().set_channel(0, 10);
+        sheet.update(FixtureMsg::ClearProgrammer, &patch, &mut groups, &mut prog, &mut dmx);
+        assert_eq!(dmx.get_universe_ref(1).unwrap().get_channel(0).unwrap().get_val(), 10);
+        assert!(prog.is_empty() && prog.selection().is_empty());
+    }
+
+    #[test]
+    fn test_groups() {
+        let patch = test_patch();
+        let mut dmx = dmx::DMX::new();
+        let mut groups = Groups::new();
+        let mut prog = Programmer::new();
+        let mut sheet = FixtureSheet::new();
+
+        // Nothing is stored without a selection
+        sheet.update(FixtureMsg::StoreGroup, &patch, &mut groups, &mut prog, &mut dmx);
+        assert!(groups.is_empty());
+
+        prog.selection_mut().set(vec![3, 1]);
+        sheet.update(FixtureMsg::StoreGroup, &patch, &mut groups, &mut prog, &mut dmx);
+        prog.selection_mut().set(vec![2]);
+        sheet.update(FixtureMsg::StoreGroup, &patch, &mut groups, &mut prog, &mut dmx);
+
+        sheet.update(FixtureMsg::RecallGroup(1), &patch, &mut groups, &mut prog, &mut dmx);
+        assert_eq!(prog.selection().ids(), &[3, 1]);
+        sheet.update(FixtureMsg::SetGroupOp(SelectionOp::Add), &patch, &mut groups, &mut prog, &mut dmx);
+        sheet.update(FixtureMsg::RecallGroup(2), &patch, &mut groups, &mut prog, &mut dmx);
+        assert_eq!(prog.selection().ids(), &[3, 1, 2]);
+        sheet.update(FixtureMsg::SetGroupOp(SelectionOp::Intersect), &patch, &mut groups, &mut prog, &mut dmx);
+        sheet.update(FixtureMsg::RecallGroup(1), &patch, &mut groups, &mut prog, &mut dmx);
+        assert_eq!(prog.selection().ids(), &[3, 1]);
+        sheet.update(FixtureMsg::RecallGroup(9), &patch, &mut groups, &mut prog, &mut dmx);
+        assert_eq!(prog.selection().len(), 2);
+    }
+}
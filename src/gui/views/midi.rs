@@ -0,0 +1,190 @@
+use iced::{
+    button,
+    scrollable,
+    text_input,
+    Align,
+    Button,
+    Checkbox,
+    Column,
+    Element,
+    Length,
+    Row,
+    Scrollable,
+    Text,
+    TextInput,
+};
+
+use log::warn;
+
+use crate::{
+    gui::style,
+    midi::{
+        CcMode,
+        MidiControl,
+        MidiMap,
+        MidiTarget,
+    },
+};
+
+use super::super::conmx::ConMXMsg;
+
+#[derive(Debug, Clone)]
+pub enum MidiMsg {
+    EntryChanged(String),
+    /// Bind the target in the entry to the next control moved on the device,
+    /// without a target the next control touched on screen is taken
+    Learn,
+    CancelLearn,
+    SetRelative(usize, bool),
+    Remove(usize),
+}
+
+/// List of MIDI mappings and learning new ones
+#[derive(Debug)]
+pub struct MidiView {
+    entry: String,
+    /// Waiting for a control touched on screen
+    picking: bool,
+
+    entry_state: text_input::State,
+    learn_state: button::State,
+    remove_states: Vec<button::State>,
+    scroll_state: scrollable::State,
+}
+
+impl MidiView {
+    pub fn new() -> Self {
+        Self {
+            entry: String::new(),
+            picking: false,
+
+            entry_state: text_input::State::new(),
+            learn_state: button::State::new(),
+            remove_states: Vec::new(),
+            scroll_state: scrollable::State::new(),
+        }
+    }
+
+    pub fn update(&mut self, msg: MidiMsg, map: &mut MidiMap) {
+        match msg {
+            MidiMsg::EntryChanged(s) => self.entry = s,
+            MidiMsg::Learn if self.entry.trim().is_empty() => self.picking = true,
+            MidiMsg::Learn => match self.entry.parse::<MidiTarget>() {
+                Ok(target) => map.learn(target),
+                Err(e) => warn!("{}", e),
+            },
+            MidiMsg::CancelLearn => {
+                self.picking = false;
+                map.cancel_learn();
+            }
+            MidiMsg::SetRelative(i, relative) => map.set_mode(i, if relative { CcMode::Relative } else { CcMode::Absolute }),
+            MidiMsg::Remove(i) => {
+                map.remove(i);
+            }
+        }
+    }
+
+    /// Take a control touched on screen as target to learn, if waiting for one
+    pub fn pick(&mut self, target: MidiTarget, map: &mut MidiMap) {
+        if self.picking {
+            self.picking = false;
+            self.entry = target.to_string();
+            map.learn(target);
+        }
+    }
+
+    pub fn view<'a>(&'a mut self, map: &MidiMap) -> Element<'a, ConMXMsg> {
+        self.remove_states.resize_with(map.len(), button::State::new);
+
+        let status = match (map.learning(), self.picking) {
+            (Some(target), _) => format!("Move a control on the device for {}", target),
+            (None, true) => "Touch a fader or button on screen".to_owned(),
+            (None, false) => format!("{} mappings", map.len()),
+        };
+        let learning = map.learning().is_some() || self.picking;
+        let learn = Button::new(&mut self.learn_state, Text::new(if learning { "Cancel" } else { "Learn" }))
+            .on_press(ConMXMsg::Midi(if learning { MidiMsg::CancelLearn } else { MidiMsg::Learn }));
+        let learn = if learning { learn.style(style::Selected) } else { learn.style(style::Theme) };
+        let controls = Row::new()
+            .spacing(10)
+            .padding(5)
+            .align_items(Align::Center)
+            .push(
+                TextInput::new(&mut self.entry_state, "Target, e.g. /conmx/executor/1/fader", &self.entry, |s| ConMXMsg::Midi(MidiMsg::EntryChanged(s)))
+                    .on_submit(ConMXMsg::Midi(MidiMsg::Learn))
+                    .width(Length::Units(400))
+                    .padding(5)
+                    .style(style::Theme)
+            )
+            .push(learn)
+            .push(Text::new(status));
+
+        let mappings = map.mappings().iter()
+            .zip(self.remove_states.iter_mut())
+            .enumerate()
+            .fold(Column::new().spacing(5).padding(5), |col, (i, (c_mapping, remove_state))| {
+                let mut row = Row::new()
+                    .spacing(10)
+                    .align_items(Align::Center)
+                    .push(Text::new(c_mapping.control.to_string()).width(Length::Units(150)))
+                    .push(Text::new(c_mapping.target.to_string()).width(Length::Fill));
+                if let MidiControl::Control { .. } = c_mapping.control {
+                    row = row.push(
+                        Checkbox::new(c_mapping.mode == CcMode::Relative, "Relative", move |r| ConMXMsg::Midi(MidiMsg::SetRelative(i, r)))
+                            .style(style::Theme)
+                    );
+                }
+                col.push(row.push(
+                    Button::new(remove_state, Text::new("Remove"))
+                        .on_press(ConMXMsg::Midi(MidiMsg::Remove(i)))
+                        .style(style::Theme)
+                ))
+            });
+
+        Column::new()
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .push(controls)
+            .push(
+                Scrollable::new(&mut self.scroll_state)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .push(mappings)
+                    .style(style::Theme)
+            )
+            .into()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::midi::MidiMessage;
+
+    #[test]
+    fn test_learn() {
+        let mut map = MidiMap::new();
+        let mut view = MidiView::new();
+
+        view.update(MidiMsg::EntryChanged("/conmx/executor/2/go".to_owned()), &mut map);
+        view.update(MidiMsg::Learn, &mut map);
+        assert_eq!(map.learning(), Some(&MidiTarget::Go(1)));
+        view.update(MidiMsg::CancelLearn, &mut map);
+        assert_eq!(map.learning(), None);
+
+        // Without a target the next control touched on screen is learned
+        view.update(MidiMsg::EntryChanged(String::new()), &mut map);
+        view.update(MidiMsg::Learn, &mut map);
+        view.pick(MidiTarget::Fader(0), &mut map);
+        view.pick(MidiTarget::Fader(1), &mut map);
+        assert_eq!(view.entry, "/conmx/executor/1/fader");
+        map.handle(&MidiMessage::ControlChange { channel: 0, controller: 1, value: 0 });
+        assert_eq!(map.mappings()[0].target, MidiTarget::Fader(0));
+
+        view.update(MidiMsg::SetRelative(0, true), &mut map);
+        assert_eq!(map.mappings()[0].mode, CcMode::Relative);
+        view.update(MidiMsg::Remove(0), &mut map);
+        assert!(map.is_empty());
+    }
+}
@@ -1 +1,7 @@
 pub mod tabview;
+pub mod direct;
+pub mod monitor;
+pub mod fixtures;
+pub mod playback;
+pub mod palettes;
+pub mod midi;
@@ -0,0 +1,310 @@
+use iced::{
+    canvas,
+    pick_list,
+    Align,
+    Color,
+    Element,
+    Length,
+    PickList,
+    Point,
+    Rectangle,
+    Row,
+    Column,
+    Size,
+    Text,
+};
+
+use crate::{
+    dmx,
+    fixture::Patch,
+};
+
+use super::super::conmx::ConMXMsg;
+
+#[derive(Debug, Clone)]
+pub enum MonitorMsg {
+    SelectUniverse(usize),
+}
+
+/// Channels of a patched fixture as shown in the monitor
+#[derive(Debug, Clone, PartialEq)]
+struct FixtureSpan {
+    start: usize,
+    len: usize,
+    label: String,
+}
+
+/// Snapshot of a universe's output drawn as a grid of cells
+#[derive(Debug, Default)]
+struct MonitorGrid {
+    values: Vec<u32>,
+    changed: Vec<bool>,
+    overridden: Vec<bool>,
+    fixtures: Vec<FixtureSpan>,
+
+    cache: canvas::Cache,
+}
+
+impl MonitorGrid {
+    const COLUMNS: usize = 32;
+    const NUMBER_SIZE: f32 = 9.;
+    const VALUE_SIZE: f32 = 14.;
+    const LABEL_SIZE: f32 = 10.;
+    const FIXTURE_BAR: f32 = 4.;
+
+    /// Take over the current output values, marking every channel that differs from the last snapshot.
+    /// Without a previous snapshot nothing is marked as changed.
+    fn refresh(&mut self, universe: Option<&dmx::DMXUniverse>, fixtures: Vec<FixtureSpan>) {
+        let channel = |i| universe.and_then(|u| u.get_channel(i));
+        let values: Vec<u32> = (0..dmx::DMXUniverse::CHANNELS)
+            .map(|i| channel(i).map(|c| c.get_val()).unwrap_or(0))
+            .collect();
+        let overridden: Vec<bool> = (0..dmx::DMXUniverse::CHANNELS)
+            .map(|i| channel(i).map(|c| c.is_overridden()).unwrap_or(false))
+            .collect();
+        let changed: Vec<bool> = if self.values.len() == values.len() {
+            self.values.iter().zip(values.iter()).map(|(old, new)| old != new).collect()
+        } else {
+            vec![false; values.len()]
+        };
+
+        if values != self.values || changed != self.changed
+            || overridden != self.overridden || fixtures != self.fixtures
+        {
+            self.cache.clear();
+        }
+        self.values = values;
+        self.changed = changed;
+        self.overridden = overridden;
+        self.fixtures = fixtures;
+    }
+
+    /// Forget the last snapshot, e.g. after switching to another universe
+    fn reset(&mut self) {
+        self.values.clear();
+        self.changed.clear();
+        self.cache.clear();
+    }
+
+    fn rows() -> usize {
+        (dmx::DMXUniverse::CHANNELS + Self::COLUMNS - 1) / Self::COLUMNS
+    }
+
+    fn cell_rect(size: Size, channel: usize) -> Rectangle {
+        let w = size.width / Self::COLUMNS as f32;
+        let h = size.height / Self::rows() as f32;
+        Rectangle {
+            x: (channel % Self::COLUMNS) as f32 * w,
+            y: (channel / Self::COLUMNS) as f32 * h,
+            width: w,
+            height: h,
+        }
+    }
+
+    /// Cell colour fading from dark at 0 to the accent colour at full
+    fn level_color(value: u32) -> Color {
+        let low = Color::from_rgb8(0x26, 0x29, 0x2E); // #26292E
+        let high = Color::from_rgb8(0x6F, 0xFF, 0xE9); // #6FFFE9
+        let t = (value.min(255) as f32) / 255.;
+        Color::from_rgb(
+            low.r + (high.r - low.r) * t,
+            low.g + (high.g - low.g) * t,
+            low.b + (high.b - low.b) * t,
+        )
+    }
+
+    fn draw_cells(&self, frame: &mut canvas::Frame) {
+        let size = frame.size();
+        for (i, value) in self.values.iter().enumerate() {
+            let r = Self::cell_rect(size, i);
+            frame.fill_rectangle(
+                Point::new(r.x + 1., r.y + 1.),
+                Size::new(r.width - 2., r.height - 2.),
+                Self::level_color(*value),
+            );
+
+            let text_color = if *value > 160 { Color::BLACK } else { Color::WHITE };
+            frame.fill_text(canvas::Text {
+                content: format!("{}", i + 1),
+                position: Point::new(r.x + 3., r.y + 2.),
+                size: Self::NUMBER_SIZE,
+                color: Color { a: 0.6, ..text_color },
+                ..canvas::Text::default()
+            });
+            frame.fill_text(canvas::Text {
+                content: format!("{}", value),
+                position: r.center(),
+                size: Self::VALUE_SIZE,
+                color: text_color,
+                horizontal_alignment: iced::HorizontalAlignment::Center,
+                vertical_alignment: iced::VerticalAlignment::Center,
+                ..canvas::Text::default()
+            });
+
+            let outline = if self.overridden.get(i).cloned().unwrap_or(false) {
+                Some(Color::from_rgb8(0xFF, 0x8C, 0x00)) // #FF8C00
+            } else if self.changed.get(i).cloned().unwrap_or(false) {
+                Some(Color::WHITE)
+            } else {
+                None
+            };
+            if let Some(color) = outline {
+                frame.stroke(
+                    &canvas::Path::rectangle(Point::new(r.x + 1.5, r.y + 1.5), Size::new(r.width - 3., r.height - 3.)),
+                    canvas::Stroke::default()
+                        .with_color(color)
+                        .with_width(2.),
+                );
+            }
+        }
+    }
+
+    fn draw_fixtures(&self, frame: &mut canvas::Frame) {
+        let size = frame.size();
+        let bar_colors = [
+            Color::from_rgb8(0x3A, 0x7B, 0xD5), // #3A7BD5
+            Color::from_rgb8(0x8E, 0x44, 0xAD), // #8E44AD
+        ];
+
+        for (n, c_fixture) in self.fixtures.iter().enumerate() {
+            let color = bar_colors[n % bar_colors.len()];
+            for c_channel in c_fixture.start..c_fixture.start + c_fixture.len {
+                let r = Self::cell_rect(size, c_channel);
+                frame.fill_rectangle(
+                    Point::new(r.x, r.y + r.height - Self::FIXTURE_BAR),
+                    Size::new(r.width, Self::FIXTURE_BAR),
+                    color,
+                );
+            }
+
+            let first = Self::cell_rect(size, c_fixture.start);
+            frame.stroke(
+                &canvas::Path::line(first.position(), Point::new(first.x, first.y + first.height)),
+                canvas::Stroke::default()
+                    .with_color(color)
+                    .with_width(3.),
+            );
+            frame.fill_text(canvas::Text {
+                content: c_fixture.label.clone(),
+                position: Point::new(first.x + 3., first.y + first.height - Self::FIXTURE_BAR - 1.),
+                size: Self::LABEL_SIZE,
+                color: Color::WHITE,
+                vertical_alignment: iced::VerticalAlignment::Bottom,
+                ..canvas::Text::default()
+            });
+        }
+    }
+}
+
+impl canvas::Program<ConMXMsg> for MonitorGrid {
+    fn draw(&self, bounds: Rectangle, _cursor: canvas::Cursor) -> Vec<canvas::Geometry> {
+        let grid = self.cache.draw(bounds.size(), |frame| {
+            frame.fill_rectangle(Point::ORIGIN, frame.size(), Color::from_rgb8(0x22, 0x22, 0x22)); // #222222
+            self.draw_cells(frame);
+            self.draw_fixtures(frame);
+        });
+        vec![grid]
+    }
+}
+
+/// Live view of everything sent out on a universe
+#[derive(Debug)]
+pub struct MonitorView {
+    universe: usize,
+    universe_ids: Vec<usize>,
+    grid: MonitorGrid,
+
+    universe_state: pick_list::State<usize>,
+}
+
+impl MonitorView {
+    pub fn new() -> Self {
+        Self {
+            universe: 0,
+            universe_ids: Vec::new(),
+            grid: MonitorGrid::default(),
+
+            universe_state: pick_list::State::default(),
+        }
+    }
+
+    pub fn update(&mut self, msg: MonitorMsg) {
+        match msg {
+            MonitorMsg::SelectUniverse(u) => {
+                self.universe = u;
+                self.grid.reset();
+            }
+        }
+    }
+
+    /// Take a new snapshot of the monitored universe, called once per frame
+    pub fn refresh(&mut self, dmx: &dmx::DMX, patch: &Patch) {
+        let fixtures = patch.fixtures_in(self.universe)
+            .into_iter()
+            .map(|f| FixtureSpan {
+                start: f.get_address(),
+                len: f.footprint(),
+                label: format!("{} {}", f.get_id(), f.get_name()),
+            })
+            .collect();
+        self.grid.refresh(dmx.get_universe_ref(self.universe), fixtures);
+    }
+
+    pub fn view<'a>(&'a mut self, dmx: &dmx::DMX) -> Element<'a, ConMXMsg> {
+        self.universe_ids = dmx.get_universe_ids();
+        let changed = self.grid.changed.iter().filter(|c| **c).count();
+        let overridden = self.grid.overridden.iter().filter(|o| **o).count();
+
+        let controls = Row::new()
+            .spacing(10)
+            .padding(5)
+            .align_items(Align::Center)
+            .push(Text::new("Universe"))
+            .push(PickList::new(
+                &mut self.universe_state,
+                &self.universe_ids[..],
+                Some(self.universe),
+                |u| ConMXMsg::Monitor(MonitorMsg::SelectUniverse(u)),
+            ))
+            .push(Text::new(format!("{} changed, {} overridden", changed, overridden)));
+
+        Column::new()
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .push(controls)
+            .push(
+                canvas::Canvas::new(&mut self.grid)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+            )
+            .into()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_refresh_changes() {
+        let mut univ = dmx::DMXUniverse::new(0);
+        let mut grid = MonitorGrid::default();
+
+        univ.set_channel(3, 100);
+        grid.refresh(Some(&univ), Vec::new());
+        assert_eq!(grid.values[3], 100);
+        assert!(grid.changed.iter().all(|c| !c));
+
+        univ.set_channel(4, 20);
+        univ.set_override_channel(3, 255);
+        grid.refresh(Some(&univ), Vec::new());
+        assert_eq!(grid.values[3], 255);
+        assert!(grid.changed[3] && grid.changed[4] && !grid.changed[5]);
+        assert!(grid.overridden[3] && !grid.overridden[4]);
+
+        // Marks only last for a single frame
+        grid.refresh(Some(&univ), Vec::new());
+        assert!(grid.changed.iter().all(|c| !c));
+    }
+}
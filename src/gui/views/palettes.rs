@@ -0,0 +1,219 @@
+use std::collections::BTreeMap;
+
+use iced::{
+    button,
+    scrollable,
+    Align,
+    Button,
+    Checkbox,
+    Column,
+    Element,
+    HorizontalAlignment,
+    Length,
+    Row,
+    Scrollable,
+    Text,
+};
+
+use crate::{
+    dmx,
+    fixture::Patch,
+    gui::style,
+    playback::{
+        Palette,
+        PaletteID,
+        PaletteKind,
+        Palettes,
+        Programmer,
+    },
+};
+
+use super::super::conmx::ConMXMsg;
+
+#[derive(Debug, Clone)]
+pub enum PaletteMsg {
+    /// Apply the palette to the selection, or store the programmer into it in update mode
+    Press(PaletteID),
+    Record(PaletteKind),
+    /// Record values per fixture type instead of per fixture
+    SetGlobal(bool),
+    SetUpdate(bool),
+}
+
+/// Grid of palette buttons, one column per kind
+#[derive(Debug)]
+pub struct PaletteView {
+    global: bool,
+    update_mode: bool,
+
+    palette_states: BTreeMap<PaletteID, button::State>,
+    record_states: Vec<button::State>,
+    scroll_state: scrollable::State,
+}
+
+impl PaletteView {
+    const PER_ROW: usize = 4;
+    const BUTTON_SIZE: u16 = 90;
+
+    pub fn new() -> Self {
+        Self {
+            global: false,
+            update_mode: false,
+
+            palette_states: BTreeMap::new(),
+            record_states: PaletteKind::ALL.iter().map(|_| button::State::new()).collect(),
+            scroll_state: scrollable::State::new(),
+        }
+    }
+
+    fn record_into(&self, palette: &mut Palette, programmer: &Programmer, patch: &Patch) {
+        if self.global {
+            palette.record_global(programmer, patch);
+        } else {
+            palette.record(programmer);
+        }
+    }
+
+    /// Palette values are set in the programmer and applied right away like in the fixture sheet
+    pub fn update(&mut self, msg: PaletteMsg, palettes: &mut Palettes, patch: &Patch, programmer: &mut Programmer, dmx: &mut dmx::DMX) {
+        match msg {
+            PaletteMsg::Press(id) if self.update_mode => {
+                if let Some(palette) = palettes.get_mut(id) {
+                    self.record_into(palette, programmer, patch);
+                }
+                self.update_mode = false;
+            }
+            PaletteMsg::Press(id) => {
+                if let Some(palette) = palettes.get(id) {
+                    programmer.set_selected_palette(patch, palette);
+                }
+            }
+            PaletteMsg::Record(kind) => {
+                let id = palettes.next_id();
+                let mut palette = Palette::new(id, &format!("{} {}", kind.name(), id), kind);
+                self.record_into(&mut palette, programmer, patch);
+                if !palette.is_empty() {
+                    palettes.store(palette);
+                }
+            }
+            PaletteMsg::SetGlobal(global) => self.global = global,
+            PaletteMsg::SetUpdate(update_mode) => self.update_mode = update_mode,
+        }
+        programmer.apply(patch, dmx);
+    }
+
+    fn palette_button<'a>(state: &'a mut button::State, palette: &Palette, active: bool) -> Button<'a, ConMXMsg> {
+        let button = Button::new(
+                state,
+                Text::new(format!("{}\n{}", palette.id, palette.name))
+                    .size(16)
+                    .horizontal_alignment(HorizontalAlignment::Center)
+            )
+            .on_press(ConMXMsg::Palettes(PaletteMsg::Press(palette.id)))
+            .width(Length::Units(Self::BUTTON_SIZE))
+            .height(Length::Units(Self::BUTTON_SIZE / 2));
+        if active { button.style(style::Selected) } else { button.style(style::Theme) }
+    }
+
+    /// Palettes used by the selected fixtures in the programmer are highlighted
+    pub fn view<'a>(&'a mut self, palettes: &Palettes, programmer: &Programmer) -> Element<'a, ConMXMsg> {
+        self.palette_states.retain(|id, _| palettes.get(*id).is_some());
+        for c_palette in palettes.iter() {
+            self.palette_states.entry(c_palette.id).or_insert_with(button::State::new);
+        }
+        let selection = programmer.selection();
+        let in_use = |palette: &Palette| selection.ids().iter()
+            .any(|id| programmer.palette(*id, palette.kind) == Some(palette.id));
+
+        let options = Row::new()
+            .spacing(20)
+            .padding(5)
+            .align_items(Align::Center)
+            .push(Checkbox::new(self.global, "Record per fixture type", |g| ConMXMsg::Palettes(PaletteMsg::SetGlobal(g))).style(style::Theme))
+            .push(Checkbox::new(self.update_mode, "Update palette on press", |u| ConMXMsg::Palettes(PaletteMsg::SetUpdate(u))).style(style::Theme));
+
+        let mut states: BTreeMap<PaletteID, &mut button::State> = self.palette_states.iter_mut()
+            .map(|(id, state)| (*id, state))
+            .collect();
+        let columns = PaletteKind::ALL.iter()
+            .zip(self.record_states.iter_mut())
+            .fold(Row::new().spacing(20).padding(5), |row, (kind, record_state)| {
+                let kind = *kind;
+                let mut column = Column::new()
+                    .spacing(5)
+                    .push(Text::new(kind.name()).size(20))
+                    .push(
+                        Button::new(record_state, Text::new("Record"))
+                            .on_press(ConMXMsg::Palettes(PaletteMsg::Record(kind)))
+                            .style(style::Theme)
+                    );
+                let mut line = Row::new().spacing(5);
+                for (i, c_palette) in palettes.of_kind(kind).enumerate() {
+                    if i > 0 && i % Self::PER_ROW == 0 {
+                        column = column.push(line);
+                        line = Row::new().spacing(5);
+                    }
+                    if let Some(state) = states.remove(&c_palette.id) {
+                        line = line.push(Self::palette_button(state, c_palette, in_use(c_palette)));
+                    }
+                }
+                row.push(column.push(line))
+            });
+
+        Column::new()
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .push(options)
+            .push(
+                Scrollable::new(&mut self.scroll_state)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .push(columns)
+                    .style(style::Theme)
+            )
+            .into()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::fixture::{
+        library,
+        Attribute,
+    };
+
+    #[test]
+    fn test_record_and_update() {
+        let mut patch = Patch::new();
+        let rgb = Arc::new(library::generic_rgb());
+        let a = patch.add("Par 1".to_owned(), rgb.clone(), 0, 0, 0).unwrap();
+        let b = patch.add("Par 2".to_owned(), rgb, 0, 0, 4).unwrap();
+        let mut dmx = dmx::DMX::new();
+        dmx.add_universe(dmx::DMXUniverse::new(0));
+        let mut palettes = Palettes::new();
+        let mut prog = Programmer::new();
+        let mut view = PaletteView::new();
+
+        prog.set(a, Attribute::Red, 1.);
+        view.update(PaletteMsg::SetGlobal(true), &mut palettes, &patch, &mut prog, &mut dmx);
+        view.update(PaletteMsg::Record(PaletteKind::Color), &mut palettes, &patch, &mut prog, &mut dmx);
+        // Nothing of the kind is touched
+        view.update(PaletteMsg::Record(PaletteKind::Position), &mut palettes, &patch, &mut prog, &mut dmx);
+        assert_eq!(palettes.len(), 1);
+
+        prog.clear();
+        prog.selection_mut().set(vec![b]);
+        view.update(PaletteMsg::Press(1), &mut palettes, &patch, &mut prog, &mut dmx);
+        assert_eq!(prog.get(b, Attribute::Red), Some(1.));
+        assert_eq!(dmx.get_universe_ref(0).unwrap().get_channel(4).unwrap().get_val(), 255);
+
+        prog.set(b, Attribute::Red, 0.5);
+        view.update(PaletteMsg::SetUpdate(true), &mut palettes, &patch, &mut prog, &mut dmx);
+        view.update(PaletteMsg::Press(1), &mut palettes, &patch, &mut prog, &mut dmx);
+        assert_eq!(palettes.get(1).unwrap().values(patch.get(a).unwrap()).get(&Attribute::Red), Some(&0.5));
+        assert!(!view.update_mode);
+    }
+}
@@ -0,0 +1,132 @@
+use iced::{
+    button,
+    slider,
+    Align,
+    Button,
+    Color,
+    Column,
+    Element,
+    HorizontalAlignment,
+    Length,
+    Row,
+    Slider,
+    Text,
+};
+
+use crate::{
+    gui::{
+        components::flash_button::FlashButton,
+        style,
+    },
+    playback::{
+        Executors,
+        Playables,
+    },
+};
+
+use super::super::conmx::ConMXMsg;
+
+#[derive(Debug, Clone)]
+pub enum PlaybackMsg {
+    SetLevel(usize, f32),
+    Flash(usize, bool),
+    Go(usize),
+    Back(usize),
+    Pause(usize),
+    Release(usize),
+}
+
+#[derive(Debug)]
+struct ExecutorStrip {
+    fader: slider::State,
+    go: button::State,
+    back: button::State,
+    pause: button::State,
+    release: button::State,
+    flash: FlashButton,
+}
+
+impl ExecutorStrip {
+    fn new() -> Self {
+        Self {
+            fader: slider::State::new(),
+            go: button::State::new(),
+            back: button::State::new(),
+            pause: button::State::new(),
+            release: button::State::new(),
+            flash: FlashButton::new("Flash"),
+        }
+    }
+}
+
+/// Row of playback faders, one strip per executor
+#[derive(Debug)]
+pub struct PlaybackBar {
+    strips: Vec<ExecutorStrip>,
+}
+
+impl PlaybackBar {
+    const STRIP_WIDTH: u16 = 150;
+
+    pub fn new() -> Self {
+        Self {
+            strips: Vec::new(),
+        }
+    }
+
+    pub fn update(&mut self, msg: PlaybackMsg, executors: &mut Executors, content: Playables) {
+        match msg {
+            PlaybackMsg::SetLevel(i, level) => executors.set_level(i, level, content),
+            PlaybackMsg::Flash(i, pressed) => executors.flash(i, pressed, content),
+            PlaybackMsg::Go(i) => executors.go(i, content),
+            PlaybackMsg::Back(i) => executors.back(i, content),
+            PlaybackMsg::Pause(i) => executors.pause(i),
+            PlaybackMsg::Release(i) => executors.release(i),
+        }
+    }
+
+    fn small_button<'a>(state: &'a mut button::State, label: &str, msg: PlaybackMsg) -> Button<'a, ConMXMsg> {
+        Button::new(state, Text::new(label).size(16).horizontal_alignment(HorizontalAlignment::Center))
+            .on_press(ConMXMsg::Playback(msg))
+            .width(Length::Fill)
+            .style(style::Theme)
+    }
+
+    pub fn view<'a>(&'a mut self, executors: &Executors, content: Playables) -> Element<'a, ConMXMsg> {
+        if self.strips.len() < executors.len() {
+            self.strips.resize_with(executors.len(), ExecutorStrip::new);
+        }
+
+        executors.iter()
+            .zip(self.strips.iter_mut())
+            .enumerate()
+            .fold(Row::new().spacing(5).padding(5).width(Length::Fill), |row, (i, (exec, strip))| {
+                let title = Text::new(format!("{} {}", exec.name, exec.status(content)))
+                    .size(16)
+                    .color(if exec.is_active() { style::ACCENT } else { Color::WHITE });
+                let buttons = Row::new()
+                    .spacing(2)
+                    .push(Self::small_button(&mut strip.back, "<", PlaybackMsg::Back(i)))
+                    .push(Self::small_button(&mut strip.pause, "||", PlaybackMsg::Pause(i)))
+                    .push(Self::small_button(&mut strip.release, "Off", PlaybackMsg::Release(i)))
+                    .push(Self::small_button(&mut strip.go, "Go", PlaybackMsg::Go(i)));
+
+                row.push(
+                    Column::new()
+                        .spacing(3)
+                        .width(Length::Units(Self::STRIP_WIDTH))
+                        .align_items(Align::Center)
+                        .push(title)
+                        .push(
+                            Slider::new(&mut strip.fader, 0.0..=1.0, exec.get_level(),
+                                move |v| ConMXMsg::Playback(PlaybackMsg::SetLevel(i, v)))
+                                .step(0.01)
+                                .style(style::Theme)
+                        )
+                        .push(buttons)
+                        .push(strip.flash.view().map(move |pressed| ConMXMsg::Playback(PlaybackMsg::Flash(i, pressed))))
+                )
+            })
+            .into()
+    }
+}
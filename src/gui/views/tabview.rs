@@ -1,68 +1,60 @@
-/*use std::sync::atomic::{
-    AtomicUsize,
-    Ordering,
+use iced::{
+    button,
+    Button,
+    Element,
+    HorizontalAlignment,
+    Length,
+    Row,
+    Text,
 };
 
+use crate::gui::style;
 
-static TAB_ID : AtomicUsize = AtomicUsize::new(0);
-fn get_id() -> usize { TAB_ID.fetch_add(1, Ordering::SeqCst) }
-
-#[derive(Debug)]
-struct Tab(usize, String);
-impl Tab {
-    pub fn new(s: String) -> Self { Tab(get_id(), s) }
-}
+use super::super::conmx::{
+    ConMXMsg,
+    ViewMain,
+};
 
 #[derive(Debug)]
-pub enum TabMsg {
-    SwitchTab(usize)
-}
-
-#[derive(Debug)]
-pub struct TabViewBuilder {
-    tabs: Vec<Tab>,
-}
-
-impl TabViewBuilder {
-    pub fn tab(self, name: String) -> TabViewBuilder { self.tabs.push(Tab(get_id(), name)); self }
-    pub fn build(self) -> TabView { TabView { tabs: self.tabs } }
+struct Tab {
+    view: ViewMain,
+    state: button::State,
 }
 
+/// Row of buttons switching between the main views
 #[derive(Debug)]
 pub struct TabView {
     tabs: Vec<Tab>,
 }
 
 impl TabView {
-    pub fn new() -> TabViewBuilder { TabViewBuilder { tabs: Vec::new() } }
-}
-
-impl Widget<TabMsg, Renderer> for TabView {
-    fn width(&self) -> Length { Length::Fill }
-
-    fn height(&self) -> Length { Length::Fill }
-
-    fn layout(&self,
-        _renderer: &Renderer,
-        _limits: &layout::Limits,
-    ) -> layout::Node {
-        layout::Node::new(Size::new(40.0, 100.0))
-    }
-
-    fn draw(
-        &self,
-        _renderer: &mut Renderer,
-        _defaults: &Defaults,
-        layout: Layout<'_>,
-        _cursor_pos: Point,
-        _viewp: &Rectangle,
-        ) -> (Primitive, mouse::Interaction) {
-        (
-            Primitive::Quad,
-            mouse::Interaction::Crosshair
-        )
+    pub fn new() -> Self {
+        Self {
+            tabs: ViewMain::ALL
+                .iter()
+                .map(|view| Tab { view: *view, state: button::State::new() })
+                .collect(),
+        }
     }
 
-    fn hash_layout(&self, state: &mut Hasher) {
+    pub fn view<'a>(&'a mut self, active: ViewMain) -> Element<'a, ConMXMsg> {
+        self.tabs
+            .iter_mut()
+            .fold(Row::new().spacing(5).padding(5).width(Length::Fill), |row, tab| {
+                let but = Button::new(
+                        &mut tab.state,
+                        Text::new(format!("{} [{}]", tab.view.title(), tab.view.shortcut_name()))
+                            .width(Length::Fill)
+                            .horizontal_alignment(HorizontalAlignment::Center))
+                    .on_press(ConMXMsg::SwitchTab(tab.view))
+                    .width(Length::Fill);
+
+                row.push(if tab.view == active {
+                    but.style(style::Selected)
+                } else {
+                    but.style(style::Theme)
+                })
+            })
+            .into()
     }
-}*/
+}
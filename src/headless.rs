@@ -0,0 +1,150 @@
+use std::{
+    io::BufRead,
+    sync::mpsc,
+    thread,
+    time::Instant,
+};
+
+use log::{
+    info,
+    warn,
+};
+
+use crate::{
+    conmx_core::{
+        Config,
+        Engine,
+    },
+    err::ConmxErr,
+    osc::{
+        OscArg,
+        OscCommand,
+        OscMessage,
+    },
+};
+
+/// Lines read from stdin while running without a window
+#[derive(Debug, Clone, PartialEq)]
+pub enum StdinCommand {
+    /// An OSC address with its arguments separated by spaces, e.g. `/conmx/executor/1/fader 0.5`
+    Remote(OscCommand),
+    ToggleClock,
+    Save,
+    Quit,
+}
+
+impl StdinCommand {
+    pub fn parse(line: &str) -> Result<Self, ConmxErr> {
+        let mut words = line.split_whitespace();
+        let address = words.next().unwrap_or("");
+        match address {
+            "clock" => return Ok(StdinCommand::ToggleClock),
+            "save" => return Ok(StdinCommand::Save),
+            "quit" | "exit" => return Ok(StdinCommand::Quit),
+            _ => {}
+        }
+        let msg = words.fold(OscMessage::new(address), |msg, word| {
+            let arg = match (word.parse::<i32>(), word.parse::<f32>()) {
+                (Ok(i), _) => OscArg::Int(i),
+                (Err(_), Ok(f)) => OscArg::Float(f),
+                _ => OscArg::Str(word.to_owned()),
+            };
+            msg.with_arg(arg)
+        });
+        OscCommand::parse(&msg).map(StdinCommand::Remote)
+    }
+}
+
+fn format_message(msg: &OscMessage) -> String {
+    msg.args.iter().fold(msg.address.clone(), |line, arg| match arg {
+        OscArg::Int(i) => format!("{} {}", line, i),
+        OscArg::Float(f) => format!("{} {}", line, f),
+        OscArg::Str(s) => format!("{} {}", line, s),
+    })
+}
+
+/// Lines of stdin, read on a separate thread so the frame loop never waits for input
+fn stdin_lines() -> mpsc::Receiver<String> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        for c_line in std::io::stdin().lock().lines() {
+            match c_line {
+                Ok(line) => {
+                    if tx.send(line).is_err() {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    warn!("Stopped reading stdin ({})", e);
+                    break;
+                }
+            }
+        }
+    });
+    rx
+}
+
+/// Run the show without a window until `quit` is read from stdin.
+/// It is controlled by OSC, the web API and commands on stdin.
+/// The node graph of the show is evaluated every frame just like in the window.
+pub fn run(config: Config) -> Result<(), ConmxErr> {
+    let show = Engine::load_show(config.show.as_deref())?;
+    let mut engine = Engine::new(&config, show);
+    let mut graph = engine.node_graph();
+    let lines = stdin_lines();
+    info!("Running headless with {} fixtures", engine.show.patch.fixtures().len());
+
+    let frame = Engine::FRAME_TIME;
+    let mut next = Instant::now();
+    loop {
+        for c_line in lines.try_iter().filter(|l| !l.trim().is_empty()) {
+            match StdinCommand::parse(&c_line) {
+                Ok(StdinCommand::Remote(command)) => {
+                    if let Some(reply) = engine.run_command(command, &mut graph) {
+                        println!("{}", format_message(&reply));
+                    }
+                }
+                Ok(StdinCommand::ToggleClock) => engine.toggle_clock(),
+                Ok(StdinCommand::Save) => engine.save_show(&graph),
+                Ok(StdinCommand::Quit) => return Ok(()),
+                Err(e) => warn!("{}", e),
+            }
+        }
+
+        engine.tick(frame.as_secs_f32(), &mut graph);
+
+        // Frames which took too long are not caught up
+        next += frame;
+        let now = Instant::now();
+        if next > now {
+            thread::sleep(next - now);
+        } else {
+            next = now;
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(StdinCommand::parse("quit").unwrap(), StdinCommand::Quit);
+        assert_eq!(StdinCommand::parse(" clock ").unwrap(), StdinCommand::ToggleClock);
+        assert_eq!(
+            StdinCommand::parse("/conmx/executor/2/fader 0.5").unwrap(),
+            StdinCommand::Remote(OscCommand::Fader(1, 0.5))
+        );
+        assert_eq!(
+            StdinCommand::parse("/conmx/universe/0/channel/1 255").unwrap(),
+            StdinCommand::Remote(OscCommand::SetChannel { universe: 0, channel: 0, value: 255 })
+        );
+        assert!(StdinCommand::parse("/conmx/universe/0/channel/1 bright").is_err());
+        assert!(StdinCommand::parse("help").is_err());
+
+        let reply = OscMessage::new("/conmx/universe/0/channel/1").with_arg(OscArg::Int(255));
+        assert_eq!(format_message(&reply), "/conmx/universe/0/channel/1 255");
+    }
+}
@@ -7,8 +7,16 @@ extern crate lazy_static;
 mod err;
 mod cli;
 mod gui;
+mod headless;
 mod dmx;
 mod node;
+mod fixture;
+mod show;
+mod playback;
+mod timecode;
+mod osc;
+mod midi;
+mod web;
 mod localization;
 mod conmx_core;
 
@@ -54,15 +62,29 @@ fn main() {
     println!("{}", localized("This is a test!"));
     println!("{}", localized("main:test-2"));
 
-    let conf = conmx_core::Config {};
-
     match cli::CliOpts::parse() {
         Ok(cliopts) => {
             match cliopts.validate() {
                 cli::CliOpts::Unvalidated(_) => error!("Options given are not composited correctly!"),
-                cli::CliOpts::Validated(opts_val) => match gui::run(opts_val, conf) {
-                    Ok(()) => (),
-                    Err(e) => error!("{}", e),
+                cli::CliOpts::Validated(opts_val) => {
+                    let conf = conmx_core::Config {
+                        show: opts_val.show.clone(),
+                        artnet_timecode: opts_val.artnet_timecode,
+                        osc_port: opts_val.osc_port,
+                        midi_device: opts_val.midi_device.clone(),
+                        web_port: opts_val.web_port,
+                        web_token: opts_val.web_token.clone(),
+                        node_ip: opts_val.node_ip,
+                        sacn_output: opts_val.sacn_output,
+                    };
+                    let result = if opts_val.headless {
+                        headless::run(conf)
+                    } else {
+                        gui::run(opts_val, conf)
+                    };
+                    if let Err(e) = result {
+                        error!("{}", e);
+                    }
                 }
             }
         }
@@ -0,0 +1,161 @@
+use std::{
+    fs::{
+        File,
+        OpenOptions,
+    },
+    io::{
+        Read,
+        Write,
+    },
+    path::Path,
+    sync::mpsc::{
+        self,
+        Receiver,
+        Sender,
+    },
+    thread,
+};
+
+use log::warn;
+
+use crate::err::ConmxErr;
+
+use super::message::{
+    MidiMessage,
+    MidiParser,
+};
+
+/// Connection to a MIDI device, polled once per frame
+pub trait MidiBackend: std::fmt::Debug {
+    /// Messages received since the last call
+    fn receive(&mut self) -> Vec<MidiMessage>;
+
+    fn send(&mut self, msg: &MidiMessage);
+}
+
+/// Raw MIDI device file like `/dev/snd/midiC1D0`, read on a separate thread
+#[derive(Debug)]
+pub struct RawMidiDevice {
+    input: Receiver<Vec<u8>>,
+    output: File,
+    parser: MidiParser,
+}
+
+impl RawMidiDevice {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, ConmxErr> {
+        let path = path.as_ref();
+        let open_err = |e| ConmxErr::Midi(format!("Could not open {} ({})", path.display(), e));
+        let output = OpenOptions::new().read(true).write(true).open(path).map_err(open_err)?;
+        let mut reader = output.try_clone().map_err(open_err)?;
+
+        let (tx, input) = mpsc::channel();
+        let name = path.display().to_string();
+        thread::spawn(move || {
+            let mut buf = [0u8; 256];
+            loop {
+                match reader.read(&mut buf) {
+                    Ok(0) => break,
+                    Ok(len) => {
+                        if tx.send(buf[..len].to_vec()).is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => {
+                        warn!("Stopped reading MIDI from {} ({})", name, e);
+                        break;
+                    }
+                }
+            }
+        });
+
+        Ok(Self {
+            input,
+            output,
+            parser: MidiParser::new(),
+        })
+    }
+}
+
+impl MidiBackend for RawMidiDevice {
+    fn receive(&mut self) -> Vec<MidiMessage> {
+        let mut messages = Vec::new();
+        while let Ok(bytes) = self.input.try_recv() {
+            messages.extend(self.parser.push(&bytes));
+        }
+        messages
+    }
+
+    fn send(&mut self, msg: &MidiMessage) {
+        if let Err(e) = self.output.write_all(&msg.to_bytes()) {
+            warn!("Could not send MIDI ({})", e);
+        }
+    }
+}
+
+/// Device without hardware, the other end is a `VirtualMidiPort` injecting messages and reading what was sent
+#[derive(Debug)]
+pub struct VirtualMidi {
+    input: Receiver<MidiMessage>,
+    output: Sender<MidiMessage>,
+}
+
+/// The controller side of a `VirtualMidi`
+#[derive(Debug)]
+pub struct VirtualMidiPort {
+    input: Sender<MidiMessage>,
+    output: Receiver<MidiMessage>,
+}
+
+impl VirtualMidi {
+    pub fn new() -> (Self, VirtualMidiPort) {
+        let (in_tx, in_rx) = mpsc::channel();
+        let (out_tx, out_rx) = mpsc::channel();
+        (
+            Self { input: in_rx, output: out_tx },
+            VirtualMidiPort { input: in_tx, output: out_rx },
+        )
+    }
+}
+
+impl MidiBackend for VirtualMidi {
+    fn receive(&mut self) -> Vec<MidiMessage> {
+        self.input.try_iter().collect()
+    }
+
+    fn send(&mut self, msg: &MidiMessage) {
+        // Nobody listens once the port is dropped
+        self.output.send(*msg).ok();
+    }
+}
+
+impl VirtualMidiPort {
+    pub fn inject(&self, msg: MidiMessage) {
+        self.input.send(msg).ok();
+    }
+
+    /// Messages sent to the device since the last call
+    pub fn sent(&self) -> Vec<MidiMessage> {
+        self.output.try_iter().collect()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_virtual() {
+        let (mut device, port) = VirtualMidi::new();
+        let msg = MidiMessage::ControlChange { channel: 0, controller: 1, value: 64 };
+        port.inject(msg);
+        assert_eq!(device.receive(), vec![msg]);
+        assert!(device.receive().is_empty());
+
+        device.send(&msg);
+        assert_eq!(port.sent(), vec![msg]);
+        drop(port);
+        device.send(&msg);
+        assert!(device.receive().is_empty());
+    }
+}
@@ -0,0 +1,411 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::err::ConmxErr;
+
+use super::message::MidiMessage;
+
+/// Control on a device a mapping listens to, channels count from 0
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MidiControl {
+    Note { channel: u8, note: u8 },
+    Control { channel: u8, controller: u8 },
+    PitchBend { channel: u8 },
+}
+
+impl MidiControl {
+    /// Control a message comes from and its raw value, note offs have the value zero
+    pub fn of(msg: &MidiMessage) -> (Self, u16) {
+        match *msg {
+            MidiMessage::NoteOn { channel, note, velocity } => (MidiControl::Note { channel, note }, velocity as u16),
+            MidiMessage::NoteOff { channel, note, .. } => (MidiControl::Note { channel, note }, 0),
+            MidiMessage::ControlChange { channel, controller, value } => (MidiControl::Control { channel, controller }, value as u16),
+            MidiMessage::PitchBend { channel, value } => (MidiControl::PitchBend { channel }, value),
+        }
+    }
+
+    /// Largest raw value
+    pub fn max(&self) -> u16 {
+        match self {
+            MidiControl::PitchBend { .. } => MidiMessage::PITCH_BEND_MAX,
+            _ => 127,
+        }
+    }
+
+    /// Message moving the control to `raw`, a note is lit by its velocity
+    pub fn message(&self, raw: u16) -> MidiMessage {
+        let raw = raw.min(self.max());
+        match *self {
+            MidiControl::Note { channel, note } => MidiMessage::NoteOn { channel, note, velocity: raw as u8 },
+            MidiControl::Control { channel, controller } => MidiMessage::ControlChange { channel, controller, value: raw as u8 },
+            MidiControl::PitchBend { channel } => MidiMessage::PitchBend { channel, value: raw },
+        }
+    }
+}
+
+impl fmt::Display for MidiControl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MidiControl::Note { channel, note } => write!(f, "Ch {} Note {}", channel + 1, note),
+            MidiControl::Control { channel, controller } => write!(f, "Ch {} CC {}", channel + 1, controller),
+            MidiControl::PitchBend { channel } => write!(f, "Ch {} Pitch bend", channel + 1),
+        }
+    }
+}
+
+/// How control changes are read
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CcMode {
+    /// The value is the position of a fader or knob
+    Absolute,
+    /// Endless encoders sending steps, 1 to 63 up and 127 down to 65 down
+    Relative,
+}
+
+impl Default for CcMode {
+    fn default() -> Self { CcMode::Absolute }
+}
+
+/// What a control drives, written like the OSC addresses:
+/// `/conmx/universe/<n>/channel/<c>`, `/conmx/executor/<n>/fader`, `/go`, `/back`, `/flash` and `/release`,
+/// `/conmx/grandmaster` and `/conmx/node/<title>/<input>`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum MidiTarget {
+    Channel { universe: usize, channel: usize },
+    Fader(usize),
+    Go(usize),
+    Back(usize),
+    Flash(usize),
+    Release(usize),
+    GrandMaster,
+    NodeParam { node: String, param: String },
+}
+
+impl MidiTarget {
+    /// Buttons act on press, faders take the value
+    pub fn is_button(&self) -> bool {
+        match self {
+            MidiTarget::Go(_) | MidiTarget::Back(_) | MidiTarget::Flash(_) | MidiTarget::Release(_) => true,
+            MidiTarget::Channel { .. } | MidiTarget::Fader(_) | MidiTarget::GrandMaster | MidiTarget::NodeParam { .. } => false,
+        }
+    }
+}
+
+impl fmt::Display for MidiTarget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MidiTarget::Channel { universe, channel } => write!(f, "/conmx/universe/{}/channel/{}", universe, channel + 1),
+            MidiTarget::Fader(i) => write!(f, "/conmx/executor/{}/fader", i + 1),
+            MidiTarget::Go(i) => write!(f, "/conmx/executor/{}/go", i + 1),
+            MidiTarget::Back(i) => write!(f, "/conmx/executor/{}/back", i + 1),
+            MidiTarget::Flash(i) => write!(f, "/conmx/executor/{}/flash", i + 1),
+            MidiTarget::Release(i) => write!(f, "/conmx/executor/{}/release", i + 1),
+            MidiTarget::GrandMaster => write!(f, "/conmx/grandmaster"),
+            MidiTarget::NodeParam { node, param } => write!(f, "/conmx/node/{}/{}", node, param),
+        }
+    }
+}
+
+impl FromStr for MidiTarget {
+    type Err = ConmxErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ConmxErr::Midi(format!("\"{}\" is not a MIDI target", s));
+        let number = |part: &str, first: usize| part.parse::<usize>().ok()
+            .filter(|i| *i >= first)
+            .map(|i| i - first)
+            .ok_or_else(invalid);
+        let parts: Vec<&str> = s.trim().trim_start_matches('/').split('/').collect();
+        match parts.as_slice() {
+            ["conmx", "universe", universe, "channel", channel] => Ok(MidiTarget::Channel {
+                universe: number(*universe, 0)?,
+                channel: number(*channel, 1)?,
+            }),
+            ["conmx", "executor", executor, action] => {
+                let executor = number(*executor, 1)?;
+                match *action {
+                    "fader" => Ok(MidiTarget::Fader(executor)),
+                    "go" => Ok(MidiTarget::Go(executor)),
+                    "back" => Ok(MidiTarget::Back(executor)),
+                    "flash" => Ok(MidiTarget::Flash(executor)),
+                    "release" => Ok(MidiTarget::Release(executor)),
+                    _ => Err(invalid()),
+                }
+            }
+            ["conmx", "grandmaster"] => Ok(MidiTarget::GrandMaster),
+            ["conmx", "node", node, param] if !node.is_empty() && !param.is_empty() => Ok(MidiTarget::NodeParam {
+                node: node.to_string(),
+                param: param.to_string(),
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl TryFrom<String> for MidiTarget {
+    type Error = ConmxErr;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<MidiTarget> for String {
+    fn from(target: MidiTarget) -> String {
+        target.to_string()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MidiMapping {
+    pub control: MidiControl,
+    pub target: MidiTarget,
+    #[serde(default)]
+    pub mode: CcMode,
+    /// Position of the control as last received or sent, as fraction of full
+    #[serde(skip)]
+    value: Option<f32>,
+}
+
+impl MidiMapping {
+    pub fn new(control: MidiControl, target: MidiTarget) -> Self {
+        Self {
+            control,
+            target,
+            mode: CcMode::default(),
+            value: None,
+        }
+    }
+
+    pub fn with_mode(mut self, mode: CcMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    fn is_relative(&self) -> bool {
+        self.mode == CcMode::Relative && matches!(self.control, MidiControl::Control { .. })
+    }
+
+    /// New value of the target for a raw value received from the control
+    fn receive(&mut self, raw: u16) -> f32 {
+        let value = if self.is_relative() {
+            let steps = if raw < 64 { raw as f32 } else { raw as f32 - 128. };
+            (self.value.unwrap_or(0.) + steps * MidiMap::RELATIVE_STEP).max(0.).min(1.)
+        } else {
+            raw as f32 / self.control.max() as f32
+        };
+        self.value = Some(value);
+        value
+    }
+}
+
+/// A received control moving a target, the value is a fraction of full and buttons are pressed above zero
+#[derive(Debug, Clone, PartialEq)]
+pub struct MidiAction {
+    pub target: MidiTarget,
+    pub value: f32,
+}
+
+/// Mappings of device controls to targets, at most one per control
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MidiMap {
+    mappings: Vec<MidiMapping>,
+    /// Target bound to the next control moved
+    #[serde(skip)]
+    learn: Option<MidiTarget>,
+}
+
+impl MidiMap {
+    /// Change of a relative control per step
+    pub const RELATIVE_STEP: f32 = 1. / 127.;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_mapping(mut self, mapping: MidiMapping) -> Self {
+        self.add(mapping);
+        self
+    }
+
+    /// Add a mapping, replacing the one of the same control
+    pub fn add(&mut self, mapping: MidiMapping) {
+        match self.mappings.iter_mut().find(|m| m.control == mapping.control) {
+            Some(existing) => *existing = mapping,
+            None => self.mappings.push(mapping),
+        }
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<MidiMapping> {
+        if index < self.mappings.len() {
+            Some(self.mappings.remove(index))
+        } else {
+            None
+        }
+    }
+
+    pub fn set_mode(&mut self, index: usize, mode: CcMode) {
+        if let Some(mapping) = self.mappings.get_mut(index) {
+            mapping.mode = mode;
+        }
+    }
+
+    pub fn mappings(&self) -> &[MidiMapping] {
+        &self.mappings
+    }
+
+    pub fn len(&self) -> usize {
+        self.mappings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mappings.is_empty()
+    }
+
+    /// Bind `target` to the next control moved on the device
+    pub fn learn(&mut self, target: MidiTarget) {
+        self.learn = Some(target);
+    }
+
+    pub fn cancel_learn(&mut self) {
+        self.learn = None;
+    }
+
+    pub fn learning(&self) -> Option<&MidiTarget> {
+        self.learn.as_ref()
+    }
+
+    /// Action for a received message. While learning the control is bound instead.
+    pub fn handle(&mut self, msg: &MidiMessage) -> Option<MidiAction> {
+        let (control, raw) = MidiControl::of(msg);
+        if let Some(target) = self.learn.take() {
+            self.add(MidiMapping::new(control, target));
+            return None;
+        }
+        let mapping = self.mappings.iter_mut().find(|m| m.control == control)?;
+        let value = mapping.receive(raw);
+        Some(MidiAction { target: mapping.target.clone(), value })
+    }
+
+    /// Messages moving the controls to the current values of their targets, e.g. for motorised faders.
+    /// `value` gives the current value of a target as fraction of full, only changes are sent.
+    /// Relative controls only take over the value for the next step.
+    pub fn feedback<F: Fn(&MidiTarget) -> Option<f32>>(&mut self, value: F) -> Vec<MidiMessage> {
+        let mut messages = Vec::new();
+        for c_mapping in self.mappings.iter_mut() {
+            let current = match value(&c_mapping.target) {
+                Some(v) => v.max(0.).min(1.),
+                None => continue,
+            };
+            if c_mapping.is_relative() {
+                c_mapping.value = Some(current);
+                continue;
+            }
+            let max = c_mapping.control.max() as f32;
+            let raw = (current * max).round() as u16;
+            if c_mapping.value.map(|v| (v * max).round() as u16) != Some(raw) {
+                messages.push(c_mapping.control.message(raw));
+                c_mapping.value = Some(current);
+            }
+        }
+        messages
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FADER: MidiControl = MidiControl::Control { channel: 0, controller: 7 };
+
+    fn cc(controller: u8, value: u8) -> MidiMessage {
+        MidiMessage::ControlChange { channel: 0, controller, value }
+    }
+
+    #[test]
+    fn test_target() {
+        for c_target in [
+            MidiTarget::Channel { universe: 1, channel: 0 },
+            MidiTarget::Flash(2),
+            MidiTarget::GrandMaster,
+            MidiTarget::NodeParam { node: "Sine".to_owned(), param: "freq".to_owned() },
+        ].iter() {
+            assert_eq!(&c_target.to_string().parse::<MidiTarget>().unwrap(), c_target);
+        }
+        assert_eq!("/conmx/executor/1/go".parse::<MidiTarget>().unwrap(), MidiTarget::Go(0));
+        assert!("/conmx/executor/0/go".parse::<MidiTarget>().is_err());
+        assert!("/conmx/node//freq".parse::<MidiTarget>().is_err());
+        assert!(MidiTarget::Go(0).is_button() && !MidiTarget::Fader(0).is_button());
+    }
+
+    #[test]
+    fn test_learn_and_handle() {
+        let mut map = MidiMap::new();
+        assert_eq!(map.handle(&cc(7, 10)), None);
+
+        map.learn(MidiTarget::Fader(0));
+        assert_eq!(map.handle(&cc(7, 10)), None);
+        assert_eq!(map.learning(), None);
+        assert_eq!(map.mappings()[0].control, FADER);
+        assert_eq!(map.handle(&cc(7, 127)), Some(MidiAction { target: MidiTarget::Fader(0), value: 1. }));
+        assert_eq!(map.handle(&cc(8, 127)), None);
+
+        map.learn(MidiTarget::Go(1));
+        map.handle(&MidiMessage::NoteOn { channel: 1, note: 60, velocity: 100 });
+        let off = map.handle(&MidiMessage::NoteOff { channel: 1, note: 60, velocity: 64 }).unwrap();
+        assert_eq!(off, MidiAction { target: MidiTarget::Go(1), value: 0. });
+
+        // Learning again on the same control replaces the mapping
+        map.learn(MidiTarget::GrandMaster);
+        map.handle(&cc(7, 0));
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.mappings()[0].target, MidiTarget::GrandMaster);
+
+        map.add(MidiMapping::new(MidiControl::PitchBend { channel: 0 }, MidiTarget::Fader(3)));
+        let bend = map.handle(&MidiMessage::PitchBend { channel: 0, value: MidiMessage::PITCH_BEND_MAX }).unwrap();
+        assert_eq!(bend.value, 1.);
+    }
+
+    #[test]
+    fn test_relative() {
+        let mut map = MidiMap::new()
+            .with_mapping(MidiMapping::new(FADER, MidiTarget::Fader(0)).with_mode(CcMode::Relative));
+        // The current value is taken over without sending anything
+        assert!(map.feedback(|_| Some(0.5)).is_empty());
+        let up = map.handle(&cc(7, 2)).unwrap();
+        assert!((up.value - (0.5 + 2. * MidiMap::RELATIVE_STEP)).abs() < 1e-6);
+        let down = map.handle(&cc(7, 126)).unwrap();
+        assert!((down.value - 0.5).abs() < 1e-6);
+        map.handle(&cc(7, 65));
+        assert_eq!(map.handle(&cc(7, 65)).unwrap().value, 0.);
+    }
+
+    #[test]
+    fn test_feedback() {
+        let mut map = MidiMap::new()
+            .with_mapping(MidiMapping::new(FADER, MidiTarget::Fader(0)))
+            .with_mapping(MidiMapping::new(MidiControl::Note { channel: 0, note: 1 }, MidiTarget::Go(0)))
+            .with_mapping(MidiMapping::new(MidiControl::PitchBend { channel: 2 }, MidiTarget::GrandMaster));
+        let values = |target: &MidiTarget| match target {
+            MidiTarget::Fader(_) => Some(0.5),
+            MidiTarget::Go(_) => Some(1.),
+            _ => None,
+        };
+        // Everything known is sent once
+        assert_eq!(map.feedback(values), vec![cc(7, 64), MidiMessage::NoteOn { channel: 0, note: 1, velocity: 127 }]);
+        assert!(map.feedback(values).is_empty());
+
+        // Values received from the device are not echoed
+        map.handle(&cc(7, 100));
+        assert!(map.feedback(|t| if *t == MidiTarget::Fader(0) { Some(100. / 127.) } else { values(t) }).is_empty());
+        assert_eq!(map.feedback(|t| if *t == MidiTarget::Fader(0) { Some(0.) } else { None }), vec![cc(7, 0)]);
+    }
+}
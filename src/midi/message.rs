@@ -0,0 +1,140 @@
+/// Channel voice messages used for control, channels count from 0
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MidiMessage {
+    NoteOn { channel: u8, note: u8, velocity: u8 },
+    NoteOff { channel: u8, note: u8, velocity: u8 },
+    ControlChange { channel: u8, controller: u8, value: u8 },
+    /// 14 bit value, centered at 8192
+    PitchBend { channel: u8, value: u16 },
+}
+
+impl MidiMessage {
+    pub const PITCH_BEND_MAX: u16 = 0x3FFF;
+
+    pub fn channel(&self) -> u8 {
+        match self {
+            MidiMessage::NoteOn { channel, .. }
+            | MidiMessage::NoteOff { channel, .. }
+            | MidiMessage::ControlChange { channel, .. }
+            | MidiMessage::PitchBend { channel, .. } => *channel,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match *self {
+            MidiMessage::NoteOn { channel, note, velocity } => vec![0x90 | (channel & 0x0F), note & 0x7F, velocity & 0x7F],
+            MidiMessage::NoteOff { channel, note, velocity } => vec![0x80 | (channel & 0x0F), note & 0x7F, velocity & 0x7F],
+            MidiMessage::ControlChange { channel, controller, value } => vec![0xB0 | (channel & 0x0F), controller & 0x7F, value & 0x7F],
+            MidiMessage::PitchBend { channel, value } => {
+                let value = value.min(Self::PITCH_BEND_MAX);
+                vec![0xE0 | (channel & 0x0F), (value & 0x7F) as u8, (value >> 7) as u8]
+            }
+        }
+    }
+
+    /// Message of a status byte and its two data bytes, other messages are not used
+    fn from_parts(status: u8, data: [u8; 2]) -> Option<Self> {
+        let channel = status & 0x0F;
+        match status & 0xF0 {
+            // Note on with velocity zero is a note off
+            0x90 if data[1] == 0 => Some(MidiMessage::NoteOff { channel, note: data[0], velocity: 0 }),
+            0x90 => Some(MidiMessage::NoteOn { channel, note: data[0], velocity: data[1] }),
+            0x80 => Some(MidiMessage::NoteOff { channel, note: data[0], velocity: data[1] }),
+            0xB0 => Some(MidiMessage::ControlChange { channel, controller: data[0], value: data[1] }),
+            0xE0 => Some(MidiMessage::PitchBend { channel, value: data[0] as u16 | (data[1] as u16) << 7 }),
+            _ => None,
+        }
+    }
+}
+
+/// Splits a raw byte stream into messages, following running status.
+/// Realtime bytes are skipped, system exclusive and other messages are dropped.
+#[derive(Debug, Clone, Default)]
+pub struct MidiParser {
+    status: Option<u8>,
+    data: Vec<u8>,
+}
+
+impl MidiParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Data bytes following a status byte
+    fn data_len(status: u8) -> usize {
+        match status & 0xF0 {
+            0xC0 | 0xD0 => 1,
+            _ => 2,
+        }
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<MidiMessage> {
+        let mut messages = Vec::new();
+        for c_byte in bytes.iter().cloned() {
+            match c_byte {
+                // Realtime messages may appear anywhere
+                0xF8..=0xFF => {}
+                // System messages cancel running status
+                0xF0..=0xF7 => {
+                    self.status = None;
+                    self.data.clear();
+                }
+                0x80..=0xEF => {
+                    self.status = Some(c_byte);
+                    self.data.clear();
+                }
+                _ => {
+                    let status = match self.status {
+                        Some(s) => s,
+                        None => continue,
+                    };
+                    self.data.push(c_byte);
+                    if self.data.len() == Self::data_len(status) {
+                        if self.data.len() == 2 {
+                            messages.extend(MidiMessage::from_parts(status, [self.data[0], self.data[1]]));
+                        }
+                        self.data.clear();
+                    }
+                }
+            }
+        }
+        messages
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let mut parser = MidiParser::new();
+        // Running status, a program change and a clock byte in between
+        let bytes = [0xB1, 7, 100, 8, 0xF8, 20, 0xC0, 5, 0x90, 60, 127, 60, 0];
+        assert_eq!(parser.push(&bytes), vec![
+            MidiMessage::ControlChange { channel: 1, controller: 7, value: 100 },
+            MidiMessage::ControlChange { channel: 1, controller: 8, value: 20 },
+            MidiMessage::NoteOn { channel: 0, note: 60, velocity: 127 },
+            MidiMessage::NoteOff { channel: 0, note: 60, velocity: 0 },
+        ]);
+
+        // Messages may be split over reads
+        assert!(parser.push(&[0xE2, 0x7F]).is_empty());
+        assert_eq!(parser.push(&[0x7F]), vec![MidiMessage::PitchBend { channel: 2, value: MidiMessage::PITCH_BEND_MAX }]);
+        // System exclusive data is dropped
+        assert!(parser.push(&[0xF0, 1, 2, 3, 0xF7, 4, 5]).is_empty());
+    }
+
+    #[test]
+    fn test_bytes() {
+        let messages = [
+            MidiMessage::NoteOn { channel: 3, note: 1, velocity: 2 },
+            MidiMessage::ControlChange { channel: 15, controller: 64, value: 127 },
+            MidiMessage::PitchBend { channel: 0, value: 8192 },
+        ];
+        let bytes: Vec<u8> = messages.iter().flat_map(|m| m.to_bytes()).collect();
+        assert_eq!(bytes[6..], [0xE0, 0, 64]);
+        assert_eq!(MidiParser::new().push(&bytes), messages.to_vec());
+    }
+}
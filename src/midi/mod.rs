@@ -0,0 +1,8 @@
+mod message;
+pub use message::*;
+
+mod backend;
+pub use backend::*;
+
+mod mapping;
+pub use mapping::*;
@@ -6,10 +6,10 @@ use std::cmp::{
 pub type PortID = usize;
 pub type NodeID = usize;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PortType {
-    Input(u32),
-    Output(u32),
+    Input(PortID),
+    Output(PortID),
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -1,14 +1,29 @@
 use std::mem::replace;
 use std::fmt::Debug;
+use std::collections::VecDeque;
 
-use crate::gui::components::grid::Drawable;
+use log::warn;
+
+use crate::gui::components::grid::{
+    Drawable,
+    helpers,
+};
+
+use super::connections::{
+    Addr,
+    PortID,
+};
+use super::port::Port;
 
 use iced::{
     canvas,
     Rectangle,
+    Point,
+    Color,
 };
 
 pub type NodeIndex = usize;
+pub type EdgeIndex = usize;
 
 pub mod err {
     use std::error::Error;
@@ -26,7 +41,25 @@ pub mod err {
     }
 }
 
-pub trait GraphNode {}
+pub trait GraphNode {
+    /// Position of the given input port in graph coordinates, used as the end point of edges.
+    fn input_anchor(&self, _port: PortID) -> Option<Point> { None }
+
+    /// Position of the given output port in graph coordinates, used as the start point of edges.
+    fn output_anchor(&self, _port: PortID) -> Option<Point> { None }
+
+    /// Recalculate the outputs of the node from its current inputs
+    fn update(&mut self) {}
+
+    /// Current value of the given output port
+    fn get_output_value(&self, _port: PortID) -> Option<Port> { None }
+
+    /// Feed a value into the given input port
+    fn set_input_value(&mut self, _port: PortID, _value: Port) {}
+
+    /// Notify the node whether an edge ends in the given input port
+    fn set_input_connected(&mut self, _port: PortID, _connected: bool) {}
+}
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Graph<T>
@@ -35,10 +68,17 @@ where T: GraphNode + Debug + Drawable,
     nodes: Vec<Option<T>>,
     free_nodes: Vec<NodeIndex>,
     edges: Vec<Option<Edge>>,
-    free_edges: Vec<NodeIndex>,
+    free_edges: Vec<EdgeIndex>,
 
 }
 
+impl<T> Default for Graph<T>
+where T: GraphNode + Debug + Drawable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<T> Graph<T>
 where T: GraphNode + Debug + Drawable {
     /// Create a new empty graph
@@ -71,6 +111,14 @@ where T: GraphNode + Debug + Drawable {
     }
 
 
+    /// Get a reference of a node contained in the graph
+    /// If None is returned the node doesn't exist.
+    pub fn get_node(&self, id: NodeIndex) -> Option<&T> {
+        self.nodes
+            .get(id)
+            .and_then(|c| c.as_ref())
+    }
+
     /// Get a mutable reference of a node contained in the graph
     /// If None is returned the node doesn't exist.
     /// # Example
@@ -117,20 +165,25 @@ where T: GraphNode + Debug + Drawable {
 
     /// Add an edge to the graph given an edge.
     pub fn add_edge(&mut self, e: Edge) -> Result<(), err::GraphError> {
-        match (self.nodes.get(e.start), self.nodes.get(e.end)) {
+        let end = e.get_end();
+        let res = self.insert_edge(e);
+        if res.is_ok() {
+            self.refresh_connected(end);
+        }
+        res
+    }
+
+    fn insert_edge(&mut self, e: Edge) -> Result<(), err::GraphError> {
+        match (self.get_node(e.start), self.get_node(e.end)) {
             (Some(_), Some(_)) => {
                 if let Some(id) = self.free_edges.pop() {
-                    match (self.nodes.get(e.start), self.nodes.get(e.end)) {
-                        (Some(_), Some(_)) => {
-                            self.edges.insert(id, Some(e));
-                            Ok(())
-                        },
-                        (_, _) => Err(err::GraphError("Not all nodes are existent.".to_owned())),
+                    if let Some(slot) = self.edges.get_mut(id) {
+                        let _ = replace(slot, Some(e));
                     }
                 } else {
                     self.edges.push(Some(e));
-                    Ok(())
                 }
+                Ok(())
             },
             (Some(_), None) => Err(err::GraphError(format!("Node not existing (End node not existing ({}))", e.end))),
             (None, Some(_)) => Err(err::GraphError(format!("Node not existing (Start node not existing ({}))", e.start))),
@@ -138,8 +191,41 @@ where T: GraphNode + Debug + Drawable {
         }
     }
 
-    pub fn get_edges(&self) -> Vec<&Edge> {
-        self.edges.iter().filter_map(|c| if let Some(c) = c { Some(c) } else { None }).collect()
+    /// Get all existing edges together with their index inside the graph.
+    pub fn get_edges(&self) -> Vec<(EdgeIndex, &Edge)> {
+        self.edges
+            .iter()
+            .enumerate()
+            .filter_map(|(i, c)| c.as_ref().map(|c| (i, c)))
+            .collect()
+    }
+
+    /// Delete a single edge given its index.
+    /// If there is no edge with that index `None` is returned.
+    pub fn delete_edge(&mut self, id: EdgeIndex) -> Option<Edge> {
+        let old_val = replace(self.edges.get_mut(id)?, None);
+        if let Some(e) = &old_val {
+            self.free_edges.push(id);
+            self.refresh_connected(e.get_end());
+        }
+        old_val
+    }
+
+    /// Tell the node owning the input `to` whether it is still connected
+    fn refresh_connected(&mut self, to: Addr) {
+        let connected = !self.edges_into(to).is_empty();
+        if let Some(n) = self.get_node_mut(to.node) {
+            n.set_input_connected(to.port, connected);
+        }
+    }
+
+    /// Get the indices of all edges ending in the given input port.
+    pub fn edges_into(&self, to: Addr) -> Vec<EdgeIndex> {
+        self.get_edges()
+            .into_iter()
+            .filter(|(_, e)| e.get_end() == to)
+            .map(|(i, _)| i)
+            .collect()
     }
 
     pub fn delete_edge_by(&mut self, start: Option<NodeIndex>, end: Option<NodeIndex>) -> Vec<Edge> {
@@ -147,10 +233,10 @@ where T: GraphNode + Debug + Drawable {
         fn delete_iterator<'a, T, I>(free_edges: &mut Vec<usize>, iter: T) -> Vec<I>
         where
             I: 'a,
-            T: Iterator<Item = &'a mut Option<I>>,
+            T: Iterator<Item = (usize, &'a mut Option<I>)>,
         {
             let mut deleted = Vec::new();
-            for (i, c) in iter.enumerate() {
+            for (i, c) in iter {
                 if let Some(replaced) = replace(c, None) {
                     deleted.push(replaced);
                     free_edges.push(i);
@@ -159,103 +245,284 @@ where T: GraphNode + Debug + Drawable {
             deleted
         }
 
-        match (start, end) {
-            (None, None) => delete_iterator(&mut self.free_edges, self.edges.iter_mut()),
+        let deleted = match (start, end) {
+            (None, None) => delete_iterator(&mut self.free_edges, self.edges.iter_mut().enumerate()),
 
-            (Some(e), None)|(None, Some(e)) => delete_iterator(&mut self.free_edges, self.edges.iter_mut().filter(|c| if let Some(c) = c {
+            (Some(e), None)|(None, Some(e)) => delete_iterator(&mut self.free_edges, self.edges.iter_mut().enumerate().filter(|(_, c)| if let Some(c) = c {
                 c.start == e || c.end == e
             } else {
                 false
             })),
 
-            (Some(s), Some(e)) => delete_iterator(&mut self.free_edges, self.edges.iter_mut().filter(|c| if let Some(c) = c {
+            (Some(s), Some(e)) => delete_iterator(&mut self.free_edges, self.edges.iter_mut().enumerate().filter(|(_, c)| if let Some(c) = c {
                 (c.start == s && c.end == e) || (c.start == e && c.end == s)
             } else {
                 false
             })),
+        };
+
+        for c_edge in deleted.iter() {
+            self.refresh_connected(c_edge.get_end());
         }
+        deleted
     }
 
 
-    /// Get the nodes field as non-mutable vector
-    pub fn get_nodes(&self) -> Vec<Option<T>> {
+    /// Get all existing nodes together with their index inside the graph.
+    pub fn get_nodes(&self) -> Vec<(NodeIndex, &T)> {
         self.nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(i, c)| c.as_ref().map(|c| (i, c)))
+            .collect()
     }
-}
 
-impl<T> Drawable for Graph<T>
-where T: GraphNode + Debug + Drawable {
+    /// Get the area covered by the given nodes.
+    /// Returns `None` if none of the nodes exist.
+    pub fn get_bounding_box_of(&self, ids: &[NodeIndex]) -> Option<Rectangle> {
+        ids.iter()
+            .filter_map(|id| self.get_node(*id))
+            .map(|n| n.get_bounding_box())
+            .fold(None, |big_bb, cbb| match big_bb {
+                Some(s) => Some(helpers::union_rect(s, cbb)),
+                None => Some(cbb),
+            })
+    }
 
-    fn draw(&self, frame: &mut canvas::Frame) {
-        for c_edge in self.edges {
-            if let Some(e) = c_edge {
-                e.draw(frame);
+    /// Get the curve an edge is drawn as.
+    /// Returns `None` if one of the connected nodes doesn't provide an anchor for the port.
+    pub fn get_wire(&self, e: &Edge) -> Option<Wire> {
+        Some(Wire::new(
+            self.get_node(e.start)?.output_anchor(e.start_port)?,
+            self.get_node(e.end)?.input_anchor(e.end_port)?,
+        ))
+    }
+
+    /// Get the order nodes have to be updated in, so every node is updated after all nodes feeding it.
+    /// Nodes which are part of a cycle are appended in index order.
+    pub fn evaluation_order(&self) -> Vec<NodeIndex> {
+        let mut in_degree = vec![0usize; self.nodes.len()];
+        for (_, e) in self.get_edges() {
+            in_degree[e.end] += 1;
+        }
+
+        let mut queue: VecDeque<NodeIndex> = self.get_nodes()
+            .into_iter()
+            .map(|(id, _)| id)
+            .filter(|id| in_degree[*id] == 0)
+            .collect();
+        let mut order = Vec::with_capacity(self.nodes.len());
+        let mut visited = vec![false; self.nodes.len()];
+
+        while let Some(id) = queue.pop_front() {
+            order.push(id);
+            visited[id] = true;
+            for (_, e) in self.get_edges().into_iter().filter(|(_, e)| e.start == id) {
+                in_degree[e.end] -= 1;
+                if in_degree[e.end] == 0 {
+                    queue.push_back(e.end);
+                }
             }
         }
-        for c_node in self.nodes {
-            if let Some(n) = c_node {
-                n.draw(frame);
+
+        for (id, _) in self.get_nodes() {
+            if !visited[id] {
+                warn!("Node {} is part of a cycle, evaluating it out of order", id);
+                order.push(id);
             }
         }
+        order
     }
 
-    fn get_bounding_box(&self) -> Rectangle {
-        let mut big_bb = None;
-        for c_node in self.get_nodes() {
-            match (c_node, big_bb) {
-                (Some(node), None) => big_bb = Some(node.get_bounding_box()),
-                (Some(node), Some(mut s)) => {
-                    let cbb = node.get_bounding_box();
-                    if cbb.x < s.x {
-                        s.x = cbb.x;
-                        s.width += s.x - cbb.x;
-                    }
-                    if cbb.y < s.y {
-                        s.y = cbb.y;
-                        s.height += s.y - cbb.y;
-                    }
-                    if (cbb.x + cbb.width) > (s.x + s.width) {
-                        s.width = (cbb.x + cbb.width) - s.x;
-                    }
-                    if (cbb.y + cbb.height) > (s.y + s.height) {
-                        s.height = (cbb.y + cbb.height) - s.y;
-                    }
+    /// Update all nodes once and pass the output values along the edges
+    pub fn evaluate(&mut self) {
+        for id in self.evaluation_order() {
+            if let Some(n) = self.get_node_mut(id) {
+                n.update();
+            }
+
+            let transfers: Vec<(Addr, Port)> = self.get_edges()
+                .into_iter()
+                .filter(|(_, e)| e.start == id)
+                .filter_map(|(_, e)| Some((
+                    e.get_end(),
+                    self.get_node(id)?.get_output_value(e.start_port)?,
+                )))
+                .collect();
+
+            for (to, value) in transfers {
+                if let Some(n) = self.get_node_mut(to.node) {
+                    n.set_input_value(to.port, value);
                 }
-                _ => (),
             }
         }
-        match big_bb {
-            Some(s) => s,
-            None => Rectangle::default(),
+    }
+
+    pub fn draw_edges(&self, frame: &mut canvas::Frame) {
+        for (_, c_edge) in self.get_edges() {
+            if let Some(wire) = self.get_wire(c_edge) {
+                wire.draw(frame);
+            }
         }
     }
+
+    pub fn draw_nodes(&self, frame: &mut canvas::Frame, scaling: f32) {
+        for (_, c_node) in self.get_nodes() {
+            c_node.draw_scaled(frame, scaling);
+        }
+    }
+}
+
+impl<T> Drawable for Graph<T>
+where T: GraphNode + Debug + Drawable {
+
+    fn draw(&self, frame: &mut canvas::Frame) {
+        self.draw_edges(frame);
+        self.draw_nodes(frame, 1.);
+    }
+
+    fn get_bounding_box(&self) -> Rectangle {
+        let all: Vec<NodeIndex> = self.get_nodes().into_iter().map(|(id, _)| id).collect();
+        self.get_bounding_box_of(&all).unwrap_or_default()
+    }
 }
 
+/// A directed edge from an output port of the start node to an input port of the end node.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Edge {
-    start: usize,
-    end: usize,
+    start: NodeIndex,
+    start_port: PortID,
+    end: NodeIndex,
+    end_port: PortID,
 }
 
 impl Edge {
-    pub fn new(start: usize, end: usize) -> Self {
+    /// Create an edge between the first ports of both nodes
+    pub fn new(start: NodeIndex, end: NodeIndex) -> Self {
         Self {
             start,
+            start_port: 0,
             end,
+            end_port: 0,
         }
     }
+
+    /// Create an edge from the output port `from` to the input port `to`
+    pub fn between(from: Addr, to: Addr) -> Self {
+        Self {
+            start: from.node,
+            start_port: from.port,
+            end: to.node,
+            end_port: to.port,
+        }
+    }
+
+    pub fn get_start(&self) -> Addr {
+        Addr { node: self.start, port: self.start_port }
+    }
+
+    pub fn get_end(&self) -> Addr {
+        Addr { node: self.end, port: self.end_port }
+    }
 }
 
-impl Drawable for Edge {
+/// Bezier curve connecting an output port with an input port.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Wire {
+    pub from: Point,
+    pub to: Point,
+}
+
+impl Wire {
+    const WIDTH: f32 = 0.25;
+    const MIN_BEND: f32 = 2.;
+    const HIT_SAMPLES: usize = 32;
+
+    pub fn new(from: Point, to: Point) -> Self {
+        Self { from, to }
+    }
+
+    fn control_points(&self) -> (Point, Point) {
+        let bend = ((self.to.x - self.from.x).abs() / 2.).max(Self::MIN_BEND);
+        (
+            Point::new(self.from.x + bend, self.from.y),
+            Point::new(self.to.x - bend, self.to.y),
+        )
+    }
+
+    /// Evaluate the curve at `t` in `[0, 1]`
+    fn point_at(&self, t: f32) -> Point {
+        let (c1, c2) = self.control_points();
+        let it = 1. - t;
+        let a = it * it * it;
+        let b = 3. * it * it * t;
+        let c = 3. * it * t * t;
+        let d = t * t * t;
+        Point::new(
+            a * self.from.x + b * c1.x + c * c2.x + d * self.to.x,
+            a * self.from.y + b * c1.y + c * c2.y + d * self.to.y,
+        )
+    }
+
+    /// Check whether `p` lies within `tolerance` of the curve
+    pub fn hit(&self, p: Point, tolerance: f32) -> bool {
+        let mut last = self.from;
+        for i in 1..=Self::HIT_SAMPLES {
+            let c = self.point_at(i as f32 / Self::HIT_SAMPLES as f32);
+            if distance_to_segment(p, last, c) <= tolerance {
+                return true;
+            }
+            last = c;
+        }
+        false
+    }
+
+    pub fn draw_with_color(&self, frame: &mut canvas::Frame, color: Color) {
+        let (c1, c2) = self.control_points();
+        let mut path_builder = canvas::path::Builder::new();
+        path_builder.move_to(self.from);
+        path_builder.bezier_curve_to(c1, c2, self.to);
+
+        let stroke_s = canvas::Stroke::default()
+            .with_color(color)
+            .with_width(Self::WIDTH);
+
+        frame.stroke(&path_builder.build(), stroke_s);
+    }
+}
+
+impl Drawable for Wire {
     fn draw(&self, frame: &mut canvas::Frame) {
-        todo!();
+        self.draw_with_color(frame, Color::from_rgb8(0xC0, 0xC0, 0xC0)); // #C0C0C0
     }
 
     fn get_bounding_box(&self) -> Rectangle {
-        todo!();
+        let (c1, c2) = self.control_points();
+        let points = [self.from, c1, c2, self.to];
+        let min_x = points.iter().map(|p| p.x).fold(f32::INFINITY, f32::min);
+        let min_y = points.iter().map(|p| p.y).fold(f32::INFINITY, f32::min);
+        let max_x = points.iter().map(|p| p.x).fold(f32::NEG_INFINITY, f32::max);
+        let max_y = points.iter().map(|p| p.y).fold(f32::NEG_INFINITY, f32::max);
+        Rectangle {
+            x: min_x,
+            y: min_y,
+            width: max_x - min_x,
+            height: max_y - min_y,
+        }
     }
 }
 
+fn distance_to_segment(p: Point, a: Point, b: Point) -> f32 {
+    let ab = b - a;
+    let len_sq = ab.x * ab.x + ab.y * ab.y;
+    if len_sq == 0. {
+        return p.distance(a);
+    }
+    let ap = p - a;
+    let t = ((ap.x * ab.x + ap.y * ab.y) / len_sq).max(0.).min(1.);
+    p.distance(Point::new(a.x + ab.x * t, a.y + ab.y * t))
+}
+
 
 #[cfg(test)]
 mod tests {
@@ -318,8 +585,8 @@ mod tests {
         let n2 = g.add_node(TestNode(2));
         let n3 = g.add_node(TestNode(3));
 
-        let _e1 = g.add_edge(Edge { start: n1, end: n2 });
-        let _e2 = g.add_edge(Edge { start: n3, end: n1 });
+        let _e1 = g.add_edge(Edge::new(n1, n2));
+        let _e2 = g.add_edge(Edge::new(n3, n1));
 
         assert_eq!(g, Graph {
             nodes: vec![ 
@@ -329,8 +596,8 @@ mod tests {
             ],
             free_nodes: vec![],
             edges: vec![
-                Some(Edge {start: 0, end: 1}),
-                Some(Edge {start: 2, end: 0}),
+                Some(Edge::new(0, 1)),
+                Some(Edge::new(2, 0)),
             ],
             free_edges: vec![],
         });
@@ -344,8 +611,8 @@ mod tests {
         let n2 = g.add_node(TestNode(-2));
         let n3 = g.add_node(TestNode(-1));
 
-        let _e1 = g.add_edge(Edge { start: n1, end: n2 });
-        let _e2 = g.add_edge(Edge { start: n3, end: n1 });
+        let _e1 = g.add_edge(Edge::new(n1, n2));
+        let _e2 = g.add_edge(Edge::new(n3, n1));
 
         let del = g.delete_node(n2).unwrap();
 
@@ -360,7 +627,7 @@ mod tests {
             free_nodes: vec![1],
             edges: vec![
                 None,
-                Some(Edge {start: 2, end: 0}),
+                Some(Edge::new(2, 0)),
             ],
             free_edges: vec![0],
         });
@@ -394,7 +661,7 @@ mod tests {
         let n_id_1 = g.add_node(TestNode(1));
         let n_id_2 = g.add_node(TestNode(2));
 
-        assert_ne!(Ok(()), g.add_edge(Edge { start: n_id_1, end: 10 }));
+        assert_ne!(Ok(()), g.add_edge(Edge::new(n_id_1, 10)));
         assert_eq!(
             g,
             Graph {
@@ -408,7 +675,7 @@ mod tests {
             }
         );
 
-        assert_eq!(Ok(()), g.add_edge(Edge { start: n_id_1, end: n_id_2 }));
+        assert_eq!(Ok(()), g.add_edge(Edge::new(n_id_1, n_id_2)));
         assert_eq!(
             g,
             Graph {
@@ -418,11 +685,16 @@ mod tests {
                 ],
                 free_nodes: vec![],
                 edges: vec![
-                    Some(Edge { start: n_id_1, end: n_id_2 }),
+                    Some(Edge::new(n_id_1, n_id_2)),
                 ],
                 free_edges: vec![],
             }
         );
+
+        // Deleted nodes can't be connected
+        g.delete_node(n_id_2);
+        assert!(g.add_edge(Edge::new(n_id_1, n_id_2)).is_err());
+        assert!(g.add_edge(Edge::new(n_id_2, n_id_1)).is_err());
     }
 
     #[test]
@@ -471,4 +743,135 @@ mod tests {
 
         assert_eq!(g.delete_edge_by(Some(n1), Some(10)), vec![]);
     }
+
+    #[test]
+    fn test_edge_ports() {
+        let mut g = Graph::new();
+        let n1 = g.add_node(TestNode(1));
+        let n2 = g.add_node(TestNode(2));
+
+        let from = Addr { node: n1, port: 1 };
+        let to = Addr { node: n2, port: 3 };
+        assert_eq!(Ok(()), g.add_edge(Edge::between(from, to)));
+        assert_eq!(Ok(()), g.add_edge(Edge::new(n1, n2)));
+
+        assert_eq!(g.edges_into(to), vec![0]);
+        assert_eq!(g.edges_into(Addr { node: n2, port: 0 }), vec![1]);
+
+        let deleted = g.delete_edge(0).unwrap();
+        assert_eq!(deleted.get_start(), from);
+        assert_eq!(deleted.get_end(), to);
+        assert_eq!(g.delete_edge(0), None);
+        assert!(g.edges_into(to).is_empty());
+
+        // Freed slot gets reused
+        assert_eq!(Ok(()), g.add_edge(Edge::between(from, to)));
+        assert_eq!(g.get_edges().len(), 2);
+        assert_eq!(g.edges_into(to), vec![0]);
+    }
+
+    #[derive(Debug, PartialEq)]
+    pub struct BoxNode(Rectangle);
+
+    impl Drawable for BoxNode {
+        fn draw(&self, _frame: &mut canvas::Frame) {
+        }
+        fn get_bounding_box(&self) -> iced::Rectangle {
+            self.0
+        }
+    }
+
+    impl GraphNode for BoxNode {}
+
+    #[test]
+    fn test_bounding_box() {
+        let mut g = Graph::new();
+        assert_eq!(g.get_bounding_box(), Rectangle::default());
+
+        let n1 = g.add_node(BoxNode(Rectangle { x: 0., y: 0., width: 10., height: 5. }));
+        // Extends to the left and up
+        let n2 = g.add_node(BoxNode(Rectangle { x: -5., y: -3., width: 2., height: 2. }));
+        // Extends to the right and down
+        let n3 = g.add_node(BoxNode(Rectangle { x: 8., y: 4., width: 4., height: 4. }));
+
+        assert_eq!(g.get_bounding_box(), Rectangle { x: -5., y: -3., width: 17., height: 11. });
+        assert_eq!(g.get_bounding_box_of(&[n1, n2]), Some(Rectangle { x: -5., y: -3., width: 15., height: 8. }));
+        assert_eq!(g.get_bounding_box_of(&[n3]), Some(Rectangle { x: 8., y: 4., width: 4., height: 4. }));
+        assert_eq!(g.get_bounding_box_of(&[42]), None);
+    }
+
+    #[derive(Debug, PartialEq)]
+    pub struct SumNode {
+        inputs: Vec<f32>,
+        output: f32,
+    }
+
+    impl Drawable for SumNode {
+        fn draw(&self, _frame: &mut canvas::Frame) {
+        }
+        fn get_bounding_box(&self) -> iced::Rectangle {
+            Rectangle::default()
+        }
+    }
+
+    impl GraphNode for SumNode {
+        fn update(&mut self) {
+            self.output = self.inputs.iter().sum();
+        }
+        fn get_output_value(&self, _port: PortID) -> Option<Port> {
+            Some(Port::Float(self.output))
+        }
+        fn set_input_value(&mut self, port: PortID, value: Port) {
+            if let Some(i) = self.inputs.get_mut(port) {
+                *i = value.as_f32();
+            }
+        }
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let mut g = Graph::new();
+        // Added out of order, c depends on b which depends on a
+        let c = g.add_node(SumNode { inputs: vec![0., 0.], output: 0. });
+        let b = g.add_node(SumNode { inputs: vec![0., 10.], output: 0. });
+        let a = g.add_node(SumNode { inputs: vec![1.], output: 0. });
+
+        assert_eq!(Ok(()), g.add_edge(Edge::between(Addr { node: a, port: 0 }, Addr { node: b, port: 0 })));
+        assert_eq!(Ok(()), g.add_edge(Edge::between(Addr { node: b, port: 0 }, Addr { node: c, port: 0 })));
+        assert_eq!(Ok(()), g.add_edge(Edge::between(Addr { node: a, port: 0 }, Addr { node: c, port: 1 })));
+
+        assert_eq!(g.evaluation_order(), vec![a, b, c]);
+
+        // A single pass propagates through the whole chain
+        g.evaluate();
+        assert_eq!(g.get_node(a).unwrap().output, 1.);
+        assert_eq!(g.get_node(b).unwrap().output, 11.);
+        assert_eq!(g.get_node(c).unwrap().output, 12.);
+    }
+
+    #[test]
+    fn test_evaluation_order_cycle() {
+        let mut g = Graph::new();
+        let n1 = g.add_node(TestNode(1));
+        let n2 = g.add_node(TestNode(2));
+        let n3 = g.add_node(TestNode(3));
+
+        assert_eq!(Ok(()), g.add_edge(Edge::new(n2, n3)));
+        assert_eq!(Ok(()), g.add_edge(Edge::new(n3, n2)));
+
+        assert_eq!(g.evaluation_order(), vec![n1, n2, n3]);
+    }
+
+    #[test]
+    fn test_wire_hit() {
+        let w = Wire::new(Point::new(0., 0.), Point::new(10., 4.));
+
+        assert!(w.hit(Point::new(0., 0.), 0.1));
+        assert!(w.hit(Point::new(10., 4.), 0.1));
+        assert!(w.hit(Point::new(5., 2.), 0.1));
+        assert!(!w.hit(Point::new(5., 8.), 0.5));
+
+        let bb = w.get_bounding_box();
+        assert!(bb.contains(Point::new(5., 2.)));
+    }
 }
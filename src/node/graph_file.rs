@@ -0,0 +1,162 @@
+use std::collections::BTreeMap;
+
+use iced::Point;
+
+use log::warn;
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use super::connections::{
+    Addr,
+    PortID,
+};
+use super::graph::{
+    Edge,
+    Graph,
+    NodeIndex,
+};
+use super::node::Node;
+use super::registry::NodeRegistry;
+
+/// A node as written to a show file, rebuilt from the template of the same name
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeEntry {
+    pub template: String,
+    pub position: [f32; 2],
+    /// Values of the editable inputs by input name
+    #[serde(default)]
+    pub params: BTreeMap<String, f32>,
+    #[serde(default)]
+    pub script: Option<String>,
+}
+
+/// An edge between two nodes given by their index in `GraphFile::nodes`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EdgeEntry {
+    pub from: (usize, PortID),
+    pub to: (usize, PortID),
+}
+
+/// On disk representation of a node graph
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct GraphFile {
+    #[serde(default)]
+    pub nodes: Vec<NodeEntry>,
+    #[serde(default)]
+    pub edges: Vec<EdgeEntry>,
+}
+
+impl GraphFile {
+    pub fn from_graph(graph: &Graph<Node>) -> Self {
+        let nodes = graph.get_nodes();
+        let entries = nodes.iter()
+            .map(|(_, n)| NodeEntry {
+                template: n.get_title().to_owned(),
+                position: [n.get_pos().x, n.get_pos().y],
+                params: n.get_inputs().iter()
+                    .filter(|i| i.get_range().is_some())
+                    .map(|i| (i.get_name().to_owned(), i.get_port().as_f32()))
+                    .collect(),
+                script: n.get_script().map(|s| s.get_source().to_owned()),
+            })
+            .collect();
+
+        let position = |id: NodeIndex| nodes.iter().position(|(c_id, _)| *c_id == id);
+        let edges = graph.get_edges()
+            .into_iter()
+            .filter_map(|(_, e)| Some(EdgeEntry {
+                from: (position(e.get_start().node)?, e.get_start().port),
+                to: (position(e.get_end().node)?, e.get_end().port),
+            }))
+            .collect();
+
+        Self {
+            nodes: entries,
+            edges,
+        }
+    }
+
+    /// Build the graph from the templates of `registry`.
+    /// Nodes of unknown templates are left out together with their edges.
+    pub fn build(&self, registry: &NodeRegistry) -> Graph<Node> {
+        let mut graph = Graph::new();
+        let mut ids: Vec<Option<NodeIndex>> = Vec::new();
+        for c_entry in self.nodes.iter() {
+            let node = registry.get(&c_entry.template)
+                .ok_or_else(|| format!("Unknown node type \"{}\"", c_entry.template))
+                .and_then(|t| (t.build)(Point::new(c_entry.position[0], c_entry.position[1])).map_err(|e| e.to_string()));
+            let mut node = match node {
+                Ok(node) => node,
+                Err(e) => {
+                    warn!("Skipped node: {}", e);
+                    ids.push(None);
+                    continue;
+                }
+            };
+            if let Some(source) = &c_entry.script {
+                // The error is shown on the node
+                let _ = node.set_script_source(source.clone());
+            }
+            for (c_name, c_value) in c_entry.params.iter() {
+                if let Some(port) = node.input_by_name(c_name) {
+                    node.set_param(port, *c_value);
+                }
+            }
+            ids.push(Some(graph.add_node(node)));
+        }
+
+        let id = |index: usize| ids.get(index).copied().flatten();
+        for c_edge in self.edges.iter() {
+            let (from, to) = match (id(c_edge.from.0), id(c_edge.to.0)) {
+                (Some(from), Some(to)) => (from, to),
+                _ => continue,
+            };
+            let edge = Edge::between(Addr { node: from, port: c_edge.from.1 }, Addr { node: to, port: c_edge.to.1 });
+            if let Err(e) = graph.add_edge(edge) {
+                warn!("Skipped edge: {}", e);
+            }
+        }
+        graph
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::GraphNode;
+    use super::super::port::Port;
+
+    #[test]
+    fn test_roundtrip() {
+        let reg = NodeRegistry::default();
+        let mut graph = Graph::new();
+        let script = graph.add_node((reg.get("Script").unwrap().build)(Point::new(1., 2.)).unwrap());
+        let multiply = graph.add_node((reg.get("Multiply").unwrap().build)(Point::new(20., 2.)).unwrap());
+        graph.get_node_mut(script).unwrap().set_script_source("value = level * 2.0;".to_owned()).unwrap();
+        graph.set_param_by_name("Script", "Level", 0.5);
+        graph.set_param_by_name("Multiply", "B", 3.);
+        graph.add_edge(Edge::between(Addr { node: script, port: 0 }, Addr { node: multiply, port: 0 })).unwrap();
+
+        let file = GraphFile::from_graph(&graph);
+        let json = serde_json::to_string(&file).unwrap();
+        let loaded: GraphFile = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, file);
+
+        let mut restored = loaded.build(&reg);
+        assert_eq!(GraphFile::from_graph(&restored), file);
+        restored.evaluate();
+        let (multiply, _) = restored.get_nodes().into_iter().find(|(_, n)| n.get_title() == "Multiply").unwrap();
+        assert_eq!(restored.get_node(multiply).unwrap().get_output_value(0), Some(Port::Float(3.)));
+
+        // Unknown templates are skipped together with their edges
+        let mut file = file;
+        file.nodes[0].template = "Unknown".to_owned();
+        let restored = file.build(&reg);
+        assert_eq!(restored.get_nodes().len(), 1);
+        assert!(restored.get_edges().is_empty());
+    }
+}
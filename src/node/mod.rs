@@ -10,6 +10,15 @@ pub use edge::*;
 mod port;
 pub use port::*;
 
+mod registry;
+pub use registry::*;
+
+mod script;
+pub use script::*;
+
+mod graph_file;
+pub use graph_file::*;
+
 
 pub mod connections;
 pub mod hooks;
@@ -4,12 +4,37 @@ use super::port::{
     Port,
     InputPort,
     OutputPort,
+    ParamRange,
+    ParamWidget,
+};
+use super::graph::{
+    Edge,
+    EdgeIndex,
+    Graph,
+    GraphNode,
+    NodeIndex,
+};
+use super::connections::{
+    Addr,
+    PortID,
+    PortType,
+};
+use super::script::{
+    Script,
+    ScriptPorts,
+};
+
+use crate::dmx::DMX;
+use crate::fixture::{
+    Groups,
+    Selection,
+    SelectionOp,
 };
-use super::graph::GraphNode;
 
 use crate::gui::components::grid::{
     self,
     helpers,
+    Drawable,
 };
 
 use iced::{
@@ -25,21 +50,73 @@ pub type DriverFunction = fn(&Vec<InputPort>) -> Vec<OutputPort>;
 
 #[derive(Clone)]
 pub struct Node {
+    title: String,
     position: Point,
 
     inputs: Vec<InputPort>,
     outputs: Vec<OutputPort>,
 
     drivers: Vec<DriverFunction>,
+    /// Runs after the drivers, so it may overwrite their outputs
+    script: Option<Script>,
+    /// Groups of the show for a node recalling them, kept up to date by `Graph::feed_groups`
+    groups: Option<Groups>,
+    /// Writes the first output into the channel given by the inputs "Universe" and "Channel", see `Graph::write_dmx`
+    dmx_output: bool,
 }
 
 impl fmt::Debug for Node {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        write!(f, "Node {{ inputs: {:?}, outputs: {:?}, driver count: {} }}", self.inputs, self.outputs, self.drivers.len())
+        write!(f, "Node {{ title: {:?}, inputs: {:?}, outputs: {:?}, driver count: {}, script: {:?} }}",
+            self.title, self.inputs, self.outputs, self.drivers.len(), self.script.as_ref().map(|s| s.get_source()))
     }
 }
 
-impl GraphNode for Node {}
+impl GraphNode for Node {
+    fn input_anchor(&self, port: PortID) -> Option<Point> {
+        self.inputs.get(port)?;
+        Some(self.position + self.port_offset(0., port))
+    }
+
+    fn output_anchor(&self, port: PortID) -> Option<Point> {
+        self.outputs.get(port)?;
+        Some(self.position + self.port_offset(Self::NODE_WIDTH, port))
+    }
+
+    fn update(&mut self) {
+        for c_driver in self.drivers.iter() {
+            for (c_out, new_val) in self.outputs.iter_mut().zip(c_driver(&self.inputs)) {
+                c_out.set_port(Port::from(&new_val));
+            }
+        }
+        if let Some(script) = self.script.as_mut() {
+            let time = script.elapsed();
+            script.run(&self.inputs, &mut self.outputs, time);
+        }
+        if let Some(groups) = &self.groups {
+            let selection = recall_group(groups, &self.inputs);
+            if let Some(out) = self.outputs.get_mut(0) {
+                out.set_port(Port::from(&selection));
+            }
+        }
+    }
+
+    fn get_output_value(&self, port: PortID) -> Option<Port> {
+        self.outputs.get(port).map(Port::from)
+    }
+
+    fn set_input_value(&mut self, port: PortID, value: Port) {
+        if let Some(i) = self.inputs.get_mut(port) {
+            i.set_port(value);
+        }
+    }
+
+    fn set_input_connected(&mut self, port: PortID, connected: bool) {
+        if let Some(i) = self.inputs.get_mut(port) {
+            i.set_connected(connected);
+        }
+    }
+}
 
 impl Node {
     const NODE_RADIUS: f32 = 1.;
@@ -47,6 +124,15 @@ impl Node {
     const PORT_HEIGHT: f32 = 1.5;
     const FOOTER_HEIGHT: f32 = 2.;
     const NODE_WIDTH: f32 = 10.;
+    const TITLE_SIZE: f32 = 0.9;
+    const LABEL_SIZE: f32 = 0.6;
+    const VALUE_SIZE: f32 = 0.5;
+    const LABEL_INSET: f32 = 0.8;
+    const SPARKLINE_WIDTH: f32 = 2.5;
+    /// Text smaller than this (in pixels) is not drawn at all
+    const MIN_TEXT_PX: f32 = 5.;
+    /// Characters of a script line or error shown in the footer
+    const FOOTER_CHARS: usize = 32;
 
     pub fn has_inputs(&self) -> bool {
         !self.inputs.is_empty()
@@ -59,11 +145,8 @@ impl Node {
         NodeBuilder::new()
     }
 
-    pub fn udpate(&mut self) {
-        for c_driver in self.drivers.iter() {
-            let _changed_ins = c_driver(&self.inputs);
-        }
-        // TODO implement
+    pub fn get_title(&self) -> &str {
+        &self.title
     }
 
     pub fn is_updated(&self, id: usize) -> bool {
@@ -73,6 +156,10 @@ impl Node {
         }
     }
 
+    pub fn get_pos(&self) -> Point {
+        self.position
+    }
+
     pub fn set_pos(&mut self, new_pos: Point) {
         self.position = new_pos;
     }
@@ -81,12 +168,449 @@ impl Node {
         self.position = self.position + translation;
     }
 
+    pub fn get_inputs(&self) -> &[InputPort] {
+        &self.inputs
+    }
+
+    pub fn get_input(&self, id: PortID) -> Option<&InputPort> {
+        self.inputs.get(id)
+    }
+
+    pub fn get_output(&self, id: PortID) -> Option<&OutputPort> {
+        self.outputs.get(id)
+    }
+
+    /// Find an input by its displayed name
+    pub fn input_by_name(&self, name: &str) -> Option<PortID> {
+        self.inputs.iter().position(|i| i.get_name() == name)
+    }
+
+    /// Find an output by its displayed name
+    pub fn output_by_name(&self, name: &str) -> Option<PortID> {
+        self.outputs.iter().position(|o| o.get_name() == name)
+    }
+
+    /// Find the port located at `p` (in graph coordinates)
+    pub fn port_at(&self, p: Point) -> Option<PortType> {
+        let hit = |anchor: Option<Point>| anchor
+            .map(|a| a.distance(p) <= Port::HEIGHT)
+            .unwrap_or(false);
+
+        (0..self.inputs.len())
+            .find(|i| hit(self.input_anchor(*i)))
+            .map(PortType::Input)
+            .or_else(|| (0..self.outputs.len())
+                .find(|i| hit(self.output_anchor(*i)))
+                .map(PortType::Output))
+    }
+
+    /// Check if the output port `out_port` can be connected to the input `in_port` of `other`
+    pub fn can_connect(&self, out_port: PortID, other: &Node, in_port: PortID) -> bool {
+        match (self.outputs.get(out_port), other.inputs.get(in_port)) {
+            (Some(o), Some(i)) => o.get_port().is_compatible(i.get_port()),
+            _ => false,
+        }
+    }
+
+    /// Area of the inline widget of an input, relative to the node origin
+    fn param_rect_rel(&self, port: PortID) -> iced::Rectangle {
+        let center = Point::ORIGIN + self.port_offset(Self::LABEL_INSET, port);
+        iced::Rectangle {
+            x: center.x,
+            y: center.y + 0.05,
+            width: Self::NODE_WIDTH / 2. - Self::LABEL_INSET - 0.3,
+            height: Self::PORT_HEIGHT / 2. - 0.15,
+        }
+    }
+
+    /// Area of the inline widget of an input in graph coordinates.
+    /// Returns `None` if the input isn't editable.
+    pub fn param_rect(&self, port: PortID) -> Option<iced::Rectangle> {
+        if !self.inputs.get(port)?.is_editable() {
+            return None;
+        }
+        let rel = self.param_rect_rel(port);
+        Some(iced::Rectangle { x: rel.x + self.position.x, y: rel.y + self.position.y, ..rel })
+    }
+
+    /// Find the editable input whose widget is located at `p` (in graph coordinates)
+    pub fn param_at(&self, p: Point) -> Option<PortID> {
+        (0..self.inputs.len())
+            .find(|i| self.param_rect(*i).map(|r| r.contains(p)).unwrap_or(false))
+    }
+
+    /// Set the value of an editable input, snapping it to the range of the input.
+    /// Returns the value which was actually set.
+    pub fn set_param(&mut self, port: PortID, value: f32) -> Option<f32> {
+        let input = self.inputs.get_mut(port)?;
+        let value = input.get_range()?.apply(value);
+        input.set_port(Port::Float(value));
+        Some(value)
+    }
+
+    pub fn get_script(&self) -> Option<&Script> {
+        self.script.as_ref()
+    }
+
+    /// Replace the source of a script node and compile it.
+    /// The ports are rebuilt if the source declares any, see `Graph::set_script_source` to keep the edges.
+    /// Returns the compile error, which is shown on the node as well.
+    pub fn set_script_source(&mut self, source: String) -> Result<(), String> {
+        let title = &self.title;
+        let script = self.script.as_mut().ok_or_else(|| format!("\"{}\" is not a script node", title))?;
+        script.set_source(source);
+        if let Some(e) = script.get_error() {
+            return Err(e.to_owned());
+        }
+        let ports = script.get_ports().clone();
+        self.set_ports(&ports);
+        Ok(())
+    }
+
+    /// Replace the ports by declared ones, ports with the same name as before keep their value.
+    /// Nothing changes if no ports are declared.
+    fn set_ports(&mut self, ports: &ScriptPorts) {
+        if ports.is_empty() {
+            return;
+        }
+        let mut old_inputs = std::mem::take(&mut self.inputs);
+        self.inputs = ports.inputs.iter()
+            .map(|(c_name, c_range)| {
+                let mut input = match old_inputs.iter().position(|i| i.get_name() == c_name.as_str()) {
+                    Some(i) => old_inputs.remove(i),
+                    None => InputPort::new(c_name.clone(), Port::Float(0.)),
+                };
+                if let Some(range) = c_range {
+                    let value = range.apply(input.get_port().as_f32());
+                    input = input.with_range(*range);
+                    input.set_port(Port::Float(value));
+                }
+                input
+            })
+            .collect();
+        let mut old_outputs = std::mem::take(&mut self.outputs);
+        self.outputs = ports.outputs.iter()
+            .map(|c_name| match old_outputs.iter().position(|o| o.get_name() == c_name.as_str()) {
+                Some(i) => old_outputs.remove(i),
+                None => OutputPort::new(c_name.clone(), Port::Float(0.)),
+            })
+            .collect();
+    }
+
+    /// Area of the footer showing the script in graph coordinates.
+    /// Returns `None` if the node has no script.
+    pub fn script_rect(&self) -> Option<iced::Rectangle> {
+        self.script.as_ref()?;
+        Some(iced::Rectangle {
+            x: self.position.x,
+            y: self.position.y + self.calculate_height() - Self::FOOTER_HEIGHT,
+            width: Self::NODE_WIDTH,
+            height: Self::FOOTER_HEIGHT,
+        })
+    }
+
+    fn port_offset(&self, x: f32, port: PortID) -> Vector {
+        Vector::new(x, Self::HEADER_HEIGHT + (Self::PORT_HEIGHT/2.) + (port as f32) * Self::PORT_HEIGHT)
+    }
+
     fn calculate_height(&self) -> f32 {
         Self::HEADER_HEIGHT +
             ((self.inputs.len().max(self.outputs.len()) as f32) * Self::PORT_HEIGHT) +
             Self::FOOTER_HEIGHT
     }
 
+    /// Draw the inline widgets of all editable inputs. Positions are relative to the node origin.
+    fn draw_params(&self, frame: &mut canvas::Frame) {
+        let track_color = Color::from_rgb8(0x40, 0x44, 0x4B); // #40444B
+        let active_color = Color::from_rgb8(0x72, 0x89, 0xDA); // #7289DA
+        let border = canvas::Stroke::default()
+            .with_color(active_color)
+            .with_width(0.05);
+
+        for (i, cin_port) in self.inputs.iter().enumerate() {
+            let range = match cin_port.get_range() {
+                Some(range) if cin_port.is_editable() => range,
+                _ => continue,
+            };
+            let r = self.param_rect_rel(i);
+            let value = cin_port.get_port().as_f32();
+
+            frame.fill_rectangle(r.position(), r.size(), track_color);
+            match range.widget() {
+                ParamWidget::Slider => {
+                    frame.fill_rectangle(
+                        r.position(),
+                        iced::Size::new(r.width * range.fraction(value), r.height),
+                        active_color,
+                    );
+                }
+                ParamWidget::Toggle => {
+                    if value > range.min {
+                        frame.fill_rectangle(r.position(), r.size(), active_color);
+                    }
+                }
+                ParamWidget::Number => (),
+            }
+            frame.stroke(&canvas::Path::rectangle(r.position(), r.size()), border.clone());
+        }
+    }
+
+    /// Draw title, port names and values. Positions are relative to the node origin.
+    fn draw_labels(&self, frame: &mut canvas::Frame, scaling: f32) {
+        let text = canvas::Text {
+            color: Color::WHITE,
+            vertical_alignment: iced::VerticalAlignment::Center,
+            ..canvas::Text::default()
+        };
+        let value_color = Color::from_rgb8(0xA0, 0xA0, 0xA0); // #A0A0A0
+
+        if Self::TITLE_SIZE * scaling >= Self::MIN_TEXT_PX {
+            frame.fill_text(canvas::Text {
+                content: self.title.clone(),
+                position: Point::new(Self::NODE_WIDTH / 2., Self::HEADER_HEIGHT / 2.),
+                size: Self::TITLE_SIZE * scaling,
+                horizontal_alignment: iced::HorizontalAlignment::Center,
+                ..text.clone()
+            });
+        }
+
+        if Self::VALUE_SIZE * scaling < Self::MIN_TEXT_PX {
+            return;
+        }
+
+        let label_dy = Self::PORT_HEIGHT / 4.;
+        for (i, cin_port) in self.inputs.iter().enumerate() {
+            let center = Point::ORIGIN + self.port_offset(Self::LABEL_INSET, i);
+            frame.fill_text(canvas::Text {
+                content: cin_port.get_name().to_owned(),
+                position: Point::new(center.x, center.y - label_dy),
+                size: Self::LABEL_SIZE * scaling,
+                ..text.clone()
+            });
+
+            let (content, position) = if cin_port.is_editable() {
+                let r = self.param_rect_rel(i);
+                (format!("{}", cin_port.get_port()), Point::new(r.x + 0.15, r.center_y()))
+            } else {
+                (format!("{}", cin_port.get_port()), Point::new(center.x, center.y + label_dy))
+            };
+            frame.fill_text(canvas::Text {
+                content,
+                position,
+                size: Self::VALUE_SIZE * scaling,
+                color: value_color,
+                ..text.clone()
+            });
+        }
+
+        for (i, cout_port) in self.outputs.iter().enumerate() {
+            let center = Point::ORIGIN + self.port_offset(Self::NODE_WIDTH - Self::LABEL_INSET, i);
+            frame.fill_text(canvas::Text {
+                content: cout_port.get_name().to_owned(),
+                position: Point::new(center.x, center.y - label_dy),
+                size: Self::LABEL_SIZE * scaling,
+                horizontal_alignment: iced::HorizontalAlignment::Right,
+                ..text.clone()
+            });
+            frame.fill_text(canvas::Text {
+                content: format!("{}", cout_port.get_port()),
+                position: Point::new(center.x, center.y + label_dy),
+                size: Self::VALUE_SIZE * scaling,
+                color: value_color,
+                horizontal_alignment: iced::HorizontalAlignment::Right,
+                ..text.clone()
+            });
+
+            let spark_area = iced::Rectangle {
+                x: Self::NODE_WIDTH / 2.,
+                y: center.y,
+                width: Self::SPARKLINE_WIDTH,
+                height: Self::PORT_HEIGHT / 2. - 0.1,
+            };
+            draw_sparkline(frame, cout_port, spark_area);
+        }
+
+        if let Some(script) = &self.script {
+            self.draw_script(frame, script, scaling, text);
+        }
+    }
+
+    /// Draw the first line of the script, or its error, into the footer
+    fn draw_script(&self, frame: &mut canvas::Frame, script: &Script, scaling: f32, text: canvas::Text) {
+        let (content, color) = match script.get_error() {
+            Some(e) => (e.to_owned(), Color::from_rgb8(0xF0, 0x47, 0x47)), // #F04747
+            None => (
+                script.get_source().lines().map(str::trim).find(|l| !l.is_empty()).unwrap_or("").to_owned(),
+                Color::from_rgb8(0xA0, 0xA0, 0xA0), // #A0A0A0
+            ),
+        };
+        let content = if content.chars().count() > Self::FOOTER_CHARS {
+            format!("{}...", content.chars().take(Self::FOOTER_CHARS).collect::<String>())
+        } else {
+            content
+        };
+        frame.fill_text(canvas::Text {
+            content,
+            position: Point::new(Self::LABEL_INSET, self.calculate_height() - Self::FOOTER_HEIGHT / 2.),
+            size: Self::VALUE_SIZE * scaling,
+            color,
+            ..text
+        });
+    }
+}
+
+/// An edge from and to the given addresses, with the names of the ports it has at a node whose ports are rebuilt
+type NamedEdge = (EdgeIndex, Addr, Addr, Option<String>, Option<String>);
+
+/// Universe index, channel index and value a DMX output node writes, the inputs count from 1
+fn dmx_target(node: &Node) -> Option<(usize, usize, u32)> {
+    let number = |name: &str| node.get_input(node.input_by_name(name)?)
+        .map(|i| i.get_port().as_f32().round())
+        .filter(|n| *n >= 1.)
+        .map(|n| n as usize - 1);
+    let value = node.get_output(0)?.get_port().as_f32().clamp(0., 255.) as u32;
+    Some((number("Universe")?, number("Channel")?, value))
+}
+
+/// Fixtures of the group numbered by the first input, added to the fixtures of the second input
+fn recall_group(groups: &Groups, inputs: &[InputPort]) -> Selection {
+    let mut selection = inputs.get(1)
+        .map(|i| Selection::from(i.get_port()))
+        .unwrap_or_default();
+    let number = inputs.get(0).map(|i| i.get_port().as_f32().round()).unwrap_or(0.);
+    if let Some(group) = groups.get(number.max(0.) as usize) {
+        group.select(&mut selection, SelectionOp::Add);
+    }
+    selection
+}
+
+/// Access to node parameters by name, e.g. from a remote control, and show data fed into nodes
+impl Graph<Node> {
+    /// Find the first node with the title `node` and its input named `param`
+    fn param_by_name(&mut self, node: &str, param: &str) -> Option<(&mut Node, PortID)> {
+        let id = self.get_nodes().into_iter()
+            .find(|(_, n)| n.get_title() == node)?
+            .0;
+        let node = self.get_node_mut(id)?;
+        let port = node.input_by_name(param)?;
+        Some((node, port))
+    }
+
+    /// Set an editable input of the first node with the title `node`.
+    /// Returns the value which was actually set.
+    pub fn set_param_by_name(&mut self, node: &str, param: &str, value: f32) -> Option<f32> {
+        let (node, port) = self.param_by_name(node, param)?;
+        node.set_param(port, value)
+    }
+
+    /// Like `set_param_by_name` with the value given as fraction of the input's range
+    pub fn set_param_fraction_by_name(&mut self, node: &str, param: &str, fraction: f32) -> Option<f32> {
+        let (node, port) = self.param_by_name(node, param)?;
+        let range = node.get_input(port)?.get_range()?;
+        node.set_param(port, range.min + fraction * (range.max - range.min))
+    }
+
+    /// Replace the source of the script node `id` like `Node::set_script_source`.
+    /// Edges stay connected to the ports of the same name, edges of removed ports are deleted.
+    pub fn set_script_source(&mut self, id: NodeIndex, source: String) -> Result<(), String> {
+        let node = self.get_node(id).ok_or_else(|| format!("Node {} does not exist", id))?;
+        let edges: Vec<NamedEdge> = self.get_edges().into_iter()
+            .filter(|(_, e)| e.get_start().node == id || e.get_end().node == id)
+            .map(|(i, e)| {
+                let from = Some(e.get_start()).filter(|a| a.node == id)
+                    .and_then(|a| node.get_output(a.port))
+                    .map(|o| o.get_name().to_owned());
+                let to = Some(e.get_end()).filter(|a| a.node == id)
+                    .and_then(|a| node.get_input(a.port))
+                    .map(|i| i.get_name().to_owned());
+                (i, e.get_start(), e.get_end(), from, to)
+            })
+            .collect();
+
+        let res = self.get_node_mut(id)
+            .ok_or_else(|| format!("Node {} does not exist", id))?
+            .set_script_source(source);
+
+        for (c_index, ..) in edges.iter() {
+            self.delete_edge(*c_index);
+        }
+        for (_, mut c_from, mut c_to, c_from_name, c_to_name) in edges {
+            let node = match self.get_node(id) {
+                Some(node) => node,
+                None => break,
+            };
+            if let Some(name) = c_from_name {
+                match node.output_by_name(&name) {
+                    Some(port) => c_from.port = port,
+                    None => continue,
+                }
+            }
+            if let Some(name) = c_to_name {
+                match node.input_by_name(&name) {
+                    Some(port) => c_to.port = port,
+                    None => continue,
+                }
+            }
+            let _ = self.add_edge(Edge::between(c_from, c_to));
+        }
+        res
+    }
+
+    /// Write the outputs of the DMX output nodes into their channels, after the graph was evaluated.
+    /// Playback and programmer run afterwards and win on the channels they write.
+    pub fn write_dmx(&self, dmx: &mut DMX) {
+        let targets = self.get_nodes().into_iter()
+            .filter(|(_, n)| n.dmx_output)
+            .filter_map(|(_, n)| dmx_target(n));
+        for (c_universe, c_channel, c_value) in targets {
+            if let Some(universe) = dmx.get_universe(c_universe) {
+                universe.set_channel(c_channel, c_value);
+            }
+        }
+    }
+
+    /// Hand the groups of the show to all nodes recalling them, before the graph is evaluated
+    pub fn feed_groups(&mut self, groups: &Groups) {
+        let ids: Vec<NodeIndex> = self.get_nodes().into_iter()
+            .filter(|(_, n)| n.groups.as_ref().map(|g| g != groups).unwrap_or(false))
+            .map(|(id, _)| id)
+            .collect();
+        for c_id in ids {
+            if let Some(n) = self.get_node_mut(c_id) {
+                n.groups = Some(groups.clone());
+            }
+        }
+    }
+}
+
+/// Draw the value history of `port` into `area`, if the value changed recently
+fn draw_sparkline(frame: &mut canvas::Frame, port: &OutputPort, area: iced::Rectangle) {
+    let history = port.get_history();
+    let min = history.iter().cloned().fold(f32::INFINITY, f32::min);
+    let max = history.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+    if history.len() < 2 || (max - min).abs() < f32::EPSILON {
+        return;
+    }
+
+    let step = area.width / (OutputPort::HISTORY_LEN - 1) as f32;
+    let start = area.x + area.width - step * (history.len() - 1) as f32;
+    let mut path_builder = canvas::path::Builder::new();
+    for (i, v) in history.iter().enumerate() {
+        let p = Point::new(
+            start + step * i as f32,
+            area.y + area.height * (1. - (v - min) / (max - min)),
+        );
+        if i == 0 {
+            path_builder.move_to(p);
+        } else {
+            path_builder.line_to(p);
+        }
+    }
+
+    let stroke_s = canvas::Stroke::default()
+        .with_color(Color::from_rgb8(0x6F, 0xFF, 0xE9)) // #6FFFE9
+        .with_width(0.08);
+    frame.stroke(&path_builder.build(), stroke_s);
 }
 
 impl grid::Drawable for Node {
@@ -94,30 +618,53 @@ impl grid::Drawable for Node {
         let path = helpers::rounded_rect_path(self.get_bounding_box(), Self::NODE_RADIUS);
 
         let fill = Color::from_rgb8(0x0F, 0x0F, 0x0F); // #0F0F0F
+        let border = match self.script.as_ref().and_then(Script::get_error) {
+            Some(_) => Color::from_rgb8(0xF0, 0x47, 0x47), // #F04747
+            None => Color::from_rgb8(0x00, 0x80, 0x00), // #008000
+        };
         let stroke_s = canvas::Stroke::default()
-            .with_color(Color::from_rgb8(0x00, 0x80, 0x00)) // #008000
+            .with_color(border)
             .with_width(2.);
 
-        frame.stroke(&path, stroke_s);
-        frame.fill(&path, fill);
+        frame.with_save(|frame| {
+            frame.translate(Vector::new(self.position.x, self.position.y));
+
+            frame.stroke(&path, stroke_s);
+            frame.fill(&path, fill);
+
+            for (i, cin_port) in self.inputs.iter().enumerate() {
+                frame.with_save(|frame| {
+                    frame.translate(self.port_offset(0., i));
+                    cin_port.draw(frame);
+                });
+            }
+
+            for (i, cout_port) in self.outputs.iter().enumerate() {
+                frame.with_save(|frame| {
+                    frame.translate(self.port_offset(Self::NODE_WIDTH, i));
+                    cout_port.draw(frame);
+                });
+            }
+        });
+    }
 
-        let mut c_transl = Vector::new(0., Self::HEADER_HEIGHT + (Self::PORT_HEIGHT/2.));
-        for cin_port in self.inputs.iter() {
-            frame.translate(c_transl);
-            cin_port.draw(frame);
-            frame.translate(Vector::default()-c_transl);
-            c_transl = c_transl + Vector::new(0., Self::PORT_HEIGHT);
-        }
+    fn draw_scaled(&self, frame: &mut canvas::Frame, scaling: f32) {
+        self.draw(frame);
 
-        let mut c_transl = Vector::new(Self::NODE_WIDTH, Self::HEADER_HEIGHT + (Self::PORT_HEIGHT/2.));
-        for cout_port in self.outputs.iter() {
-            frame.translate(c_transl);
-            cout_port.draw(frame);
-            frame.translate(Vector::default()-c_transl);
-            c_transl = c_transl + Vector::new(0., Self::PORT_HEIGHT);
-        }
+        frame.with_save(|frame| {
+            frame.translate(Vector::new(self.position.x, self.position.y));
 
+            let separator = canvas::Path::line(
+                Point::new(0., Self::HEADER_HEIGHT),
+                Point::new(Self::NODE_WIDTH, Self::HEADER_HEIGHT),
+            );
+            frame.stroke(&separator, canvas::Stroke::default()
+                .with_color(Color::from_rgb8(0x00, 0x80, 0x00)) // #008000
+                .with_width(0.1));
 
+            self.draw_params(frame);
+            self.draw_labels(frame, scaling);
+        });
     }
 
     fn get_bounding_box(&self) -> iced::Rectangle {
@@ -129,22 +676,35 @@ impl grid::Drawable for Node {
 }
 
 pub struct NodeBuilder {
+    title: String,
     starting_pos: Point,
     inputs: Vec<InputPort>,
     outputs: Vec<InputPort>,
     drivers: Vec<DriverFunction>,
+    script: Option<String>,
+    groups: bool,
+    dmx_output: bool,
 }
 
 impl NodeBuilder {
     fn new() -> Self {
         Self {
+            title: String::from("Node"),
             starting_pos: Point::default(),
             inputs: Vec::with_capacity(20),
             outputs: Vec::with_capacity(10),
             drivers: Vec::with_capacity(10),
+            script: None,
+            groups: false,
+            dmx_output: false,
         }
     }
 
+    pub fn with_title(mut self, title: String) -> Self {
+        self.title = title;
+        self
+    }
+
     pub fn with_starting_pos(mut self, pos: Point) -> Self {
         self.starting_pos = pos;
         self
@@ -155,6 +715,12 @@ impl NodeBuilder {
         self
     }
 
+    /// Add an input editable within `min` and `max` in increments of `step`
+    pub fn with_in_range(mut self, name: String, port: Port, min: f32, max: f32, step: f32) -> Self {
+        self.inputs.push(InputPort::new(name, port).with_range(ParamRange::new(min, max, step)));
+        self
+    }
+
     pub fn with_out(mut self, name: String, port: Port) -> Self {
         self.outputs.push(InputPort::new(name, port));
         self
@@ -165,10 +731,29 @@ impl NodeBuilder {
         self
     }
 
+    /// Define the behaviour with a script, see `Script` for the variables it can access
+    pub fn with_script(mut self, source: String) -> Self {
+        self.script = Some(source);
+        self
+    }
+
+    /// Recall a group of the show into the first output, see `Graph::feed_groups`
+    pub fn with_groups(mut self) -> Self {
+        self.groups = true;
+        self
+    }
+
+    /// Send the first output to the DMX universes, see `Graph::write_dmx`
+    pub fn with_dmx_output(mut self) -> Self {
+        self.dmx_output = true;
+        self
+    }
+
     pub fn build(self) -> Result<Node, err::NodeCreationErr> {
         // TODO Check if drivers overlap and warn appropiatly
 
-        Ok(Node {
+        let mut node = Node {
+            title: self.title,
             position: self.starting_pos,
             inputs: self.inputs,
             outputs: self.outputs
@@ -176,6 +761,13 @@ impl NodeBuilder {
                 .map(OutputPort::from)
                 .collect(),
             drivers: self.drivers,
-        })
+            script: self.script.map(Script::new),
+            groups: if self.groups { Some(Groups::new()) } else { None },
+            dmx_output: self.dmx_output,
+        };
+        if let Some(ports) = node.script.as_ref().map(|s| s.get_ports().clone()) {
+            node.set_ports(&ports);
+        }
+        Ok(node)
     }
 }
@@ -1,6 +1,15 @@
-use crate::gui::components::grid::{
-    self,
-    Drawable,
+use std::fmt;
+use std::collections::VecDeque;
+
+use crate::{
+    fixture::{
+        FixtureID,
+        Selection,
+    },
+    gui::components::grid::{
+        self,
+        Drawable,
+    },
 };
 
 use iced::{
@@ -11,7 +20,7 @@ use iced::{
 };
 
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Port {
     Float(f32),
     Unsigned8(u8),
@@ -20,7 +29,49 @@ pub enum Port {
 }
 
 impl Port {
-    const HEIGHT: f32 = 0.5;
+    pub(super) const HEIGHT: f32 = 0.5;
+
+    /// Check whether a value of this port can be fed into the `other` port.
+    /// Scalar ports convert between each other, arrays only connect to arrays.
+    pub fn is_compatible(&self, other: &Port) -> bool {
+        match (self, other) {
+            (Port::Array(_), Port::Array(_)) => true,
+            (Port::Array(_), _)|(_, Port::Array(_)) => false,
+            (_, _) => true,
+        }
+    }
+
+    /// Get the scalar value of the port, arrays evaluate to their first element
+    pub fn as_f32(&self) -> f32 {
+        match self {
+            Port::Float(v) => *v,
+            Port::Unsigned8(v) => *v as f32,
+            Port::Integer(v) => *v as f32,
+            Port::Array(a) => a.first().map(|v| v.as_f32()).unwrap_or(0.),
+        }
+    }
+
+    /// Convert the value into the type of `like`, so an input keeps its type when fed by an edge
+    pub fn converted(&self, like: &Port) -> Port {
+        match (like, self) {
+            (Port::Array(_), Port::Array(a)) => Port::Array(a.clone()),
+            (Port::Array(_), v) => Port::Array(vec![Box::new(v.clone())]),
+            (Port::Float(_), v) => Port::Float(v.as_f32()),
+            (Port::Unsigned8(_), v) => Port::Unsigned8(v.as_f32().round().max(0.).min(255.) as u8),
+            (Port::Integer(_), v) => Port::Integer(v.as_f32().round() as i32),
+        }
+    }
+}
+
+impl fmt::Display for Port {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Port::Float(v) => write!(f, "{:.2}", v),
+            Port::Unsigned8(v) => write!(f, "{}", v),
+            Port::Integer(v) => write!(f, "{}", v),
+            Port::Array(a) => write!(f, "[{}]", a.len()),
+        }
+    }
 }
 
 impl From<&InputPort> for Port {
@@ -34,20 +85,140 @@ impl From<&OutputPort> for Port {
     }
 }
 
+/// Fixtures of a selection or group are passed between nodes as an array of their ids
+impl From<&Selection> for Port {
+    fn from(s: &Selection) -> Port {
+        Port::Array(s.ids().iter().map(|id| Box::new(Port::Integer(*id as i32))).collect())
+    }
+}
+impl From<&Port> for Selection {
+    fn from(p: &Port) -> Selection {
+        let ids: Vec<FixtureID> = match p {
+            Port::Array(a) => a.iter().map(|v| v.as_f32()).filter(|v| *v >= 0.).map(|v| v as FixtureID).collect(),
+            _ => Vec::new(),
+        };
+        Selection::from(ids)
+    }
+}
+
+
+/// Kind of inline widget used to edit an unconnected input
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParamWidget {
+    Slider,
+    Number,
+    Toggle,
+}
+
+/// Value range of an editable input
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParamRange {
+    pub min: f32,
+    pub max: f32,
+    pub step: f32,
+}
+
+impl ParamRange {
+    pub fn new(min: f32, max: f32, step: f32) -> Self {
+        Self { min, max, step }
+    }
+
+    /// Default range for the type of `port`, arrays are not editable
+    pub fn for_port(port: &Port) -> Option<Self> {
+        match port {
+            Port::Float(_) => Some(Self::new(0., 1., 0.01)),
+            Port::Unsigned8(_) => Some(Self::new(0., 255., 1.)),
+            Port::Integer(_) => Some(Self::new(f32::NEG_INFINITY, f32::INFINITY, 1.)),
+            Port::Array(_) => None,
+        }
+    }
+
+    /// Snap `v` to the step size and clamp it into the range
+    pub fn apply(&self, v: f32) -> f32 {
+        let base = if self.min.is_finite() { self.min } else { 0. };
+        let snapped = if self.step > 0. {
+            base + ((v - base) / self.step).round() * self.step
+        } else {
+            v
+        };
+        snapped.max(self.min).min(self.max)
+    }
+
+    /// Position of `v` inside the range in `[0, 1]`
+    pub fn fraction(&self, v: f32) -> f32 {
+        if self.is_bounded() && self.max > self.min {
+            ((v - self.min) / (self.max - self.min)).max(0.).min(1.)
+        } else {
+            0.
+        }
+    }
+
+    pub fn is_bounded(&self) -> bool {
+        self.min.is_finite() && self.max.is_finite()
+    }
+
+    pub fn widget(&self) -> ParamWidget {
+        if !self.is_bounded() {
+            ParamWidget::Number
+        } else if (self.max - self.min) <= self.step {
+            ParamWidget::Toggle
+        } else {
+            ParamWidget::Slider
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct InputPort {
     disp_name: String,
     port: Port,
+    range: Option<ParamRange>,
+    connected: bool,
 }
 
 impl InputPort {
     pub fn new(disp_name: String, port: Port) -> Self {
         InputPort {
             disp_name,
+            range: ParamRange::for_port(&port),
             port,
+            connected: false,
         }
     }
+
+    pub fn with_range(mut self, range: ParamRange) -> Self {
+        self.range = Some(range);
+        self
+    }
+
+    pub fn get_range(&self) -> Option<ParamRange> {
+        self.range
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    pub fn set_connected(&mut self, connected: bool) {
+        self.connected = connected;
+    }
+
+    /// Check if the value can be edited inline, which is the case for unconnected inputs with a range
+    pub fn is_editable(&self) -> bool {
+        !self.connected && self.range.is_some()
+    }
+
+    pub fn get_port(&self) -> &Port {
+        &self.port
+    }
+
+    pub fn set_port(&mut self, port: Port) {
+        self.port = port.converted(&self.port);
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.disp_name
+    }
 }
 
 impl Drawable for InputPort {
@@ -61,10 +232,7 @@ impl Drawable for InputPort {
 
 impl From<OutputPort> for InputPort {
     fn from(o: OutputPort) -> InputPort {
-        InputPort {
-            disp_name: o.disp_name,
-            port: o.port,
-        }
+        Self::new(o.disp_name, o.port)
     }
 }
 
@@ -74,20 +242,50 @@ pub struct OutputPort {
     disp_name: String,
     port: Port,
     updated: bool,
+    history: VecDeque<f32>,
 }
 
 impl OutputPort {
+    /// Number of past values kept for the sparkline
+    pub const HISTORY_LEN: usize = 48;
+
     pub fn new(disp_name: String, port: Port) -> Self {
         OutputPort {
             disp_name,
             port,
             updated: true,
+            history: VecDeque::with_capacity(Self::HISTORY_LEN),
         }
     }
 
+    /// Set a newly calculated value, keeping the type of the port
+    pub fn set_port(&mut self, port: Port) {
+        let port = port.converted(&self.port);
+        self.updated = port != self.port;
+        self.port = port;
+
+        if self.history.len() >= Self::HISTORY_LEN {
+            self.history.pop_front();
+        }
+        self.history.push_back(self.port.as_f32());
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.disp_name
+    }
+
+    /// Past values of the port, oldest first
+    pub fn get_history(&self) -> &VecDeque<f32> {
+        &self.history
+    }
+
     pub fn get_updated(&self) -> bool {
         self.updated
     }
+
+    pub fn get_port(&self) -> &Port {
+        &self.port
+    }
 }
 
 impl Drawable for OutputPort {
@@ -139,3 +337,48 @@ fn draw_port(port: &Port, frame: &mut canvas::Frame) {
     frame.fill(&path, fill_s);
     frame.stroke(&path, stroke_s);
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_param_range() {
+        let r = ParamRange::new(0., 1., 0.25);
+        assert_eq!(r.apply(0.3), 0.25);
+        assert_eq!(r.apply(0.4), 0.5);
+        assert_eq!(r.apply(7.), 1.);
+        assert_eq!(r.apply(-1.), 0.);
+        assert_eq!(r.fraction(0.5), 0.5);
+        assert_eq!(r.widget(), ParamWidget::Slider);
+
+        assert_eq!(ParamRange::new(0., 1., 1.).widget(), ParamWidget::Toggle);
+
+        let int = ParamRange::for_port(&Port::Integer(0)).unwrap();
+        assert_eq!(int.widget(), ParamWidget::Number);
+        assert_eq!(int.apply(-4.6), -5.);
+        assert_eq!(int.fraction(10.), 0.);
+
+        assert_eq!(ParamRange::for_port(&Port::Array(vec![])), None);
+    }
+
+    #[test]
+    fn test_input_conversion() {
+        let mut i = InputPort::new("In".to_owned(), Port::Unsigned8(0));
+        i.set_port(Port::Float(300.4));
+        assert_eq!(i.get_port(), &Port::Unsigned8(255));
+
+        assert!(i.is_editable());
+        i.set_connected(true);
+        assert!(!i.is_editable());
+    }
+
+    #[test]
+    fn test_selection_port() {
+        let port = Port::from(&Selection::from(vec![3, 1]));
+        assert_eq!(port.to_string(), "[2]");
+        assert_eq!(Selection::from(&port).ids(), &[3, 1]);
+        assert!(Selection::from(&Port::Float(2.)).is_empty());
+    }
+}
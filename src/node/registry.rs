@@ -0,0 +1,402 @@
+use iced::Point;
+
+use super::node::Node;
+use super::port::{
+    Port,
+    InputPort,
+    OutputPort,
+};
+use super::err::NodeCreationErr;
+
+pub type NodeConstructor = fn(Point) -> Result<Node, NodeCreationErr>;
+
+/// A node type that can be instantiated by the user
+#[derive(Debug, Clone)]
+pub struct NodeTemplate {
+    pub name: &'static str,
+    pub category: &'static str,
+    pub build: NodeConstructor,
+}
+
+#[derive(Debug, Clone)]
+pub struct NodeRegistry {
+    templates: Vec<NodeTemplate>,
+}
+
+impl Default for NodeRegistry {
+    fn default() -> Self {
+        let mut reg = Self::new();
+        for c_template in builtin_templates() {
+            reg.register(c_template);
+        }
+        reg
+    }
+}
+
+impl NodeRegistry {
+    /// Create a registry without any node types
+    pub fn new() -> Self {
+        Self {
+            templates: Vec::new(),
+        }
+    }
+
+    pub fn register(&mut self, template: NodeTemplate) -> &mut Self {
+        self.templates.push(template);
+        self
+    }
+
+    /// Get the template named `name`
+    pub fn get(&self, name: &str) -> Option<&NodeTemplate> {
+        self.templates.iter().find(|t| t.name == name)
+    }
+
+    /// Get all registered templates ordered by category and name
+    pub fn by_category(&self) -> Vec<&NodeTemplate> {
+        let mut all: Vec<&NodeTemplate> = self.templates.iter().collect();
+        all.sort_by(|a, b| (a.category, a.name).cmp(&(b.category, b.name)));
+        all
+    }
+
+    /// Get all templates matching `query`, best match first.
+    /// An empty query returns every template ordered by category.
+    pub fn search(&self, query: &str) -> Vec<&NodeTemplate> {
+        if query.trim().is_empty() {
+            return self.by_category();
+        }
+
+        let mut scored: Vec<(i32, &NodeTemplate)> = self.templates
+            .iter()
+            .filter_map(|t| {
+                let by_name = fuzzy_score(query, t.name);
+                let by_full = fuzzy_score(query, &format!("{} {}", t.category, t.name))
+                    .map(|s| s - 1);
+                by_name.max(by_full).map(|s| (s, t))
+            })
+            .collect();
+        scored.sort_by(|(sa, a), (sb, b)| sb.cmp(sa)
+            .then_with(|| (a.category, a.name).cmp(&(b.category, b.name))));
+        scored.into_iter().map(|(_, t)| t).collect()
+    }
+}
+
+/// Score how well `query` matches `candidate` as a case insensitive subsequence.
+/// Returns `None` if not all characters of the query are contained in order.
+/// Consecutive matches and matches at word starts are rated higher.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i32> {
+    let mut score = 0;
+    let mut last_match: Option<usize> = None;
+    let cand: Vec<char> = candidate.to_lowercase().chars().collect();
+    let mut c_idx = 0;
+
+    for q in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = (c_idx..cand.len()).find(|i| cand[*i] == q)?;
+
+        score += 1;
+        if found == 0 || !cand[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        match last_match {
+            Some(l) if l + 1 == found => score += 2,
+            Some(l) => score -= ((found - l - 1) as i32).min(3),
+            None => score -= (found as i32).min(3),
+        }
+
+        last_match = Some(found);
+        c_idx = found + 1;
+    }
+    Some(score)
+}
+
+
+fn input_f32(inputs: &Vec<InputPort>, id: usize) -> f32 {
+    inputs.get(id)
+        .map(|i| i.get_port().as_f32())
+        .unwrap_or(0.)
+}
+
+fn constant_driver(inputs: &Vec<InputPort>) -> Vec<OutputPort> {
+    vec![OutputPort::new("Value".to_owned(), Port::Float(input_f32(inputs, 0)))]
+}
+
+fn add_driver(inputs: &Vec<InputPort>) -> Vec<OutputPort> {
+    vec![OutputPort::new("Sum".to_owned(), Port::Float(input_f32(inputs, 0) + input_f32(inputs, 1)))]
+}
+
+fn multiply_driver(inputs: &Vec<InputPort>) -> Vec<OutputPort> {
+    vec![OutputPort::new("Product".to_owned(), Port::Float(input_f32(inputs, 0) * input_f32(inputs, 1)))]
+}
+
+fn clamp_driver(inputs: &Vec<InputPort>) -> Vec<OutputPort> {
+    let (min, max) = (input_f32(inputs, 1), input_f32(inputs, 2));
+    vec![OutputPort::new("Value".to_owned(), Port::Float(input_f32(inputs, 0).max(min).min(max)))]
+}
+
+fn to_dmx_driver(inputs: &Vec<InputPort>) -> Vec<OutputPort> {
+    let v = (input_f32(inputs, 0).max(0.).min(1.) * 255.).round() as u8;
+    vec![OutputPort::new("DMX".to_owned(), Port::Unsigned8(v))]
+}
+
+fn switch_driver(inputs: &Vec<InputPort>) -> Vec<OutputPort> {
+    let selected = if input_f32(inputs, 0) > 0. { 2 } else { 1 };
+    vec![OutputPort::new("Value".to_owned(), Port::Float(input_f32(inputs, selected)))]
+}
+
+/// Source of new script nodes, a pulse which can be edited into any effect
+const DEFAULT_SCRIPT: &str = "// Pulse the level at speed Hz
+// in: Level 0..1 0.01
+// in: Speed 0..10 0.1
+// out: Value
+value = level * (0.5 + 0.5 * sin(time * speed * 2.0 * PI()));
+";
+
+fn builtin_templates() -> Vec<NodeTemplate> {
+    vec![
+        NodeTemplate {
+            name: "Constant",
+            category: "Input",
+            build: |pos| Node::new()
+                .with_title("Constant".to_owned())
+                .with_starting_pos(pos)
+                .with_in("Value".to_owned(), Port::Float(0.))
+                .with_out("Value".to_owned(), Port::Float(0.))
+                .with_driver(constant_driver)
+                .build(),
+        },
+        NodeTemplate {
+            name: "Group",
+            category: "Input",
+            build: |pos| Node::new()
+                .with_title("Group".to_owned())
+                .with_starting_pos(pos)
+                .with_in_range("Group".to_owned(), Port::Integer(1), 1., 999., 1.)
+                .with_in("Add".to_owned(), Port::Array(Vec::new()))
+                .with_out("Fixtures".to_owned(), Port::Array(Vec::new()))
+                .with_groups()
+                .build(),
+        },
+        NodeTemplate {
+            name: "Add",
+            category: "Math",
+            build: |pos| Node::new()
+                .with_title("Add".to_owned())
+                .with_starting_pos(pos)
+                .with_in("A".to_owned(), Port::Float(0.))
+                .with_in("B".to_owned(), Port::Float(0.))
+                .with_out("Sum".to_owned(), Port::Float(0.))
+                .with_driver(add_driver)
+                .build(),
+        },
+        NodeTemplate {
+            name: "Multiply",
+            category: "Math",
+            build: |pos| Node::new()
+                .with_title("Multiply".to_owned())
+                .with_starting_pos(pos)
+                .with_in("A".to_owned(), Port::Float(0.))
+                .with_in_range("B".to_owned(), Port::Float(1.), 0., 10., 0.1)
+                .with_out("Product".to_owned(), Port::Float(0.))
+                .with_driver(multiply_driver)
+                .build(),
+        },
+        NodeTemplate {
+            name: "Clamp",
+            category: "Math",
+            build: |pos| Node::new()
+                .with_title("Clamp".to_owned())
+                .with_starting_pos(pos)
+                .with_in("Value".to_owned(), Port::Float(0.))
+                .with_in("Min".to_owned(), Port::Float(0.))
+                .with_in("Max".to_owned(), Port::Float(1.))
+                .with_out("Value".to_owned(), Port::Float(0.))
+                .with_driver(clamp_driver)
+                .build(),
+        },
+        NodeTemplate {
+            name: "Switch",
+            category: "Logic",
+            build: |pos| Node::new()
+                .with_title("Switch".to_owned())
+                .with_starting_pos(pos)
+                .with_in_range("Select B".to_owned(), Port::Unsigned8(0), 0., 1., 1.)
+                .with_in("A".to_owned(), Port::Float(0.))
+                .with_in("B".to_owned(), Port::Float(1.))
+                .with_out("Value".to_owned(), Port::Float(0.))
+                .with_driver(switch_driver)
+                .build(),
+        },
+        NodeTemplate {
+            name: "Script",
+            category: "Logic",
+            build: |pos| Node::new()
+                .with_title("Script".to_owned())
+                .with_starting_pos(pos)
+                .with_in_range("Level".to_owned(), Port::Float(1.), 0., 1., 0.01)
+                .with_in_range("Speed".to_owned(), Port::Float(1.), 0., 10., 0.1)
+                .with_out("Value".to_owned(), Port::Float(0.))
+                .with_script(DEFAULT_SCRIPT.to_owned())
+                .build(),
+        },
+        NodeTemplate {
+            name: "To DMX",
+            category: "Convert",
+            build: |pos| Node::new()
+                .with_title("To DMX".to_owned())
+                .with_starting_pos(pos)
+                .with_in("Level".to_owned(), Port::Float(0.))
+                .with_in_range("Universe".to_owned(), Port::Integer(1), 1., 64., 1.)
+                .with_in_range("Channel".to_owned(), Port::Integer(1), 1., 512., 1.)
+                .with_out("DMX".to_owned(), Port::Unsigned8(0))
+                .with_driver(to_dmx_driver)
+                .with_dmx_output()
+                .build(),
+        },
+    ]
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dmx::{
+        DMX,
+        DMXUniverse,
+    };
+    use crate::fixture::{
+        Group,
+        Groups,
+        Selection,
+    };
+    use super::super::{
+        connections::Addr,
+        Edge,
+        Graph,
+        GraphNode,
+    };
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert!(fuzzy_score("add", "Add").is_some());
+        assert!(fuzzy_score("mly", "Multiply").is_some());
+        assert!(fuzzy_score("xyz", "Multiply").is_none());
+        assert!(fuzzy_score("dda", "Add").is_none());
+
+        // Prefixes rate better than scattered matches
+        assert!(fuzzy_score("mul", "Multiply") > fuzzy_score("mly", "Multiply"));
+        // Word starts rate better than matches inside a word
+        assert!(fuzzy_score("d", "To DMX") > fuzzy_score("d", "Add"));
+    }
+
+    #[test]
+    fn test_search() {
+        let reg = NodeRegistry::default();
+
+        let all = reg.search("");
+        assert_eq!(all.len(), builtin_templates().len());
+        assert_eq!(all[0].category, "Convert");
+
+        let found = reg.search("mul");
+        assert_eq!(found[0].name, "Multiply");
+
+        // Categories are searchable as well
+        let found = reg.search("math");
+        assert_eq!(found.len(), 3);
+        assert!(found.iter().all(|t| t.category == "Math"));
+
+        assert!(reg.search("qqq").is_empty());
+    }
+
+    #[test]
+    fn test_build_templates() {
+        let reg = NodeRegistry::default();
+        for c_template in reg.by_category() {
+            assert!((c_template.build)(Point::new(1., 2.)).is_ok(), "{} failed to build", c_template.name);
+        }
+
+        let script = reg.search("script")[0];
+        let node = (script.build)(Point::ORIGIN).unwrap();
+        assert_eq!(node.get_script().and_then(|s| s.get_error()), None);
+        assert_eq!(node.get_script().unwrap().get_ports().inputs.len(), 2);
+    }
+
+    #[test]
+    fn test_to_dmx() {
+        let reg = NodeRegistry::default();
+        let mut graph = Graph::new();
+        let constant = graph.add_node((reg.get("Constant").unwrap().build)(Point::ORIGIN).unwrap());
+        let to_dmx = graph.add_node((reg.get("To DMX").unwrap().build)(Point::ORIGIN).unwrap());
+        graph.add_edge(Edge::between(Addr { node: constant, port: 0 }, Addr { node: to_dmx, port: 0 })).unwrap();
+        graph.set_param_by_name("Constant", "Value", 0.5);
+        graph.set_param_by_name("To DMX", "Universe", 2.);
+        graph.set_param_by_name("To DMX", "Channel", 10.);
+        graph.evaluate();
+
+        let mut dmx = DMX::new();
+        dmx.add_universe(DMXUniverse::new(0)).add_universe(DMXUniverse::new(1));
+        graph.write_dmx(&mut dmx);
+        assert_eq!(dmx.get_universe_ref(1).unwrap().get_channel(9).unwrap().get_val(), 128);
+        assert_eq!(dmx.get_universe_ref(0).unwrap().get_channel(9).unwrap().get_val(), 0);
+    }
+
+    #[test]
+    fn test_script_ports() {
+        let reg = NodeRegistry::default();
+        let mut graph = Graph::new();
+        let constant = graph.add_node((reg.get("Constant").unwrap().build)(Point::ORIGIN).unwrap());
+        let script = graph.add_node((reg.get("Script").unwrap().build)(Point::ORIGIN).unwrap());
+        let dmx = graph.add_node((reg.get("To DMX").unwrap().build)(Point::ORIGIN).unwrap());
+        graph.add_edge(Edge::between(Addr { node: constant, port: 0 }, Addr { node: script, port: 1 })).unwrap();
+        graph.add_edge(Edge::between(Addr { node: script, port: 0 }, Addr { node: dmx, port: 0 })).unwrap();
+        graph.set_param_by_name("Script", "Speed", 8.);
+
+        // Ports of the same name keep their edges and values
+        graph.set_script_source(script, "// in: Gain\n// in: Speed 0..5\n// out: Inverted\n// out: Value\nvalue = gain;\ninverted = 1.0 - gain;".to_owned()).unwrap();
+        let node = graph.get_node(script).unwrap();
+        let names: Vec<&str> = node.get_inputs().iter().map(|i| i.get_name()).collect();
+        assert_eq!(names, ["Gain", "Speed"]);
+        assert_eq!(node.output_by_name("Value"), Some(1));
+        assert_eq!(node.get_input(1).unwrap().get_port(), &Port::Float(5.));
+        assert!(node.get_input(1).unwrap().is_connected());
+        assert!(!node.get_input(0).unwrap().is_connected());
+        let edges: Vec<(Addr, Addr)> = graph.get_edges().into_iter().map(|(_, e)| (e.get_start(), e.get_end())).collect();
+        assert_eq!(edges.len(), 2);
+        assert!(edges.contains(&(Addr { node: constant, port: 0 }, Addr { node: script, port: 1 })));
+        assert!(edges.contains(&(Addr { node: script, port: 1 }, Addr { node: dmx, port: 0 })));
+
+        // Edges of removed ports are deleted
+        graph.set_script_source(script, "// in: Gain\n// out: Inverted\ninverted = 1.0 - gain;".to_owned()).unwrap();
+        assert!(graph.get_edges().is_empty());
+        assert!(!graph.get_node(dmx).unwrap().get_input(0).unwrap().is_connected());
+
+        // Invalid declarations keep the ports
+        assert!(graph.set_script_source(script, "// in: Speed 1..x".to_owned()).is_err());
+        assert_eq!(graph.get_node(script).unwrap().get_inputs().len(), 1);
+        assert!(graph.set_script_source(constant, String::new()).is_err());
+    }
+
+    #[test]
+    fn test_group_node() {
+        let mut groups = Groups::new();
+        groups.store(Group::new(2, "Front").with_fixtures(&[3, 1]));
+
+        let reg = NodeRegistry::default();
+        let template = reg.search("group")[0];
+        let mut graph = Graph::new();
+        let id = graph.add_node((template.build)(Point::ORIGIN).unwrap());
+        {
+            let node = graph.get_node_mut(id).unwrap();
+            node.set_param(0, 2.);
+            node.set_input_value(1, Port::from(&Selection::from(vec![5, 3])));
+        }
+        graph.feed_groups(&groups);
+        graph.evaluate();
+        let out = graph.get_node(id).unwrap().get_output_value(0).unwrap();
+        assert_eq!(Selection::from(&out).ids(), &[5, 3, 1]);
+
+        // Unknown groups only pass the added fixtures on
+        graph.get_node_mut(id).unwrap().set_param(0, 4.);
+        graph.evaluate();
+        let out = graph.get_node(id).unwrap().get_output_value(0).unwrap();
+        assert_eq!(Selection::from(&out).ids(), &[5, 3]);
+    }
+}
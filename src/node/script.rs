@@ -0,0 +1,356 @@
+use std::time::Instant;
+
+use log::info;
+
+use rhai::{
+    AST,
+    Array,
+    Dynamic,
+    Engine,
+    Scope,
+    FLOAT,
+    INT,
+};
+
+use super::port::{
+    Port,
+    InputPort,
+    OutputPort,
+    ParamRange,
+};
+
+/// Operations a single run may take before it is aborted, so endless loops can't freeze the desk
+const MAX_OPERATIONS: u64 = 100_000;
+const MAX_CALL_LEVELS: usize = 32;
+const MAX_COLLECTION_SIZE: usize = 4096;
+
+lazy_static! {
+    static ref ENGINE: Engine = sandboxed_engine();
+}
+
+/// Rhai engine for node scripts.
+/// The language itself has no file or network access and modules can't be imported
+/// (`no_module`), so scripts only see the variables they get passed.
+fn sandboxed_engine() -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_max_operations(MAX_OPERATIONS)
+        .set_max_call_levels(MAX_CALL_LEVELS)
+        .set_max_expr_depths(64, 32)
+        .set_max_string_size(MAX_COLLECTION_SIZE)
+        .set_max_array_size(MAX_COLLECTION_SIZE)
+        .set_max_map_size(MAX_COLLECTION_SIZE)
+        .disable_symbol("eval")
+        .on_print(|s| info!("Script: {}", s));
+    engine
+}
+
+/// Name of the script variable of a port, e.g. "Select B" becomes `select_b`
+pub fn variable_name(port_name: &str) -> String {
+    let name: String = port_name.trim()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", name)
+    } else {
+        name
+    }
+}
+
+fn to_dynamic(port: &Port) -> Dynamic {
+    match port {
+        Port::Float(v) => Dynamic::from(*v as FLOAT),
+        Port::Unsigned8(v) => Dynamic::from(*v as INT),
+        Port::Integer(v) => Dynamic::from(*v as INT),
+        Port::Array(a) => Dynamic::from(a.iter().map(|v| to_dynamic(v)).collect::<Array>()),
+    }
+}
+
+/// Convert a script value into the type of `like`. Returns `None` for values without a number.
+fn from_dynamic(value: Dynamic, like: &Port) -> Option<Port> {
+    let port = if value.is::<Array>() {
+        let items = value.cast::<Array>()
+            .into_iter()
+            .filter_map(|v| from_dynamic(v, &Port::Float(0.)))
+            .map(Box::new)
+            .collect();
+        Port::Array(items)
+    } else if let Some(v) = value.clone().try_cast::<FLOAT>() {
+        Port::Float(v as f32)
+    } else if let Some(v) = value.clone().try_cast::<INT>() {
+        Port::Float(v as f32)
+    } else {
+        Port::Float(if value.try_cast::<bool>()? { 1. } else { 0. })
+    };
+    Some(port.converted(like))
+}
+
+/// Ports a script declares in the comment lines at its top, e.g.
+/// `// in: Speed 0..10 0.1` for an input editable from 0 to 10 in steps of 0.1 and `// out: Value`.
+/// Range and step of inputs are optional.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ScriptPorts {
+    pub inputs: Vec<(String, Option<ParamRange>)>,
+    pub outputs: Vec<String>,
+}
+
+impl ScriptPorts {
+    /// Default step of declared ranges
+    const STEP: f32 = 0.01;
+
+    /// Read the declarations up to the first line which is neither empty nor a comment
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut ports = Self::default();
+        let header = source.lines()
+            .map(str::trim)
+            .take_while(|l| l.is_empty() || l.starts_with("//"));
+        for (c_number, c_line) in header.enumerate() {
+            let line = c_line.trim_start_matches('/').trim();
+            if let Some(rest) = line.strip_prefix("in:") {
+                let input = Self::parse_input(rest)
+                    .map_err(|e| format!("{} (line {})", e, c_number + 1))?;
+                ports.inputs.push(input);
+            } else if let Some(rest) = line.strip_prefix("out:") {
+                let name = rest.trim();
+                if name.is_empty() {
+                    return Err(format!("Output without a name (line {})", c_number + 1));
+                }
+                ports.outputs.push(name.to_owned());
+            }
+        }
+        Ok(ports)
+    }
+
+    /// Parse `Name [min..max [step]]`, the name may contain spaces
+    fn parse_input(declaration: &str) -> Result<(String, Option<ParamRange>), String> {
+        let words: Vec<&str> = declaration.split_whitespace().collect();
+        let range_at = words.iter().position(|w| w.contains(".."));
+        let name = words[..range_at.unwrap_or(words.len())].join(" ");
+        if name.is_empty() {
+            return Err("Input without a name".to_owned());
+        }
+        let range_at = match range_at {
+            Some(i) => i,
+            None => return Ok((name, None)),
+        };
+
+        let number = |w: &str| w.trim().parse::<f32>().map_err(|_| format!("\"{}\" is not a number", w));
+        let mut bounds = words[range_at].splitn(2, "..");
+        let min = number(bounds.next().unwrap_or(""))?;
+        let max = number(bounds.next().unwrap_or(""))?;
+        let step = match words.get(range_at + 1) {
+            Some(w) => number(w)?,
+            None => Self::STEP,
+        };
+        if words.len() > range_at + 2 || max < min {
+            return Err(format!("Invalid range of input \"{}\"", name));
+        }
+        Ok((name, Some(ParamRange::new(min, max, step))))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty() && self.outputs.is_empty()
+    }
+}
+
+/// Behaviour of a node defined by a Rhai script.
+/// Inputs and outputs are variables named after their ports, `time` holds the seconds since the
+/// script was compiled and the object map `state` keeps its content between runs.
+/// The ports are declared in the header of the source, see `ScriptPorts`.
+#[derive(Clone)]
+pub struct Script {
+    source: String,
+    ports: ScriptPorts,
+    ast: Option<AST>,
+    error: Option<String>,
+    state: Dynamic,
+    started: Instant,
+}
+
+impl Script {
+    pub fn new(source: String) -> Self {
+        let mut script = Self {
+            source: String::new(),
+            ports: ScriptPorts::default(),
+            ast: None,
+            error: None,
+            state: Dynamic::from(rhai::Map::new()),
+            started: Instant::now(),
+        };
+        script.set_source(source);
+        script
+    }
+
+    pub fn get_source(&self) -> &str {
+        &self.source
+    }
+
+    /// Ports declared by the source, empty if it declares none or the declarations are invalid
+    pub fn get_ports(&self) -> &ScriptPorts {
+        &self.ports
+    }
+
+    /// Compile error of the source or the error of the last run
+    pub fn get_error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Replace and compile the source and read its port declarations, which resets the state and time.
+    /// A script which doesn't compile keeps the outputs unchanged until it is fixed.
+    pub fn set_source(&mut self, source: String) {
+        let compiled = ScriptPorts::parse(&source)
+            .and_then(|ports| ENGINE.compile(&source).map(|ast| (ports, ast)).map_err(|e| e.to_string()));
+        match compiled {
+            Ok((ports, ast)) => {
+                self.ports = ports;
+                self.ast = Some(ast);
+                self.error = None;
+            }
+            Err(e) => {
+                self.ports = ScriptPorts::default();
+                self.ast = None;
+                self.error = Some(e);
+            }
+        }
+        self.source = source;
+        self.state = Dynamic::from(rhai::Map::new());
+        self.started = Instant::now();
+    }
+
+    pub fn elapsed(&self) -> f32 {
+        self.started.elapsed().as_secs_f32()
+    }
+
+    /// Run the script at `time` with the values of `inputs` and write the results into `outputs`
+    pub fn run(&mut self, inputs: &[InputPort], outputs: &mut [OutputPort], time: f32) {
+        let ast = match &self.ast {
+            Some(ast) => ast,
+            None => return,
+        };
+
+        let mut scope = Scope::new();
+        for c_input in inputs.iter() {
+            scope.push_dynamic(variable_name(c_input.get_name()), to_dynamic(c_input.get_port()));
+        }
+        for c_output in outputs.iter() {
+            scope.push_dynamic(variable_name(c_output.get_name()), to_dynamic(c_output.get_port()));
+        }
+        scope.push("time", time as FLOAT);
+        scope.push_dynamic("state", self.state.clone());
+
+        if let Err(e) = ENGINE.eval_ast_with_scope::<Dynamic>(&mut scope, ast) {
+            self.error = Some(e.to_string());
+            return;
+        }
+        self.error = None;
+
+        for c_output in outputs.iter_mut() {
+            let value = scope.get_value::<Dynamic>(&variable_name(c_output.get_name()))
+                .and_then(|v| from_dynamic(v, c_output.get_port()));
+            if let Some(value) = value {
+                c_output.set_port(value);
+            }
+        }
+        if let Some(state) = scope.get_value::<Dynamic>("state") {
+            self.state = state;
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ports() -> (Vec<InputPort>, Vec<OutputPort>) {
+        (
+            vec![
+                InputPort::new("Level".to_owned(), Port::Float(0.5)),
+                InputPort::new("Select B".to_owned(), Port::Unsigned8(1)),
+            ],
+            vec![
+                OutputPort::new("Value".to_owned(), Port::Float(0.)),
+                OutputPort::new("DMX".to_owned(), Port::Unsigned8(0)),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_run() {
+        assert_eq!(variable_name("Select B"), "select_b");
+        assert_eq!(variable_name("2nd"), "_2nd");
+
+        let (inputs, mut outputs) = ports();
+        let mut script = Script::new(r#"
+            if !("runs" in state) { state.runs = 0.0; }
+            state.runs += 1.0;
+            value = level * time + state.runs;
+            dmx = if select_b > 0 { 300 } else { 0 };
+        "#.to_owned());
+        assert_eq!(script.get_error(), None);
+
+        script.run(&inputs, &mut outputs, 2.);
+        assert_eq!(outputs[0].get_port(), &Port::Float(2.));
+        // Values are converted into the type of their output
+        assert_eq!(outputs[1].get_port(), &Port::Unsigned8(255));
+        script.run(&inputs, &mut outputs, 2.);
+        assert_eq!(outputs[0].get_port(), &Port::Float(3.));
+
+        // Changing the source resets the state
+        script.set_source("value = state.len();".to_owned());
+        script.run(&inputs, &mut outputs, 0.);
+        assert_eq!(outputs[0].get_port(), &Port::Float(0.));
+    }
+
+    #[test]
+    fn test_errors() {
+        let (inputs, mut outputs) = ports();
+
+        let mut script = Script::new("value = (level;".to_owned());
+        assert!(script.get_error().is_some());
+        script.run(&inputs, &mut outputs, 0.);
+        assert_eq!(outputs[0].get_port(), &Port::Float(0.));
+
+        // Runtime errors are shown until a run succeeds
+        script.set_source("value = unknown_variable;".to_owned());
+        assert_eq!(script.get_error(), None);
+        script.run(&inputs, &mut outputs, 0.);
+        assert!(script.get_error().is_some());
+
+        // Endless loops are aborted
+        script.set_source("loop { value += 1.0; }".to_owned());
+        script.run(&inputs, &mut outputs, 0.);
+        assert!(script.get_error().is_some());
+        assert_eq!(outputs[0].get_port(), &Port::Float(0.));
+
+        // Neither modules nor eval are available
+        assert!(Script::new(r#"import "std" as std;"#.to_owned()).get_error().is_some());
+        script.set_source(r#"eval("value = 1.0");"#.to_owned());
+        script.run(&inputs, &mut outputs, 0.);
+        assert!(script.get_error().is_some());
+        assert_eq!(outputs[0].get_port(), &Port::Float(0.));
+    }
+
+    #[test]
+    fn test_ports() {
+        let script = Script::new("// Blend\n// in: Select B 0..1 1\n//in: Level\n\n// out: Value\nvalue = level;\n// in: Ignored\n".to_owned());
+        assert_eq!(script.get_error(), None);
+        assert_eq!(script.get_ports(), &ScriptPorts {
+            inputs: vec![
+                ("Select B".to_owned(), Some(ParamRange::new(0., 1., 1.))),
+                ("Level".to_owned(), None),
+            ],
+            outputs: vec!["Value".to_owned()],
+        });
+        assert_eq!(ScriptPorts::parse("// in: Speed -1..10").unwrap().inputs[0].1, Some(ParamRange::new(-1., 10., 0.01)));
+        assert!(Script::new("value = 1.0;".to_owned()).get_ports().is_empty());
+
+        // Invalid declarations are shown like compile errors
+        for c_source in &["// in: 0..1", "// in: Speed 1..x", "// in: Speed 2..1", "// out:"] {
+            let script = Script::new(c_source.to_string());
+            assert!(script.get_error().is_some(), "{} was accepted", c_source);
+            assert!(script.get_ports().is_empty());
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use crate::{
+    dmx::{
+        DMX,
+        DMXUniverse,
+    },
+    err::ConmxErr,
+    playback::Executors,
+};
+
+use super::message::{
+    OscArg,
+    OscMessage,
+};
+
+/// Remote control commands, the address space is:
+///
+/// - `/conmx/universe/<n>/channel/<c> [value]` sets a channel to an int (0-255) or a float (0-1),
+///   without a value the channel is sent back. Channels count from 1 like DMX addresses.
+/// - `/conmx/executor/<n>/go`, `/back`, `/release` and `/fader <level>`, executors count from 1
+/// - `/conmx/grandmaster <level>`
+/// - `/conmx/node/<title>/<input> <value>` sets an input of the node with that title
+/// - `/conmx/subscribe` and `/conmx/unsubscribe` start and stop feedback to the sender
+#[derive(Debug, Clone, PartialEq)]
+pub enum OscCommand {
+    SetChannel { universe: usize, channel: usize, value: u32 },
+    GetChannel { universe: usize, channel: usize },
+    Go(usize),
+    Back(usize),
+    Release(usize),
+    Fader(usize, f32),
+    GrandMaster(f32),
+    NodeParam { node: String, param: String, value: f32 },
+    Subscribe,
+    Unsubscribe,
+}
+
+fn index(part: &str, first: usize, what: &str) -> Result<usize, ConmxErr> {
+    part.parse::<usize>().ok()
+        .filter(|i| *i >= first)
+        .map(|i| i - first)
+        .ok_or_else(|| ConmxErr::Osc(format!("\"{}\" is not a valid {}", part, what)))
+}
+
+fn level(msg: &OscMessage) -> Result<f32, ConmxErr> {
+    msg.args.first()
+        .and_then(OscArg::as_f32)
+        .map(|l| l.max(0.).min(1.))
+        .ok_or_else(|| ConmxErr::Osc(format!("{} needs a level", msg.address)))
+}
+
+/// DMX value of a channel argument, ints are taken as they are and floats as fractions of full
+fn dmx_value(arg: &OscArg) -> Option<u32> {
+    match arg {
+        OscArg::Int(i) => Some((*i).max(0).min(255) as u32),
+        OscArg::Float(f) => Some((f.max(0.).min(1.) * 255.).round() as u32),
+        OscArg::Str(_) => None,
+    }
+}
+
+impl OscCommand {
+    pub fn parse(msg: &OscMessage) -> Result<Self, ConmxErr> {
+        let unknown = || ConmxErr::Osc(format!("Unknown address {}", msg.address));
+        match msg.parts().as_slice() {
+            ["conmx", "universe", universe, "channel", channel] => {
+                let universe = index(universe, 0, "universe")?;
+                let channel = index(channel, 1, "channel")?;
+                if channel >= DMXUniverse::CHANNELS {
+                    return Err(ConmxErr::Osc(format!("Channel {} is outside of the universe", channel + 1)));
+                }
+                match msg.args.first() {
+                    Some(arg) => dmx_value(arg)
+                        .map(|value| OscCommand::SetChannel { universe, channel, value })
+                        .ok_or_else(|| ConmxErr::Osc(format!("{} needs a number", msg.address))),
+                    None => Ok(OscCommand::GetChannel { universe, channel }),
+                }
+            }
+            ["conmx", "executor", executor, action] => {
+                let executor = index(executor, 1, "executor")?;
+                match *action {
+                    "go" => Ok(OscCommand::Go(executor)),
+                    "back" => Ok(OscCommand::Back(executor)),
+                    "release" => Ok(OscCommand::Release(executor)),
+                    "fader" => Ok(OscCommand::Fader(executor, level(msg)?)),
+                    _ => Err(unknown()),
+                }
+            }
+            ["conmx", "grandmaster"] => Ok(OscCommand::GrandMaster(level(msg)?)),
+            ["conmx", "node", node, param] => msg.args.first()
+                .and_then(OscArg::as_f32)
+                .map(|value| OscCommand::NodeParam { node: node.to_string(), param: param.to_string(), value })
+                .ok_or_else(|| ConmxErr::Osc(format!("{} needs a value", msg.address))),
+            ["conmx", "subscribe"] => Ok(OscCommand::Subscribe),
+            ["conmx", "unsubscribe"] => Ok(OscCommand::Unsubscribe),
+            _ => Err(unknown()),
+        }
+    }
+}
+
+/// Message with the current value of a channel, sent as reply and as feedback
+pub fn channel_message(universe: usize, channel: usize, value: u32) -> OscMessage {
+    OscMessage::new(&format!("/conmx/universe/{}/channel/{}", universe, channel + 1))
+        .with_arg(OscArg::Int(value as i32))
+}
+
+/// Messages describing everything sent as feedback: all channels, the executor faders and
+/// whether they are running, and the grand master
+pub fn state_messages(dmx: &DMX, executors: &Executors) -> Vec<OscMessage> {
+    let mut messages = Vec::new();
+    for c_id in dmx.get_universe_ids() {
+        if let Some(universe) = dmx.get_universe_ref(c_id) {
+            messages.extend((0..DMXUniverse::CHANNELS)
+                .filter_map(|c| universe.get_channel(c).map(|ch| channel_message(c_id, c, ch.get_val()))));
+        }
+    }
+    for (i, c_exec) in executors.iter().enumerate() {
+        messages.push(OscMessage::new(&format!("/conmx/executor/{}/fader", i + 1)).with_arg(OscArg::Float(c_exec.get_level())));
+        messages.push(OscMessage::new(&format!("/conmx/executor/{}/active", i + 1)).with_arg(OscArg::Int(c_exec.is_active() as i32)));
+    }
+    messages.push(OscMessage::new("/conmx/grandmaster").with_arg(OscArg::Float(executors.grand_master())));
+    messages
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(address: &str, args: Vec<OscArg>) -> Result<OscCommand, ConmxErr> {
+        OscCommand::parse(&OscMessage { address: address.to_owned(), args })
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse("/conmx/universe/1/channel/10", vec![OscArg::Int(300)]).unwrap(),
+            OscCommand::SetChannel { universe: 1, channel: 9, value: 255 }
+        );
+        assert_eq!(
+            parse("/conmx/universe/0/channel/1", vec![OscArg::Float(0.5)]).unwrap(),
+            OscCommand::SetChannel { universe: 0, channel: 0, value: 128 }
+        );
+        assert_eq!(parse("/conmx/universe/0/channel/512", vec![]).unwrap(), OscCommand::GetChannel { universe: 0, channel: 511 });
+        assert!(parse("/conmx/universe/0/channel/0", vec![]).is_err());
+        assert!(parse("/conmx/universe/0/channel/513", vec![]).is_err());
+
+        assert_eq!(parse("/conmx/executor/1/go", vec![]).unwrap(), OscCommand::Go(0));
+        assert_eq!(parse("/conmx/executor/3/fader", vec![OscArg::Float(0.25)]).unwrap(), OscCommand::Fader(2, 0.25));
+        assert!(parse("/conmx/executor/3/fader", vec![]).is_err());
+        assert!(parse("/conmx/executor/1/jump", vec![]).is_err());
+        assert_eq!(parse("/conmx/grandmaster", vec![OscArg::Int(2)]).unwrap(), OscCommand::GrandMaster(1.));
+        assert_eq!(
+            parse("/conmx/node/Sine/freq", vec![OscArg::Float(2.)]).unwrap(),
+            OscCommand::NodeParam { node: "Sine".to_owned(), param: "freq".to_owned(), value: 2. }
+        );
+        assert_eq!(parse("/conmx/subscribe", vec![]).unwrap(), OscCommand::Subscribe);
+        assert!(parse("/other/subscribe", vec![]).is_err());
+    }
+
+    #[test]
+    fn test_state() {
+        let mut dmx = DMX::new();
+        dmx.add_universe(DMXUniverse::new(0));
+        dmx.get_universe(0).unwrap().set_channel(4, 200);
+        let messages = state_messages(&dmx, &Executors::new(2));
+        assert_eq!(messages.len(), DMXUniverse::CHANNELS + 2 * 2 + 1);
+        assert_eq!(messages[4], channel_message(0, 4, 200));
+        assert_eq!(messages[4].address, "/conmx/universe/0/channel/5");
+        assert_eq!(messages.last().unwrap().args, vec![OscArg::Float(1.)]);
+    }
+}
@@ -0,0 +1,189 @@
+use crate::err::ConmxErr;
+
+const BUNDLE_ID: &[u8; 8] = b"#bundle\0";
+
+/// Arguments of a message, only the types needed for remote control are supported
+#[derive(Debug, Clone, PartialEq)]
+pub enum OscArg {
+    Int(i32),
+    Float(f32),
+    Str(String),
+}
+
+impl OscArg {
+    pub fn as_f32(&self) -> Option<f32> {
+        match self {
+            OscArg::Int(i) => Some(*i as f32),
+            OscArg::Float(f) => Some(*f),
+            OscArg::Str(_) => None,
+        }
+    }
+
+    fn tag(&self) -> u8 {
+        match self {
+            OscArg::Int(_) => b'i',
+            OscArg::Float(_) => b'f',
+            OscArg::Str(_) => b's',
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OscMessage {
+    pub address: String,
+    pub args: Vec<OscArg>,
+}
+
+/// Append a string with its terminating zero, padded to 4 bytes
+fn write_string(buf: &mut Vec<u8>, s: &str) {
+    buf.extend_from_slice(s.as_bytes());
+    buf.push(0);
+    while buf.len() % 4 != 0 {
+        buf.push(0);
+    }
+}
+
+/// Read a padded string starting at `pos`, moving `pos` behind the padding
+fn read_string(packet: &[u8], pos: &mut usize) -> Result<String, ConmxErr> {
+    let len = packet.get(*pos..)
+        .and_then(|rest| rest.iter().position(|b| *b == 0))
+        .ok_or_else(|| ConmxErr::Osc("Unterminated string".to_owned()))?;
+    let s = std::str::from_utf8(&packet[*pos..*pos + len])
+        .map_err(|e| ConmxErr::Osc(format!("Invalid string ({})", e)))?
+        .to_owned();
+    *pos = (*pos + len + 4) / 4 * 4;
+    Ok(s)
+}
+
+fn read_4(packet: &[u8], pos: &mut usize) -> Result<[u8; 4], ConmxErr> {
+    let bytes = packet.get(*pos..*pos + 4)
+        .ok_or_else(|| ConmxErr::Osc("Message ends inside an argument".to_owned()))?;
+    *pos += 4;
+    Ok([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+impl OscMessage {
+    pub fn new(address: &str) -> Self {
+        Self {
+            address: address.to_owned(),
+            args: Vec::new(),
+        }
+    }
+
+    pub fn with_arg(mut self, arg: OscArg) -> Self {
+        self.args.push(arg);
+        self
+    }
+
+    /// Parts of the address between the slashes
+    pub fn parts(&self) -> Vec<&str> {
+        self.address.trim_start_matches('/').split('/').collect()
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        write_string(&mut buf, &self.address);
+        let tags: String = std::iter::once(',')
+            .chain(self.args.iter().map(|a| a.tag() as char))
+            .collect();
+        write_string(&mut buf, &tags);
+        for c_arg in self.args.iter() {
+            match c_arg {
+                OscArg::Int(i) => buf.extend_from_slice(&i.to_be_bytes()),
+                OscArg::Float(f) => buf.extend_from_slice(&f.to_be_bytes()),
+                OscArg::Str(s) => write_string(&mut buf, s),
+            }
+        }
+        buf
+    }
+
+    /// Read a single message. The booleans `T` and `F` are read as integers.
+    pub fn decode(packet: &[u8]) -> Result<Self, ConmxErr> {
+        let mut pos = 0;
+        let address = read_string(packet, &mut pos)?;
+        if !address.starts_with('/') {
+            return Err(ConmxErr::Osc(format!("\"{}\" is not an address", address)));
+        }
+        // Old senders leave out the type tags for messages without arguments
+        if pos >= packet.len() {
+            return Ok(Self::new(&address));
+        }
+        let tags = read_string(packet, &mut pos)?;
+        let tags = tags.strip_prefix(',')
+            .ok_or_else(|| ConmxErr::Osc(format!("Missing type tags for {}", address)))?;
+
+        let mut args = Vec::with_capacity(tags.len());
+        for c_tag in tags.chars() {
+            args.push(match c_tag {
+                'i' => OscArg::Int(i32::from_be_bytes(read_4(packet, &mut pos)?)),
+                'f' => OscArg::Float(f32::from_be_bytes(read_4(packet, &mut pos)?)),
+                's' => OscArg::Str(read_string(packet, &mut pos)?),
+                'T' => OscArg::Int(1),
+                'F' => OscArg::Int(0),
+                _ => return Err(ConmxErr::Osc(format!("Unsupported argument type '{}' for {}", c_tag, address))),
+            });
+        }
+        Ok(Self { address, args })
+    }
+}
+
+/// All messages of a packet, bundles are flattened and their time tags ignored
+pub fn decode_packet(packet: &[u8]) -> Result<Vec<OscMessage>, ConmxErr> {
+    if !packet.starts_with(BUNDLE_ID) {
+        return OscMessage::decode(packet).map(|m| vec![m]);
+    }
+    // Behind the id follows the 8 byte time tag
+    let mut pos = BUNDLE_ID.len() + 8;
+    let mut messages = Vec::new();
+    while pos < packet.len() {
+        let size = i32::from_be_bytes(read_4(packet, &mut pos)?).max(0) as usize;
+        let element = packet.get(pos..pos + size)
+            .ok_or_else(|| ConmxErr::Osc("Bundle element exceeds the packet".to_owned()))?;
+        messages.extend(decode_packet(element)?);
+        pos += size;
+    }
+    Ok(messages)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let msg = OscMessage::new("/conmx/node/Sine/freq")
+            .with_arg(OscArg::Float(0.5))
+            .with_arg(OscArg::Int(-3))
+            .with_arg(OscArg::Str("abc".to_owned()));
+        let packet = msg.encode();
+        assert_eq!(packet.len() % 4, 0);
+        assert_eq!(OscMessage::decode(&packet).unwrap(), msg);
+        assert_eq!(msg.parts(), vec!["conmx", "node", "Sine", "freq"]);
+
+        // Address padded to 4 bytes, then ",i" and the argument
+        let packet = OscMessage::new("/go").with_arg(OscArg::Int(1)).encode();
+        assert_eq!(packet, b"/go\0,i\0\0\0\0\0\x01".to_vec());
+
+        assert!(OscMessage::decode(b"go\0\0").is_err());
+        assert!(OscMessage::decode(b"/go\0,i\0\0\0\0").is_err());
+        assert!(OscMessage::decode(b"/go\0,d\0\0").is_err());
+    }
+
+    #[test]
+    fn test_bundle() {
+        let a = OscMessage::new("/a").with_arg(OscArg::Int(1));
+        let b = OscMessage::new("/b");
+        let mut packet = BUNDLE_ID.to_vec();
+        packet.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 1]);
+        for c_msg in [&a, &b].iter() {
+            let encoded = c_msg.encode();
+            packet.extend_from_slice(&(encoded.len() as i32).to_be_bytes());
+            packet.extend_from_slice(&encoded);
+        }
+        assert_eq!(decode_packet(&packet).unwrap(), vec![a, b]);
+
+        packet.extend_from_slice(&100i32.to_be_bytes());
+        assert!(decode_packet(&packet).is_err());
+    }
+}
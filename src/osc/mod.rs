@@ -0,0 +1,8 @@
+mod message;
+pub use message::*;
+
+mod command;
+pub use command::*;
+
+mod server;
+pub use server::*;
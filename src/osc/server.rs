@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+use std::net::{
+    SocketAddr,
+    ToSocketAddrs,
+    UdpSocket,
+};
+
+use log::warn;
+
+use crate::err::ConmxErr;
+
+use super::{
+    command::OscCommand,
+    message::{
+        decode_packet,
+        OscArg,
+        OscMessage,
+    },
+};
+
+/// Receives commands on a non blocking socket, polled once per frame,
+/// and sends changed values back to subscribed clients
+#[derive(Debug)]
+pub struct OscServer {
+    socket: UdpSocket,
+    subscribers: Vec<SocketAddr>,
+    /// Subscribers which did not get the complete state yet
+    fresh: Vec<SocketAddr>,
+    /// Arguments last sent per address
+    sent: HashMap<String, Vec<OscArg>>,
+}
+
+impl OscServer {
+    /// Port used when none is given
+    pub const PORT: u16 = 7700;
+
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> Result<Self, ConmxErr> {
+        let socket = UdpSocket::bind(addr)
+            .map_err(|e| ConmxErr::Net(format!("Could not bind OSC socket ({})", e)))?;
+        socket.set_nonblocking(true)
+            .map_err(|e| ConmxErr::Net(format!("Could not configure OSC socket ({})", e)))?;
+        Ok(Self {
+            socket,
+            subscribers: Vec::new(),
+            fresh: Vec::new(),
+            sent: HashMap::new(),
+        })
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr, ConmxErr> {
+        self.socket.local_addr()
+            .map_err(|e| ConmxErr::Net(format!("OSC socket has no address ({})", e)))
+    }
+
+    pub fn subscribers(&self) -> &[SocketAddr] {
+        &self.subscribers
+    }
+
+    /// Commands received since the last call with their sender.
+    /// Subscriptions are handled here, invalid messages are logged and skipped.
+    pub fn poll(&mut self) -> Vec<(OscCommand, SocketAddr)> {
+        let mut buf = [0u8; 4096];
+        let mut commands = Vec::new();
+        while let Ok((len, from)) = self.socket.recv_from(&mut buf) {
+            let messages = match decode_packet(&buf[..len]) {
+                Ok(messages) => messages,
+                Err(e) => {
+                    warn!("{} from {}", e, from);
+                    continue;
+                }
+            };
+            for c_msg in messages {
+                match OscCommand::parse(&c_msg) {
+                    Ok(OscCommand::Subscribe) => {
+                        if !self.subscribers.contains(&from) {
+                            self.subscribers.push(from);
+                            self.fresh.push(from);
+                        }
+                    }
+                    Ok(OscCommand::Unsubscribe) => {
+                        self.subscribers.retain(|s| *s != from);
+                        self.fresh.retain(|s| *s != from);
+                    }
+                    Ok(command) => commands.push((command, from)),
+                    Err(e) => warn!("{} from {}", e, from),
+                }
+            }
+        }
+        commands
+    }
+
+    pub fn send(&self, msg: &OscMessage, to: SocketAddr) {
+        if let Err(e) = self.socket.send_to(&msg.encode(), to) {
+            warn!("Could not send {} to {} ({})", msg.address, to, e);
+        }
+    }
+
+    /// Send the messages whose arguments changed since the last call to all subscribers,
+    /// new subscribers get all of them
+    pub fn feedback(&mut self, state: Vec<OscMessage>) {
+        for c_msg in state {
+            let changed = self.sent.get(&c_msg.address) != Some(&c_msg.args);
+            let targets = if changed { &self.subscribers } else { &self.fresh };
+            for c_target in targets.iter() {
+                self.send(&c_msg, *c_target);
+            }
+            if changed {
+                self.sent.insert(c_msg.address, c_msg.args);
+            }
+        }
+        self.fresh.clear();
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        thread,
+        time::Duration,
+    };
+
+    /// Wait until `f` gives something, packets on localhost take a moment to arrive
+    fn wait_for<T>(mut f: impl FnMut() -> Option<T>) -> Option<T> {
+        for _ in 0..50 {
+            if let Some(t) = f() {
+                return Some(t);
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        None
+    }
+
+    fn receive(client: &UdpSocket) -> Option<OscMessage> {
+        let mut buf = [0u8; 1024];
+        client.recv(&mut buf).ok()
+            .and_then(|len| OscMessage::decode(&buf[..len]).ok())
+    }
+
+    #[test]
+    fn test_commands_and_feedback() {
+        let mut server = OscServer::bind("127.0.0.1:0").unwrap();
+        let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+        client.set_read_timeout(Some(Duration::from_millis(500))).unwrap();
+        client.connect(server.local_addr().unwrap()).unwrap();
+
+        client.send(&OscMessage::new("/conmx/executor/1/go").encode()).unwrap();
+        client.send(b"garbage").unwrap();
+        client.send(&OscMessage::new("/conmx/subscribe").encode()).unwrap();
+        let (command, from) = wait_for(|| server.poll().into_iter().next()).unwrap();
+        assert_eq!(command, OscCommand::Go(0));
+        assert_eq!(from, client.local_addr().unwrap());
+        wait_for(|| if server.subscribers().is_empty() { server.poll(); None } else { Some(()) }).unwrap();
+
+        let gm = |level| OscMessage::new("/conmx/grandmaster").with_arg(OscArg::Float(level));
+        // A new subscriber gets the full state, afterwards only changes are sent
+        server.feedback(vec![gm(1.)]);
+        assert_eq!(receive(&client), Some(gm(1.)));
+        server.feedback(vec![gm(1.)]);
+        server.feedback(vec![gm(0.5)]);
+        assert_eq!(receive(&client), Some(gm(0.5)));
+
+        client.send(&OscMessage::new("/conmx/unsubscribe").encode()).unwrap();
+        wait_for(|| if server.subscribers().is_empty() { Some(()) } else { server.poll(); None }).unwrap();
+    }
+}
@@ -0,0 +1,380 @@
+use std::collections::BTreeMap;
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use super::{
+    CueTarget,
+    CueValues,
+    Programmer,
+};
+
+/// Order in which the steps of a chase are played
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChaseDirection {
+    Forward,
+    Reverse,
+    /// Forward and back again without repeating the first and last step
+    Bounce,
+    /// Any step except the current one
+    Random,
+}
+
+/// How long each step of a chase lasts
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ChaseSpeed {
+    /// Every step uses its own time
+    StepTime,
+    /// Every step lasts the given number of beats at the tempo
+    Bpm { bpm: f32, beats: f32 },
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct ChaseStep {
+    pub values: CueValues,
+    /// Seconds, used when the chase runs at step time
+    pub time: f32,
+}
+
+impl ChaseStep {
+    pub fn new(time: f32) -> Self {
+        Self {
+            values: CueValues::default(),
+            time,
+        }
+    }
+
+    pub fn with_value(mut self, target: CueTarget, value: f32) -> Self {
+        self.values.set(target, value);
+        self
+    }
+}
+
+/// Steps played in a loop, e.g. for a simple running light
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Chase {
+    pub name: String,
+    pub steps: Vec<ChaseStep>,
+    pub direction: ChaseDirection,
+    pub speed: ChaseSpeed,
+    /// Fraction of each step spent fading from the previous step, 0 snaps and 1 fades all the time
+    pub crossfade: f32,
+}
+
+impl Chase {
+    /// Step time of recorded steps
+    pub const DEFAULT_STEP_TIME: f32 = 1.;
+    /// Steps shorter than this are stretched, so a zero time can not stall the player
+    const MIN_STEP_TIME: f32 = 0.01;
+
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            steps: Vec::new(),
+            direction: ChaseDirection::Forward,
+            speed: ChaseSpeed::StepTime,
+            crossfade: 0.,
+        }
+    }
+
+    pub fn with_step(mut self, step: ChaseStep) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    pub fn with_direction(mut self, direction: ChaseDirection) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    pub fn with_speed(mut self, speed: ChaseSpeed) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    pub fn with_crossfade(mut self, crossfade: f32) -> Self {
+        self.crossfade = crossfade.max(0.).min(1.);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Record the programmer as a new step at the end of the chase, returns its index
+    pub fn record(&mut self, programmer: &Programmer) -> usize {
+        let mut step = ChaseStep::new(Self::DEFAULT_STEP_TIME);
+        step.values.record(programmer);
+        self.steps.push(step);
+        self.steps.len() - 1
+    }
+
+    /// Length of a step in seconds
+    pub fn step_time(&self, index: usize) -> f32 {
+        let time = match self.speed {
+            ChaseSpeed::StepTime => self.steps.get(index).map(|s| s.time).unwrap_or(0.),
+            ChaseSpeed::Bpm { bpm, beats } if bpm > 0. => 60. / bpm * beats,
+            ChaseSpeed::Bpm { .. } => 0.,
+        };
+        time.max(Self::MIN_STEP_TIME)
+    }
+}
+
+/// Plays a chase, the chase itself is passed in on every call like for the `CuePlayer`
+#[derive(Debug, Clone)]
+pub struct ChasePlayer {
+    current: Option<usize>,
+    previous: Option<usize>,
+    /// Seconds since the current step started
+    elapsed: f32,
+    running: bool,
+    /// Bounce is on its way back
+    backwards: bool,
+    /// State of the xorshift generator for random order
+    seed: u32,
+}
+
+impl Default for ChasePlayer {
+    fn default() -> Self {
+        Self {
+            current: None,
+            previous: None,
+            elapsed: 0.,
+            running: false,
+            backwards: false,
+            seed: 0x2545_F491,
+        }
+    }
+}
+
+impl ChasePlayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn current(&self) -> Option<usize> {
+        self.current
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.current.is_some()
+    }
+
+    /// Start from the first step, or the last one when playing in reverse
+    pub fn start(&mut self, chase: &Chase) {
+        if chase.is_empty() {
+            return;
+        }
+        self.previous = None;
+        self.current = Some(match chase.direction {
+            ChaseDirection::Reverse => chase.len() - 1,
+            _ => 0,
+        });
+        self.backwards = false;
+        self.elapsed = 0.;
+        self.running = true;
+    }
+
+    pub fn stop(&mut self) {
+        self.current = None;
+        self.previous = None;
+        self.running = false;
+    }
+
+    /// Hold the current step until resumed
+    pub fn pause(&mut self) {
+        self.running = false;
+    }
+
+    pub fn resume(&mut self) {
+        if self.current.is_some() {
+            self.running = true;
+        }
+    }
+
+    fn random(&mut self) -> u32 {
+        let mut x = self.seed;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.seed = x;
+        x
+    }
+
+    fn next_index(&mut self, chase: &Chase, current: usize, forward: bool) -> usize {
+        let n = chase.len();
+        if n < 2 {
+            return 0;
+        }
+        match (chase.direction, forward) {
+            (ChaseDirection::Forward, true) | (ChaseDirection::Reverse, false) => (current + 1) % n,
+            (ChaseDirection::Forward, false) | (ChaseDirection::Reverse, true) => (current + n - 1) % n,
+            (ChaseDirection::Bounce, _) => {
+                let backwards = self.backwards != !forward;
+                let backwards = match current {
+                    0 => false,
+                    c if c + 1 >= n => true,
+                    _ => backwards,
+                };
+                if forward {
+                    self.backwards = backwards;
+                }
+                if backwards { current - 1 } else { current + 1 }
+            }
+            (ChaseDirection::Random, _) => (current + 1 + self.random() as usize % (n - 1)) % n,
+        }
+    }
+
+    fn step(&mut self, chase: &Chase, forward: bool) {
+        let current = match self.current {
+            Some(c) if c < chase.len() => c,
+            _ => {
+                self.start(chase);
+                return;
+            }
+        };
+        let next = self.next_index(chase, current, forward);
+        self.previous = Some(current);
+        self.current = Some(next);
+        self.elapsed = 0.;
+    }
+
+    /// Go to the next step by hand, the timing restarts from there
+    pub fn next_step(&mut self, chase: &Chase) {
+        self.step(chase, true);
+    }
+
+    /// Go to the step before by hand
+    pub fn prev_step(&mut self, chase: &Chase) {
+        self.step(chase, false);
+    }
+
+    /// Advance by `dt` seconds
+    pub fn update(&mut self, chase: &Chase, dt: f32) {
+        if !self.running {
+            return;
+        }
+        self.elapsed += dt;
+        while let Some(current) = self.current {
+            let time = chase.step_time(current);
+            if self.elapsed < time {
+                break;
+            }
+            let elapsed = self.elapsed - time;
+            self.step(chase, true);
+            self.elapsed = elapsed;
+        }
+    }
+
+    /// Values of the current step, crossfaded from the previous one
+    pub fn output(&self, chase: &Chase) -> BTreeMap<CueTarget, f32> {
+        let current = match self.current.and_then(|c| chase.steps.get(c)) {
+            Some(step) => step.values.to_map(),
+            None => return BTreeMap::new(),
+        };
+        let previous = self.previous
+            .and_then(|p| chase.steps.get(p))
+            .map(|s| s.values.to_map())
+            .unwrap_or_default();
+
+        let fade = chase.step_time(self.current.unwrap_or(0)) * chase.crossfade;
+        let progress = if fade <= 0. || previous.is_empty() { 1. } else { (self.elapsed / fade).min(1.) };
+
+        let mut out = BTreeMap::new();
+        for c_target in previous.keys().chain(current.keys()) {
+            let (start, end) = match (previous.get(c_target), current.get(c_target)) {
+                (Some(s), Some(e)) => (*s, *e),
+                (None, Some(e)) => (if c_target.is_intensity() { 0. } else { *e }, *e),
+                (Some(s), None) => (*s, if c_target.is_intensity() { 0. } else { *s }),
+                (None, None) => continue,
+            };
+            out.insert(*c_target, start + (end - start) * progress);
+        }
+        out
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Attribute;
+
+    fn int(id: usize) -> CueTarget {
+        CueTarget::Attribute(id, Attribute::Intensity)
+    }
+
+    fn test_chase(steps: usize) -> Chase {
+        (0..steps).fold(Chase::new("Run"), |chase, i| chase.with_step(ChaseStep::new(1.).with_value(int(i), 1.)))
+    }
+
+    fn order(chase: &Chase, steps: usize) -> Vec<usize> {
+        let mut player = ChasePlayer::new();
+        player.start(chase);
+        let mut order = vec![player.current().unwrap()];
+        for _ in 1..steps {
+            player.update(chase, chase.step_time(0));
+            order.push(player.current().unwrap());
+        }
+        order
+    }
+
+    #[test]
+    fn test_directions() {
+        let chase = test_chase(4);
+        assert_eq!(order(&chase, 6), vec![0, 1, 2, 3, 0, 1]);
+        let chase = chase.with_direction(ChaseDirection::Reverse);
+        assert_eq!(order(&chase, 6), vec![3, 2, 1, 0, 3, 2]);
+        let chase = chase.with_direction(ChaseDirection::Bounce);
+        assert_eq!(order(&chase, 8), vec![0, 1, 2, 3, 2, 1, 0, 1]);
+
+        let chase = chase.with_direction(ChaseDirection::Random);
+        let steps = order(&chase, 20);
+        assert!(steps.windows(2).all(|w| w[0] != w[1] && w[1] < 4));
+
+        // Manual steps go against the direction when stepping back
+        let chase = test_chase(3).with_direction(ChaseDirection::Bounce);
+        let mut player = ChasePlayer::new();
+        player.next_step(&chase);
+        assert_eq!(player.current(), Some(0));
+        player.next_step(&chase);
+        player.next_step(&chase);
+        player.next_step(&chase);
+        assert_eq!(player.current(), Some(1));
+        player.prev_step(&chase);
+        assert_eq!(player.current(), Some(2));
+    }
+
+    #[test]
+    fn test_timing_and_crossfade() {
+        let chase = test_chase(2)
+            .with_speed(ChaseSpeed::Bpm { bpm: 120., beats: 1. })
+            .with_crossfade(0.5);
+        assert_eq!(chase.step_time(1), 0.5);
+
+        let mut player = ChasePlayer::new();
+        player.start(&chase);
+        assert_eq!(player.output(&chase).get(&int(0)), Some(&1.));
+        player.update(&chase, 0.6);
+        assert_eq!(player.current(), Some(1));
+        // 0.1s into a 0.25s crossfade
+        let out = player.output(&chase);
+        assert!((out[&int(1)] - 0.4).abs() < 1e-4 && (out[&int(0)] - 0.6).abs() < 1e-4);
+
+        player.pause();
+        player.update(&chase, 10.);
+        assert_eq!(player.current(), Some(1));
+        player.stop();
+        assert!(player.output(&chase).is_empty());
+    }
+}
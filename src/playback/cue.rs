@@ -0,0 +1,407 @@
+use std::collections::BTreeMap;
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::fixture::{
+    Attribute,
+    FixtureID,
+    Patch,
+};
+
+use super::{
+    PaletteID,
+    PaletteKind,
+    PaletteRef,
+    Palettes,
+    Programmer,
+};
+
+/// What a cue value controls
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum CueTarget {
+    Attribute(FixtureID, Attribute),
+    /// Raw channel given as (universe, channel), for anything not patched as a fixture
+    Channel(usize, usize),
+}
+
+impl CueTarget {
+    /// Intensities fade in from and out to zero.
+    /// All other targets start at their first value and hold their last one.
+    pub fn is_intensity(&self) -> bool {
+        match self {
+            CueTarget::Attribute(_, attribute) => *attribute == Attribute::Intensity,
+            CueTarget::Channel(_, _) => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CueValue {
+    pub target: CueTarget,
+    /// Fraction between 0 and 1
+    pub value: f32,
+}
+
+/// Values kept sorted by target, at most one per target
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CueValues(Vec<CueValue>);
+
+impl CueValues {
+    pub fn set(&mut self, target: CueTarget, value: f32) {
+        let value = value.max(0.).min(1.);
+        match self.0.binary_search_by_key(&target, |v| v.target) {
+            Ok(i) => self.0[i].value = value,
+            Err(i) => self.0.insert(i, CueValue { target, value }),
+        }
+    }
+
+    pub fn get(&self, target: CueTarget) -> Option<f32> {
+        self.0.binary_search_by_key(&target, |v| v.target)
+            .ok()
+            .map(|i| self.0[i].value)
+    }
+
+    pub fn remove(&mut self, target: CueTarget) {
+        self.0.retain(|v| v.target != target);
+    }
+
+    pub fn as_slice(&self) -> &[CueValue] {
+        &self.0
+    }
+
+    pub fn to_map(&self) -> BTreeMap<CueTarget, f32> {
+        self.0.iter().map(|v| (v.target, v.value)).collect()
+    }
+
+    /// Store the values touched in the programmer, keeping all other values
+    pub fn record(&mut self, programmer: &Programmer) {
+        for (c_id, c_attr, c_value) in programmer.touched() {
+            self.set(CueTarget::Attribute(c_id, c_attr), c_value);
+        }
+    }
+}
+
+/// Fade times in seconds. Rising values use the fade in, falling values the fade out time.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct CueTiming {
+    pub fade_in: f32,
+    pub fade_out: f32,
+    pub delay: f32,
+}
+
+impl CueTiming {
+    pub fn new(fade_in: f32, fade_out: f32, delay: f32) -> Self {
+        Self { fade_in, fade_out, delay }
+    }
+
+    /// Same time for rising and falling values without delay
+    pub fn fade(time: f32) -> Self {
+        Self::new(time, time, 0.)
+    }
+
+    /// Time until all values have reached their targets
+    pub fn duration(&self) -> f32 {
+        self.delay + self.fade_in.max(self.fade_out)
+    }
+
+    /// Fraction of the fade done after `elapsed` seconds
+    pub fn progress(&self, elapsed: f32, rising: bool) -> f32 {
+        let fade = if rising { self.fade_in } else { self.fade_out };
+        let t = elapsed - self.delay;
+        if t < 0. {
+            0.
+        } else if fade <= 0. {
+            1.
+        } else {
+            (t / fade).min(1.)
+        }
+    }
+}
+
+/// How a cue is started
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Trigger {
+    /// Manually with GO
+    Go,
+    /// Seconds after the previous cue has finished fading
+    Follow(f32),
+    /// Seconds after the previous cue was started
+    Wait(f32),
+}
+
+impl Default for Trigger {
+    fn default() -> Self { Trigger::Go }
+}
+
+/// A look stored as target values and palette references.
+/// Only the values changed by the cue are stored, everything else tracks through from the cues before.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Cue {
+    pub name: String,
+    values: CueValues,
+    /// Sorted by fixture and kind, at most one per fixture and kind
+    #[serde(default)]
+    palettes: Vec<PaletteRef>,
+    #[serde(default)]
+    pub timing: CueTiming,
+    #[serde(default)]
+    pub trigger: Trigger,
+}
+
+impl Cue {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_timing(mut self, timing: CueTiming) -> Self {
+        self.timing = timing;
+        self
+    }
+
+    pub fn with_trigger(mut self, trigger: Trigger) -> Self {
+        self.trigger = trigger;
+        self
+    }
+
+    pub fn with_value(mut self, target: CueTarget, value: f32) -> Self {
+        self.set(target, value);
+        self
+    }
+
+    pub fn set(&mut self, target: CueTarget, value: f32) {
+        self.values.set(target, value);
+    }
+
+    pub fn get(&self, target: CueTarget) -> Option<f32> {
+        self.values.get(target)
+    }
+
+    pub fn remove(&mut self, target: CueTarget) {
+        self.values.remove(target);
+    }
+
+    pub fn values(&self) -> &[CueValue] {
+        self.values.as_slice()
+    }
+
+    /// Take the values of a kind for the fixture from a palette, replacing the values stored for it
+    pub fn set_palette(&mut self, fixture: FixtureID, kind: PaletteKind, palette: PaletteID) {
+        let stored: Vec<CueTarget> = self.values.as_slice().iter()
+            .map(|v| v.target)
+            .filter(|t| match t {
+                CueTarget::Attribute(id, attr) => *id == fixture && PaletteKind::of(*attr) == kind,
+                CueTarget::Channel(_, _) => false,
+            })
+            .collect();
+        for c_target in stored {
+            self.values.remove(c_target);
+        }
+        let palette_ref = PaletteRef { fixture, kind, palette };
+        match self.palettes.binary_search_by_key(&(fixture, kind), |r| (r.fixture, r.kind)) {
+            Ok(i) => self.palettes[i] = palette_ref,
+            Err(i) => self.palettes.insert(i, palette_ref),
+        }
+    }
+
+    pub fn with_palette(mut self, fixture: FixtureID, kind: PaletteKind, palette: PaletteID) -> Self {
+        self.set_palette(fixture, kind, palette);
+        self
+    }
+
+    pub fn remove_palette(&mut self, fixture: FixtureID, kind: PaletteKind) {
+        self.palettes.retain(|r| (r.fixture, r.kind) != (fixture, kind));
+    }
+
+    pub fn palettes(&self) -> &[PaletteRef] {
+        &self.palettes
+    }
+
+    /// Store the values touched in the programmer, keeping all other values of the cue.
+    /// Values taken from a palette are stored as a reference to it.
+    pub fn record(&mut self, programmer: &Programmer) {
+        let ids: Vec<FixtureID> = programmer.touched().iter().map(|(id, _, _)| *id).collect();
+        self.record_fixtures(programmer, &ids);
+    }
+
+    /// Store only the touched values of the given fixtures, e.g. of a group or the selection
+    pub fn record_fixtures(&mut self, programmer: &Programmer, ids: &[FixtureID]) {
+        for (c_id, c_attr, c_value) in programmer.touched().into_iter().filter(|(id, _, _)| ids.contains(id)) {
+            let kind = PaletteKind::of(c_attr);
+            match programmer.palette(c_id, kind) {
+                Some(palette) => self.set_palette(c_id, kind, palette),
+                None => {
+                    self.remove_palette(c_id, kind);
+                    self.values.set(CueTarget::Attribute(c_id, c_attr), c_value);
+                }
+            }
+        }
+    }
+
+    /// Values of the cue with its palette references looked up, own values win over palette values
+    pub fn resolved(&self, palettes: &Palettes, patch: &Patch) -> BTreeMap<CueTarget, f32> {
+        let mut out = palettes.resolve(&self.palettes, patch);
+        out.extend(self.values.to_map());
+        out
+    }
+}
+
+/// Cues played one after another
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct CueList {
+    pub name: String,
+    pub cues: Vec<Cue>,
+}
+
+impl CueList {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            cues: Vec::new(),
+        }
+    }
+
+    pub fn with_cue(mut self, cue: Cue) -> Self {
+        self.cues.push(cue);
+        self
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Cue> {
+        self.cues.get(index)
+    }
+
+    pub fn len(&self) -> usize {
+        self.cues.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cues.is_empty()
+    }
+
+    /// Record the programmer as a new cue at the end of the list, returns its index
+    pub fn record(&mut self, programmer: &Programmer) -> usize {
+        let mut cue = Cue::new(&format!("Cue {}", self.cues.len() + 1));
+        cue.record(programmer);
+        self.cues.push(cue);
+        self.cues.len() - 1
+    }
+
+    /// Record only the given fixtures of the programmer as a new cue, returns its index
+    pub fn record_fixtures(&mut self, programmer: &Programmer, ids: &[FixtureID]) -> usize {
+        let mut cue = Cue::new(&format!("Cue {}", self.cues.len() + 1));
+        cue.record_fixtures(programmer, ids);
+        self.cues.push(cue);
+        self.cues.len() - 1
+    }
+
+    /// All values on stage in the cue at `index`, including the ones tracked from the cues before.
+    /// Palettes are looked up now, so changing a palette changes every cue using it.
+    pub fn tracked_state(&self, index: usize, palettes: &Palettes, patch: &Patch) -> BTreeMap<CueTarget, f32> {
+        let mut state = BTreeMap::new();
+        for c_cue in self.cues.iter().take(index + 1) {
+            state.extend(c_cue.resolved(palettes, patch));
+        }
+        state
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracking() {
+        let (palettes, patch) = (Palettes::new(), Patch::new());
+        let int = CueTarget::Attribute(1, Attribute::Intensity);
+        let red = CueTarget::Attribute(1, Attribute::Red);
+        let list = CueList::new("Main")
+            .with_cue(Cue::new("1").with_value(int, 1.).with_value(red, 0.5))
+            .with_cue(Cue::new("2").with_value(int, 0.4))
+            .with_cue(Cue::new("3").with_value(CueTarget::Channel(0, 10), 1.));
+
+        let state = list.tracked_state(1, &palettes, &patch);
+        assert_eq!(state.get(&int), Some(&0.4));
+        // Red tracks through from cue 1
+        assert_eq!(state.get(&red), Some(&0.5));
+        assert_eq!(list.tracked_state(2, &palettes, &patch).len(), 3);
+        assert_eq!(list.tracked_state(10, &palettes, &patch).len(), 3);
+
+        let mut cue = Cue::new("4").with_value(red, 0.2).with_value(int, 2.);
+        assert_eq!(cue.values()[0].target, int);
+        assert_eq!(cue.get(int), Some(1.));
+        cue.remove(int);
+        assert_eq!(cue.get(int), None);
+    }
+
+    #[test]
+    fn test_record_fixtures() {
+        let mut prog = Programmer::new();
+        prog.set(1, Attribute::Intensity, 1.);
+        prog.set(2, Attribute::Intensity, 0.5);
+        prog.set(3, Attribute::Red, 0.2);
+
+        let mut list = CueList::new("Main");
+        let index = list.record_fixtures(&prog, &[3, 1]);
+        let targets: Vec<CueTarget> = list.get(index).unwrap().values().iter().map(|v| v.target).collect();
+        assert_eq!(targets, vec![CueTarget::Attribute(1, Attribute::Intensity), CueTarget::Attribute(3, Attribute::Red)]);
+        let index = list.record(&prog);
+        assert_eq!(list.get(index).unwrap().values().len(), 3);
+    }
+
+    #[test]
+    fn test_palettes() {
+        use std::sync::Arc;
+        use crate::fixture::library;
+        use super::super::Palette;
+
+        let mut patch = Patch::new();
+        let rgb = Arc::new(library::generic_rgb());
+        let a = patch.add("Par".to_owned(), rgb.clone(), 0, 0, 0).unwrap();
+        let mut palettes = Palettes::new();
+        palettes.store(Palette::new(1, "Red", PaletteKind::Color).with_global(&rgb, Attribute::Red, 1.));
+        palettes.store(Palette::new(2, "Green", PaletteKind::Color).with_global(&rgb, Attribute::Green, 1.));
+        let red = CueTarget::Attribute(a, Attribute::Red);
+        let green = CueTarget::Attribute(a, Attribute::Green);
+
+        let mut prog = Programmer::new();
+        prog.selection_mut().set(vec![a]);
+        prog.set(a, Attribute::Intensity, 1.);
+        prog.set_selected_palette(&patch, palettes.get(1).unwrap());
+        let mut list = CueList::new("Main");
+        list.record(&prog);
+        let cue = list.get(0).unwrap();
+        assert_eq!(cue.values().len(), 1);
+        assert_eq!(cue.palettes(), &[PaletteRef { fixture: a, kind: PaletteKind::Color, palette: 1 }]);
+
+        list.cues.push(Cue::new("2").with_palette(a, PaletteKind::Color, 2));
+        assert_eq!(list.tracked_state(1, &palettes, &patch).get(&green), Some(&1.));
+
+        // Updating the palette updates the cue
+        palettes.get_mut(1).unwrap().set_global(&rgb, Attribute::Red, 0.5);
+        assert_eq!(list.tracked_state(0, &palettes, &patch).get(&red), Some(&0.5));
+
+        // Values set by hand replace the reference
+        prog.set(a, Attribute::Red, 0.2);
+        list.cues[0].record(&prog);
+        assert!(list.get(0).unwrap().palettes().is_empty());
+        assert_eq!(list.tracked_state(0, &palettes, &patch).get(&red), Some(&0.2));
+    }
+
+    #[test]
+    fn test_timing() {
+        let timing = CueTiming::new(2., 4., 1.);
+        assert_eq!(timing.duration(), 5.);
+        assert_eq!(timing.progress(0.5, true), 0.);
+        assert_eq!(timing.progress(2., true), 0.5);
+        assert_eq!(timing.progress(2., false), 0.25);
+        assert_eq!(timing.progress(10., false), 1.);
+        assert_eq!(CueTiming::default().progress(0., true), 1.);
+    }
+}
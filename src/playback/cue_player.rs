@@ -0,0 +1,305 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    dmx::DMX,
+    fixture::{
+        value,
+        Patch,
+    },
+};
+
+use super::{
+    CueList,
+    CueTarget,
+    CueTiming,
+    Palettes,
+    Trigger,
+};
+
+/// Plays a cue list with GO, BACK and PAUSE, crossfading between the tracked states of the cues.
+/// The list itself is passed in on every call, so it can be edited while playing.
+/// Palettes are looked up when a cue is started.
+#[derive(Debug, Clone, Default)]
+pub struct CuePlayer {
+    current: Option<usize>,
+    /// Output when the running fade was started
+    from: BTreeMap<CueTarget, f32>,
+    /// Tracked state of the current cue
+    to: BTreeMap<CueTarget, f32>,
+    timing: CueTiming,
+    /// Seconds since the current cue was started
+    elapsed: f32,
+    paused: bool,
+}
+
+impl CuePlayer {
+    /// Fade time when going back to the previous cue
+    pub const BACK_TIME: f32 = 1.;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Index of the cue that is faded to or on stage
+    pub fn current(&self) -> Option<usize> {
+        self.current
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn is_fading(&self) -> bool {
+        self.current.is_some() && self.elapsed < self.timing.duration()
+    }
+
+    /// Whether anything is output, also true while fading out after a release
+    pub fn is_active(&self) -> bool {
+        !self.to.is_empty() || (self.elapsed < self.timing.duration() && !self.from.is_empty())
+    }
+
+    /// Fraction of the running fade that is done
+    pub fn progress(&self) -> f32 {
+        let duration = self.timing.duration();
+        if duration <= 0. { 1. } else { (self.elapsed / duration).min(1.) }
+    }
+
+    /// Start the next cue, or continue a paused fade
+    pub fn go(&mut self, list: &CueList, palettes: &Palettes, patch: &Patch) {
+        if self.paused {
+            self.paused = false;
+            return;
+        }
+        let next = self.current.map(|i| i + 1).unwrap_or(0);
+        if let Some(cue) = list.get(next) {
+            self.start(next, list.tracked_state(next, palettes, patch), cue.timing);
+        }
+    }
+
+    /// Fade back to the previous cue
+    pub fn back(&mut self, list: &CueList, palettes: &Palettes, patch: &Patch) {
+        self.paused = false;
+        if let Some(i) = self.current.filter(|i| *i > 0 && *i <= list.len()) {
+            self.start(i - 1, list.tracked_state(i - 1, palettes, patch), CueTiming::fade(Self::BACK_TIME));
+        }
+    }
+
+    /// Jump to a cue with its own timing
+    pub fn goto(&mut self, list: &CueList, palettes: &Palettes, patch: &Patch, index: usize) {
+        self.paused = false;
+        if let Some(cue) = list.get(index) {
+            self.start(index, list.tracked_state(index, palettes, patch), cue.timing);
+        }
+    }
+
+    /// Freeze the running fade and all follow times until the next GO
+    pub fn pause(&mut self) {
+        if self.current.is_some() {
+            self.paused = true;
+        }
+    }
+
+    /// Fade out all intensities and stop playing
+    pub fn release(&mut self, time: f32) {
+        self.from = self.output();
+        self.to.clear();
+        self.timing = CueTiming::fade(time);
+        self.elapsed = 0.;
+        self.current = None;
+        self.paused = false;
+    }
+
+    fn start(&mut self, index: usize, state: BTreeMap<CueTarget, f32>, timing: CueTiming) {
+        let mut from = self.output();
+        // Values dropped by the last fade are not on stage anymore
+        from.retain(|t, v| self.to.contains_key(t) || (t.is_intensity() && *v > 0.));
+        self.from = from;
+        self.to = state;
+        self.timing = timing;
+        self.elapsed = 0.;
+        self.current = Some(index);
+    }
+
+    /// Advance by `dt` seconds and start following cues whose time has come
+    pub fn update(&mut self, list: &CueList, palettes: &Palettes, patch: &Patch, dt: f32) {
+        if self.paused {
+            return;
+        }
+        if self.current.is_some() || self.is_active() {
+            self.elapsed += dt;
+        }
+
+        let next = match self.current.and_then(|i| list.get(i + 1)) {
+            Some(next) => next,
+            None => return,
+        };
+        let due = match next.trigger {
+            Trigger::Go => false,
+            Trigger::Follow(wait) => self.elapsed >= self.timing.duration() + wait,
+            Trigger::Wait(wait) => self.elapsed >= wait,
+        };
+        if due {
+            self.go(list, palettes, patch);
+        }
+    }
+
+    /// Current value of every target played
+    pub fn output(&self) -> BTreeMap<CueTarget, f32> {
+        let mut out = BTreeMap::new();
+        for c_target in self.from.keys().chain(self.to.keys()) {
+            if out.contains_key(c_target) {
+                continue;
+            }
+            let (start, end) = match (self.from.get(c_target), self.to.get(c_target)) {
+                (Some(s), Some(e)) => (*s, *e),
+                (None, Some(e)) => (if c_target.is_intensity() { 0. } else { *e }, *e),
+                (Some(s), None) => (*s, if c_target.is_intensity() { 0. } else { *s }),
+                (None, None) => continue,
+            };
+            let progress = self.timing.progress(self.elapsed, end >= start);
+            out.insert(*c_target, start + (end - start) * progress);
+        }
+        out
+    }
+
+    /// Write the output into the universes
+    pub fn apply(&self, patch: &Patch, dmx: &mut DMX) {
+        apply_values(&self.output(), patch, dmx);
+    }
+}
+
+/// Write played values into the channels of their fixtures or universes
+pub fn apply_values(values: &BTreeMap<CueTarget, f32>, patch: &Patch, dmx: &mut DMX) {
+    for (c_target, c_value) in values {
+        match *c_target {
+            CueTarget::Attribute(id, attribute) => {
+                if let Some(fixture) = patch.get(id) {
+                    fixture.set_attribute(dmx, attribute, *c_value);
+                }
+            }
+            CueTarget::Channel(universe, channel) => {
+                if let Some(u) = dmx.get_universe(universe) {
+                    u.set_channel(channel, value::to_dmx8(*c_value) as u32);
+                }
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::{
+        dmx::DMXUniverse,
+        fixture::{
+            library,
+            Attribute,
+        },
+        playback::Cue,
+    };
+
+    const INT: CueTarget = CueTarget::Attribute(1, Attribute::Intensity);
+    const PAN: CueTarget = CueTarget::Attribute(2, Attribute::Pan);
+
+    fn test_list() -> CueList {
+        CueList::new("Main")
+            .with_cue(Cue::new("1").with_value(INT, 1.).with_value(PAN, 0.2).with_timing(CueTiming::fade(2.)))
+            .with_cue(Cue::new("2").with_value(INT, 0.5).with_timing(CueTiming::new(1., 4., 1.)))
+            .with_cue(Cue::new("3").with_value(PAN, 1.).with_trigger(Trigger::Follow(1.)))
+    }
+
+    fn close(a: Option<&f32>, b: f32) -> bool {
+        a.map(|a| (a - b).abs() < 1e-4).unwrap_or(false)
+    }
+
+    #[test]
+    fn test_go_and_fade() {
+        let list = test_list();
+        let (palettes, patch) = (Palettes::new(), Patch::new());
+        let mut player = CuePlayer::new();
+        assert!(player.output().is_empty());
+
+        player.go(&list, &palettes, &patch);
+        player.update(&list, &palettes, &patch, 1.);
+        let out = player.output();
+        // Intensity fades in from zero, other attributes snap
+        assert!(close(out.get(&INT), 0.5));
+        assert!(close(out.get(&PAN), 0.2));
+
+        player.update(&list, &palettes, &patch, 1.);
+        player.go(&list, &palettes, &patch);
+        // Falling value after the delay, pan tracks
+        player.update(&list, &palettes, &patch, 3.);
+        let out = player.output();
+        assert!(close(out.get(&INT), 0.75));
+        assert!(close(out.get(&PAN), 0.2));
+
+        player.pause();
+        player.update(&list, &palettes, &patch, 10.);
+        assert!(close(player.output().get(&INT), 0.75));
+        player.go(&list, &palettes, &patch);
+        assert_eq!(player.current(), Some(1));
+        player.update(&list, &palettes, &patch, 2.);
+        assert!(close(player.output().get(&INT), 0.5));
+
+        // Cue 3 follows one second after cue 2 has finished
+        player.update(&list, &palettes, &patch, 0.5);
+        assert_eq!(player.current(), Some(1));
+        player.update(&list, &palettes, &patch, 0.5);
+        assert_eq!(player.current(), Some(2));
+        assert!(close(player.output().get(&PAN), 1.));
+        assert!(close(player.output().get(&INT), 0.5));
+    }
+
+    #[test]
+    fn test_back_and_release() {
+        let list = CueList::new("Main")
+            .with_cue(Cue::new("1").with_value(INT, 1.))
+            .with_cue(Cue::new("2").with_value(PAN, 1.).with_value(CueTarget::Channel(0, 100), 1.));
+        let (palettes, patch) = (Palettes::new(), Patch::new());
+        let mut player = CuePlayer::new();
+        player.goto(&list, &palettes, &patch, 1);
+        player.update(&list, &palettes, &patch, 0.1);
+        assert_eq!(player.output().len(), 3);
+
+        // Values introduced by cue 2 leave when going back
+        player.back(&list, &palettes, &patch);
+        player.update(&list, &palettes, &patch, CuePlayer::BACK_TIME / 2.);
+        assert!(close(player.output().get(&CueTarget::Channel(0, 100)), 0.5));
+        player.update(&list, &palettes, &patch, CuePlayer::BACK_TIME);
+        player.go(&list, &palettes, &patch);
+        player.back(&list, &palettes, &patch);
+        player.update(&list, &palettes, &patch, CuePlayer::BACK_TIME);
+        player.go(&list, &palettes, &patch);
+        assert!(player.output().get(&PAN).is_some());
+
+        player.release(2.);
+        player.update(&list, &palettes, &patch, 1.);
+        assert!(close(player.output().get(&INT), 0.5));
+        assert!(player.is_active());
+        player.update(&list, &palettes, &patch, 1.);
+        assert!(!player.is_active());
+        assert_eq!(player.current(), None);
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut dmx = DMX::new();
+        dmx.add_universe(DMXUniverse::new(0));
+        let mut patch = Patch::new();
+        patch.add("Dim".to_owned(), Arc::new(library::generic_dimmer()), 0, 0, 0).unwrap();
+        let list = CueList::new("Main")
+            .with_cue(Cue::new("1").with_value(INT, 1.).with_value(CueTarget::Channel(0, 20), 0.5));
+
+        let palettes = Palettes::new();
+        let mut player = CuePlayer::new();
+        player.go(&list, &palettes, &patch);
+        player.update(&list, &palettes, &patch, 0.);
+        player.apply(&patch, &mut dmx);
+        let universe = dmx.get_universe_ref(0).unwrap();
+        assert_eq!(universe.get_channel(0).unwrap().get_val(), 255);
+        assert_eq!(universe.get_channel(20).unwrap().get_val(), 128);
+    }
+}
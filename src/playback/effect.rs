@@ -0,0 +1,307 @@
+use std::collections::BTreeMap;
+use std::f32::consts::PI;
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::fixture::{
+    Attribute,
+    FixtureID,
+};
+
+use super::CueTarget;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Waveform {
+    Sine,
+    Triangle,
+    Square,
+    SawUp,
+    SawDown,
+}
+
+impl Waveform {
+    pub const ALL: [Waveform; 5] = [
+        Waveform::Sine,
+        Waveform::Triangle,
+        Waveform::Square,
+        Waveform::SawUp,
+        Waveform::SawDown,
+    ];
+
+    /// Value between 0 and 1 at `phase` given in cycles, starting at the low point
+    pub fn value(&self, phase: f32) -> f32 {
+        let p = phase.rem_euclid(1.);
+        match self {
+            Waveform::Sine => 0.5 - 0.5 * (2. * PI * p).cos(),
+            Waveform::Triangle => 1. - (2. * p - 1.).abs(),
+            Waveform::Square => if p < 0.5 { 1. } else { 0. },
+            Waveform::SawUp => p,
+            Waveform::SawDown => 1. - p,
+        }
+    }
+}
+
+/// Which way the phase spread runs through the fixtures
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EffectDirection {
+    /// The wave travels from the first to the last fixture
+    Forward,
+    Reverse,
+}
+
+/// An attribute moved by an effect
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EffectAttribute {
+    pub attribute: Attribute,
+    pub low: f32,
+    pub high: f32,
+    /// Phase offset in degrees, e.g. 90° on tilt against pan for a circle
+    pub phase: f32,
+}
+
+impl EffectAttribute {
+    pub fn new(attribute: Attribute, low: f32, high: f32) -> Self {
+        Self { attribute, low, high, phase: 0. }
+    }
+
+    pub fn with_phase(mut self, phase: f32) -> Self {
+        self.phase = phase;
+        self
+    }
+}
+
+/// A waveform running over an ordered group of fixtures
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Effect {
+    pub name: String,
+    pub fixtures: Vec<FixtureID>,
+    pub attributes: Vec<EffectAttribute>,
+    pub waveform: Waveform,
+    /// Cycles per second
+    pub rate: f32,
+    /// Phase difference in degrees spread over the fixtures, 360° spreads one cycle evenly
+    pub spread: f32,
+    /// Number of mirrored wings the fixtures are split into
+    pub wings: usize,
+    /// Number of neighbouring fixtures sharing the same phase
+    pub groups: usize,
+    pub direction: EffectDirection,
+}
+
+impl Effect {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            fixtures: Vec::new(),
+            attributes: Vec::new(),
+            waveform: Waveform::Sine,
+            rate: 0.5,
+            spread: 360.,
+            wings: 1,
+            groups: 1,
+            direction: EffectDirection::Forward,
+        }
+    }
+
+    pub fn with_fixtures(mut self, fixtures: &[FixtureID]) -> Self {
+        self.fixtures = fixtures.to_vec();
+        self
+    }
+
+    pub fn with_attribute(mut self, attribute: EffectAttribute) -> Self {
+        self.attributes.push(attribute);
+        self
+    }
+
+    pub fn with_waveform(mut self, waveform: Waveform) -> Self {
+        self.waveform = waveform;
+        self
+    }
+
+    pub fn with_rate(mut self, rate: f32) -> Self {
+        self.rate = rate;
+        self
+    }
+
+    pub fn with_spread(mut self, spread: f32) -> Self {
+        self.spread = spread;
+        self
+    }
+
+    pub fn with_wings(mut self, wings: usize) -> Self {
+        self.wings = wings.max(1);
+        self
+    }
+
+    pub fn with_groups(mut self, groups: usize) -> Self {
+        self.groups = groups.max(1);
+        self
+    }
+
+    pub fn with_direction(mut self, direction: EffectDirection) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Phase offset in cycles of the fixture at `index` in the group
+    pub fn offset(&self, index: usize) -> f32 {
+        let groups = self.groups.max(1);
+        let wings = self.wings.max(1);
+        let blocks = (self.fixtures.len() + groups - 1) / groups;
+        let wing_size = ((blocks + wings - 1) / wings).max(1);
+
+        let block = index / groups;
+        let mut position = block % wing_size;
+        // Every second wing runs mirrored
+        if (block / wing_size) % 2 == 1 {
+            position = wing_size - 1 - position;
+        }
+
+        let offset = position as f32 / wing_size as f32 * self.spread / 360.;
+        match self.direction {
+            EffectDirection::Forward => offset,
+            EffectDirection::Reverse => -offset,
+        }
+    }
+
+    /// Values of all fixtures and attributes `time` seconds after the start
+    pub fn values(&self, time: f32) -> BTreeMap<CueTarget, f32> {
+        let mut out = BTreeMap::new();
+        for (i, c_id) in self.fixtures.iter().enumerate() {
+            let phase = time * self.rate - self.offset(i);
+            for c_attr in self.attributes.iter() {
+                let v = self.waveform.value(phase - c_attr.phase / 360.);
+                out.insert(
+                    CueTarget::Attribute(*c_id, c_attr.attribute),
+                    (c_attr.low + (c_attr.high - c_attr.low) * v).max(0.).min(1.),
+                );
+            }
+        }
+        out
+    }
+}
+
+/// Runs an effect, which is passed in on every call like for the `CuePlayer`
+#[derive(Debug, Clone, Default)]
+pub struct EffectPlayer {
+    /// Seconds since the start, `None` when stopped
+    time: Option<f32>,
+    paused: bool,
+}
+
+impl EffectPlayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.time.is_some()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn start(&mut self) {
+        self.time = Some(0.);
+        self.paused = false;
+    }
+
+    pub fn stop(&mut self) {
+        self.time = None;
+        self.paused = false;
+    }
+
+    /// Freeze the effect at its current values
+    pub fn pause(&mut self) {
+        self.paused = self.time.is_some();
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        if self.paused {
+            return;
+        }
+        if let Some(time) = self.time.as_mut() {
+            *time += dt;
+        }
+    }
+
+    pub fn output(&self, effect: &Effect) -> BTreeMap<CueTarget, f32> {
+        match self.time {
+            Some(time) => effect.values(time),
+            None => BTreeMap::new(),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn test_waveforms() {
+        for c_wave in Waveform::ALL.iter() {
+            assert!((0..100).map(|i| c_wave.value(i as f32 / 100.)).all(|v| (0. ..=1.).contains(&v)));
+        }
+        assert!(close(Waveform::Sine.value(0.), 0.) && close(Waveform::Sine.value(0.5), 1.));
+        assert!(close(Waveform::Triangle.value(0.25), 0.5) && close(Waveform::Triangle.value(1.5), 1.));
+        assert!(close(Waveform::SawDown.value(0.25), 0.75));
+    }
+
+    #[test]
+    fn test_spread() {
+        let effect = Effect::new("Wave").with_fixtures(&[1, 2, 3, 4]);
+        let offsets: Vec<f32> = (0..4).map(|i| effect.offset(i)).collect();
+        assert_eq!(offsets, vec![0., 0.25, 0.5, 0.75]);
+
+        // Two wings mirror each other
+        let effect = effect.with_wings(2);
+        let offsets: Vec<f32> = (0..4).map(|i| effect.offset(i)).collect();
+        assert_eq!(offsets, vec![0., 0.5, 0.5, 0.]);
+
+        // Pairs share their phase
+        let effect = Effect::new("Wave").with_fixtures(&[1, 2, 3, 4]).with_groups(2).with_spread(180.);
+        let offsets: Vec<f32> = (0..4).map(|i| effect.offset(i)).collect();
+        assert_eq!(offsets, vec![0., 0., 0.25, 0.25]);
+        assert_eq!(effect.with_direction(EffectDirection::Reverse).offset(2), -0.25);
+    }
+
+    #[test]
+    fn test_values() {
+        let effect = Effect::new("Circle")
+            .with_fixtures(&[1, 2])
+            .with_attribute(EffectAttribute::new(Attribute::Pan, 0.25, 0.75))
+            .with_attribute(EffectAttribute::new(Attribute::Tilt, 0.25, 0.75).with_phase(90.))
+            .with_rate(1.)
+            .with_spread(180.);
+        let pan = |id| CueTarget::Attribute(id, Attribute::Pan);
+        let tilt = |id| CueTarget::Attribute(id, Attribute::Tilt);
+
+        let values = effect.values(0.);
+        assert!(close(values[&pan(1)], 0.25) && close(values[&tilt(1)], 0.5));
+        assert!(close(values[&pan(2)], 0.5));
+
+        let mut player = EffectPlayer::new();
+        assert!(player.output(&effect).is_empty());
+        player.start();
+        player.update(0.5);
+        assert!(close(player.output(&effect)[&pan(1)], 0.75));
+        player.pause();
+        player.update(0.25);
+        assert!(close(player.output(&effect)[&pan(1)], 0.75));
+        player.stop();
+        assert!(!player.is_active());
+    }
+}
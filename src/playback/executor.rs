@@ -0,0 +1,651 @@
+use std::collections::{
+    BTreeMap,
+    BTreeSet,
+};
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    dmx::DMX,
+    fixture::Patch,
+};
+
+use super::{
+    apply_values,
+    Chase,
+    ChasePlayer,
+    CueList,
+    CuePlayer,
+    CueTarget,
+    Effect,
+    EffectPlayer,
+    Palettes,
+};
+
+/// What an executor plays, the index points into the cue lists, chases or effects of the show
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ExecutorContent {
+    Empty,
+    CueList(usize),
+    Chase(usize),
+    Effect(usize),
+}
+
+impl Default for ExecutorContent {
+    fn default() -> Self { ExecutorContent::Empty }
+}
+
+/// Everything in the show executors can play, with the palettes and patch cues are looked up in
+#[derive(Debug, Clone, Copy)]
+pub struct Playables<'a> {
+    pub cue_lists: &'a [CueList],
+    pub chases: &'a [Chase],
+    pub effects: &'a [Effect],
+    pub palettes: &'a Palettes,
+    pub patch: &'a Patch,
+}
+
+/// How the intensities of an executor are merged with other executors.
+/// All other attributes are always merged latest takes precedence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Priority {
+    /// Highest takes precedence
+    HTP,
+    /// Latest takes precedence
+    LTP,
+}
+
+/// When a running executor lets go of its values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReleaseMode {
+    /// Keep running until released explicitly
+    Manual,
+    /// Release when the fader is pulled to zero
+    OnZero,
+}
+
+/// A playback fader running one piece of content
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Executor {
+    pub name: String,
+    pub content: ExecutorContent,
+    pub priority: Priority,
+    pub release: ReleaseMode,
+    /// Fade out time in seconds when released
+    pub release_time: f32,
+    #[serde(default)]
+    level: f32,
+    #[serde(skip)]
+    flash: bool,
+    #[serde(skip)]
+    player: CuePlayer,
+    #[serde(skip)]
+    chase_player: ChasePlayer,
+    #[serde(skip)]
+    effect_player: EffectPlayer,
+    /// Order of the last activation, later activations win for latest takes precedence
+    #[serde(skip)]
+    activated: u64,
+}
+
+impl Executor {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            content: ExecutorContent::Empty,
+            priority: Priority::HTP,
+            release: ReleaseMode::OnZero,
+            release_time: 0.,
+            level: 0.,
+            flash: false,
+            player: CuePlayer::new(),
+            chase_player: ChasePlayer::new(),
+            effect_player: EffectPlayer::new(),
+            activated: 0,
+        }
+    }
+
+    pub fn with_content(mut self, content: ExecutorContent) -> Self {
+        self.content = content;
+        self
+    }
+
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn with_release(mut self, release: ReleaseMode, time: f32) -> Self {
+        self.release = release;
+        self.release_time = time;
+        self
+    }
+
+    pub fn with_level(mut self, level: f32) -> Self {
+        self.level = level;
+        self
+    }
+
+    pub fn get_level(&self) -> f32 {
+        self.level
+    }
+
+    pub fn is_flashed(&self) -> bool {
+        self.flash
+    }
+
+    /// Level scaling the intensities, full while flashed
+    pub fn master(&self) -> f32 {
+        if self.flash { 1. } else { self.level }
+    }
+
+    pub fn player(&self) -> &CuePlayer {
+        &self.player
+    }
+
+    pub fn chase_player(&self) -> &ChasePlayer {
+        &self.chase_player
+    }
+
+    pub fn effect_player(&self) -> &EffectPlayer {
+        &self.effect_player
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.player.is_active() || self.chase_player.is_active() || self.effect_player.is_active()
+    }
+
+    fn cue_list<'l>(&self, content: Playables<'l>) -> Option<&'l CueList> {
+        match self.content {
+            ExecutorContent::CueList(i) => content.cue_lists.get(i),
+            _ => None,
+        }
+    }
+
+    fn chase<'l>(&self, content: Playables<'l>) -> Option<&'l Chase> {
+        match self.content {
+            ExecutorContent::Chase(i) => content.chases.get(i),
+            _ => None,
+        }
+    }
+
+    fn effect<'l>(&self, content: Playables<'l>) -> Option<&'l Effect> {
+        match self.content {
+            ExecutorContent::Effect(i) => content.effects.get(i),
+            _ => None,
+        }
+    }
+
+    /// Short description of the playback state, e.g. `2/5` for the second of five cues or steps
+    pub fn status(&self, content: Playables) -> String {
+        let (position, len, paused) = match (self.cue_list(content), self.chase(content), self.effect(content)) {
+            (Some(list), _, _) => (self.player.current(), list.len(), self.player.is_paused()),
+            (None, Some(chase), _) => (self.chase_player.current(), chase.len(), !self.chase_player.is_running()),
+            (None, None, Some(_)) => {
+                let paused = if self.effect_player.is_paused() { " ||" } else { "" };
+                return format!("{}{}", if self.effect_player.is_active() { "On" } else { "Off" }, paused);
+            }
+            (None, None, None) => return "-".to_owned(),
+        };
+        match position {
+            Some(i) => format!("{}/{}{}", i + 1, len, if paused { " ||" } else { "" }),
+            None => format!("-/{}", len),
+        }
+    }
+
+    /// Next cue, for chases start, resume or step by hand and for effects start or resume
+    fn go(&mut self, content: Playables) {
+        if let Some(list) = self.cue_list(content) {
+            self.player.go(list, content.palettes, content.patch);
+        } else if let Some(chase) = self.chase(content) {
+            if !self.chase_player.is_active() {
+                self.chase_player.start(chase);
+            } else if !self.chase_player.is_running() {
+                self.chase_player.resume();
+            } else {
+                self.chase_player.next_step(chase);
+            }
+        } else if self.effect(content).is_some() {
+            if self.effect_player.is_active() {
+                self.effect_player.resume();
+            } else {
+                self.effect_player.start();
+            }
+        }
+    }
+
+    /// Play the content from the beginning, the first cue, the first step or a restarted effect
+    fn start(&mut self, content: Playables) {
+        if let Some(list) = self.cue_list(content) {
+            self.player.goto(list, content.palettes, content.patch, 0);
+        } else if let Some(chase) = self.chase(content) {
+            self.chase_player.start(chase);
+        } else if self.effect(content).is_some() {
+            self.effect_player.start();
+        }
+    }
+
+    fn goto(&mut self, cue: usize, content: Playables) {
+        if let Some(list) = self.cue_list(content) {
+            self.player.goto(list, content.palettes, content.patch, cue);
+        }
+    }
+
+    fn back(&mut self, content: Playables) {
+        if let Some(list) = self.cue_list(content) {
+            self.player.back(list, content.palettes, content.patch);
+        } else if let Some(chase) = self.chase(content) {
+            self.chase_player.prev_step(chase);
+        }
+    }
+
+    fn pause(&mut self) {
+        self.player.pause();
+        self.chase_player.pause();
+        self.effect_player.pause();
+    }
+
+    fn release(&mut self) {
+        self.player.release(self.release_time);
+        self.chase_player.stop();
+        self.effect_player.stop();
+    }
+
+    fn update(&mut self, content: Playables, dt: f32) {
+        if let Some(list) = self.cue_list(content) {
+            self.player.update(list, content.palettes, content.patch, dt);
+        } else if let Some(chase) = self.chase(content) {
+            self.chase_player.update(chase, dt);
+        } else {
+            self.effect_player.update(dt);
+        }
+    }
+
+    /// Played values with the intensities scaled by the master level
+    pub fn output(&self, content: Playables) -> BTreeMap<CueTarget, f32> {
+        let master = self.master();
+        let mut out = match (self.cue_list(content), self.chase(content), self.effect(content)) {
+            (Some(_), _, _) => self.player.output(),
+            (None, Some(chase), _) => self.chase_player.output(chase),
+            (None, None, Some(effect)) => self.effect_player.output(effect),
+            (None, None, None) => BTreeMap::new(),
+        };
+        for (c_target, c_value) in out.iter_mut() {
+            if c_target.is_intensity() {
+                *c_value *= master;
+            }
+        }
+        out
+    }
+}
+
+/// All executors of a show, merged into one output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Executors {
+    executors: Vec<Executor>,
+    /// Level scaling the intensities of all executors
+    #[serde(default = "full_level")]
+    grand_master: f32,
+    #[serde(skip)]
+    activations: u64,
+    /// Targets written by the last `apply`
+    #[serde(skip)]
+    written: BTreeSet<CueTarget>,
+}
+
+impl Default for Executors {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+fn full_level() -> f32 {
+    1.
+}
+
+impl Executors {
+    /// Executors shown on the playback bar of a new show
+    pub const DEFAULT_COUNT: usize = 8;
+
+    /// `count` executors, the first one playing the first cue list at full level,
+    /// the second one the first chase and the third one the first effect, all others empty
+    pub fn new(count: usize) -> Self {
+        Self {
+            executors: (0..count)
+                .map(|i| match i {
+                    0 => Executor::new("Main")
+                        .with_content(ExecutorContent::CueList(0))
+                        .with_release(ReleaseMode::Manual, 0.)
+                        .with_level(1.),
+                    1 => Executor::new("Chase")
+                        .with_content(ExecutorContent::Chase(0)),
+                    2 => Executor::new("Effect")
+                        .with_content(ExecutorContent::Effect(0)),
+                    _ => Executor::new(&format!("Exec {}", i + 1)),
+                })
+                .collect(),
+            grand_master: 1.,
+            activations: 0,
+            written: BTreeSet::new(),
+        }
+    }
+
+    pub fn grand_master(&self) -> f32 {
+        self.grand_master
+    }
+
+    pub fn set_grand_master(&mut self, level: f32) {
+        self.grand_master = level.max(0.).min(1.);
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Executor> {
+        self.executors.get(index)
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut Executor> {
+        self.executors.get_mut(index)
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<Executor> {
+        self.executors.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.executors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.executors.is_empty()
+    }
+
+    pub fn push(&mut self, executor: Executor) -> usize {
+        self.executors.push(executor);
+        self.executors.len() - 1
+    }
+
+    fn activate(&mut self, index: usize) -> Option<&mut Executor> {
+        self.activations += 1;
+        let order = self.activations;
+        let exec = self.executors.get_mut(index)?;
+        exec.activated = order;
+        Some(exec)
+    }
+
+    pub fn go(&mut self, index: usize, content: Playables) {
+        if let Some(exec) = self.activate(index) {
+            exec.go(content);
+        }
+    }
+
+    pub fn back(&mut self, index: usize, content: Playables) {
+        if let Some(exec) = self.activate(index) {
+            exec.back(content);
+        }
+    }
+
+    /// Restart the content of the executor, unlike GO which steps a running chase
+    pub fn start(&mut self, index: usize, content: Playables) {
+        if let Some(exec) = self.activate(index) {
+            exec.start(content);
+        }
+    }
+
+    /// Jump to a cue of the cue list played by the executor
+    pub fn goto(&mut self, index: usize, cue: usize, content: Playables) {
+        if let Some(exec) = self.activate(index) {
+            exec.goto(cue, content);
+        }
+    }
+
+    pub fn pause(&mut self, index: usize) {
+        if let Some(exec) = self.executors.get_mut(index) {
+            exec.pause();
+        }
+    }
+
+    pub fn release(&mut self, index: usize) {
+        if let Some(exec) = self.executors.get_mut(index) {
+            exec.release();
+        }
+    }
+
+    /// Move the fader. Raising it from zero starts the content, pulling it to zero may release it.
+    pub fn set_level(&mut self, index: usize, level: f32, content: Playables) {
+        let level = level.max(0.).min(1.);
+        let (was_zero, running) = match self.executors.get(index) {
+            Some(exec) => (exec.level <= 0., exec.is_active()),
+            None => return,
+        };
+        if was_zero && level > 0. {
+            if let Some(exec) = self.activate(index) {
+                if !running {
+                    exec.go(content);
+                }
+            }
+        }
+        if let Some(exec) = self.executors.get_mut(index) {
+            exec.level = level;
+            if level <= 0. && !exec.flash && exec.release == ReleaseMode::OnZero {
+                exec.release();
+            }
+        }
+    }
+
+    /// Hold the executor at full while the flash button is pressed
+    pub fn flash(&mut self, index: usize, pressed: bool, content: Playables) {
+        if pressed {
+            if let Some(exec) = self.activate(index) {
+                exec.flash = true;
+                if !exec.is_active() {
+                    exec.go(content);
+                }
+            }
+        } else if let Some(exec) = self.executors.get_mut(index) {
+            exec.flash = false;
+            if exec.level <= 0. && exec.release == ReleaseMode::OnZero {
+                exec.release();
+            }
+        }
+    }
+
+    /// Advance all executors by `dt` seconds
+    pub fn update(&mut self, content: Playables, dt: f32) {
+        for c_exec in self.executors.iter_mut() {
+            c_exec.update(content, dt);
+        }
+    }
+
+    /// Merged output of all active executors.
+    /// Intensities of HTP executors take the highest value, everything else the one of the latest activated executor.
+    /// All intensities are scaled by the grand master.
+    pub fn output(&self, content: Playables) -> BTreeMap<CueTarget, f32> {
+        let mut active: Vec<&Executor> = self.executors.iter()
+            .filter(|e| e.is_active())
+            .collect();
+        active.sort_by_key(|e| e.activated);
+
+        let mut out: BTreeMap<CueTarget, f32> = BTreeMap::new();
+        for c_exec in active {
+            for (c_target, c_value) in c_exec.output(content) {
+                match out.get_mut(&c_target) {
+                    Some(v) if c_target.is_intensity() && c_exec.priority == Priority::HTP => *v = v.max(c_value),
+                    _ => { out.insert(c_target, c_value); }
+                }
+            }
+        }
+        for (c_target, c_value) in out.iter_mut() {
+            if c_target.is_intensity() {
+                *c_value *= self.grand_master;
+            }
+        }
+        out
+    }
+
+    /// Write the merged output into the universes.
+    /// Intensities no longer played by any executor are set to zero, other attributes keep their last value.
+    pub fn apply(&mut self, dmx: &mut DMX, content: Playables) {
+        let mut out = self.output(content);
+        let written: BTreeSet<CueTarget> = out.keys().cloned().collect();
+        for c_target in self.written.difference(&written).filter(|t| t.is_intensity()) {
+            out.insert(*c_target, 0.);
+        }
+        apply_values(&out, content.patch, dmx);
+        self.written = written;
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fixture::Attribute,
+        playback::{
+            ChaseStep,
+            Cue,
+            EffectAttribute,
+            Waveform,
+        },
+    };
+
+    const INT: CueTarget = CueTarget::Attribute(1, Attribute::Intensity);
+    const RED: CueTarget = CueTarget::Attribute(1, Attribute::Red);
+
+    fn test_lists() -> Vec<CueList> {
+        vec![
+            CueList::new("A").with_cue(Cue::new("1").with_value(INT, 1.).with_value(RED, 1.)),
+            CueList::new("B").with_cue(Cue::new("1").with_value(INT, 0.6).with_value(RED, 0.2)),
+        ]
+    }
+
+    fn test_executors() -> Executors {
+        let mut execs = Executors::default();
+        execs.push(Executor::new("A").with_content(ExecutorContent::CueList(0)));
+        execs.push(Executor::new("B").with_content(ExecutorContent::CueList(1)));
+        execs
+    }
+
+    #[test]
+    fn test_merge() {
+        let lists = test_lists();
+        let (palettes, patch) = (Palettes::new(), Patch::new());
+        let content = Playables { cue_lists: &lists, chases: &[], effects: &[], palettes: &palettes, patch: &patch };
+        let mut execs = test_executors();
+
+        execs.set_level(0, 0.5, content);
+        execs.set_level(1, 1., content);
+        execs.update(content, 0.);
+        let out = execs.output(content);
+        // Highest intensity wins, latest activated executor wins the colour
+        assert_eq!(out.get(&INT), Some(&0.6));
+        assert_eq!(out.get(&RED), Some(&0.2));
+
+        execs.go(0, content);
+        assert_eq!(execs.output(content).get(&RED), Some(&1.));
+
+        execs.get_mut(0).unwrap().priority = Priority::LTP;
+        execs.go(0, content);
+        assert_eq!(execs.output(content).get(&INT), Some(&0.5));
+
+        // The grand master only scales intensities
+        execs.set_grand_master(0.5);
+        assert_eq!(execs.output(content).get(&INT), Some(&0.25));
+        assert_eq!(execs.output(content).get(&RED), Some(&1.));
+        execs.set_grand_master(2.);
+        assert_eq!(execs.grand_master(), 1.);
+    }
+
+    #[test]
+    fn test_fader_and_flash() {
+        let lists = test_lists();
+        let (palettes, patch) = (Palettes::new(), Patch::new());
+        let content = Playables { cue_lists: &lists, chases: &[], effects: &[], palettes: &palettes, patch: &patch };
+        let mut execs = test_executors();
+        assert!(execs.output(content).is_empty());
+
+        // Raising the fader starts the cue list, pulling it down releases it
+        execs.set_level(0, 0.5, content);
+        assert_eq!(execs.get(0).unwrap().status(content), "1/1");
+        execs.set_level(0, 0., content);
+        execs.update(content, 0.1);
+        assert!(!execs.get(0).unwrap().is_active());
+
+        execs.flash(1, true, content);
+        assert_eq!(execs.output(content).get(&INT), Some(&0.6));
+        execs.flash(1, false, content);
+        execs.update(content, 0.1);
+        assert!(execs.output(content).is_empty());
+
+        // Manual release keeps running at zero
+        execs.get_mut(1).unwrap().release = ReleaseMode::Manual;
+        execs.set_level(1, 1., content);
+        execs.set_level(1, 0., content);
+        assert_eq!(execs.output(content).get(&INT), Some(&0.));
+        assert_eq!(execs.output(content).get(&RED), Some(&0.2));
+        execs.release(1);
+        execs.update(content, 0.1);
+        assert!(!execs.get(1).unwrap().is_active());
+
+        assert_eq!(Executors::new(3).len(), 3);
+        assert_eq!(Executors::new(3).get(0).unwrap().get_level(), 1.);
+    }
+
+    #[test]
+    fn test_chase() {
+        let chases = vec![Chase::new("Run")
+            .with_step(ChaseStep::new(1.).with_value(INT, 1.))
+            .with_step(ChaseStep::new(1.).with_value(RED, 1.))];
+        let (palettes, patch) = (Palettes::new(), Patch::new());
+        let content = Playables { cue_lists: &[], chases: &chases, effects: &[], palettes: &palettes, patch: &patch };
+        let mut execs = Executors::default();
+        execs.push(Executor::new("Chase").with_content(ExecutorContent::Chase(0)).with_level(0.5));
+
+        execs.go(0, content);
+        assert_eq!(execs.get(0).unwrap().status(content), "1/2");
+        assert_eq!(execs.output(content).get(&INT), Some(&0.5));
+        execs.update(content, 1.);
+        assert_eq!(execs.output(content).get(&RED), Some(&1.));
+
+        // Go steps by hand while running and resumes when paused
+        execs.go(0, content);
+        assert_eq!(execs.get(0).unwrap().status(content), "1/2");
+        execs.pause(0);
+        assert_eq!(execs.get(0).unwrap().status(content), "1/2 ||");
+        execs.go(0, content);
+        execs.update(content, 1.);
+        assert_eq!(execs.get(0).unwrap().status(content), "2/2");
+        execs.start(0, content);
+        assert_eq!(execs.get(0).unwrap().status(content), "1/2");
+
+        execs.release(0);
+        assert!(execs.output(content).is_empty());
+    }
+
+    #[test]
+    fn test_effect() {
+        let effects = vec![Effect::new("Pulse")
+            .with_fixtures(&[1])
+            .with_attribute(EffectAttribute::new(Attribute::Intensity, 0., 1.))
+            .with_waveform(Waveform::SawUp)
+            .with_rate(1.)];
+        let (palettes, patch) = (Palettes::new(), Patch::new());
+        let content = Playables { cue_lists: &[], chases: &[], effects: &effects, palettes: &palettes, patch: &patch };
+        let mut execs = Executors::default();
+        execs.push(Executor::new("Effect").with_content(ExecutorContent::Effect(0)).with_level(0.5));
+        assert_eq!(execs.get(0).unwrap().status(content), "Off");
+
+        execs.go(0, content);
+        execs.update(content, 0.5);
+        assert_eq!(execs.output(content).get(&INT), Some(&0.25));
+        execs.pause(0);
+        execs.update(content, 0.25);
+        assert_eq!(execs.get(0).unwrap().status(content), "On ||");
+        assert_eq!(execs.output(content).get(&INT), Some(&0.25));
+
+        execs.set_level(0, 0., content);
+        assert!(execs.output(content).is_empty());
+    }
+}
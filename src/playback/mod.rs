@@ -0,0 +1,20 @@
+mod programmer;
+pub use programmer::*;
+
+mod palette;
+pub use palette::*;
+
+mod cue;
+pub use cue::*;
+
+mod cue_player;
+pub use cue_player::*;
+
+mod executor;
+pub use executor::*;
+
+mod chase;
+pub use chase::*;
+
+mod effect;
+pub use effect::*;
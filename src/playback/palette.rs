@@ -0,0 +1,334 @@
+use std::collections::BTreeMap;
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::fixture::{
+    Attribute,
+    Fixture,
+    FixtureID,
+    FixtureType,
+    Patch,
+};
+
+use super::{
+    CueTarget,
+    Programmer,
+};
+
+pub type PaletteID = usize;
+
+/// Attributes a palette stores, every attribute belongs to exactly one kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum PaletteKind {
+    Intensity,
+    Color,
+    Position,
+    /// Zoom, focus, gobo and shutter
+    Beam,
+}
+
+impl PaletteKind {
+    pub const ALL: [PaletteKind; 4] = [
+        PaletteKind::Intensity,
+        PaletteKind::Color,
+        PaletteKind::Position,
+        PaletteKind::Beam,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PaletteKind::Intensity => "Intensity",
+            PaletteKind::Color => "Color",
+            PaletteKind::Position => "Position",
+            PaletteKind::Beam => "Beam",
+        }
+    }
+
+    pub fn of(attribute: Attribute) -> PaletteKind {
+        match attribute {
+            Attribute::Intensity => PaletteKind::Intensity,
+            Attribute::Red | Attribute::Green | Attribute::Blue | Attribute::White | Attribute::Amber
+                | Attribute::UV | Attribute::Cyan | Attribute::Magenta | Attribute::Yellow
+                | Attribute::ColorWheel => PaletteKind::Color,
+            Attribute::Pan | Attribute::Tilt => PaletteKind::Position,
+            Attribute::Zoom | Attribute::Focus | Attribute::Gobo | Attribute::Shutter => PaletteKind::Beam,
+        }
+    }
+}
+
+/// Fixture types are stored by value in the show, so global values are keyed by manufacturer and name
+fn type_key(fixture_type: &FixtureType) -> String {
+    format!("{} {}", fixture_type.manufacturer, fixture_type.name)
+}
+
+/// Reusable attribute values of one kind, e.g. a colour or a position.
+/// Values are stored per fixture, values stored per fixture type are used for all fixtures of the type without own values.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Palette {
+    pub id: PaletteID,
+    pub name: String,
+    pub kind: PaletteKind,
+    #[serde(default)]
+    fixtures: BTreeMap<FixtureID, BTreeMap<Attribute, f32>>,
+    #[serde(default)]
+    types: BTreeMap<String, BTreeMap<Attribute, f32>>,
+}
+
+impl Palette {
+    pub fn new(id: PaletteID, name: &str, kind: PaletteKind) -> Self {
+        Self {
+            id,
+            name: name.to_owned(),
+            kind,
+            fixtures: BTreeMap::new(),
+            types: BTreeMap::new(),
+        }
+    }
+
+    /// Set the value of a single fixture, attributes of other kinds are ignored
+    pub fn set(&mut self, id: FixtureID, attribute: Attribute, value: f32) {
+        if PaletteKind::of(attribute) == self.kind {
+            self.fixtures.entry(id).or_default().insert(attribute, value.max(0.).min(1.));
+        }
+    }
+
+    /// Set the value used by all fixtures of a type without own values
+    pub fn set_global(&mut self, fixture_type: &FixtureType, attribute: Attribute, value: f32) {
+        if PaletteKind::of(attribute) == self.kind {
+            self.types.entry(type_key(fixture_type)).or_default().insert(attribute, value.max(0.).min(1.));
+        }
+    }
+
+    pub fn with_value(mut self, id: FixtureID, attribute: Attribute, value: f32) -> Self {
+        self.set(id, attribute, value);
+        self
+    }
+
+    pub fn with_global(mut self, fixture_type: &FixtureType, attribute: Attribute, value: f32) -> Self {
+        self.set_global(fixture_type, attribute, value);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fixtures.is_empty() && self.types.is_empty()
+    }
+
+    /// Values for a fixture, its own values take precedence over the ones of its type
+    pub fn values(&self, fixture: &Fixture) -> BTreeMap<Attribute, f32> {
+        let mut out: BTreeMap<Attribute, f32> = self.types.get(&type_key(fixture.get_type()))
+            .cloned()
+            .unwrap_or_default();
+        if let Some(own) = self.fixtures.get(&fixture.get_id()) {
+            out.extend(own.iter().map(|(a, v)| (*a, *v)));
+        }
+        out.retain(|a, _| fixture.has_attribute(*a));
+        out
+    }
+
+    /// Store the touched values of this kind per fixture, keeping values of untouched fixtures
+    pub fn record(&mut self, programmer: &Programmer) {
+        for (c_id, c_attr, c_value) in programmer.touched() {
+            self.set(c_id, c_attr, c_value);
+        }
+    }
+
+    /// Store the touched values of this kind per fixture type, the first touched fixture of a type wins
+    pub fn record_global(&mut self, programmer: &Programmer, patch: &Patch) {
+        let mut recorded: BTreeMap<String, BTreeMap<Attribute, f32>> = BTreeMap::new();
+        for (c_id, c_attr, c_value) in programmer.touched() {
+            if let Some(fixture) = patch.get(c_id).filter(|_| PaletteKind::of(c_attr) == self.kind) {
+                recorded.entry(type_key(fixture.get_type())).or_default().entry(c_attr).or_insert(c_value);
+            }
+        }
+        for (c_key, c_values) in recorded {
+            self.types.entry(c_key).or_default().extend(c_values);
+        }
+    }
+}
+
+/// A cue or programmer value taken from a palette, resolved whenever it is played
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct PaletteRef {
+    pub fixture: FixtureID,
+    pub kind: PaletteKind,
+    pub palette: PaletteID,
+}
+
+/// All palettes of a show ordered by id
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(from = "Vec<Palette>", into = "Vec<Palette>")]
+pub struct Palettes {
+    palettes: Vec<Palette>,
+}
+
+impl Palettes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn index(&self, id: PaletteID) -> Result<usize, usize> {
+        self.palettes.binary_search_by_key(&id, |p| p.id)
+    }
+
+    pub fn get(&self, id: PaletteID) -> Option<&Palette> {
+        self.index(id).ok().map(|i| &self.palettes[i])
+    }
+
+    pub fn get_mut(&mut self, id: PaletteID) -> Option<&mut Palette> {
+        match self.index(id) {
+            Ok(i) => Some(&mut self.palettes[i]),
+            Err(_) => None,
+        }
+    }
+
+    /// Insert a palette, replacing the one stored under the same id
+    pub fn store(&mut self, palette: Palette) {
+        match self.index(palette.id) {
+            Ok(i) => self.palettes[i] = palette,
+            Err(i) => self.palettes.insert(i, palette),
+        }
+    }
+
+    pub fn remove(&mut self, id: PaletteID) -> Option<Palette> {
+        self.index(id).ok().map(|i| self.palettes.remove(i))
+    }
+
+    /// Lowest free id, starting at 1
+    pub fn next_id(&self) -> PaletteID {
+        (1..).find(|id| self.get(*id).is_none()).unwrap_or(1)
+    }
+
+    /// Record the touched values of `kind` as a new palette, returns its id.
+    /// Nothing is stored if no attribute of the kind is touched.
+    pub fn record(&mut self, kind: PaletteKind, programmer: &Programmer) -> Option<PaletteID> {
+        let id = self.next_id();
+        let mut palette = Palette::new(id, &format!("{} {}", kind.name(), id), kind);
+        palette.record(programmer);
+        if palette.is_empty() {
+            return None;
+        }
+        self.store(palette);
+        Some(id)
+    }
+
+    /// Values of the referenced palettes, references to missing palettes or fixtures are skipped
+    pub fn resolve(&self, refs: &[PaletteRef], patch: &Patch) -> BTreeMap<CueTarget, f32> {
+        let mut out = BTreeMap::new();
+        for c_ref in refs {
+            let (palette, fixture) = match (self.get(c_ref.palette), patch.get(c_ref.fixture)) {
+                (Some(p), Some(f)) => (p, f),
+                _ => continue,
+            };
+            for (c_attr, c_value) in palette.values(fixture) {
+                out.insert(CueTarget::Attribute(c_ref.fixture, c_attr), c_value);
+            }
+        }
+        out
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Palette> {
+        self.palettes.iter()
+    }
+
+    pub fn of_kind(&self, kind: PaletteKind) -> impl Iterator<Item = &Palette> {
+        self.palettes.iter().filter(move |p| p.kind == kind)
+    }
+
+    pub fn len(&self) -> usize {
+        self.palettes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.palettes.is_empty()
+    }
+}
+
+impl From<Vec<Palette>> for Palettes {
+    fn from(list: Vec<Palette>) -> Self {
+        let mut palettes = Self::new();
+        for c_palette in list {
+            palettes.store(c_palette);
+        }
+        palettes
+    }
+}
+
+impl From<Palettes> for Vec<Palette> {
+    fn from(palettes: Palettes) -> Self {
+        palettes.palettes
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::fixture::library;
+
+    #[test]
+    fn test_fallback() {
+        let mut patch = Patch::new();
+        let rgb = Arc::new(library::generic_rgb());
+        let a = patch.add("Par 1".to_owned(), rgb.clone(), 0, 0, 0).unwrap();
+        let b = patch.add("Par 2".to_owned(), rgb.clone(), 0, 0, 4).unwrap();
+        let dim = patch.add("Dim".to_owned(), Arc::new(library::generic_dimmer()), 0, 0, 10).unwrap();
+
+        let palette = Palette::new(1, "Red", PaletteKind::Color)
+            .with_global(&rgb, Attribute::Red, 1.)
+            .with_global(&rgb, Attribute::Green, 0.)
+            .with_value(b, Attribute::Green, 0.5)
+            // Other kinds are not stored
+            .with_value(b, Attribute::Pan, 0.5);
+        let a_values = palette.values(patch.get(a).unwrap());
+        assert_eq!(a_values.get(&Attribute::Red), Some(&1.));
+        assert_eq!(a_values.get(&Attribute::Green), Some(&0.));
+        let b_values = palette.values(patch.get(b).unwrap());
+        assert_eq!(b_values.get(&Attribute::Green), Some(&0.5));
+        assert_eq!(b_values.get(&Attribute::Pan), None);
+        assert!(palette.values(patch.get(dim).unwrap()).is_empty());
+
+        let mut palettes = Palettes::new();
+        palettes.store(palette);
+        let refs = [
+            PaletteRef { fixture: a, kind: PaletteKind::Color, palette: 1 },
+            PaletteRef { fixture: dim, kind: PaletteKind::Color, palette: 1 },
+            PaletteRef { fixture: b, kind: PaletteKind::Color, palette: 7 },
+        ];
+        let values = palettes.resolve(&refs, &patch);
+        assert_eq!(values.len(), 2);
+        assert_eq!(values.get(&CueTarget::Attribute(a, Attribute::Red)), Some(&1.));
+    }
+
+    #[test]
+    fn test_record() {
+        let mut patch = Patch::new();
+        let rgb = Arc::new(library::generic_rgb());
+        let a = patch.add("Par 1".to_owned(), rgb.clone(), 0, 0, 0).unwrap();
+        let b = patch.add("Par 2".to_owned(), rgb, 0, 0, 4).unwrap();
+
+        let mut prog = Programmer::new();
+        let mut palettes = Palettes::new();
+        assert_eq!(palettes.record(PaletteKind::Color, &prog), None);
+
+        prog.set(a, Attribute::Intensity, 1.);
+        prog.set(a, Attribute::Blue, 0.8);
+        assert_eq!(palettes.record(PaletteKind::Color, &prog), Some(1));
+        let palette = palettes.get(1).unwrap();
+        assert_eq!(palette.name, "Color 1");
+        assert_eq!(palette.values(patch.get(a).unwrap()).len(), 1);
+        assert!(palette.values(patch.get(b).unwrap()).is_empty());
+
+        let mut global = Palette::new(2, "Blue", PaletteKind::Color);
+        global.record_global(&prog, &patch);
+        assert_eq!(global.values(patch.get(b).unwrap()).get(&Attribute::Blue), Some(&0.8));
+
+        palettes.store(global);
+        let json = serde_json::to_string(&palettes).unwrap();
+        assert_eq!(serde_json::from_str::<Palettes>(&json).unwrap(), palettes);
+        assert_eq!(palettes.of_kind(PaletteKind::Color).count(), 2);
+    }
+}
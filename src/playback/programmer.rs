@@ -0,0 +1,261 @@
+use std::collections::{
+    BTreeMap,
+    BTreeSet,
+};
+
+use crate::{
+    dmx::DMX,
+    fixture::{
+        color::Rgb,
+        Attribute,
+        FixtureID,
+        Patch,
+        Selection,
+    },
+};
+
+use super::{
+    Palette,
+    PaletteID,
+    PaletteKind,
+    PaletteRef,
+};
+
+/// Values set by the operator on selected fixtures.
+/// Touched attributes override whatever playback outputs until they are released or the programmer is cleared.
+/// The values are written as channel overrides, so playback keeps running underneath.
+#[derive(Debug, Clone, Default)]
+pub struct Programmer {
+    selection: Selection,
+    values: BTreeMap<FixtureID, BTreeMap<Attribute, f32>>,
+    /// Palettes the touched values were taken from, until they are changed by hand
+    palettes: BTreeMap<(FixtureID, PaletteKind), PaletteID>,
+    /// Channels overridden by the last `apply` as (universe, channel)
+    applied: BTreeSet<(usize, usize)>,
+}
+
+impl Programmer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn selection(&self) -> &Selection {
+        &self.selection
+    }
+
+    pub fn selection_mut(&mut self) -> &mut Selection {
+        &mut self.selection
+    }
+
+    /// Touch an attribute of a fixture
+    pub fn set(&mut self, id: FixtureID, attribute: Attribute, value: f32) {
+        self.values.entry(id).or_default().insert(attribute, value.max(0.).min(1.));
+        self.palettes.remove(&(id, PaletteKind::of(attribute)));
+    }
+
+    /// Touch the attributes of the palette on every selected fixture it has values for
+    pub fn set_selected_palette(&mut self, patch: &Patch, palette: &Palette) {
+        for c_fixture in self.selection.ids().to_vec().iter().filter_map(|id| patch.get(*id)) {
+            let values = palette.values(c_fixture);
+            if values.is_empty() {
+                continue;
+            }
+            for (c_attr, c_value) in values {
+                self.set(c_fixture.get_id(), c_attr, c_value);
+            }
+            self.palettes.insert((c_fixture.get_id(), palette.kind), palette.id);
+        }
+    }
+
+    /// Palette the values of a kind were taken from
+    pub fn palette(&self, id: FixtureID, kind: PaletteKind) -> Option<PaletteID> {
+        self.palettes.get(&(id, kind)).cloned()
+    }
+
+    /// All palettes in use, e.g. to record them into a cue
+    pub fn palette_refs(&self) -> Vec<PaletteRef> {
+        self.palettes.iter()
+            .map(|((fixture, kind), palette)| PaletteRef { fixture: *fixture, kind: *kind, palette: *palette })
+            .collect()
+    }
+
+    /// Touch an attribute on every selected fixture that has it
+    pub fn set_selected(&mut self, patch: &Patch, attribute: Attribute, value: f32) {
+        let ids: Vec<FixtureID> = self.selection.ids().iter()
+            .filter(|id| patch.get(**id).map(|f| f.has_attribute(attribute)).unwrap_or(false))
+            .cloned()
+            .collect();
+        for c_id in ids {
+            self.set(c_id, attribute, value);
+        }
+    }
+
+    /// Touch the colour attributes of the selected fixtures, mixed for each fixture's emitters
+    pub fn set_selected_color(&mut self, patch: &Patch, rgb: Rgb) {
+        for c_fixture in self.selection.ids().to_vec().iter().filter_map(|id| patch.get(*id)) {
+            for (c_attr, c_value) in c_fixture.color_values(rgb) {
+                self.set(c_fixture.get_id(), c_attr, c_value);
+            }
+        }
+    }
+
+    /// Touch pan and tilt of the selected fixtures, given in degrees from the center position
+    pub fn set_selected_position(&mut self, patch: &Patch, pan: f32, tilt: f32) {
+        for c_fixture in self.selection.ids().to_vec().iter().filter_map(|id| patch.get(*id)) {
+            for (c_attr, c_value) in c_fixture.position_values(pan, tilt) {
+                self.set(c_fixture.get_id(), c_attr, c_value);
+            }
+        }
+    }
+
+    pub fn get(&self, id: FixtureID, attribute: Attribute) -> Option<f32> {
+        self.values.get(&id).and_then(|a| a.get(&attribute)).cloned()
+    }
+
+    pub fn is_touched(&self, id: FixtureID, attribute: Attribute) -> bool {
+        self.get(id, attribute).is_some()
+    }
+
+    /// Hand an attribute back to playback
+    pub fn release(&mut self, id: FixtureID, attribute: Attribute) {
+        if let Some(attributes) = self.values.get_mut(&id) {
+            attributes.remove(&attribute);
+            if attributes.is_empty() {
+                self.values.remove(&id);
+            }
+        }
+        self.palettes.remove(&(id, PaletteKind::of(attribute)));
+    }
+
+    /// Release all attributes of the selected fixtures
+    pub fn release_selected(&mut self) {
+        for c_id in self.selection.ids() {
+            self.values.remove(c_id);
+        }
+        let selection = &self.selection;
+        self.palettes.retain(|(id, _), _| !selection.contains(*id));
+    }
+
+    /// Release everything and clear the selection
+    pub fn clear(&mut self) {
+        self.values.clear();
+        self.palettes.clear();
+        self.selection.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// All touched values ordered by fixture and attribute, e.g. to record them into a cue
+    pub fn touched(&self) -> Vec<(FixtureID, Attribute, f32)> {
+        self.values.iter()
+            .flat_map(|(id, attributes)| attributes.iter().map(move |(a, v)| (*id, *a, *v)))
+            .collect()
+    }
+
+    /// Write the touched values as overrides and release channels that are no longer touched.
+    /// Called every frame after playback has written its values.
+    pub fn apply(&mut self, patch: &Patch, dmx: &mut DMX) {
+        let mut applied = BTreeSet::new();
+        for (c_id, c_attr, c_value) in self.touched() {
+            let fixture = match patch.get(c_id) {
+                Some(f) => f,
+                None => continue,
+            };
+            let universe_id = fixture.get_universe();
+            if let Some(universe) = dmx.get_universe(universe_id) {
+                for (c_channel, c_dmx) in fixture.channel_values(c_attr, c_value) {
+                    universe.set_override_channel(c_channel, c_dmx as u32);
+                    applied.insert((universe_id, c_channel));
+                }
+            }
+        }
+
+        for (c_universe, c_channel) in self.applied.difference(&applied) {
+            if let Some(universe) = dmx.get_universe(*c_universe) {
+                universe.revert_override_channel(*c_channel);
+            }
+        }
+        self.applied = applied;
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::{
+        dmx::DMXUniverse,
+        fixture::library,
+    };
+
+    #[test]
+    fn test_programmer_priority() {
+        let mut dmx = DMX::new();
+        dmx.add_universe(DMXUniverse::new(0));
+        let mut patch = Patch::new();
+        let dimmer = Arc::new(library::generic_dimmer());
+        let a = patch.add("Dim 1".to_owned(), dimmer.clone(), 0, 0, 0).unwrap();
+        let b = patch.add("Dim 2".to_owned(), dimmer, 0, 0, 1).unwrap();
+        let head = patch.add("Spot".to_owned(), Arc::new(library::generic_moving_head()), 0, 0, 10).unwrap();
+        let val = |dmx: &DMX, c| dmx.get_universe_ref(0).unwrap().get_channel(c).unwrap().get_val();
+
+        let mut prog = Programmer::new();
+        prog.selection_mut().set(vec![a, head]);
+        prog.set_selected(&patch, Attribute::Intensity, 1.);
+        prog.set_selected(&patch, Attribute::Pan, 0.5);
+        assert!(prog.is_touched(a, Attribute::Intensity));
+        assert!(!prog.is_touched(b, Attribute::Intensity));
+        // Only fixtures having the attribute get touched
+        assert!(!prog.is_touched(a, Attribute::Pan));
+        assert_eq!(prog.touched().len(), 3);
+
+        // Playback writes underneath, the programmer wins
+        let universe = dmx.get_universe(0).unwrap();
+        universe.set_channel(0, 20);
+        universe.set_channel(1, 30);
+        prog.apply(&patch, &mut dmx);
+        assert_eq!(val(&dmx, 0), 255);
+        assert_eq!(val(&dmx, 1), 30);
+        assert_eq!(val(&dmx, 10), 128);
+
+        // Released attributes fall back to playback
+        prog.release(a, Attribute::Intensity);
+        prog.apply(&patch, &mut dmx);
+        assert_eq!(val(&dmx, 0), 20);
+        assert_eq!(val(&dmx, 10), 128);
+
+        prog.clear();
+        prog.apply(&patch, &mut dmx);
+        assert!(prog.is_empty() && prog.selection().is_empty());
+        assert_eq!(val(&dmx, 10), 0);
+    }
+
+    #[test]
+    fn test_palettes() {
+        let mut patch = Patch::new();
+        let rgb = Arc::new(library::generic_rgb());
+        let a = patch.add("Par 1".to_owned(), rgb.clone(), 0, 0, 0).unwrap();
+        let dim = patch.add("Dim".to_owned(), Arc::new(library::generic_dimmer()), 0, 0, 10).unwrap();
+        let palette = Palette::new(3, "Red", PaletteKind::Color)
+            .with_global(&rgb, Attribute::Red, 1.)
+            .with_global(&rgb, Attribute::Blue, 0.);
+
+        let mut prog = Programmer::new();
+        prog.selection_mut().set(vec![a, dim]);
+        prog.set_selected_palette(&patch, &palette);
+        assert_eq!(prog.get(a, Attribute::Red), Some(1.));
+        assert_eq!(prog.palette(a, PaletteKind::Color), Some(3));
+        assert_eq!(prog.palette(dim, PaletteKind::Color), None);
+        assert_eq!(prog.palette_refs().len(), 1);
+
+        // Changing a value by hand drops the reference
+        prog.set(a, Attribute::Blue, 0.5);
+        assert_eq!(prog.palette(a, PaletteKind::Color), None);
+        prog.set_selected_palette(&patch, &palette);
+        prog.release_selected();
+        assert!(prog.palette_refs().is_empty());
+    }
+}
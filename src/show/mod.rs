@@ -0,0 +1,5 @@
+mod stage;
+pub use stage::*;
+
+mod show;
+pub use show::*;
@@ -0,0 +1,349 @@
+use std::{
+    fs,
+    path::Path,
+    sync::Arc,
+};
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    dmx::DMX,
+    err::ConmxErr,
+    fixture::{
+        FixtureID,
+        FixtureType,
+        Groups,
+        Patch,
+    },
+    playback::{
+        Chase,
+        CueList,
+        Effect,
+        Executors,
+        Palettes,
+        Playables,
+    },
+    midi::MidiMap,
+    node::GraphFile,
+    timecode::EventList,
+};
+
+use super::stage::{
+    StageLayout,
+    StagePos,
+};
+
+/// Everything the user programs, saved and loaded as a whole
+#[derive(Debug, Clone, Default)]
+pub struct Show {
+    pub patch: Patch,
+    pub stage: StageLayout,
+    pub groups: Groups,
+    pub palettes: Palettes,
+    pub cue_lists: Vec<CueList>,
+    pub chases: Vec<Chase>,
+    pub effects: Vec<Effect>,
+    pub executors: Executors,
+    pub event_lists: Vec<EventList>,
+    pub midi: MidiMap,
+    /// Node graph as last saved, the editor and the evaluator work on a `Graph` built from it
+    pub nodes: GraphFile,
+}
+
+/// A patched fixture as written to a show file.
+/// The type is an index into the fixture types of the file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PatchEntry {
+    id: FixtureID,
+    name: String,
+    fixture_type: usize,
+    mode: usize,
+    universe: usize,
+    address: usize,
+    #[serde(default)]
+    invert_pan: bool,
+    #[serde(default)]
+    invert_tilt: bool,
+}
+
+/// On disk representation of a show
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ShowFile {
+    fixture_types: Vec<FixtureType>,
+    patch: Vec<PatchEntry>,
+    #[serde(default)]
+    stage: StageLayout,
+    #[serde(default)]
+    groups: Groups,
+    #[serde(default)]
+    palettes: Palettes,
+    #[serde(default)]
+    cue_lists: Vec<CueList>,
+    #[serde(default)]
+    chases: Vec<Chase>,
+    #[serde(default)]
+    effects: Vec<Effect>,
+    #[serde(default = "default_executors")]
+    executors: Executors,
+    #[serde(default)]
+    event_lists: Vec<EventList>,
+    #[serde(default)]
+    midi: MidiMap,
+    #[serde(default)]
+    nodes: GraphFile,
+}
+
+fn default_executors() -> Executors {
+    Executors::new(Executors::DEFAULT_COUNT)
+}
+
+impl Show {
+    pub fn new() -> Self {
+        Self {
+            patch: Patch::new(),
+            executors: default_executors(),
+            ..Self::default()
+        }
+    }
+
+    /// Stage position of a patched fixture, placed by default if it was never moved
+    pub fn stage_position(&self, id: FixtureID) -> Option<StagePos> {
+        let index = self.patch.fixtures().iter().position(|f| f.get_id() == id)?;
+        Some(self.stage.position_or_default(id, index))
+    }
+
+    /// Move a fixture on the stage by `offset` metres
+    pub fn move_on_stage(&mut self, id: FixtureID, offset: [f32; 2]) {
+        if let Some([x, y]) = self.stage_position(id) {
+            self.stage.set_position(id, [x + offset[0], y + offset[1]]);
+        }
+    }
+
+    /// Content the executors can play
+    pub fn playables(&self) -> Playables {
+        Self::content(&self.cue_lists, &self.chases, &self.effects, &self.palettes, &self.patch)
+    }
+
+    /// The executors together with the content they play, borrowed separately so both can be used at once
+    pub fn playback(&mut self) -> (&mut Executors, Playables) {
+        let Show { executors, cue_lists, chases, effects, palettes, patch, .. } = self;
+        (executors, Self::content(cue_lists, chases, effects, palettes, patch))
+    }
+
+    /// Advance all executors by `dt` seconds and write their output
+    pub fn run_playback(&mut self, dt: f32, dmx: &mut DMX) {
+        let (executors, content) = self.playback();
+        executors.update(content, dt);
+        executors.apply(dmx, content);
+    }
+
+    /// Playables made of the borrowed fields, which leaves the executors free to be borrowed mutably
+    fn content<'a>(cue_lists: &'a [CueList], chases: &'a [Chase], effects: &'a [Effect], palettes: &'a Palettes, patch: &'a Patch) -> Playables<'a> {
+        Playables {
+            cue_lists,
+            chases,
+            effects,
+            palettes,
+            patch,
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, ConmxErr> {
+        let mut fixture_types: Vec<FixtureType> = Vec::new();
+        let mut patch = Vec::new();
+        for c_fixture in self.patch.fixtures() {
+            let type_idx = match fixture_types.iter().position(|t| t == c_fixture.get_type()) {
+                Some(i) => i,
+                None => {
+                    fixture_types.push(c_fixture.get_type().clone());
+                    fixture_types.len() - 1
+                }
+            };
+            let (invert_pan, invert_tilt) = c_fixture.get_inverted();
+            patch.push(PatchEntry {
+                id: c_fixture.get_id(),
+                name: c_fixture.get_name().to_owned(),
+                fixture_type: type_idx,
+                mode: c_fixture.get_mode_index(),
+                universe: c_fixture.get_universe(),
+                address: c_fixture.get_address(),
+                invert_pan,
+                invert_tilt,
+            });
+        }
+
+        let file = ShowFile {
+            fixture_types,
+            patch,
+            stage: self.stage.clone(),
+            groups: self.groups.clone(),
+            palettes: self.palettes.clone(),
+            cue_lists: self.cue_lists.clone(),
+            chases: self.chases.clone(),
+            effects: self.effects.clone(),
+            executors: self.executors.clone(),
+            event_lists: self.event_lists.clone(),
+            midi: self.midi.clone(),
+            nodes: self.nodes.clone(),
+        };
+        serde_json::to_string_pretty(&file)
+            .map_err(|e| ConmxErr::Show(format!("Could not serialize show ({})", e)))
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, ConmxErr> {
+        let file: ShowFile = serde_json::from_str(json)
+            .map_err(|e| ConmxErr::Show(format!("Invalid show file ({})", e)))?;
+
+        let types: Vec<Arc<FixtureType>> = file.fixture_types.into_iter().map(Arc::new).collect();
+        let mut patch = Patch::new();
+        for c_entry in file.patch {
+            let fixture_type = types.get(c_entry.fixture_type)
+                .ok_or_else(|| ConmxErr::Show(format!("Fixture {} uses unknown type {}", c_entry.id, c_entry.fixture_type)))?;
+            let id = patch.add_with_id(c_entry.id, c_entry.name, Arc::clone(fixture_type), c_entry.mode, c_entry.universe, c_entry.address)?;
+            if let Some(fixture) = patch.get_mut(id) {
+                fixture.set_inverted(c_entry.invert_pan, c_entry.invert_tilt);
+            }
+        }
+
+        Ok(Self {
+            patch,
+            stage: file.stage,
+            groups: file.groups,
+            palettes: file.palettes,
+            cue_lists: file.cue_lists,
+            chases: file.chases,
+            effects: file.effects,
+            executors: file.executors,
+            event_lists: file.event_lists,
+            midi: file.midi,
+            nodes: file.nodes,
+        })
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ConmxErr> {
+        let json = fs::read_to_string(path.as_ref())
+            .map_err(|e| ConmxErr::Show(format!("Could not read {} ({})", path.as_ref().display(), e)))?;
+        Self::from_json(&json)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), ConmxErr> {
+        fs::write(path.as_ref(), self.to_json()?)
+            .map_err(|e| ConmxErr::Show(format!("Could not write {} ({})", path.as_ref().display(), e)))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fixture::{
+            library,
+            Attribute,
+            Group,
+        },
+        playback::{
+            ChaseStep,
+            Cue,
+            CueTarget,
+            EffectAttribute,
+            ExecutorContent,
+            Palette,
+            PaletteKind,
+            Trigger,
+        },
+        midi::{
+            CcMode,
+            MidiControl,
+            MidiMapping,
+            MidiTarget,
+        },
+        node::NodeEntry,
+        timecode::{
+            Timecode,
+            TimecodeAction,
+        },
+    };
+
+    #[test]
+    fn test_roundtrip() {
+        let mut show = Show::new();
+        let rgb = Arc::new(library::generic_rgb());
+        show.patch.add("Par 1".to_owned(), rgb.clone(), 1, 0, 0).unwrap();
+        show.patch.add("Par 2".to_owned(), rgb, 1, 0, 4).unwrap();
+        show.patch.add_with_id(10, "Spot".to_owned(), Arc::new(library::generic_moving_head()), 0, 1, 100).unwrap();
+        show.stage.set_position(10, [3., 4.5]);
+        show.patch.get_mut(10).unwrap().set_inverted(true, false);
+        show.groups.store(Group::new(2, "All").with_fixtures(&[10, 1, 2]));
+        show.palettes.store(Palette::new(1, "Wide", PaletteKind::Beam).with_value(10, Attribute::Zoom, 0.8));
+        show.chases.push(Chase::new("Run").with_step(ChaseStep::new(0.5).with_value(CueTarget::Channel(0, 3), 1.)));
+        show.effects.push(Effect::new("Wave").with_fixtures(&[1, 2]).with_attribute(EffectAttribute::new(Attribute::Intensity, 0., 1.)));
+        show.cue_lists.push(CueList::new("Main").with_cue(
+            Cue::new("1")
+                .with_value(CueTarget::Attribute(10, Attribute::Pan), 0.5)
+                .with_value(CueTarget::Channel(1, 0), 1.)
+                .with_palette(10, PaletteKind::Beam, 1)
+                .with_trigger(Trigger::Wait(2.))
+        ));
+        show.midi.add(MidiMapping::new(MidiControl::Control { channel: 0, controller: 7 }, MidiTarget::Fader(0)).with_mode(CcMode::Relative));
+        show.event_lists.push(EventList::new("Song").with_event(Timecode::new(0, 1, 0, 0), TimecodeAction::Go(0)));
+        show.nodes.nodes.push(NodeEntry {
+            template: "Constant".to_owned(),
+            position: [1., 2.],
+            params: Default::default(),
+            script: None,
+        });
+
+        let json = show.to_json().unwrap();
+        let loaded = Show::from_json(&json).unwrap();
+
+        assert_eq!(loaded.patch.fixtures().len(), 3);
+        let spot = loaded.patch.get(10).unwrap();
+        assert_eq!(spot.get_name(), "Spot");
+        assert_eq!(spot.address_name(), "1.101");
+        assert_eq!(spot.get_type().name, "Moving Head");
+        assert_eq!(spot.get_inverted(), (true, false));
+        assert_eq!(loaded.patch.get(2).unwrap().get_mode().name, "4 channel");
+        assert_eq!(loaded.stage, show.stage);
+        assert_eq!(loaded.groups.get(2).unwrap().ids(), &[10, 1, 2]);
+        assert_eq!(loaded.palettes, show.palettes);
+        assert_eq!(loaded.cue_lists, show.cue_lists);
+        assert_eq!(loaded.chases, show.chases);
+        assert_eq!(loaded.effects, show.effects);
+        assert_eq!(loaded.event_lists, show.event_lists);
+        assert_eq!(loaded.midi, show.midi);
+        assert_eq!(loaded.nodes, show.nodes);
+        assert_eq!(loaded.executors.len(), Executors::DEFAULT_COUNT);
+        assert_eq!(loaded.executors.get(0).unwrap().content, ExecutorContent::CueList(0));
+
+        // Fixtures added after loading do not collide with loaded ids
+        let mut loaded = loaded;
+        let id = loaded.patch.add("Par 3".to_owned(), Arc::new(library::generic_rgb()), 0, 0, 200).unwrap();
+        assert_eq!(id, 11);
+
+        assert!(Show::from_json("{ \"patch\": 5 }").is_err());
+    }
+
+    #[test]
+    fn test_move_on_stage() {
+        let mut show = Show::new();
+        let id = show.patch.add("Par".to_owned(), Arc::new(library::generic_rgb()), 0, 0, 0).unwrap();
+        let [x, y] = show.stage_position(id).unwrap();
+
+        show.move_on_stage(id, [1., -0.5]);
+        assert_eq!(show.stage.get_position(id), Some([x + 1., y - 0.5]));
+        assert!(show.stage_position(id + 1).is_none());
+    }
+
+    #[test]
+    fn test_default_positions() {
+        let stage = StageLayout::default();
+        let a = stage.position_or_default(1, 0);
+        let b = stage.position_or_default(2, 1);
+        assert!(a != b);
+        assert!(a[0] >= 0. && a[0] <= stage.width);
+    }
+}
@@ -0,0 +1,58 @@
+use std::collections::BTreeMap;
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::fixture::FixtureID;
+
+/// Position of a fixture on the stage in metres, x from stage left and y from the back wall towards the audience
+pub type StagePos = [f32; 2];
+
+/// Placement of fixtures on the stage plot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StageLayout {
+    pub width: f32,
+    pub depth: f32,
+    positions: BTreeMap<FixtureID, StagePos>,
+}
+
+impl Default for StageLayout {
+    fn default() -> Self {
+        Self {
+            width: 12.,
+            depth: 8.,
+            positions: BTreeMap::new(),
+        }
+    }
+}
+
+impl StageLayout {
+    /// Distance between fixtures that were not placed yet
+    const DEFAULT_SPACING: f32 = 1.;
+
+    pub fn get_position(&self, id: FixtureID) -> Option<StagePos> {
+        self.positions.get(&id).cloned()
+    }
+
+    /// Position of a fixture, falling back to a row along the front edge for fixtures never placed.
+    /// `index` is the position of the fixture in the patch.
+    pub fn position_or_default(&self, id: FixtureID, index: usize) -> StagePos {
+        self.get_position(id).unwrap_or_else(|| {
+            let per_row = ((self.width / Self::DEFAULT_SPACING) as usize).max(1);
+            [
+                Self::DEFAULT_SPACING / 2. + (index % per_row) as f32 * Self::DEFAULT_SPACING,
+                self.depth - Self::DEFAULT_SPACING / 2. - (index / per_row) as f32 * Self::DEFAULT_SPACING,
+            ]
+        })
+    }
+
+    pub fn set_position(&mut self, id: FixtureID, pos: StagePos) {
+        self.positions.insert(id, pos);
+    }
+
+    pub fn remove(&mut self, id: FixtureID) {
+        self.positions.remove(&id);
+    }
+}
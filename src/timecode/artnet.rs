@@ -0,0 +1,128 @@
+use std::net::{
+    SocketAddr,
+    ToSocketAddrs,
+    UdpSocket,
+};
+
+use crate::err::ConmxErr;
+
+use super::timecode::{
+    FrameRate,
+    Timecode,
+};
+
+const ARTNET_ID: &[u8; 8] = b"Art-Net\0";
+const OP_TIMECODE: u16 = 0x9700;
+const PROTOCOL_VERSION: u16 = 14;
+const TIMECODE_LEN: usize = 19;
+
+/// Read an ArtTimeCode packet, any other packet gives `None`
+pub fn parse_art_timecode(packet: &[u8]) -> Option<(Timecode, FrameRate)> {
+    if packet.len() < TIMECODE_LEN || &packet[..8] != ARTNET_ID {
+        return None;
+    }
+    if u16::from_le_bytes([packet[8], packet[9]]) != OP_TIMECODE {
+        return None;
+    }
+    let rate = FrameRate::from_artnet(packet[18])?;
+    let timecode = Timecode::new(packet[17], packet[16], packet[15], packet[14]);
+    if timecode.hours > 23 || timecode.minutes > 59 || timecode.seconds > 59 || timecode.frames >= rate.frames() {
+        return None;
+    }
+    Some((timecode, rate))
+}
+
+/// Build an ArtTimeCode packet, e.g. to send the internal clock to other consoles
+pub fn art_timecode_packet(timecode: Timecode, rate: FrameRate) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(TIMECODE_LEN);
+    packet.extend_from_slice(ARTNET_ID);
+    packet.extend_from_slice(&OP_TIMECODE.to_le_bytes());
+    packet.extend_from_slice(&PROTOCOL_VERSION.to_be_bytes());
+    // Filler and stream id
+    packet.extend_from_slice(&[0, 0]);
+    packet.extend_from_slice(&[timecode.frames, timecode.seconds, timecode.minutes, timecode.hours, rate.to_artnet()]);
+    packet
+}
+
+/// Receives ArtTimeCode on a non blocking socket, polled once per frame
+#[derive(Debug)]
+pub struct ArtTimecodeReceiver {
+    socket: UdpSocket,
+}
+
+impl ArtTimecodeReceiver {
+    /// Art-Net port every node sends to
+    pub const PORT: u16 = 6454;
+
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> Result<Self, ConmxErr> {
+        let socket = UdpSocket::bind(addr)
+            .map_err(|e| ConmxErr::Net(format!("Could not bind timecode socket ({})", e)))?;
+        socket.set_nonblocking(true)
+            .map_err(|e| ConmxErr::Net(format!("Could not configure timecode socket ({})", e)))?;
+        Ok(Self { socket })
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr, ConmxErr> {
+        self.socket.local_addr()
+            .map_err(|e| ConmxErr::Net(format!("Timecode socket has no address ({})", e)))
+    }
+
+    /// Latest frame of all packets received since the last call
+    pub fn poll(&self) -> Option<(Timecode, FrameRate)> {
+        let mut buf = [0u8; 512];
+        let mut latest = None;
+        while let Ok((len, _)) = self.socket.recv_from(&mut buf) {
+            if let Some(frame) = parse_art_timecode(&buf[..len]) {
+                latest = Some(frame);
+            }
+        }
+        latest
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        thread,
+        time::Duration,
+    };
+
+    #[test]
+    fn test_packet() {
+        let tc = Timecode::new(10, 20, 30, 24);
+        let packet = art_timecode_packet(tc, FrameRate::Smpte30);
+        assert_eq!(packet.len(), TIMECODE_LEN);
+        assert_eq!(parse_art_timecode(&packet), Some((tc, FrameRate::Smpte30)));
+        // 24 frames do not exist at 24 fps
+        assert_eq!(parse_art_timecode(&art_timecode_packet(tc, FrameRate::Film24)), None);
+
+        let mut other = packet.clone();
+        other[9] = 0x50;
+        assert_eq!(parse_art_timecode(&other), None);
+        assert_eq!(parse_art_timecode(&packet[..10]), None);
+    }
+
+    #[test]
+    fn test_receive() {
+        let receiver = ArtTimecodeReceiver::bind("127.0.0.1:0").unwrap();
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let target = receiver.local_addr().unwrap();
+        assert_eq!(receiver.poll(), None);
+
+        sender.send_to(&art_timecode_packet(Timecode::new(0, 0, 1, 0), FrameRate::Ebu25), target).unwrap();
+        sender.send_to(b"not art-net", target).unwrap();
+        sender.send_to(&art_timecode_packet(Timecode::new(0, 0, 1, 1), FrameRate::Ebu25), target).unwrap();
+
+        let mut received = None;
+        for _ in 0..50 {
+            thread::sleep(Duration::from_millis(10));
+            received = receiver.poll().or(received);
+            if received == Some((Timecode::new(0, 0, 1, 1), FrameRate::Ebu25)) {
+                break;
+            }
+        }
+        assert_eq!(received, Some((Timecode::new(0, 0, 1, 1), FrameRate::Ebu25)));
+    }
+}
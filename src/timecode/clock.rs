@@ -0,0 +1,180 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use super::timecode::{
+    FrameRate,
+    Timecode,
+};
+
+/// Where the clock takes its time from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ClockSource {
+    /// Runs on its own, started and located by hand
+    Internal,
+    /// Follows received timecode, e.g. ArtTimeCode packets
+    External,
+}
+
+/// Show time in seconds, following an internal transport or received timecode.
+/// Between received frames and while the signal drops out the clock keeps counting on its own,
+/// it stops when nothing was received for longer than the freewheel time.
+#[derive(Debug, Clone)]
+pub struct TimecodeClock {
+    source: ClockSource,
+    rate: FrameRate,
+    time: f64,
+    running: bool,
+    /// Seconds since the last received frame
+    since_received: f32,
+    freewheel: f32,
+}
+
+impl TimecodeClock {
+    /// Default time to keep running without received timecode
+    pub const FREEWHEEL: f32 = 2.;
+
+    pub fn new(source: ClockSource) -> Self {
+        Self {
+            source,
+            rate: FrameRate::default(),
+            time: 0.,
+            running: false,
+            since_received: 0.,
+            freewheel: Self::FREEWHEEL,
+        }
+    }
+
+    pub fn with_rate(mut self, rate: FrameRate) -> Self {
+        self.rate = rate;
+        self
+    }
+
+    pub fn with_freewheel(mut self, freewheel: f32) -> Self {
+        self.freewheel = freewheel.max(0.);
+        self
+    }
+
+    pub fn source(&self) -> ClockSource {
+        self.source
+    }
+
+    pub fn set_source(&mut self, source: ClockSource) {
+        self.source = source;
+        self.running = false;
+    }
+
+    pub fn rate(&self) -> FrameRate {
+        self.rate
+    }
+
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    pub fn timecode(&self) -> Timecode {
+        Timecode::from_seconds(self.time, self.rate)
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// External timecode was received within the freewheel time, but not within the last frames
+    pub fn is_freewheeling(&self) -> bool {
+        self.source == ClockSource::External && self.running && self.since_received > 2. / self.rate.frames() as f32
+    }
+
+    /// Start the internal transport
+    pub fn play(&mut self) {
+        if self.source == ClockSource::Internal {
+            self.running = true;
+        }
+    }
+
+    pub fn stop(&mut self) {
+        if self.source == ClockSource::Internal {
+            self.running = false;
+        }
+    }
+
+    /// Jump the internal transport to `time` seconds
+    pub fn locate(&mut self, time: f64) {
+        if self.source == ClockSource::Internal {
+            self.time = time.max(0.);
+        }
+    }
+
+    /// Take over a received frame, ignored while running on the internal clock
+    pub fn receive(&mut self, timecode: Timecode, rate: FrameRate) {
+        if self.source != ClockSource::External {
+            return;
+        }
+        self.rate = rate;
+        self.time = timecode.to_seconds(rate);
+        self.since_received = 0.;
+        self.running = true;
+    }
+
+    /// Advance by `dt` seconds
+    pub fn update(&mut self, dt: f32) {
+        if !self.running {
+            return;
+        }
+        if self.source == ClockSource::External {
+            self.since_received += dt;
+            if self.since_received > self.freewheel {
+                self.running = false;
+                return;
+            }
+        }
+        self.time = (self.time + dt as f64).rem_euclid(Timecode::DAY);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_internal() {
+        let mut clock = TimecodeClock::new(ClockSource::Internal);
+        clock.update(1.);
+        assert_eq!(clock.time(), 0.);
+        clock.play();
+        clock.update(1.5);
+        assert_eq!(clock.timecode(), Timecode::new(0, 0, 1, 12));
+        clock.locate(60.);
+        clock.stop();
+        clock.update(1.);
+        assert_eq!(clock.time(), 60.);
+
+        // Received timecode is ignored
+        clock.receive(Timecode::new(1, 0, 0, 0), FrameRate::Ebu25);
+        assert_eq!(clock.time(), 60.);
+    }
+
+    #[test]
+    fn test_freewheel() {
+        let mut clock = TimecodeClock::new(ClockSource::External).with_freewheel(1.);
+        clock.play();
+        assert!(!clock.is_running());
+
+        clock.receive(Timecode::new(0, 10, 0, 0), FrameRate::Smpte30);
+        clock.update(0.5);
+        assert!(clock.is_running() && clock.is_freewheeling());
+        assert_eq!(clock.time(), 600.5);
+        clock.update(0.25);
+        assert!(clock.is_running());
+        clock.update(0.5);
+        assert!(!clock.is_running());
+        assert_eq!(clock.time(), 600.75);
+
+        // A jump is taken over right away
+        clock.receive(Timecode::new(0, 5, 0, 0), FrameRate::Smpte30);
+        assert!(clock.is_running() && !clock.is_freewheeling());
+        assert_eq!(clock.time(), 300.);
+    }
+}
@@ -0,0 +1,268 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::playback::{
+    Executor,
+    Executors,
+    Playables,
+};
+
+use super::timecode::{
+    FrameRate,
+    Timecode,
+};
+
+/// What an event does with an executor, given by its index
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimecodeAction {
+    Go(usize),
+    /// Jump to a cue of the executor's cue list
+    Goto(usize, usize),
+    /// Start the chase or effect of the executor from the beginning
+    Start(usize),
+    Release(usize),
+}
+
+impl TimecodeAction {
+    pub fn executor(&self) -> usize {
+        match self {
+            TimecodeAction::Go(i) | TimecodeAction::Goto(i, _) | TimecodeAction::Start(i) | TimecodeAction::Release(i) => *i,
+        }
+    }
+
+    pub fn run(&self, executors: &mut Executors, content: Playables) {
+        match *self {
+            TimecodeAction::Go(i) => executors.go(i, content),
+            TimecodeAction::Goto(i, cue) => executors.goto(i, cue, content),
+            TimecodeAction::Start(i) => executors.start(i, content),
+            TimecodeAction::Release(i) => executors.release(i),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TimecodeEvent {
+    pub time: Timecode,
+    pub action: TimecodeAction,
+}
+
+/// Actions fired at timecodes, kept sorted by time
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct EventList {
+    pub name: String,
+    #[serde(default)]
+    pub rate: FrameRate,
+    events: Vec<TimecodeEvent>,
+}
+
+impl EventList {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_rate(mut self, rate: FrameRate) -> Self {
+        self.rate = rate;
+        self
+    }
+
+    pub fn with_event(mut self, time: Timecode, action: TimecodeAction) -> Self {
+        self.add(time, action);
+        self
+    }
+
+    /// Insert an event, after all events at the same time
+    pub fn add(&mut self, time: Timecode, action: TimecodeAction) {
+        let index = self.events.iter().position(|e| e.time > time).unwrap_or(self.events.len());
+        self.events.insert(index, TimecodeEvent { time, action });
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<TimecodeEvent> {
+        if index < self.events.len() {
+            Some(self.events.remove(index))
+        } else {
+            None
+        }
+    }
+
+    pub fn events(&self) -> &[TimecodeEvent] {
+        &self.events
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    fn seconds(&self, event: &TimecodeEvent) -> f64 {
+        event.time.to_seconds(self.rate)
+    }
+
+    /// Actions of the events after `from` up to and including `to` seconds
+    pub fn due(&self, from: f64, to: f64) -> Vec<TimecodeAction> {
+        self.events.iter()
+            .filter(|e| self.seconds(e) > from && self.seconds(e) <= to)
+            .map(|e| e.action)
+            .collect()
+    }
+
+    /// Actions recreating the state at `time` after a jump, one per executor.
+    /// The cue is the last GOTO plus the GOs after it, so GOs are never replayed one by one.
+    /// Executors which are `running` already are not started again,
+    /// executors of the list without any event up to `time` are released.
+    pub fn chase(&self, time: f64, running: impl Fn(usize) -> bool) -> Vec<TimecodeAction> {
+        let mut states: Vec<(usize, ChasedState)> = Vec::new();
+        for c_event in self.events.iter().filter(|e| self.seconds(e) <= time) {
+            let executor = c_event.action.executor();
+            let last = states.iter().position(|(i, _)| *i == executor);
+            let state = match (c_event.action, last.map(|i| states[i].1)) {
+                (TimecodeAction::Go(_), Some(ChasedState::Cue(cue))) => ChasedState::Cue(cue + 1),
+                // GO on a released or stopped executor starts at the first cue
+                (TimecodeAction::Go(_), _) => ChasedState::Cue(0),
+                (TimecodeAction::Goto(_, cue), _) => ChasedState::Cue(cue),
+                (TimecodeAction::Start(_), _) => ChasedState::Started,
+                (TimecodeAction::Release(_), _) => ChasedState::Released,
+            };
+            match last {
+                Some(i) => states[i].1 = state,
+                None => states.push((executor, state)),
+            }
+        }
+        for c_event in self.events.iter() {
+            let executor = c_event.action.executor();
+            if !states.iter().any(|(i, _)| *i == executor) {
+                states.push((executor, ChasedState::Released));
+            }
+        }
+
+        states.into_iter()
+            .filter_map(|(executor, state)| match state {
+                ChasedState::Cue(cue) => Some(TimecodeAction::Goto(executor, cue)),
+                ChasedState::Started if running(executor) => None,
+                ChasedState::Started => Some(TimecodeAction::Start(executor)),
+                ChasedState::Released => Some(TimecodeAction::Release(executor)),
+            })
+            .collect()
+    }
+}
+
+/// State of an executor after the events up to a time
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ChasedState {
+    Cue(usize),
+    Started,
+    Released,
+}
+
+/// Fires the events of event lists as the clock passes them.
+/// Small steps fire every event passed, larger jumps and running backwards chase the state instead,
+/// so skipping through a song does not fire every cue on the way.
+#[derive(Debug, Clone, Default)]
+pub struct EventPlayer {
+    /// Clock time of the last update
+    last: Option<f64>,
+}
+
+impl EventPlayer {
+    /// Largest step in seconds treated as normal running
+    pub const MAX_STEP: f64 = 1.;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forget the last position, the next update only takes over the time
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+
+    /// Actions due at the new clock time, `executors` tells which ones don't need to be restarted
+    pub fn update(&mut self, lists: &[EventList], time: f64, executors: &Executors) -> Vec<TimecodeAction> {
+        let last = self.last.replace(time);
+        let running = |i: usize| executors.get(i).map(Executor::is_active).unwrap_or(false);
+        match last {
+            Some(last) if time >= last && time - last <= Self::MAX_STEP => {
+                lists.iter().flat_map(|l| l.due(last, time)).collect()
+            }
+            Some(_) => lists.iter().flat_map(|l| l.chase(time, running)).collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tc(seconds: u8) -> Timecode {
+        Timecode::new(0, 0, seconds, 0)
+    }
+
+    fn test_list() -> EventList {
+        EventList::new("Song")
+            .with_event(tc(10), TimecodeAction::Go(0))
+            .with_event(tc(2), TimecodeAction::Goto(0, 0))
+            .with_event(tc(5), TimecodeAction::Start(1))
+            .with_event(tc(5), TimecodeAction::Go(0))
+            .with_event(tc(20), TimecodeAction::Release(1))
+    }
+
+    #[test]
+    fn test_list_order() {
+        let list = test_list();
+        let times: Vec<Timecode> = list.events().iter().map(|e| e.time).collect();
+        assert_eq!(times, vec![tc(2), tc(5), tc(5), tc(10), tc(20)]);
+        assert_eq!(list.events()[1].action, TimecodeAction::Start(1));
+        assert_eq!(list.due(2., 10.), vec![TimecodeAction::Start(1), TimecodeAction::Go(0), TimecodeAction::Go(0)]);
+    }
+
+    #[test]
+    fn test_player() {
+        let lists = vec![test_list()];
+        let executors = Executors::new(2);
+        let mut player = EventPlayer::new();
+        assert!(player.update(&lists, 1.5, &executors).is_empty());
+        assert_eq!(player.update(&lists, 2., &executors), vec![TimecodeAction::Goto(0, 0)]);
+        for c_time in [2., 2.9, 3.8, 4.7].iter() {
+            assert!(player.update(&lists, *c_time, &executors).is_empty());
+        }
+        assert_eq!(player.update(&lists, 5.3, &executors), vec![TimecodeAction::Start(1), TimecodeAction::Go(0)]);
+
+        // Jumping ahead chases the state instead of firing the GO at 10s
+        assert_eq!(player.update(&lists, 15., &executors), vec![TimecodeAction::Goto(0, 2), TimecodeAction::Start(1)]);
+        assert_eq!(player.update(&lists, 25., &executors), vec![TimecodeAction::Goto(0, 2), TimecodeAction::Release(1)]);
+        // Running backwards as well, executors started later are released
+        assert_eq!(player.update(&lists, 3., &executors), vec![TimecodeAction::Goto(0, 0), TimecodeAction::Release(1)]);
+        assert_eq!(player.update(&lists, 0., &executors), vec![TimecodeAction::Release(0), TimecodeAction::Release(1)]);
+    }
+
+    #[test]
+    fn test_chase() {
+        let list = test_list()
+            .with_event(tc(12), TimecodeAction::Go(0))
+            .with_event(tc(14), TimecodeAction::Go(0))
+            .with_event(tc(1), TimecodeAction::Go(2))
+            .with_event(tc(3), TimecodeAction::Go(2))
+            .with_event(tc(22), TimecodeAction::Go(1));
+        let stopped = |_: usize| false;
+
+        // The GOTO at 2s and the GOs at 5, 10 and 12s lead to the fourth cue
+        assert_eq!(list.chase(12.5, stopped), vec![TimecodeAction::Goto(2, 1), TimecodeAction::Goto(0, 3), TimecodeAction::Start(1)]);
+        assert_eq!(list.chase(14., stopped)[1], TimecodeAction::Goto(0, 4));
+        // Running chases keep running
+        assert_eq!(list.chase(12.5, |i| i == 1), vec![TimecodeAction::Goto(2, 1), TimecodeAction::Goto(0, 3)]);
+        // A GO after the release starts from the first cue
+        assert_eq!(list.chase(30., stopped)[2], TimecodeAction::Goto(1, 0));
+        // Executors without an event yet are off, even if they are running
+        assert_eq!(list.chase(4., |_| true), vec![TimecodeAction::Goto(2, 1), TimecodeAction::Goto(0, 0), TimecodeAction::Release(1)]);
+        assert_eq!(list.chase(0.5, |_| true), vec![TimecodeAction::Release(2), TimecodeAction::Release(0), TimecodeAction::Release(1)]);
+    }
+}
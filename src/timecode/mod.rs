@@ -0,0 +1,11 @@
+mod timecode;
+pub use timecode::*;
+
+mod clock;
+pub use clock::*;
+
+mod events;
+pub use events::*;
+
+mod artnet;
+pub use artnet::*;
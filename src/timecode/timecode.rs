@@ -0,0 +1,140 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::err::ConmxErr;
+
+/// Frame rates of SMPTE timecode, numbered like the type field of ArtTimeCode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FrameRate {
+    Film24,
+    Ebu25,
+    /// 29.97 drop frame, frames are counted like 30 fps
+    DropFrame30,
+    Smpte30,
+}
+
+impl Default for FrameRate {
+    fn default() -> Self { FrameRate::Ebu25 }
+}
+
+impl FrameRate {
+    /// Frames counted per second of timecode
+    pub fn frames(&self) -> u8 {
+        match self {
+            FrameRate::Film24 => 24,
+            FrameRate::Ebu25 => 25,
+            FrameRate::DropFrame30 | FrameRate::Smpte30 => 30,
+        }
+    }
+
+    pub fn from_artnet(kind: u8) -> Option<Self> {
+        match kind {
+            0 => Some(FrameRate::Film24),
+            1 => Some(FrameRate::Ebu25),
+            2 => Some(FrameRate::DropFrame30),
+            3 => Some(FrameRate::Smpte30),
+            _ => None,
+        }
+    }
+
+    pub fn to_artnet(&self) -> u8 {
+        match self {
+            FrameRate::Film24 => 0,
+            FrameRate::Ebu25 => 1,
+            FrameRate::DropFrame30 => 2,
+            FrameRate::Smpte30 => 3,
+        }
+    }
+}
+
+/// Position as hours, minutes, seconds and frames
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Timecode {
+    pub hours: u8,
+    pub minutes: u8,
+    pub seconds: u8,
+    pub frames: u8,
+}
+
+impl Timecode {
+    /// Timecode wraps around after a day
+    pub const DAY: f64 = 24. * 3600.;
+
+    pub fn new(hours: u8, minutes: u8, seconds: u8, frames: u8) -> Self {
+        Self { hours, minutes, seconds, frames }
+    }
+
+    /// Timecode of the frame running at `time` seconds
+    pub fn from_seconds(time: f64, rate: FrameRate) -> Self {
+        let time = time.rem_euclid(Self::DAY);
+        let whole = time.floor() as u32;
+        let frames = ((time - whole as f64) * rate.frames() as f64).floor() as u8;
+        Self {
+            hours: (whole / 3600) as u8,
+            minutes: (whole / 60 % 60) as u8,
+            seconds: (whole % 60) as u8,
+            frames: frames.min(rate.frames() - 1),
+        }
+    }
+
+    pub fn to_seconds(&self, rate: FrameRate) -> f64 {
+        self.hours as f64 * 3600.
+            + self.minutes as f64 * 60.
+            + self.seconds as f64
+            + self.frames as f64 / rate.frames() as f64
+    }
+}
+
+impl fmt::Display for Timecode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:02}:{:02}:{:02}:{:02}", self.hours, self.minutes, self.seconds, self.frames)
+    }
+}
+
+/// Parses `hh:mm:ss:ff`, the frames may be separated with `;` for drop frame
+impl FromStr for Timecode {
+    type Err = ConmxErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ConmxErr::Timecode(format!("\"{}\" is not a timecode like 01:00:00:00", s));
+        let parts = s.trim()
+            .split(|c| c == ':' || c == ';')
+            .map(|p| p.parse::<u8>().map_err(|_| invalid()))
+            .collect::<Result<Vec<u8>, ConmxErr>>()?;
+        match parts.as_slice() {
+            [h, m, s, f] if *h < 24 && *m < 60 && *s < 60 && *f < 30 => Ok(Self::new(*h, *m, *s, *f)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversion() {
+        let tc = Timecode::new(1, 2, 3, 12);
+        assert!((tc.to_seconds(FrameRate::Ebu25) - 3723.48).abs() < 1e-9);
+        assert_eq!(Timecode::from_seconds(3723.5, FrameRate::Ebu25), tc);
+        assert_eq!(Timecode::from_seconds(3723.5, FrameRate::Smpte30).frames, 15);
+        assert_eq!(Timecode::from_seconds(Timecode::DAY + 1., FrameRate::Film24), Timecode::new(0, 0, 1, 0));
+        assert!(Timecode::new(0, 0, 2, 0) > Timecode::new(0, 0, 1, 24));
+    }
+
+    #[test]
+    fn test_parse() {
+        let tc: Timecode = "01:02:03:04".parse().unwrap();
+        assert_eq!(tc, Timecode::new(1, 2, 3, 4));
+        assert_eq!(tc.to_string(), "01:02:03:04");
+        assert_eq!("00:00:10;29".parse::<Timecode>().unwrap().frames, 29);
+        assert!("1:2:3".parse::<Timecode>().is_err());
+        assert!("00:61:00:00".parse::<Timecode>().is_err());
+    }
+}
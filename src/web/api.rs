@@ -0,0 +1,288 @@
+use std::collections::BTreeMap;
+
+use serde::{
+    de::DeserializeOwned,
+    Deserialize,
+};
+use serde_json::{
+    json,
+    Value,
+};
+
+use crate::{
+    dmx::DMX,
+    fixture::{
+        Attribute,
+        Fixture,
+        Patch,
+    },
+    playback::Programmer,
+    show::Show,
+};
+
+use super::http::{
+    HttpRequest,
+    HttpResponse,
+};
+
+/// What the API reads and changes, borrowed for one request
+pub struct ApiContext<'a> {
+    pub dmx: &'a mut DMX,
+    pub show: &'a mut Show,
+    pub programmer: &'a mut Programmer,
+}
+
+#[derive(Deserialize)]
+struct ChannelBody {
+    value: u32,
+}
+
+#[derive(Deserialize)]
+struct LevelBody {
+    level: f32,
+}
+
+fn not_found(what: &str) -> HttpResponse {
+    HttpResponse::error(404, &format!("{} not found", what))
+}
+
+fn body<T: DeserializeOwned>(req: &HttpRequest) -> Result<T, HttpResponse> {
+    serde_json::from_slice(&req.body)
+        .map_err(|e| HttpResponse::error(400, &format!("Invalid body ({})", e)))
+}
+
+/// A number counted from 1 in the path as index
+fn index(segment: &str, len: usize, what: &str) -> Result<usize, HttpResponse> {
+    match segment.parse::<usize>() {
+        Ok(n) if n >= 1 && n <= len => Ok(n - 1),
+        _ => Err(not_found(&format!("{} {}", what, segment))),
+    }
+}
+
+fn universe_json(dmx: &DMX, id: &str) -> Result<Value, HttpResponse> {
+    let universe = id.parse::<usize>().ok()
+        .and_then(|id| dmx.get_universe_ref(id))
+        .ok_or_else(|| not_found(&format!("Universe {}", id)))?;
+    let channels: Vec<u32> = (0..crate::dmx::DMXUniverse::CHANNELS)
+        .map(|c| universe.get_channel(c).map(|c| c.get_val()).unwrap_or(0))
+        .collect();
+    Ok(json!({ "id": universe.get_id(), "channels": channels }))
+}
+
+fn fixture<'a>(patch: &'a Patch, id: &str) -> Result<&'a Fixture, HttpResponse> {
+    id.parse().ok()
+        .and_then(|id| patch.get(id))
+        .ok_or_else(|| not_found(&format!("Fixture {}", id)))
+}
+
+fn fixture_json(fixture: &Fixture, dmx: &DMX) -> Value {
+    let values: BTreeMap<Attribute, f32> = fixture.get_mode().attributes().into_iter()
+        .filter_map(|a| Some((a, fixture.get_attribute(dmx, a)?)))
+        .collect();
+    json!({
+        "id": fixture.get_id(),
+        "name": fixture.get_name(),
+        "type": format!("{} {}", fixture.get_type().manufacturer, fixture.get_type().name),
+        "mode": fixture.get_mode().name,
+        "address": fixture.address_name(),
+        "values": values,
+    })
+}
+
+/// State of all executors and the grand master, also sent on the stream when it changes
+pub fn executors_json(show: &Show) -> Value {
+    let content = show.playables();
+    let executors: Vec<Value> = show.executors.iter()
+        .enumerate()
+        .map(|(i, c_exec)| json!({
+            "number": i + 1,
+            "name": c_exec.name,
+            "level": c_exec.get_level(),
+            "active": c_exec.is_active(),
+            "status": c_exec.status(content),
+        }))
+        .collect();
+    json!({ "grandmaster": show.executors.grand_master(), "executors": executors })
+}
+
+fn route(req: &HttpRequest, ctx: &mut ApiContext) -> Result<HttpResponse, HttpResponse> {
+    let segments = req.segments();
+    let path = match segments.split_first() {
+        Some((&"api", path)) => path,
+        _ => return Err(not_found(&format!("Path {}", req.path))),
+    };
+
+    match (req.method.as_str(), path) {
+        // Preflight of browsers, answered with the CORS headers only
+        ("OPTIONS", _) => Ok(HttpResponse::no_content()),
+
+        ("GET", ["universes"]) => Ok(HttpResponse::json(&ctx.dmx.get_universe_ids())),
+        ("GET", ["universes", id]) => Ok(HttpResponse::json(&universe_json(ctx.dmx, id)?)),
+        ("PUT", ["universes", id, "channels", channel]) => {
+            let channel = index(channel, crate::dmx::DMXUniverse::CHANNELS, "Channel")?;
+            let value = body::<ChannelBody>(req)?.value.min(255);
+            let universe = id.parse::<usize>().ok()
+                .and_then(|id| ctx.dmx.get_universe(id))
+                .ok_or_else(|| not_found(&format!("Universe {}", id)))?;
+            universe.set_channel(channel, value);
+            Ok(HttpResponse::no_content())
+        }
+
+        ("GET", ["fixtures"]) => {
+            let fixtures: Vec<Value> = ctx.show.patch.fixtures().iter()
+                .map(|f| fixture_json(f, ctx.dmx))
+                .collect();
+            Ok(HttpResponse::json(&fixtures))
+        }
+        ("GET", ["fixtures", id]) => {
+            let fixture = fixture(&ctx.show.patch, id)?;
+            Ok(HttpResponse::json(&fixture_json(fixture, ctx.dmx)))
+        }
+        // Values are touched in the programmer, like set by hand
+        ("PUT", ["fixtures", id]) => {
+            let values = body::<BTreeMap<Attribute, f32>>(req)?;
+            let fixture = fixture(&ctx.show.patch, id)?;
+            if let Some(attribute) = values.keys().find(|a| !fixture.has_attribute(**a)) {
+                return Err(HttpResponse::error(400, &format!("Fixture {} has no {}", id, attribute.name())));
+            }
+            for (c_attr, c_value) in values {
+                ctx.programmer.set(fixture.get_id(), c_attr, c_value);
+            }
+            ctx.programmer.apply(&ctx.show.patch, ctx.dmx);
+            Ok(HttpResponse::json(&fixture_json(fixture, ctx.dmx)))
+        }
+        ("DELETE", ["fixtures", id]) => {
+            let fixture = fixture(&ctx.show.patch, id)?;
+            for c_attr in fixture.get_mode().attributes() {
+                ctx.programmer.release(fixture.get_id(), c_attr);
+            }
+            ctx.programmer.apply(&ctx.show.patch, ctx.dmx);
+            Ok(HttpResponse::no_content())
+        }
+
+        ("GET", ["executors"]) => Ok(HttpResponse::json(&executors_json(ctx.show))),
+        ("PUT", ["executors", number]) => {
+            let index = index(number, ctx.show.executors.len(), "Executor")?;
+            let level = body::<LevelBody>(req)?.level;
+            let (executors, content) = ctx.show.playback();
+            executors.set_level(index, level, content);
+            Ok(HttpResponse::no_content())
+        }
+        ("POST", ["executors", number, action]) => {
+            let index = index(number, ctx.show.executors.len(), "Executor")?;
+            let (executors, content) = ctx.show.playback();
+            match *action {
+                "go" => executors.go(index, content),
+                "back" => executors.back(index, content),
+                "release" => executors.release(index),
+                _ => return Err(not_found(&format!("Action {}", action))),
+            }
+            Ok(HttpResponse::no_content())
+        }
+        ("POST", ["executors", number, "goto", cue]) => {
+            let index = index(number, ctx.show.executors.len(), "Executor")?;
+            let cue = cue.parse::<usize>().ok()
+                .filter(|c| *c >= 1)
+                .ok_or_else(|| not_found(&format!("Cue {}", cue)))?;
+            let (executors, content) = ctx.show.playback();
+            executors.goto(index, cue - 1, content);
+            Ok(HttpResponse::no_content())
+        }
+        ("PUT", ["grandmaster"]) => {
+            let level = body::<LevelBody>(req)?.level;
+            ctx.show.executors.set_grand_master(level);
+            Ok(HttpResponse::no_content())
+        }
+
+        ("GET", ["cuelists"]) => {
+            let lists: Vec<Value> = ctx.show.cue_lists.iter()
+                .enumerate()
+                .map(|(i, c_list)| json!({ "number": i + 1, "name": c_list.name, "cues": c_list.len() }))
+                .collect();
+            Ok(HttpResponse::json(&lists))
+        }
+        ("GET", ["cuelists", number]) => {
+            let index = index(number, ctx.show.cue_lists.len(), "Cue list")?;
+            Ok(HttpResponse::json(&ctx.show.cue_lists[index]))
+        }
+
+        (_, ["universes", ..]) | (_, ["fixtures", ..]) | (_, ["executors", ..]) | (_, ["grandmaster"]) | (_, ["cuelists", ..]) =>
+            Err(HttpResponse::error(405, &format!("{} is not allowed on {}", req.method, req.path))),
+        _ => Err(not_found(&format!("Path {}", req.path))),
+    }
+}
+
+/// Answer a request to the JSON API below `/api`, authentication is up to the caller
+pub fn handle(req: &HttpRequest, ctx: &mut ApiContext) -> HttpResponse {
+    route(req, ctx).unwrap_or_else(|e| e)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::{
+        dmx::DMXUniverse,
+        fixture::library,
+        playback::{
+            Cue,
+            CueList,
+            CueTarget,
+        },
+    };
+
+    fn request(method: &str, path: &str, body: &str) -> HttpRequest {
+        HttpRequest {
+            method: method.to_owned(),
+            path: path.to_owned(),
+            body: body.as_bytes().to_vec(),
+            ..HttpRequest::default()
+        }
+    }
+
+    fn json_of(response: HttpResponse) -> Value {
+        assert_eq!(response.status, 200);
+        serde_json::from_slice(&response.body).unwrap()
+    }
+
+    #[test]
+    fn test_routes() {
+        let mut dmx = DMX::new();
+        dmx.add_universe(DMXUniverse::new(0));
+        let mut show = Show::new();
+        let rgb = Arc::new(library::generic_rgb());
+        let id = show.patch.add("Par 1".to_owned(), rgb, 0, 0, 10).unwrap();
+        show.cue_lists.push(CueList::new("Main").with_cue(Cue::new("Warm").with_value(CueTarget::Attribute(id, Attribute::Red), 1.)));
+        let mut programmer = Programmer::new();
+        let mut ctx = ApiContext { dmx: &mut dmx, show: &mut show, programmer: &mut programmer };
+
+        assert_eq!(json_of(handle(&request("GET", "/api/universes", ""), &mut ctx)), json!([0]));
+        assert_eq!(handle(&request("PUT", "/api/universes/0/channels/1", "{\"value\":300}"), &mut ctx).status, 204);
+        assert_eq!(json_of(handle(&request("GET", "/api/universes/0", ""), &mut ctx))["channels"][0], json!(255));
+        assert_eq!(handle(&request("PUT", "/api/universes/0/channels/513", "{\"value\":1}"), &mut ctx).status, 404);
+        assert_eq!(handle(&request("PUT", "/api/universes/0/channels/1", "{}"), &mut ctx).status, 400);
+        assert_eq!(handle(&request("GET", "/api/universes/7", ""), &mut ctx).status, 404);
+
+        let fixture = json_of(handle(&request("PUT", &format!("/api/fixtures/{}", id), "{\"Red\":1.0}"), &mut ctx));
+        assert_eq!(fixture["address"], json!("0.011"));
+        assert_eq!(fixture["values"]["Red"], json!(1.0));
+        assert_eq!(ctx.programmer.get(id, Attribute::Red), Some(1.));
+        assert_eq!(handle(&request("PUT", &format!("/api/fixtures/{}", id), "{\"Pan\":1.0}"), &mut ctx).status, 400);
+        assert_eq!(handle(&request("DELETE", &format!("/api/fixtures/{}", id), ""), &mut ctx).status, 204);
+        assert!(ctx.programmer.is_empty());
+        assert_eq!(json_of(handle(&request("GET", "/api/fixtures", ""), &mut ctx))[0]["values"]["Red"], json!(0.0));
+
+        assert_eq!(handle(&request("POST", "/api/executors/1/go", ""), &mut ctx).status, 204);
+        assert_eq!(handle(&request("PUT", "/api/executors/2", "{\"level\":0.5}"), &mut ctx).status, 204);
+        let executors = json_of(handle(&request("GET", "/api/executors", ""), &mut ctx));
+        assert_eq!(executors["executors"][0]["status"], json!("1/1"));
+        assert_eq!(executors["executors"][1]["level"], json!(0.5));
+        assert_eq!(handle(&request("POST", "/api/executors/1/jump", ""), &mut ctx).status, 404);
+        assert_eq!(handle(&request("POST", "/api/executors/99/go", ""), &mut ctx).status, 404);
+
+        assert_eq!(json_of(handle(&request("GET", "/api/cuelists/1", ""), &mut ctx))["cues"][0]["name"], json!("Warm"));
+        assert_eq!(handle(&request("DELETE", "/api/cuelists/1", ""), &mut ctx).status, 405);
+        assert_eq!(handle(&request("GET", "/other", ""), &mut ctx).status, 404);
+    }
+}
@@ -0,0 +1,213 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::err::ConmxErr;
+
+/// A parsed request, header names are lower case
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct HttpRequest {
+    pub method: String,
+    pub path: String,
+    pub query: BTreeMap<String, String>,
+    pub headers: BTreeMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+/// Decode `%XX` escapes and `+` of a query or path part
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len() => {
+                let escaped = std::str::from_utf8(&bytes[i + 1..i + 3]).ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                match escaped {
+                    Some(b) => {
+                        out.push(b);
+                        i += 3;
+                        continue;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            b'+' => out.push(b' '),
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+impl HttpRequest {
+    /// Largest request accepted, including the body
+    pub const MAX_SIZE: usize = 64 * 1024;
+
+    /// Read a request from the start of `buf`, `None` while it is incomplete.
+    /// Also returns the number of bytes the request took.
+    pub fn parse(buf: &[u8]) -> Result<Option<(Self, usize)>, ConmxErr> {
+        let head_len = match find(buf, b"\r\n\r\n") {
+            Some(i) => i,
+            None if buf.len() > Self::MAX_SIZE => return Err(ConmxErr::Web("Request header too large".to_owned())),
+            None => return Ok(None),
+        };
+        let head = std::str::from_utf8(&buf[..head_len])
+            .map_err(|_| ConmxErr::Web("Request header is not UTF-8".to_owned()))?;
+        let mut lines = head.split("\r\n");
+
+        let request_line = lines.next().unwrap_or("");
+        let mut parts = request_line.split(' ');
+        let (method, target) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(method), Some(target), Some(version)) if version.starts_with("HTTP/1.") => (method, target),
+            _ => return Err(ConmxErr::Web(format!("Invalid request line \"{}\"", request_line))),
+        };
+        let (path, query) = match target.find('?') {
+            Some(i) => (&target[..i], &target[i + 1..]),
+            None => (target, ""),
+        };
+        let query = query.split('&')
+            .filter(|p| !p.is_empty())
+            .map(|p| match p.find('=') {
+                Some(i) => (percent_decode(&p[..i]), percent_decode(&p[i + 1..])),
+                None => (percent_decode(p), String::new()),
+            })
+            .collect();
+
+        let mut headers = BTreeMap::new();
+        for c_line in lines {
+            let i = c_line.find(':')
+                .ok_or_else(|| ConmxErr::Web(format!("Invalid header \"{}\"", c_line)))?;
+            headers.insert(c_line[..i].trim().to_ascii_lowercase(), c_line[i + 1..].trim().to_owned());
+        }
+
+        let body_len = match headers.get("content-length") {
+            Some(len) => len.parse::<usize>()
+                .map_err(|_| ConmxErr::Web(format!("Invalid content length \"{}\"", len)))?,
+            None => 0,
+        };
+        let total = head_len + 4 + body_len;
+        if total > Self::MAX_SIZE {
+            return Err(ConmxErr::Web("Request too large".to_owned()));
+        }
+        if buf.len() < total {
+            return Ok(None);
+        }
+
+        Ok(Some((
+            Self {
+                method: method.to_owned(),
+                path: percent_decode(path),
+                query,
+                headers,
+                body: buf[head_len + 4..total].to_vec(),
+            },
+            total,
+        )))
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(&name.to_ascii_lowercase()).map(String::as_str)
+    }
+
+    /// Parts of the path between the slashes, without empty ones
+    pub fn segments(&self) -> Vec<&str> {
+        self.path.split('/').filter(|s| !s.is_empty()).collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    pub fn json<T: Serialize>(value: &T) -> Self {
+        match serde_json::to_vec(value) {
+            Ok(body) => Self { status: 200, content_type: "application/json", body },
+            Err(e) => Self::error(500, &format!("Could not serialize response ({})", e)),
+        }
+    }
+
+    /// JSON object with the message as `error`
+    pub fn error(status: u16, message: &str) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: serde_json::json!({ "error": message }).to_string().into_bytes(),
+        }
+    }
+
+    pub fn no_content() -> Self {
+        Self { status: 204, content_type: "text/plain", body: Vec::new() }
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            101 => "Switching Protocols",
+            200 => "OK",
+            204 => "No Content",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            _ => "Internal Server Error",
+        }
+    }
+
+    /// The response with its headers, the connection is closed afterwards.
+    /// Any origin may call the API since every request needs the token anyway.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\
+            Access-Control-Allow-Origin: *\r\nAccess-Control-Allow-Headers: Authorization, Content-Type\r\n\
+            Access-Control-Allow-Methods: GET, PUT, POST, DELETE, OPTIONS\r\n\r\n",
+            self.status, self.reason(), self.content_type, self.body.len()
+        ).into_bytes();
+        bytes.extend_from_slice(&self.body);
+        bytes
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let raw = b"PUT /api/executors/1?token=a%20b&x HTTP/1.1\r\nHost: x\r\nContent-Length: 5\r\n\r\n{\"a\":";
+        assert_eq!(HttpRequest::parse(&raw[..20]).unwrap(), None);
+        assert_eq!(HttpRequest::parse(&raw[..raw.len() - 1]).unwrap(), None);
+
+        let mut buf = raw.to_vec();
+        buf.extend_from_slice(b"GET / HTTP/1.1\r\n\r\n");
+        let (req, len) = HttpRequest::parse(&buf).unwrap().unwrap();
+        assert_eq!(len, raw.len());
+        assert_eq!(req.method, "PUT");
+        assert_eq!(req.segments(), vec!["api", "executors", "1"]);
+        assert_eq!(req.query.get("token").map(String::as_str), Some("a b"));
+        assert_eq!(req.query.get("x").map(String::as_str), Some(""));
+        assert_eq!(req.header("HOST"), Some("x"));
+        assert_eq!(req.body, b"{\"a\":".to_vec());
+
+        assert!(HttpRequest::parse(b"nonsense\r\n\r\n").is_err());
+        assert!(HttpRequest::parse(b"GET / HTTP/1.1\r\nContent-Length: x\r\n\r\n").is_err());
+    }
+
+    #[test]
+    fn test_response() {
+        let bytes = HttpResponse::error(404, "Unknown").to_bytes();
+        let text = String::from_utf8(bytes).unwrap();
+        assert!(text.starts_with("HTTP/1.1 404 Not Found\r\n"));
+        assert!(text.contains("Content-Length: 19\r\n"));
+        assert!(text.ends_with("\r\n\r\n{\"error\":\"Unknown\"}"));
+    }
+}
@@ -0,0 +1,11 @@
+mod http;
+pub use http::*;
+
+mod websocket;
+pub use websocket::*;
+
+mod api;
+pub use api::*;
+
+mod server;
+pub use server::*;
@@ -0,0 +1,437 @@
+use std::{
+    collections::BTreeMap,
+    io::{
+        ErrorKind,
+        Read,
+        Write,
+    },
+    net::{
+        Shutdown,
+        SocketAddr,
+        TcpListener,
+        TcpStream,
+        ToSocketAddrs,
+    },
+};
+
+use log::{
+    info,
+    warn,
+};
+use serde_json::{
+    json,
+    Value,
+};
+
+use crate::{
+    dmx::{
+        DMX,
+        DMXUniverse,
+    },
+    err::ConmxErr,
+    show::Show,
+};
+
+use super::{
+    api::{
+        self,
+        ApiContext,
+    },
+    http::{
+        HttpRequest,
+        HttpResponse,
+    },
+    websocket::{
+        self,
+        WsFrame,
+    },
+};
+
+#[derive(Debug, PartialEq)]
+enum ClientKind {
+    /// Waiting for a request
+    Http,
+    /// Upgraded to a WebSocket on the stream path, `fresh` until it got the complete state
+    Stream { fresh: bool },
+    /// Closed once the output is sent
+    Closing,
+}
+
+#[derive(Debug)]
+struct Client {
+    stream: TcpStream,
+    addr: SocketAddr,
+    input: Vec<u8>,
+    output: Vec<u8>,
+    kind: ClientKind,
+}
+
+impl Client {
+    /// Unhandled input kept at most, a request or WebSocket frame of the largest size with its frame header
+    const MAX_INPUT: usize = HttpRequest::MAX_SIZE + 14;
+
+    /// Read everything available, false once the connection is gone or sent more than it may
+    fn receive(&mut self) -> bool {
+        let mut buf = [0u8; 4096];
+        loop {
+            match self.stream.read(&mut buf) {
+                Ok(0) => return false,
+                Ok(len) => {
+                    self.input.extend_from_slice(&buf[..len]);
+                    if self.input.len() > Self::MAX_INPUT {
+                        warn!("Closed {}, it sent more than {} bytes at once", self.addr, Self::MAX_INPUT);
+                        return false;
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return true,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(_) => return false,
+            }
+        }
+    }
+
+    /// Write as much output as the socket takes, false once the connection is gone
+    fn flush(&mut self) -> bool {
+        while !self.output.is_empty() {
+            match self.stream.write(&self.output) {
+                Ok(0) => return false,
+                Ok(len) => {
+                    self.output.drain(..len);
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return true,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(_) => return false,
+            }
+        }
+        true
+    }
+
+    fn respond(&mut self, response: HttpResponse) {
+        self.output.extend(response.to_bytes());
+        self.kind = ClientKind::Closing;
+    }
+}
+
+/// Local HTTP server for the JSON API and a WebSocket stream of live values,
+/// every request needs the configured token. Polled once per frame on non blocking sockets.
+#[derive(Debug)]
+pub struct WebServer {
+    listener: TcpListener,
+    token: String,
+    clients: Vec<Client>,
+    /// Channel values last streamed per universe
+    sent_frames: BTreeMap<usize, Vec<u32>>,
+    /// Executor state last streamed
+    sent_executors: Option<Value>,
+}
+
+impl WebServer {
+    /// Port used when none is given
+    pub const PORT: u16 = 8080;
+    /// Path of the WebSocket stream
+    pub const STREAM_PATH: &'static str = "/api/stream";
+    /// Clients not reading their stream are dropped once this many bytes are waiting
+    const MAX_BACKLOG: usize = 1024 * 1024;
+
+    pub fn bind<A: ToSocketAddrs>(addr: A, token: &str) -> Result<Self, ConmxErr> {
+        if token.is_empty() {
+            return Err(ConmxErr::Web("An empty token would give anybody access".to_owned()));
+        }
+        let listener = TcpListener::bind(addr)
+            .map_err(|e| ConmxErr::Net(format!("Could not bind web server ({})", e)))?;
+        listener.set_nonblocking(true)
+            .map_err(|e| ConmxErr::Net(format!("Could not configure web server ({})", e)))?;
+        Ok(Self {
+            listener,
+            token: token.to_owned(),
+            clients: Vec::new(),
+            sent_frames: BTreeMap::new(),
+            sent_executors: None,
+        })
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr, ConmxErr> {
+        self.listener.local_addr()
+            .map_err(|e| ConmxErr::Net(format!("Web server has no address ({})", e)))
+    }
+
+    /// Number of connected WebSocket streams
+    pub fn streams(&self) -> usize {
+        self.clients.iter().filter(|c| matches!(c.kind, ClientKind::Stream { .. })).count()
+    }
+
+    /// Token given as bearer or, for browsers opening a WebSocket, as `token` in the query.
+    /// Compared in constant time.
+    fn authorized(&self, req: &HttpRequest) -> bool {
+        let given = req.header("authorization")
+            .and_then(|a| a.strip_prefix("Bearer "))
+            .or_else(|| req.query.get("token").map(String::as_str))
+            .unwrap_or("");
+        given.len() == self.token.len()
+            && given.bytes().zip(self.token.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+    }
+
+    fn handle_request(&self, client: &mut Client, ctx: &mut ApiContext) {
+        let (req, len) = match HttpRequest::parse(&client.input) {
+            Ok(Some(parsed)) => parsed,
+            Ok(None) => return,
+            Err(e) => {
+                warn!("{} from {}", e, client.addr);
+                client.input.clear();
+                return client.respond(HttpResponse::error(400, &e.to_string()));
+            }
+        };
+        client.input.drain(..len);
+
+        // Preflight requests of browsers never carry credentials
+        if req.method != "OPTIONS" && !self.authorized(&req) {
+            warn!("Unauthorized request for {} from {}", req.path, client.addr);
+            return client.respond(HttpResponse::error(401, "Missing or wrong token"));
+        }
+        if req.path == Self::STREAM_PATH && websocket::is_upgrade(&req) {
+            if let Some(handshake) = websocket::handshake_response(&req) {
+                client.output.extend(handshake);
+                client.kind = ClientKind::Stream { fresh: true };
+                info!("Streaming to {}", client.addr);
+                return;
+            }
+        }
+        client.respond(api::handle(&req, ctx));
+    }
+
+    fn handle_frames(client: &mut Client) {
+        loop {
+            let (frame, len) = match WsFrame::decode_client(&client.input) {
+                Ok(Some(decoded)) => decoded,
+                Ok(None) => return,
+                Err(e) => {
+                    warn!("{} from {}", e, client.addr);
+                    client.output.extend(WsFrame::Close.encode());
+                    client.kind = ClientKind::Closing;
+                    return;
+                }
+            };
+            client.input.drain(..len);
+            match frame {
+                WsFrame::Ping(data) => client.output.extend(WsFrame::Pong(data).encode()),
+                WsFrame::Close => {
+                    client.output.extend(WsFrame::Close.encode());
+                    client.kind = ClientKind::Closing;
+                    return;
+                }
+                // The stream only goes out, changes are made with requests
+                WsFrame::Text(_) | WsFrame::Binary(_) | WsFrame::Pong(_) => {}
+            }
+        }
+    }
+
+    /// Accept new clients, answer their requests and send what is waiting
+    pub fn poll(&mut self, ctx: &mut ApiContext) {
+        loop {
+            match self.listener.accept() {
+                Ok((stream, addr)) => match stream.set_nonblocking(true) {
+                    Ok(()) => self.clients.push(Client {
+                        stream,
+                        addr,
+                        input: Vec::new(),
+                        output: Vec::new(),
+                        kind: ClientKind::Http,
+                    }),
+                    Err(e) => warn!("Could not configure connection of {} ({})", addr, e),
+                },
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => {
+                    warn!("Could not accept web client ({})", e);
+                    break;
+                }
+            }
+        }
+
+        let mut clients = std::mem::take(&mut self.clients);
+        for c_client in clients.iter_mut() {
+            if c_client.kind == ClientKind::Closing {
+                continue;
+            }
+            if !c_client.receive() {
+                c_client.kind = ClientKind::Closing;
+                c_client.output.clear();
+                continue;
+            }
+            match c_client.kind {
+                ClientKind::Http => self.handle_request(c_client, ctx),
+                ClientKind::Stream { .. } => Self::handle_frames(c_client),
+                ClientKind::Closing => {}
+            }
+        }
+        self.clients = clients;
+        self.flush();
+    }
+
+    /// Send waiting output and drop closed or stalled clients
+    fn flush(&mut self) {
+        self.clients.retain(|c| {
+            if c.output.len() > Self::MAX_BACKLOG {
+                warn!("Dropping web client {}, it does not keep up", c.addr);
+                return false;
+            }
+            true
+        });
+        for c_client in self.clients.iter_mut() {
+            if !c_client.flush() {
+                c_client.kind = ClientKind::Closing;
+                c_client.output.clear();
+            }
+        }
+        self.clients.retain(|c| {
+            let done = c.kind == ClientKind::Closing && c.output.is_empty();
+            if done {
+                c.stream.shutdown(Shutdown::Both).ok();
+            }
+            !done
+        });
+    }
+
+    fn send_text(&mut self, text: &str, only_fresh: bool) {
+        let frame = WsFrame::Text(text.to_owned()).encode();
+        for c_client in self.clients.iter_mut() {
+            match c_client.kind {
+                ClientKind::Stream { fresh } if fresh || !only_fresh => c_client.output.extend_from_slice(&frame),
+                _ => {}
+            }
+        }
+    }
+
+    /// Stream the universes and executor state that changed since the last call,
+    /// new streams get everything
+    pub fn broadcast(&mut self, dmx: &DMX, show: &Show) {
+        if self.streams() == 0 {
+            return;
+        }
+
+        for c_id in dmx.get_universe_ids() {
+            let universe = match dmx.get_universe_ref(c_id) {
+                Some(u) => u,
+                None => continue,
+            };
+            let values: Vec<u32> = (0..DMXUniverse::CHANNELS)
+                .map(|c| universe.get_channel(c).map(|c| c.get_val()).unwrap_or(0))
+                .collect();
+            let changed = self.sent_frames.get(&c_id) != Some(&values);
+            let text = json!({ "type": "dmx", "universe": c_id, "channels": values }).to_string();
+            self.send_text(&text, !changed);
+            if changed {
+                self.sent_frames.insert(c_id, values);
+            }
+        }
+
+        let mut state = api::executors_json(show);
+        let changed = self.sent_executors.as_ref() != Some(&state);
+        self.sent_executors = Some(state.clone());
+        state["type"] = json!("executors");
+        self.send_text(&state.to_string(), !changed);
+
+        for c_client in self.clients.iter_mut() {
+            if let ClientKind::Stream { fresh } = &mut c_client.kind {
+                *fresh = false;
+            }
+        }
+        self.flush();
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        thread,
+        time::Duration,
+    };
+    use crate::playback::Programmer;
+
+    /// Read from the client until `done` is true for what arrived, polling the server meanwhile
+    fn exchange(server: &mut WebServer, ctx: &mut ApiContext, client: &mut TcpStream, done: impl Fn(&[u8]) -> bool) -> Vec<u8> {
+        let mut received = Vec::new();
+        let mut buf = [0u8; 4096];
+        for _ in 0..100 {
+            server.poll(ctx);
+            match client.read(&mut buf) {
+                Ok(0) => break,
+                Ok(len) => received.extend_from_slice(&buf[..len]),
+                Err(_) => thread::sleep(Duration::from_millis(10)),
+            }
+            if done(&received) {
+                break;
+            }
+        }
+        received
+    }
+
+    fn connect(server: &WebServer, request: &str) -> TcpStream {
+        let mut client = TcpStream::connect(server.local_addr().unwrap()).unwrap();
+        client.set_nonblocking(true).unwrap();
+        client.write_all(request.as_bytes()).unwrap();
+        client
+    }
+
+    fn frames(mut data: &[u8]) -> Vec<Value> {
+        let mut frames = Vec::new();
+        while let Some((WsFrame::Text(text), len)) = WsFrame::decode(data).unwrap() {
+            frames.push(serde_json::from_str(&text).unwrap());
+            data = &data[len..];
+        }
+        frames
+    }
+
+    #[test]
+    fn test_requests_and_stream() {
+        let mut dmx = DMX::new();
+        dmx.add_universe(DMXUniverse::new(0));
+        let mut show = Show::new();
+        let mut programmer = Programmer::new();
+        let mut server = WebServer::bind("127.0.0.1:0", "secret").unwrap();
+        assert!(WebServer::bind("127.0.0.1:0", "").is_err());
+        let closed = |_: &[u8]| false;
+
+        {
+            let mut ctx = ApiContext { dmx: &mut dmx, show: &mut show, programmer: &mut programmer };
+            let mut client = connect(&server, "GET /api/universes HTTP/1.1\r\nAuthorization: Bearer wrong\r\n\r\n");
+            let response = String::from_utf8(exchange(&mut server, &mut ctx, &mut client, closed)).unwrap();
+            assert!(response.starts_with("HTTP/1.1 401 "));
+
+            let mut client = connect(&server, "PUT /api/universes/0/channels/1 HTTP/1.1\r\nAuthorization: Bearer secret\r\nContent-Length: 13\r\n\r\n{\"value\":100}");
+            let response = String::from_utf8(exchange(&mut server, &mut ctx, &mut client, closed)).unwrap();
+            assert!(response.starts_with("HTTP/1.1 204 "));
+
+            // Clients sending more than a request may hold are dropped without reading on
+            let mut client = connect(&server, &"a".repeat(Client::MAX_INPUT + 1));
+            assert!(exchange(&mut server, &mut ctx, &mut client, closed).is_empty());
+        }
+        assert_eq!(dmx.get_universe_ref(0).unwrap().get_channel(0).unwrap().get_val(), 100);
+
+        let mut ctx = ApiContext { dmx: &mut dmx, show: &mut show, programmer: &mut programmer };
+        let mut client = connect(&server, "GET /api/stream?token=secret HTTP/1.1\r\nUpgrade: websocket\r\n\
+            Connection: Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\r\n");
+        let handshake = exchange(&mut server, &mut ctx, &mut client, |r| r.ends_with(b"\r\n\r\n"));
+        assert!(String::from_utf8(handshake).unwrap().contains("Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n"));
+        assert_eq!(server.streams(), 1);
+
+        // A new stream gets the complete state, afterwards only changes
+        server.broadcast(ctx.dmx, ctx.show);
+        let state = frames(&exchange(&mut server, &mut ctx, &mut client, |r| frames(r).len() == 2));
+        assert_eq!(state[0]["type"], json!("dmx"));
+        assert_eq!(state[0]["channels"][0], json!(100));
+        assert_eq!(state[1]["type"], json!("executors"));
+
+        server.broadcast(ctx.dmx, ctx.show);
+        ctx.show.executors.set_grand_master(0.5);
+        server.broadcast(ctx.dmx, ctx.show);
+        let changes = frames(&exchange(&mut server, &mut ctx, &mut client, |r| !frames(r).is_empty()));
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0]["grandmaster"], json!(0.5));
+
+        client.write_all(&[0x88, 0x80, 1, 2, 3, 4]).unwrap();
+        exchange(&mut server, &mut ctx, &mut client, closed);
+        assert_eq!(server.streams(), 0);
+    }
+}
@@ -0,0 +1,230 @@
+use crate::err::ConmxErr;
+
+use super::http::HttpRequest;
+
+/// Appended to the key of the client before hashing, see RFC 6455
+const HANDSHAKE_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+const OP_TEXT: u8 = 0x1;
+const OP_BINARY: u8 = 0x2;
+const OP_CLOSE: u8 = 0x8;
+const OP_PING: u8 = 0x9;
+const OP_PONG: u8 = 0xA;
+
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    for c_block in message.chunks(64) {
+        let mut w = [0u32; 80];
+        for i in 0..16 {
+            w[i] = u32::from_be_bytes([c_block[4 * i], c_block[4 * i + 1], c_block[4 * i + 2], c_block[4 * i + 3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, c_word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let temp = a.rotate_left(5).wrapping_add(f).wrapping_add(e).wrapping_add(k).wrapping_add(*c_word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (c_h, c_v) in h.iter_mut().zip([a, b, c, d, e].iter()) {
+            *c_h = c_h.wrapping_add(*c_v);
+        }
+    }
+
+    let mut digest = [0u8; 20];
+    for (i, c_h) in h.iter().enumerate() {
+        digest[4 * i..4 * i + 4].copy_from_slice(&c_h.to_be_bytes());
+    }
+    digest
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity((data.len() + 2) / 3 * 4);
+    for c_chunk in data.chunks(3) {
+        let n = (c_chunk[0] as u32) << 16
+            | (*c_chunk.get(1).unwrap_or(&0) as u32) << 8
+            | *c_chunk.get(2).unwrap_or(&0) as u32;
+        for i in 0..4 {
+            if i <= c_chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Value of the `Sec-WebSocket-Accept` header answering the key of a client
+pub fn accept_key(key: &str) -> String {
+    base64(&sha1(format!("{}{}", key.trim(), HANDSHAKE_GUID).as_bytes()))
+}
+
+/// Whether the request asks to switch to a WebSocket
+pub fn is_upgrade(req: &HttpRequest) -> bool {
+    req.header("upgrade").map(|u| u.eq_ignore_ascii_case("websocket")).unwrap_or(false)
+        && req.header("sec-websocket-key").is_some()
+}
+
+/// Response accepting the upgrade of a request
+pub fn handshake_response(req: &HttpRequest) -> Option<Vec<u8>> {
+    let key = req.header("sec-websocket-key")?;
+    Some(format!(
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        accept_key(key)
+    ).into_bytes())
+}
+
+/// Frames a client sends, fragmented messages are not supported
+#[derive(Debug, Clone, PartialEq)]
+pub enum WsFrame {
+    Text(String),
+    Binary(Vec<u8>),
+    Ping(Vec<u8>),
+    Pong(Vec<u8>),
+    Close,
+}
+
+fn encode_frame(opcode: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = vec![0x80 | opcode];
+    match payload.len() {
+        len if len < 126 => frame.push(len as u8),
+        len if len <= u16::MAX as usize => {
+            frame.push(126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    frame
+}
+
+impl WsFrame {
+    /// Frame as sent by the server, without a mask
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            WsFrame::Text(text) => encode_frame(OP_TEXT, text.as_bytes()),
+            WsFrame::Binary(data) => encode_frame(OP_BINARY, data),
+            WsFrame::Ping(data) => encode_frame(OP_PING, data),
+            WsFrame::Pong(data) => encode_frame(OP_PONG, data),
+            WsFrame::Close => encode_frame(OP_CLOSE, &[]),
+        }
+    }
+
+    /// Read a frame from the start of `buf`, `None` while it is incomplete.
+    /// Also returns the number of bytes the frame took.
+    pub fn decode(buf: &[u8]) -> Result<Option<(Self, usize)>, ConmxErr> {
+        if buf.len() < 2 {
+            return Ok(None);
+        }
+        if buf[0] & 0x80 == 0 {
+            return Err(ConmxErr::Web("Fragmented WebSocket messages are not supported".to_owned()));
+        }
+        let masked = buf[1] & 0x80 != 0;
+        let (len, mut pos) = match buf[1] & 0x7F {
+            126 if buf.len() >= 4 => (u16::from_be_bytes([buf[2], buf[3]]) as usize, 4),
+            127 if buf.len() >= 10 => {
+                let mut len = [0u8; 8];
+                len.copy_from_slice(&buf[2..10]);
+                (u64::from_be_bytes(len) as usize, 10)
+            }
+            126 | 127 => return Ok(None),
+            len => (len as usize, 2),
+        };
+        if len > HttpRequest::MAX_SIZE {
+            return Err(ConmxErr::Web("WebSocket frame too large".to_owned()));
+        }
+        let mask = if masked {
+            match buf.get(pos..pos + 4) {
+                Some(m) => {
+                    pos += 4;
+                    [m[0], m[1], m[2], m[3]]
+                }
+                None => return Ok(None),
+            }
+        } else {
+            [0; 4]
+        };
+        let payload: Vec<u8> = match buf.get(pos..pos + len) {
+            Some(p) => p.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]).collect(),
+            None => return Ok(None),
+        };
+
+        let frame = match buf[0] & 0x0F {
+            OP_TEXT => WsFrame::Text(String::from_utf8(payload)
+                .map_err(|_| ConmxErr::Web("WebSocket text is not UTF-8".to_owned()))?),
+            OP_BINARY => WsFrame::Binary(payload),
+            OP_PING => WsFrame::Ping(payload),
+            OP_PONG => WsFrame::Pong(payload),
+            OP_CLOSE => WsFrame::Close,
+            op => return Err(ConmxErr::Web(format!("Unknown WebSocket opcode {}", op))),
+        };
+        Ok(Some((frame, pos + len)))
+    }
+
+    /// Like `decode` for frames sent by a client, which have to be masked (RFC 6455, section 5.1)
+    pub fn decode_client(buf: &[u8]) -> Result<Option<(Self, usize)>, ConmxErr> {
+        if buf.len() >= 2 && buf[1] & 0x80 == 0 {
+            return Err(ConmxErr::Web("Unmasked WebSocket frame from a client".to_owned()));
+        }
+        Self::decode(buf)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handshake() {
+        let hex: String = sha1(b"abc").iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(hex, "a9993e364706816aba3e25717850c26c9cd0d89d");
+        assert_eq!(base64(b"foob"), "Zm9vYg==");
+        assert_eq!(base64(b"fooba"), "Zm9vYmE=");
+        // Example of RFC 6455
+        assert_eq!(accept_key("dGhlIHNhbXBsZSBub25jZQ=="), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+    }
+
+    #[test]
+    fn test_frames() {
+        // Masked "Hello" of RFC 6455
+        let masked = [0x81, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51, 0x58];
+        assert_eq!(WsFrame::decode(&masked[..6]).unwrap(), None);
+        assert_eq!(WsFrame::decode(&masked).unwrap(), Some((WsFrame::Text("Hello".to_owned()), masked.len())));
+        assert_eq!(WsFrame::decode_client(&masked).unwrap(), Some((WsFrame::Text("Hello".to_owned()), masked.len())));
+        // Clients have to mask their frames
+        let unmasked = WsFrame::Text("Hello".to_owned()).encode();
+        assert!(WsFrame::decode(&unmasked).unwrap().is_some());
+        assert!(WsFrame::decode_client(&unmasked).is_err());
+        assert_eq!(WsFrame::decode_client(&unmasked[..1]).unwrap(), None);
+
+        let long = WsFrame::Text("x".repeat(300));
+        let encoded = long.encode();
+        assert_eq!(encoded[1..4], [126, 1, 44]);
+        assert_eq!(WsFrame::decode(&encoded).unwrap(), Some((long, encoded.len())));
+        assert_eq!(WsFrame::decode(&WsFrame::Close.encode()).unwrap().unwrap().0, WsFrame::Close);
+        assert!(WsFrame::decode(&[0x01, 0x00]).is_err());
+    }
+}